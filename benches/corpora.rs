@@ -0,0 +1,171 @@
+//! Reader throughput over generated corpora, compared with quick-xml and xml-rs.
+//!
+//! Run with `cargo bench-corpora`.
+
+use std::{fmt::Write, sync::LazyLock};
+
+use divan::{Bencher, counter::BytesCount};
+use quick_xml::{Reader, events::Event};
+use rexml::reader::{XmlNode, XmlReader};
+
+fn main() {
+    divan::main();
+}
+
+const CORPORA: &[&str] = &["nested", "attrs", "text", "namespaces", "entities"];
+
+/// Approximate size of each generated corpus.
+const SIZE: usize = 1 << 20;
+
+fn generate(until: impl Fn(&String) -> bool, mut push: impl FnMut(&mut String, usize)) -> String {
+    let mut xml = String::from(r#"<?xml version="1.0" encoding="UTF-8"?><root>"#);
+    let mut i = 0;
+
+    while until(&xml) {
+        push(&mut xml, i);
+        i += 1;
+    }
+
+    xml.push_str("</root>");
+    xml
+}
+
+fn corpus(kind: &str) -> &'static str {
+    static NESTED: LazyLock<String> = LazyLock::new(|| {
+        generate(
+            |xml| xml.len() < SIZE,
+            |xml, _| {
+                for depth in 0..64 {
+                    write!(xml, "<n{}>", depth).unwrap();
+                }
+
+                for depth in (0..64).rev() {
+                    write!(xml, "</n{}>", depth).unwrap();
+                }
+            },
+        )
+    });
+
+    static ATTRS: LazyLock<String> = LazyLock::new(|| {
+        generate(
+            |xml| xml.len() < SIZE,
+            |xml, i| {
+                xml.push_str("<el");
+
+                for attr in 0..24 {
+                    write!(xml, r#" attr{}="value {} {}""#, attr, i, attr).unwrap();
+                }
+
+                xml.push_str("/>");
+            },
+        )
+    });
+
+    static TEXT: LazyLock<String> = LazyLock::new(|| {
+        generate(
+            |xml| xml.len() < SIZE,
+            |xml, i| {
+                write!(xml, "<p>paragraph {} ", i).unwrap();
+
+                for _ in 0..16 {
+                    xml.push_str("lorem ipsum dolor sit amet, consectetur adipiscing elit. ");
+                }
+
+                xml.push_str("</p>");
+            },
+        )
+    });
+
+    static NAMESPACES: LazyLock<String> = LazyLock::new(|| {
+        generate(
+            |xml| xml.len() < SIZE,
+            |xml, i| {
+                write!(
+                    xml,
+                    r#"<a:g xmlns:a="urn:a:{i}" xmlns:b="urn:b:{i}" b:id="{i}"><b:rect a:x="1" b:y="2"/><a:use b:href="urn:c"/></a:g>"#
+                )
+                .unwrap();
+            },
+        )
+    });
+
+    static ENTITIES: LazyLock<String> = LazyLock::new(|| {
+        generate(
+            |xml| xml.len() < SIZE,
+            |xml, i| {
+                write!(
+                    xml,
+                    r#"<e v="&lt;{i}&gt;">&amp; &#x20AC; &quot;{i}&quot; &lt;tag&gt; &apos;&#65;&apos;</e>"#
+                )
+                .unwrap();
+            },
+        )
+    });
+
+    match kind {
+        "nested" => &NESTED,
+        "attrs" => &ATTRS,
+        "text" => &TEXT,
+        "namespaces" => &NAMESPACES,
+        "entities" => &ENTITIES,
+        _ => unreachable!(),
+    }
+}
+
+#[divan::bench(args = CORPORA)]
+fn rexml(bencher: Bencher, kind: &str) {
+    let xml = corpus(kind);
+
+    bencher.counter(BytesCount::of_str(xml)).bench(|| {
+        let mut reader = XmlReader::from(xml.as_bytes());
+
+        while let Some(node) = reader.read_next().unwrap() {
+            match node {
+                XmlNode::Start(start) => {
+                    for attr in start.attrs() {
+                        reader.attr_value(&attr.unwrap()).unwrap();
+                    }
+                }
+                XmlNode::CharData(chardata) => {
+                    reader.text(&chardata).unwrap();
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+#[divan::bench(args = CORPORA)]
+fn quick_xml(bencher: Bencher, kind: &str) {
+    let xml = corpus(kind);
+
+    bencher.counter(BytesCount::of_str(xml)).bench(|| {
+        let mut reader = Reader::from_str(xml);
+
+        loop {
+            match reader.read_event().unwrap() {
+                Event::Eof => break,
+                Event::Start(start) | Event::Empty(start) => {
+                    for attr in start.attributes() {
+                        attr.unwrap().unescape_value().unwrap();
+                    }
+                }
+                Event::Text(text) => {
+                    text.unescape().unwrap();
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+#[divan::bench(args = CORPORA)]
+fn xml_rs(bencher: Bencher, kind: &str) {
+    let xml = corpus(kind);
+
+    bencher.counter(BytesCount::of_str(xml)).bench(|| {
+        for event in xml::EventReader::from_str(xml) {
+            event.unwrap();
+        }
+    });
+}
@@ -1,5 +1,9 @@
+use divan::AllocProfiler;
 use quick_xml::{Reader, events::Event};
-use rexml::reader::{ReadState, XmlNode};
+use rexml::reader::{EventBuf, ReadState, XmlNode, XmlReader};
+
+#[global_allocator]
+static ALLOC: AllocProfiler = AllocProfiler::system();
 
 fn main() {
     divan::main();
@@ -11,17 +15,43 @@ fn rexml_read() {
         ReadState::XmlDecl,
         include_str!("../spec/cat.svg").as_bytes(),
     ) {
-        match node.unwrap() {
+        if let XmlNode::Start(start) = node.unwrap() {
+            for attr in start.attrs() {
+                attr.unwrap();
+            }
+        }
+    }
+}
+
+/// Collect the attributes and decoded text of each event into new buffers.
+#[divan::bench(sample_count = 1000)]
+fn rexml_read_owned() {
+    let mut reader = XmlReader::from(include_str!("../spec/cat.svg").as_bytes());
+
+    while let Some(node) = reader.read_next().unwrap() {
+        match node {
             XmlNode::Start(start) => {
-                for attr in start.attrs() {
-                    attr.unwrap();
-                }
+                divan::black_box(start.attrs().collect::<Result<Vec<_>, _>>().unwrap());
+            }
+            XmlNode::CharData(chardata) => {
+                divan::black_box(reader.text(&chardata).unwrap().into_owned());
             }
             _ => {}
         }
     }
 }
 
+/// Read the same events as `rexml_read_owned` into one reused [`EventBuf`].
+#[divan::bench(sample_count = 1000)]
+fn rexml_read_into() {
+    let mut reader = XmlReader::from(include_str!("../spec/cat.svg").as_bytes());
+    let mut buf = EventBuf::new();
+
+    while reader.read_next_into(&mut buf).unwrap() {
+        divan::black_box((buf.attrs(), buf.text()));
+    }
+}
+
 #[divan::bench(sample_count = 1000)]
 fn xml_dom_read() {
     xml_dom::parser::read_xml(include_str!("../spec/cat.svg")).unwrap();
@@ -0,0 +1,37 @@
+//! Feed arbitrary bytes to `PushParser` in chunks, checking it never panics and yields the
+//! same events as `XmlReader` over the whole input.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rexml::reader::{PushEvent, PushParser, XmlReader};
+
+fuzz_target!(|data: &[u8]| {
+    let Some((chunk, source)) = data.split_first() else {
+        return;
+    };
+
+    let chunk = usize::from(*chunk).max(1);
+
+    let expected = XmlReader::from(source)
+        .map_while(Result::ok)
+        .map(|node| node.into_owned())
+        .collect::<Vec<_>>();
+
+    let mut parser = PushParser::new();
+    let mut events = vec![];
+    let mut chunks = source.chunks(chunk);
+
+    loop {
+        match parser.next_event() {
+            Ok(PushEvent::Node(node)) => events.push(node.into_owned()),
+            Ok(PushEvent::NeedMoreData) => match chunks.next() {
+                Some(chunk) => parser.feed(chunk),
+                None => parser.finish(),
+            },
+            Ok(PushEvent::Eof) | Err(_) => break,
+        }
+    }
+
+    assert_eq!(events, expected);
+});
@@ -0,0 +1,78 @@
+//! Drive `XmlReader` and the lazy parsers of its events over arbitrary bytes.
+//!
+//! The first byte selects the reader options, the rest is the document. Any input may fail to
+//! parse, but must never panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use parserc::ControlFlow;
+use rexml::reader::{Diagnostic, XmlNode, XmlReaderBuilder};
+
+fuzz_target!(|data: &[u8]| {
+    let Some((options, source)) = data.split_first() else {
+        return;
+    };
+
+    let mut reader = XmlReaderBuilder::new()
+        .lenient(options & 1 != 0)
+        .strict(options & 2 != 0)
+        .fragment(options & 4 != 0)
+        .coalesce_text(options & 8 != 0)
+        .trim_text(options & 16 != 0)
+        .check_names(options & 32 != 0)
+        .check_chars(options & 64 != 0)
+        .normalize_newlines(options & 128 != 0)
+        .build(source);
+
+    loop {
+        let node = match reader.read_next() {
+            Ok(Some(node)) => node,
+            Ok(None) => break,
+            Err(err) => {
+                if let ControlFlow::Fatal(err) | ControlFlow::Recovable(err) = &err {
+                    let diagnostic = Diagnostic::new(source, err);
+
+                    if let Ok(source) = std::str::from_utf8(source) {
+                        _ = diagnostic.apply(source);
+                    }
+
+                    _ = reader.error_position(err);
+                }
+
+                break;
+            }
+        };
+
+        _ = reader.node_position();
+
+        match &node {
+            XmlNode::DocType(doctype) => {
+                _ = doctype.root_name();
+                _ = doctype.system_id();
+                _ = doctype.internal_subset();
+                _ = doctype.parse_decl();
+            }
+            XmlNode::XmlDecl(decl) => decl.pseudo_attrs().for_each(drop),
+            XmlNode::PI(pi) => pi.pseudo_attrs().for_each(drop),
+            XmlNode::Start(start) => {
+                for attr in start.attrs() {
+                    let Ok(attr) = attr else {
+                        break;
+                    };
+
+                    _ = reader.attr_value(&attr);
+                }
+
+                _ = reader.get_attr(start, b"id");
+            }
+            XmlNode::CharData(chardata) => {
+                _ = reader.text(chardata);
+            }
+            XmlNode::CData(cdata) => {
+                _ = reader.cdata(cdata);
+            }
+            _ => {}
+        }
+    }
+});
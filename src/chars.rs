@@ -0,0 +1,276 @@
+//! Xml character classes and helpers shared by `reader` and `writer`.
+//!
+//! ASCII lookups are answered by 256-bit [`ByteSet`] tables, the remaining code points
+//! by binary searching sorted range tables taken verbatim from the spec.
+
+use std::borrow::Cow;
+
+use crate::types::XmlVersion;
+
+/// A set of bytes backed by a 256-bit bitset.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ByteSet([u64; 4]);
+
+impl ByteSet {
+    /// The empty set.
+    pub const EMPTY: ByteSet = ByteSet([0; 4]);
+
+    /// Returns a copy of this set with `c` inserted.
+    pub const fn with(self, c: u8) -> Self {
+        let mut bits = self.0;
+        bits[(c >> 6) as usize] |= 1 << (c & 63);
+        ByteSet(bits)
+    }
+
+    /// Returns a copy of this set with the bytes `from..=to` inserted.
+    pub const fn with_range(self, from: u8, to: u8) -> Self {
+        let mut set = self;
+        let mut c = from;
+
+        while c <= to {
+            set = set.with(c);
+
+            if c == u8::MAX {
+                break;
+            }
+
+            c += 1;
+        }
+
+        set
+    }
+
+    /// Returns true if `c` is in this set.
+    #[inline(always)]
+    pub const fn contains(&self, c: u8) -> bool {
+        self.0[(c >> 6) as usize] & (1 << (c & 63)) != 0
+    }
+}
+
+/// [`S`](https://www.w3.org/TR/xml11/#NT-S) bytes.
+pub const WS: ByteSet = ByteSet::EMPTY
+    .with(b'\x20')
+    .with(b'\x09')
+    .with(b'\x0d')
+    .with(b'\x0a');
+
+/// ASCII subset of [`NameStartChar`](https://www.w3.org/TR/xml11/#NT-NameStartChar).
+pub const NAME_START_ASCII: ByteSet = ByteSet::EMPTY
+    .with(b':')
+    .with(b'_')
+    .with_range(b'A', b'Z')
+    .with_range(b'a', b'z');
+
+/// ASCII subset of [`NameChar`](https://www.w3.org/TR/xml11/#NT-NameChar).
+pub const NAME_ASCII: ByteSet = NAME_START_ASCII
+    .with(b'-')
+    .with(b'.')
+    .with_range(b'0', b'9');
+
+/// Non-ASCII ranges of [`NameStartChar`](https://www.w3.org/TR/xml11/#NT-NameStartChar).
+pub const NAME_START_RANGES: &[(char, char)] = &[
+    ('\u{C0}', '\u{D6}'),
+    ('\u{D8}', '\u{F6}'),
+    ('\u{F8}', '\u{2FF}'),
+    ('\u{370}', '\u{37D}'),
+    ('\u{37F}', '\u{1FFF}'),
+    ('\u{200C}', '\u{200D}'),
+    ('\u{2070}', '\u{218F}'),
+    ('\u{2C00}', '\u{2FEF}'),
+    ('\u{3001}', '\u{D7FF}'),
+    ('\u{F900}', '\u{FDCF}'),
+    ('\u{FDF0}', '\u{FFFD}'),
+    ('\u{10000}', '\u{EFFFF}'),
+];
+
+/// Non-ASCII ranges [`NameChar`](https://www.w3.org/TR/xml11/#NT-NameChar) adds to `NameStartChar`.
+pub const NAME_EXTRA_RANGES: &[(char, char)] = &[
+    ('\u{B7}', '\u{B7}'),
+    ('\u{300}', '\u{36F}'),
+    ('\u{203F}', '\u{2040}'),
+];
+
+/// Ranges of xml 1.0 [`Char`](https://www.w3.org/TR/xml/#NT-Char).
+pub const CHAR10_RANGES: &[(char, char)] = &[
+    ('\u{9}', '\u{A}'),
+    ('\u{D}', '\u{D}'),
+    ('\u{20}', '\u{D7FF}'),
+    ('\u{E000}', '\u{FFFD}'),
+    ('\u{10000}', '\u{10FFFF}'),
+];
+
+/// Ranges of xml 1.1 [`Char`](https://www.w3.org/TR/xml11/#NT-Char).
+pub const CHAR11_RANGES: &[(char, char)] = &[
+    ('\u{1}', '\u{D7FF}'),
+    ('\u{E000}', '\u{FFFD}'),
+    ('\u{10000}', '\u{10FFFF}'),
+];
+
+/// Ranges of xml 1.1 [`RestrictedChar`](https://www.w3.org/TR/xml11/#NT-RestrictedChar).
+pub const RESTRICTED_CHAR_RANGES: &[(char, char)] = &[
+    ('\u{1}', '\u{8}'),
+    ('\u{B}', '\u{C}'),
+    ('\u{E}', '\u{1F}'),
+    ('\u{7F}', '\u{84}'),
+    ('\u{86}', '\u{9F}'),
+];
+
+/// Returns true if `c` is in one of the sorted, non-overlapping `ranges`.
+#[inline(always)]
+pub fn in_ranges(ranges: &[(char, char)], c: char) -> bool {
+    ranges
+        .binary_search_by(|(from, to)| {
+            if c < *from {
+                std::cmp::Ordering::Greater
+            } else if c > *to {
+                std::cmp::Ordering::Less
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+/// Check if the `c` is whitespace.
+#[inline(always)]
+pub fn is_ws(c: u8) -> bool {
+    WS.contains(c)
+}
+
+/// Check if the `c` is a [`NameStartChar`](https://www.w3.org/TR/xml11/#NT-NameStartChar).
+#[inline(always)]
+pub fn is_name_start_char(c: char) -> bool {
+    if c.is_ascii() {
+        NAME_START_ASCII.contains(c as u8)
+    } else {
+        in_ranges(NAME_START_RANGES, c)
+    }
+}
+
+/// Check if the `c` is a [`NameChar`](https://www.w3.org/TR/xml11/#NT-NameChar).
+#[inline(always)]
+pub fn is_name_char(c: char) -> bool {
+    if c.is_ascii() {
+        NAME_ASCII.contains(c as u8)
+    } else {
+        in_ranges(NAME_START_RANGES, c) || in_ranges(NAME_EXTRA_RANGES, c)
+    }
+}
+
+/// Check if the `c` is a legal [`Char`](https://www.w3.org/TR/xml11/#NT-Char) of the xml `version`.
+///
+/// For xml 1.1 this includes `RestrictedChar`, which may only appear as character references;
+/// see [`is_restricted_char`].
+#[inline(always)]
+pub fn is_char(version: XmlVersion, c: char) -> bool {
+    match version {
+        XmlVersion::Ver10 => in_ranges(CHAR10_RANGES, c),
+        XmlVersion::Ver11 => in_ranges(CHAR11_RANGES, c),
+    }
+}
+
+/// Check if the `c` is a xml 1.1 [`RestrictedChar`](https://www.w3.org/TR/xml11/#NT-RestrictedChar).
+#[inline(always)]
+pub fn is_restricted_char(c: char) -> bool {
+    in_ranges(RESTRICTED_CHAR_RANGES, c)
+}
+
+/// Check if the `c` is a [`PubidChar`](https://www.w3.org/TR/xml11/#NT-PubidChar).
+#[inline(always)]
+pub fn is_pubid_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, ' ' | '\r' | '\n') || "-'()+,./:=?;!*#@$_%".contains(c)
+}
+
+/// Check if `name` matches the [`Name`](https://www.w3.org/TR/xml11/#NT-Name) production.
+pub fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    match chars.next() {
+        Some(c) if is_name_start_char(c) => chars.all(is_name_char),
+        _ => false,
+    }
+}
+
+/// Returns the offset of the first `--` in the [`Comment`](https://www.w3.org/TR/xml11/#NT-Comment)
+/// `content`, or of the last `-` if it ends with one; `None` if it is legal.
+pub fn illegal_comment_offset(content: &[u8]) -> Option<usize> {
+    memchr::memmem::find(content, b"--").or_else(|| {
+        content
+            .last()
+            .filter(|c| **c == b'-')
+            .map(|_| content.len() - 1)
+    })
+}
+
+/// Replace `\r\n` and `\r` line breaks with `\n`, see [`End-of-Line Handling`](https://www.w3.org/TR/xml/#sec-line-ends).
+pub fn normalize_newlines(text: &str) -> Cow<'_, str> {
+    if memchr::memchr(b'\r', text.as_bytes()).is_none() {
+        return Cow::Borrowed(text);
+    }
+
+    text.replace("\r\n", "\n").replace('\r', "\n").into()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::XmlVersion;
+
+    use super::*;
+
+    #[test]
+    fn test_byte_set() {
+        let set = ByteSet::EMPTY.with(0).with(255).with_range(b'a', b'c');
+
+        assert!(set.contains(0));
+        assert!(set.contains(255));
+        assert!(set.contains(b'b'));
+        assert!(!set.contains(b'd'));
+        assert!(ByteSet::EMPTY.with_range(250, 255).contains(255));
+
+        for c in 0..=255u8 {
+            assert_eq!(is_ws(c), matches!(c, b' ' | b'\t' | b'\r' | b'\n'));
+        }
+    }
+
+    #[test]
+    fn test_illegal_comment() {
+        assert_eq!(illegal_comment_offset(b" a - b "), None);
+        assert_eq!(illegal_comment_offset(b" a -- b "), Some(3));
+        assert_eq!(illegal_comment_offset(b" a -"), Some(3));
+        assert_eq!(illegal_comment_offset(b""), None);
+    }
+
+    #[test]
+    fn test_normalize_newlines() {
+        assert!(matches!(normalize_newlines("a\nb"), Cow::Borrowed("a\nb")));
+        assert_eq!(normalize_newlines("a\r\nb\rc\r\r\nd"), "a\nb\nc\n\nd");
+    }
+
+    #[test]
+    fn test_names() {
+        assert!(is_name("svg:rect"));
+        assert!(is_name("_x-1.2"));
+        assert!(is_name("\u{e9}t\u{e9}"));
+        assert!(is_name("a\u{B7}b"));
+        assert!(!is_name(""));
+        assert!(!is_name("1abc"));
+        assert!(!is_name("-abc"));
+        assert!(!is_name("a b"));
+        assert!(!is_name("a\u{D7}b"));
+        assert!(!is_name_start_char('\u{B7}'));
+        assert!(is_name_start_char('\u{10000}'));
+        assert!(!is_name_start_char('\u{F0000}'));
+    }
+
+    #[test]
+    fn test_chars() {
+        assert!(is_char(XmlVersion::Ver10, '\t'));
+        assert!(!is_char(XmlVersion::Ver10, '\u{1}'));
+        assert!(is_char(XmlVersion::Ver11, '\u{1}'));
+        assert!(!is_char(XmlVersion::Ver11, '\u{0}'));
+        assert!(!is_char(XmlVersion::Ver10, '\u{FFFE}'));
+        assert!(is_char(XmlVersion::Ver10, '\u{10FFFF}'));
+        assert!(!is_restricted_char('\u{85}'));
+        assert!(is_restricted_char('\u{7F}'));
+    }
+}
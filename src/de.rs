@@ -0,0 +1,800 @@
+//! Serde deserialization of xml documents.
+//!
+//! The root element maps to the target type:
+//!
+//! - attributes map to fields named `@name`,
+//! - child elements map to fields named after the element, repeated elements to a `Vec`,
+//! - the text content maps to the field `$text`, or to the value itself for primitives.
+//!
+//! The deserializer walks a [`Document`] rather than reader events: repeated elements
+//! collected into a `Vec` needn't be adjacent, and fields may be requested in any order, so
+//! the children of an element are looked up by name instead of consumed in document order.
+//! [`from_str`] parses the whole text into a [`Document`] first, so memory grows with the
+//! size of the document, not of the target value; parse with [`Document::parse`] and call
+//! [`from_document`] to reuse the tree, or read large documents with
+//! [`XmlReader`](crate::reader::XmlReader) directly.
+//!
+//! ```
+//! #[derive(serde::Deserialize)]
+//! struct Rect {
+//!     #[serde(rename = "@width")]
+//!     width: f32,
+//!     title: Option<String>,
+//! }
+//!
+//! let rect: Rect = rexml::de::from_str(r#"<?xml version="1.0"?><rect width="10"><title>r</title></rect>"#).unwrap();
+//!
+//! assert_eq!(rect.width, 10.0);
+//! assert_eq!(rect.title.as_deref(), Some("r"));
+//! ```
+
+use std::{borrow::Cow, fmt::Display};
+
+use serde::{
+    Deserialize,
+    de::{
+        self, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess,
+        Visitor,
+    },
+    forward_to_deserialize_any,
+};
+
+use crate::dom::{Document, DomError, Node, NodeId};
+
+/// Error returned by [`from_str`] and [`from_document`].
+#[derive(Debug, thiserror::Error, PartialEq, Clone)]
+pub enum DeError {
+    #[error("{0}")]
+    Custom(String),
+    #[error(transparent)]
+    Dom(#[from] DomError),
+    /// A text or attribute value can't be parsed as the target type.
+    #[error("invalid {ty} `{value}`")]
+    Parse { ty: &'static str, value: String },
+    #[error("document has no root element")]
+    NoRoot,
+}
+
+impl de::Error for DeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        DeError::Custom(msg.to_string())
+    }
+}
+
+/// Deserialize an instance of `T` from the xml text `xml`.
+///
+/// Builds a [`Document`] of the whole text first, see the [module docs](self).
+pub fn from_str<T>(xml: &str) -> Result<T, DeError>
+where
+    T: de::DeserializeOwned,
+{
+    from_document(&Document::parse(xml)?)
+}
+
+/// Deserialize an instance of `T` from the root element of `document`.
+pub fn from_document<'de, T>(document: &'de Document) -> Result<T, DeError>
+where
+    T: Deserialize<'de>,
+{
+    let id = document.root().ok_or(DeError::NoRoot)?;
+
+    T::deserialize(ElementDeserializer { document, id })
+}
+
+/// Deserializer of a text or attribute value.
+struct TextDeserializer<'de>(Cow<'de, str>);
+
+impl TextDeserializer<'_> {
+    fn parse<T: std::str::FromStr>(&self, ty: &'static str) -> Result<T, DeError> {
+        self.0.trim().parse().map_err(|_| DeError::Parse {
+            ty,
+            value: self.0.to_string(),
+        })
+    }
+}
+
+macro_rules! deserialize_parse {
+    ($($method:ident => $visit:ident($ty:ty),)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                visitor.$visit(self.parse::<$ty>(stringify!($ty))?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for TextDeserializer<'de> {
+    type Error = DeError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            Cow::Borrowed(value) => visitor.visit_borrowed_str(value),
+            Cow::Owned(value) => visitor.visit_string(value),
+        }
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0.trim() {
+            "true" | "1" => visitor.visit_bool(true),
+            "false" | "0" => visitor.visit_bool(false),
+            _ => Err(DeError::Parse {
+                ty: "bool",
+                value: self.0.to_string(),
+            }),
+        }
+    }
+
+    deserialize_parse! {
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_i128 => visit_i128(i128),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_u128 => visit_u128(u128),
+        deserialize_f32 => visit_f32(f32),
+        deserialize_f64 => visit_f64(f64),
+        deserialize_char => visit_char(char),
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Whitespace separated lists, e.g. `viewBox="0 0 10 10"`.
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let items = match self.0 {
+            Cow::Borrowed(value) => value.split_whitespace().map(Cow::Borrowed).collect(),
+            Cow::Owned(value) => value
+                .split_whitespace()
+                .map(|item| Cow::Owned(item.to_string()))
+                .collect::<Vec<_>>(),
+        };
+
+        visitor.visit_seq(Items(items.into_iter().map(TextDeserializer)))
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_enum(self.0.trim().to_string().into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        str string bytes byte_buf unit_struct tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Deserializer of one element.
+struct ElementDeserializer<'de> {
+    document: &'de Document,
+    id: NodeId,
+}
+
+impl<'de> ElementDeserializer<'de> {
+    fn text(&self) -> TextDeserializer<'de> {
+        TextDeserializer(Cow::Owned(self.document.text(self.id)))
+    }
+
+    fn is_simple(&self) -> bool {
+        self.document
+            .element(self.id)
+            .is_some_and(|el| el.attrs.is_empty())
+            && self.document.child_elements(self.id).next().is_none()
+    }
+}
+
+macro_rules! deserialize_text {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                self.text().$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ElementDeserializer<'de> {
+    type Error = DeError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if self.is_simple() {
+            self.text().deserialize_any(visitor)
+        } else {
+            self.deserialize_map(visitor)
+        }
+    }
+
+    deserialize_text! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_string deserialize_bytes deserialize_byte_buf deserialize_identifier
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// The child elements, in document order.
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let document = self.document;
+
+        visitor.visit_seq(Items(
+            document
+                .child_elements(self.id)
+                .map(|id| ElementDeserializer { document, id }),
+        ))
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(ElementMap::new(self.document, self.id))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    /// Unit variants from the text content, other variants from the first child element.
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.document.child_elements(self.id).next() {
+            Some(id) => visitor.visit_enum(ElementEnum {
+                document: self.document,
+                id,
+            }),
+            None => self.text().deserialize_enum(name, variants, visitor),
+        }
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+}
+
+/// Deserializer of all child elements sharing one name.
+struct GroupDeserializer<'de> {
+    document: &'de Document,
+    ids: Vec<NodeId>,
+}
+
+impl<'de> GroupDeserializer<'de> {
+    fn first(&self) -> ElementDeserializer<'de> {
+        ElementDeserializer {
+            document: self.document,
+            id: self.ids[0],
+        }
+    }
+}
+
+macro_rules! deserialize_first {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                self.first().$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for GroupDeserializer<'de> {
+    type Error = DeError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if self.ids.len() > 1 {
+            self.deserialize_seq(visitor)
+        } else {
+            self.first().deserialize_any(visitor)
+        }
+    }
+
+    deserialize_first! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_string deserialize_bytes deserialize_byte_buf deserialize_identifier
+        deserialize_unit deserialize_map deserialize_ignored_any
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.first().deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let document = self.document;
+
+        visitor.visit_seq(Items(
+            self.ids
+                .into_iter()
+                .map(|id| ElementDeserializer { document, id }),
+        ))
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.first().deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.first().deserialize_enum(name, variants, visitor)
+    }
+}
+
+/// A [`SeqAccess`] over a list of deserializers.
+struct Items<T>(T);
+
+impl<'de, T, D> SeqAccess<'de> for Items<T>
+where
+    T: Iterator<Item = D>,
+    D: de::Deserializer<'de, Error = DeError>,
+{
+    type Error = DeError;
+
+    fn next_element_seed<S>(&mut self, seed: S) -> Result<Option<S::Value>, Self::Error>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        self.0.next().map(|item| seed.deserialize(item)).transpose()
+    }
+}
+
+enum Value<'de> {
+    Attr(&'de str),
+    Text(String),
+    Elements(Vec<NodeId>),
+}
+
+/// A [`MapAccess`] over the attributes, child element groups and text of one element.
+struct ElementMap<'de> {
+    document: &'de Document,
+    entries: std::vec::IntoIter<(Cow<'de, str>, Value<'de>)>,
+    value: Option<Value<'de>>,
+}
+
+impl<'de> ElementMap<'de> {
+    fn new(document: &'de Document, id: NodeId) -> Self {
+        let mut entries: Vec<(Cow<'de, str>, Value<'de>)> = vec![];
+
+        if let Some(el) = document.element(id) {
+            for attr in &el.attrs {
+                entries.push((
+                    Cow::Owned(format!("@{}", attr.name)),
+                    Value::Attr(&attr.value),
+                ));
+            }
+        }
+
+        let mut text = String::new();
+
+        for child in document.children(id) {
+            match document.node(*child) {
+                Node::Element(el) => {
+                    let group = entries.iter_mut().find_map(|(key, value)| match value {
+                        Value::Elements(ids) if key == &el.name => Some(ids),
+                        _ => None,
+                    });
+
+                    match group {
+                        Some(ids) => ids.push(*child),
+                        None => {
+                            entries.push((Cow::Borrowed(&el.name), Value::Elements(vec![*child])))
+                        }
+                    }
+                }
+                Node::Text(content) | Node::CData(content) => text.push_str(content),
+                _ => {}
+            }
+        }
+
+        if !text.trim().is_empty() {
+            entries.push((Cow::Borrowed("$text"), Value::Text(text)));
+        }
+
+        Self {
+            document,
+            entries: entries.into_iter(),
+            value: None,
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for ElementMap<'de> {
+    type Error = DeError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let Some((key, value)) = self.entries.next() else {
+            return Ok(None);
+        };
+
+        self.value = Some(value);
+
+        seed.deserialize(TextDeserializer(key)).map(Some)
+    }
+
+    fn next_value_seed<S>(&mut self, seed: S) -> Result<S::Value, Self::Error>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            Some(Value::Attr(value)) => seed.deserialize(TextDeserializer(Cow::Borrowed(value))),
+            Some(Value::Text(value)) => seed.deserialize(TextDeserializer(Cow::Owned(value))),
+            Some(Value::Elements(ids)) => seed.deserialize(GroupDeserializer {
+                document: self.document,
+                ids,
+            }),
+            None => Err(de::Error::custom("value requested before key")),
+        }
+    }
+}
+
+/// An [`EnumAccess`] selecting the variant by element name.
+struct ElementEnum<'de> {
+    document: &'de Document,
+    id: NodeId,
+}
+
+impl<'de> EnumAccess<'de> for ElementEnum<'de> {
+    type Error = DeError;
+    type Variant = ElementDeserializer<'de>;
+
+    fn variant_seed<S>(self, seed: S) -> Result<(S::Value, Self::Variant), Self::Error>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        let name = self
+            .document
+            .element(self.id)
+            .map(|el| el.name.as_str())
+            .unwrap_or_default();
+
+        let variant = seed.deserialize(TextDeserializer(Cow::Borrowed(name)))?;
+
+        Ok((
+            variant,
+            ElementDeserializer {
+                document: self.document,
+                id: self.id,
+            },
+        ))
+    }
+}
+
+impl<'de> VariantAccess<'de> for ElementDeserializer<'de> {
+    type Error = DeError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<S>(self, seed: S) -> Result<S::Value, Self::Error>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::{DeError, from_str};
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Unit {
+        Px,
+        Mm,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Rect {
+        #[serde(rename = "@width")]
+        width: f32,
+        #[serde(rename = "@unit")]
+        unit: Unit,
+        #[serde(rename = "$text")]
+        label: Option<String>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Shape {
+        #[serde(rename = "circle")]
+        Circle {
+            #[serde(rename = "@r")]
+            r: u32,
+        },
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Svg {
+        #[serde(rename = "@viewBox")]
+        view_box: Vec<i32>,
+        title: String,
+        #[serde(rename = "rect")]
+        rects: Vec<Rect>,
+        desc: Option<String>,
+        shape: Shape,
+        visible: bool,
+    }
+
+    #[test]
+    fn test_from_str() {
+        let svg: Svg = from_str(
+            r#"<?xml version="1.0"?>
+            <svg viewBox="0 0 10 20">
+                <title>hello &amp; bye</title>
+                <rect width="1.5" unit="px">first</rect>
+                <rect width="2" unit="mm"/>
+                <shape><circle r="3"/></shape>
+                <visible>true</visible>
+            </svg>"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            svg,
+            Svg {
+                view_box: vec![0, 0, 10, 20],
+                title: "hello & bye".to_string(),
+                rects: vec![
+                    Rect {
+                        width: 1.5,
+                        unit: Unit::Px,
+                        label: Some("first".to_string())
+                    },
+                    Rect {
+                        width: 2.0,
+                        unit: Unit::Mm,
+                        label: None
+                    }
+                ],
+                desc: None,
+                shape: Shape::Circle { r: 3 },
+                visible: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct A {
+            #[serde(rename = "@n")]
+            n: u8,
+        }
+
+        assert_eq!(
+            from_str::<A>(r#"<?xml version="1.0"?><a n="300"/>"#).unwrap_err(),
+            DeError::Parse {
+                ty: "u8",
+                value: "300".to_string()
+            }
+        );
+
+        assert!(matches!(
+            from_str::<A>(r#"<?xml version="1.0"?><a/>"#),
+            Err(DeError::Custom(_))
+        ));
+    }
+}
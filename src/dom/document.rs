@@ -0,0 +1,276 @@
+use crate::types::{Prolog, TextCollector, Whitespace, XmlVersion};
+
+use super::{Element, Node, NodeId};
+
+#[derive(Debug, PartialEq, Clone)]
+struct Slot {
+    node: Node,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+/// An arena-allocated xml document tree.
+///
+/// Nodes are addressed by [`NodeId`]. Detached nodes stay allocated until the document is dropped.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Document {
+    slots: Vec<Slot>,
+    /// version of the xml declaration.
+    pub version: XmlVersion,
+    /// encoding of the xml declaration.
+    pub encoding: Option<String>,
+    /// standalone flag of the xml declaration.
+    pub standalone: Option<bool>,
+    /// raw content of the doctype declaration.
+    pub doctype: Option<String>,
+}
+
+impl Default for Document {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Document {
+    /// Create a document containing only the document node.
+    pub fn new() -> Self {
+        Self {
+            slots: vec![Slot {
+                node: Node::Document,
+                parent: None,
+                children: vec![],
+            }],
+            version: XmlVersion::Ver10,
+            encoding: None,
+            standalone: None,
+            doctype: None,
+        }
+    }
+
+    /// Returns the xml declaration and doctype of this document.
+    pub fn prolog(&self) -> Prolog {
+        Prolog {
+            version: self.version,
+            encoding: self.encoding.clone(),
+            standalone: self.standalone,
+            doctype: self.doctype.clone(),
+            namespaces: vec![],
+        }
+    }
+
+    /// Replace the xml declaration and doctype of this document.
+    ///
+    /// The namespaces of `prolog` are declared on the root element, if any, unless it declares
+    /// the prefix already.
+    pub fn set_prolog(&mut self, prolog: Prolog) {
+        self.version = prolog.version;
+        self.encoding = prolog.encoding;
+        self.standalone = prolog.standalone;
+        self.doctype = prolog.doctype;
+
+        if let Some(root) = self.root().and_then(|root| self.element_mut(root)) {
+            for (prefix, uri) in prolog.namespaces {
+                let name = match prefix {
+                    Some(prefix) => format!("xmlns:{}", prefix),
+                    None => "xmlns".to_string(),
+                };
+
+                if root.get_attr(&name).is_none() {
+                    root.set_attr(name, uri);
+                }
+            }
+        }
+    }
+
+    /// Returns the document node.
+    pub fn document_node(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    /// Returns the root element.
+    pub fn root(&self) -> Option<NodeId> {
+        self.child_elements(self.document_node()).next()
+    }
+
+    /// Returns the number of allocated nodes, including detached ones.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns true if the document has no nodes besides the document node.
+    pub fn is_empty(&self) -> bool {
+        self.slots.len() == 1
+    }
+
+    /// Returns the node `id`.
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.slots[id.0].node
+    }
+
+    /// Returns the mutable node `id`.
+    pub fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        &mut self.slots[id.0].node
+    }
+
+    /// Returns the element data of node `id`, if it is an element.
+    pub fn element(&self, id: NodeId) -> Option<&Element> {
+        self.node(id).as_element()
+    }
+
+    /// Returns the mutable element data of node `id`, if it is an element.
+    pub fn element_mut(&mut self, id: NodeId) -> Option<&mut Element> {
+        self.node_mut(id).as_element_mut()
+    }
+
+    /// Returns the parent of node `id`.
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.slots[id.0].parent
+    }
+
+    /// Returns the children of node `id` in document order.
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.slots[id.0].children
+    }
+
+    /// Create an iterator over the element children of node `id`.
+    pub fn child_elements(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.children(id)
+            .iter()
+            .copied()
+            .filter(|child| self.element(*child).is_some())
+    }
+
+    /// Create a pre-order iterator over node `id` and its descendants.
+    pub fn descendants(&self, id: NodeId) -> Descendants<'_> {
+        Descendants {
+            document: self,
+            stack: vec![id],
+        }
+    }
+
+    /// Returns the concatenated text and cdata content of node `id` and its descendants.
+    pub fn text(&self, id: NodeId) -> String {
+        self.descendants(id)
+            .filter_map(|id| self.node(id).as_text())
+            .collect()
+    }
+
+    /// Returns the text content of node `id` like [`text`](Self::text), applying the whitespace `policy`.
+    ///
+    /// For [`Whitespace::XmlSpace`] the `xml:space` attributes of `id` and its ancestors decide
+    /// where whitespace is preserved.
+    pub fn text_with(&self, id: NodeId, policy: Whitespace) -> String {
+        let xml_space = std::iter::successors(Some(id), |id| self.parent(*id))
+            .find_map(|id| self.element(id)?.get_attr("xml:space"));
+
+        let mut collector = TextCollector::default();
+
+        self.collect_text(
+            id,
+            policy,
+            policy.preserves(xml_space.map(str::as_bytes)),
+            &mut collector,
+        );
+
+        collector.finish()
+    }
+
+    fn collect_text(
+        &self,
+        id: NodeId,
+        policy: Whitespace,
+        preserve: bool,
+        collector: &mut TextCollector,
+    ) {
+        match self.node(id) {
+            Node::Text(text) | Node::CData(text) => collector.push(text, preserve),
+            node => {
+                let preserve = match node.as_element().and_then(|el| el.get_attr("xml:space")) {
+                    Some(xml_space) => policy.preserves(Some(xml_space.as_bytes())),
+                    None => preserve,
+                };
+
+                for child in self.children(id) {
+                    self.collect_text(*child, policy, preserve, collector);
+                }
+            }
+        }
+    }
+
+    /// Returns the namespace bound to `prefix` by the `xmlns` attributes of element `id` or its ancestors.
+    pub fn lookup_namespace(&self, id: NodeId, prefix: Option<&str>) -> Option<&str> {
+        let mut current = Some(id);
+
+        while let Some(id) = current {
+            if let Node::Element(el) = self.node(id) {
+                let value = match prefix {
+                    Some(prefix) => el
+                        .attrs
+                        .iter()
+                        .find(|attr| attr.name.strip_prefix("xmlns:") == Some(prefix))
+                        .map(|attr| attr.value.as_ref()),
+                    None => el.get_attr("xmlns"),
+                };
+
+                if let Some(value) = value {
+                    return Some(value).filter(|value| !value.is_empty());
+                }
+            }
+
+            current = self.parent(id);
+        }
+
+        None
+    }
+
+    /// Append `node` as the last child of `parent`.
+    pub fn append(&mut self, parent: NodeId, node: Node) -> NodeId {
+        let index = self.children(parent).len();
+        self.insert(parent, index, node)
+    }
+
+    /// Insert `node` as the `index`th child of `parent`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the child count of `parent`.
+    pub fn insert(&mut self, parent: NodeId, index: usize, node: Node) -> NodeId {
+        let id = NodeId(self.slots.len());
+
+        self.slots.push(Slot {
+            node,
+            parent: Some(parent),
+            children: vec![],
+        });
+
+        self.slots[parent.0].children.insert(index, id);
+
+        id
+    }
+
+    /// Remove node `id` and its subtree from its parent.
+    pub fn detach(&mut self, id: NodeId) {
+        if let Some(parent) = self.slots[id.0].parent.take() {
+            self.slots[parent.0].children.retain(|child| *child != id);
+        }
+    }
+}
+
+/// Iterator created by [`Document::descendants`].
+pub struct Descendants<'a> {
+    document: &'a Document,
+    stack: Vec<NodeId>,
+}
+
+impl Iterator for Descendants<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.stack.pop()?;
+
+        self.stack
+            .extend(self.document.children(id).iter().rev().copied());
+
+        Some(id)
+    }
+}
@@ -0,0 +1,259 @@
+//! A DOM-style tree built on the event [`reader`](crate::reader).
+
+mod node;
+pub use node::*;
+
+mod document;
+pub use document::*;
+
+mod parse;
+pub use parse::*;
+
+mod validate;
+
+#[cfg(feature = "xpath")]
+mod query;
+#[cfg(feature = "xpath")]
+pub use query::*;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        intern::Interner,
+        types::{Prolog, Whitespace},
+    };
+
+    use super::{Document, DomError, Element, Node};
+
+    const SVG: &str = r#"<?xml version="1.0" encoding="UTF-8"?><!--c--><svg width="10"><g fill="none"><rect fill="none"/>a &amp; b</g><![CDATA[<c>]]></svg>"#;
+
+    #[test]
+    fn test_parse() {
+        let document = Document::parse(SVG).unwrap();
+
+        assert_eq!(document.encoding.as_deref(), Some("UTF-8"));
+        assert_eq!(document.children(document.document_node()).len(), 2);
+
+        let svg = document.root().unwrap();
+
+        assert_eq!(document.element(svg).unwrap().get_attr("width"), Some("10"));
+        assert_eq!(document.text(svg), "a & b<c>");
+
+        let names = document
+            .descendants(svg)
+            .filter_map(|id| document.element(id))
+            .map(|el| el.name.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, ["svg", "g", "rect"]);
+    }
+
+    #[test]
+    fn test_interner() {
+        let mut interner = Interner::new();
+
+        let document = Document::parse_with_interner(SVG, &mut interner).unwrap();
+
+        let fills = document
+            .descendants(document.document_node())
+            .filter_map(|id| document.element(id))
+            .flat_map(|el| el.attrs.iter().filter(|attr| attr.name == "fill"))
+            .collect::<Vec<_>>();
+
+        assert!(Arc::ptr_eq(&fills[0].value, &fills[1].value));
+        assert_eq!(interner.len(), 2);
+    }
+
+    #[test]
+    fn test_mutation() {
+        let mut document = Document::parse(SVG).unwrap();
+
+        let svg = document.root().unwrap();
+        let g = document.child_elements(svg).next().unwrap();
+
+        document.detach(g);
+
+        let title = document.insert(svg, 0, Node::Element(Element::new("title")));
+        document.append(title, Node::Text("hello".to_string()));
+        document.element_mut(svg).unwrap().set_attr("height", "5");
+
+        assert_eq!(document.text(svg), "hello<c>");
+        assert_eq!(document.parent(g), None);
+        assert_eq!(
+            document.element_mut(svg).unwrap().remove_attr("width"),
+            Some(Arc::from("10"))
+        );
+        assert_eq!(document.element(svg).unwrap().attrs.len(), 1);
+    }
+
+    #[test]
+    fn test_checked_mutation() {
+        let mut document =
+            Document::parse(r#"<?xml version="1.0"?><svg xmlns:xlink="urn:xlink"/>"#).unwrap();
+
+        let svg = document.root().unwrap();
+
+        let mut el = Element::new("use");
+        el.set_attr("xlink:href", "#a");
+        el.set_attr("xml:lang", "en");
+
+        let id = document.try_append(svg, Node::Element(el)).unwrap();
+
+        assert_eq!(
+            document.try_append(svg, Node::Element(Element::new("1a"))),
+            Err(DomError::InvalidName("1a".to_string()))
+        );
+
+        assert_eq!(
+            document.try_append(svg, Node::Element(Element::new("svg:g"))),
+            Err(DomError::UnboundPrefix("svg".to_string()))
+        );
+
+        let mut el = Element::new("a:g");
+        el.set_attr("xmlns:a", "urn:xlink");
+        el.set_attr("a:href", "#b");
+        el.set_attr("xlink:href", "#c");
+
+        assert_eq!(
+            document.try_append(svg, Node::Element(el)),
+            Err(DomError::DuplicateAttr("xlink:href".to_string()))
+        );
+
+        assert_eq!(
+            document.try_set_attr(id, "xlink:href", "#d"),
+            Ok(Some(Arc::from("#a")))
+        );
+        assert_eq!(
+            document.try_set_attr(id, "ev:event", "click"),
+            Err(DomError::UnboundPrefix("ev".to_string()))
+        );
+        assert_eq!(document.element(id).unwrap().attrs.len(), 2);
+        assert_eq!(document.children(svg).len(), 1);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            Document::parse(r#"<?xml version="1.0"?><a><b></a>"#),
+            Err(DomError::Mismatch {
+                expected: "b".to_string(),
+                found: "a".to_string()
+            })
+        );
+
+        assert!(matches!(
+            Document::parse(r#"<?xml version="1.0"?><a>&x;</a>"#),
+            Err(DomError::Read(diagnostic)) if diagnostic.offset == Some(24)
+        ));
+    }
+
+    #[test]
+    fn test_parse_fragment() {
+        let prolog = Prolog {
+            encoding: Some("UTF-8".to_string()),
+            doctype: Some("svg".to_string()),
+            ..Default::default()
+        };
+
+        let document = Document::parse_fragment("<g><rect/></g>", prolog.clone()).unwrap();
+
+        assert_eq!(document.prolog(), prolog);
+        assert_eq!(
+            document.element(document.root().unwrap()).unwrap().name,
+            "g"
+        );
+
+        let document = Document::parse_fragment("<svg/>", Prolog::svg11()).unwrap();
+        let svg = document.element(document.root().unwrap()).unwrap();
+
+        assert_eq!(svg.get_attr("xmlns"), Some("http://www.w3.org/2000/svg"));
+        assert_eq!(
+            svg.get_attr("xmlns:xlink"),
+            Some("http://www.w3.org/1999/xlink")
+        );
+    }
+
+    #[test]
+    fn test_text_with() {
+        let document = Document::parse(
+            r#"<?xml version="1.0"?><a xml:space="preserve"><b xml:space="default"> x  <c> y </c></b><d> z  w </d></a>"#,
+        )
+        .unwrap();
+
+        let root = document.root().unwrap();
+        let b = document.children(root)[0];
+        let d = document.children(root)[1];
+
+        assert_eq!(
+            document.text_with(root, Whitespace::Preserve),
+            " x   y  z  w "
+        );
+        assert_eq!(document.text_with(root, Whitespace::Collapse), "x y z w");
+        assert_eq!(document.text_with(root, Whitespace::XmlSpace), "x y  z  w ");
+        assert_eq!(document.text_with(b, Whitespace::XmlSpace), "x y");
+        assert_eq!(document.text_with(d, Whitespace::XmlSpace), " z  w ");
+    }
+
+    #[cfg(feature = "xpath")]
+    #[test]
+    fn test_select() {
+        use super::{Query, QueryError};
+
+        let document = Document::parse(
+            r#"<?xml version="1.0"?><root xmlns:svg="http://www.w3.org/2000/svg"><svg:svg><svg:rect fill="red" id="a"/><svg:g><svg:rect fill="blue" id="b"/><svg:rect fill="red" id="c"/></svg:g></svg:svg><s:rect xmlns:s="http://www.w3.org/2000/svg" fill="red" id="d"/><item><title>x</title></item><item><title>y</title>text</item></root>"#,
+        )
+        .unwrap();
+
+        let ids = |expr: &str| {
+            document
+                .select(expr)
+                .unwrap()
+                .into_iter()
+                .map(|id| document.element(id).unwrap().get_attr("id").unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids("//svg:rect[@fill='red']"), ["a", "c"]);
+        assert_eq!(ids("//svg:rect[@fill!='red']"), ["b"]);
+        assert_eq!(ids("/root/svg:svg/svg:g/*[2]"), ["c"]);
+        assert_eq!(ids("//svg:g/svg:rect[1]"), ["b"]);
+        assert_eq!(ids("//*[@id='a' or @id='d']"), ["a", "d"]);
+        assert_eq!(ids("//*[@fill='red' and @id='c']"), ["c"]);
+
+        let query = Query::parse("//svg:rect[@fill='red']")
+            .unwrap()
+            .namespace("svg", "http://www.w3.org/2000/svg");
+
+        let found = query
+            .select(&document, document.document_node())
+            .into_iter()
+            .map(|id| document.element(id).unwrap().get_attr("id").unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(found, ["a", "c", "d"]);
+
+        let items = document.select("//item[title='y']").unwrap();
+        assert_eq!(items.len(), 1);
+
+        let texts = document.select("//item/text()").unwrap();
+        assert_eq!(texts.len(), 1);
+        assert_eq!(document.node(texts[0]).as_text(), Some("text"));
+
+        assert_eq!(document.select("//title[text()='x']/..").unwrap().len(), 1);
+        assert_eq!(document.select("/").unwrap(), [document.document_node()]);
+
+        assert_eq!(
+            document.select("//a[@b='c'"),
+            Err(QueryError {
+                offset: 10,
+                message: "expect `]`"
+            })
+        );
+        assert_eq!(
+            document.select("//a[0]").unwrap_err().message,
+            "positions start at 1"
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+/// Index of one node in its [`Document`](super::Document).
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct NodeId(pub(super) usize);
+
+/// A node of the document tree.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Node {
+    /// The document node, parent of the root element and the prolog/epilog misc nodes.
+    Document,
+    Element(Element),
+    /// Character data, with references expanded.
+    Text(String),
+    CData(String),
+    Comment(String),
+    PI {
+        target: String,
+        data: String,
+    },
+}
+
+impl Node {
+    /// Returns the element data, if this is an element node.
+    pub fn as_element(&self) -> Option<&Element> {
+        match self {
+            Node::Element(element) => Some(element),
+            _ => None,
+        }
+    }
+
+    /// Returns the mutable element data, if this is an element node.
+    pub fn as_element_mut(&mut self) -> Option<&mut Element> {
+        match self {
+            Node::Element(element) => Some(element),
+            _ => None,
+        }
+    }
+
+    /// Returns the text of a text or cdata node.
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Node::Text(text) | Node::CData(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+/// An attribute of an [`Element`], the value is shared when parsed with an [`Interner`](crate::intern::Interner).
+#[derive(Debug, PartialEq, Clone)]
+pub struct Attribute {
+    pub name: String,
+    pub value: Arc<str>,
+}
+
+/// The data of an element node.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Element {
+    /// qualified name.
+    pub name: String,
+    /// attributes in document order.
+    pub attrs: Vec<Attribute>,
+}
+
+impl Element {
+    /// Create an element without attributes.
+    pub fn new<N>(name: N) -> Self
+    where
+        N: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            attrs: vec![],
+        }
+    }
+
+    /// Returns the value of the attribute `name`.
+    pub fn get_attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|attr| attr.name == name)
+            .map(|attr| attr.value.as_ref())
+    }
+
+    /// Set the attribute `name`, returns the previous value.
+    pub fn set_attr<N, V>(&mut self, name: N, value: V) -> Option<Arc<str>>
+    where
+        N: Into<String>,
+        V: Into<Arc<str>>,
+    {
+        let name = name.into();
+        let value = value.into();
+
+        match self.attrs.iter_mut().find(|attr| attr.name == name) {
+            Some(attr) => Some(std::mem::replace(&mut attr.value, value)),
+            None => {
+                self.attrs.push(Attribute { name, value });
+                None
+            }
+        }
+    }
+
+    /// Remove the attribute `name`, returns its value.
+    pub fn remove_attr(&mut self, name: &str) -> Option<Arc<str>> {
+        let index = self.attrs.iter().position(|attr| attr.name == name)?;
+
+        Some(self.attrs.remove(index).value)
+    }
+}
@@ -0,0 +1,185 @@
+use std::sync::Arc;
+
+use parserc::ControlFlow;
+
+use crate::{
+    intern::Interner,
+    reader::{Diagnostic, ReadError, ReadState, XmlNode, XmlReader},
+    types::Prolog,
+};
+
+use super::{Attribute, Document, Element, Node};
+
+/// Error returned by [`Document::parse`].
+#[derive(Debug, thiserror::Error, PartialEq, Clone)]
+pub enum DomError {
+    /// The reader failed.
+    #[error("{0}")]
+    Read(Diagnostic),
+    /// The document ended inside an element.
+    #[error("incomplete document")]
+    Incomplete,
+    /// An end tag doesn't close the open element.
+    #[error("expect `</{expected}>`, found `</{found}>`")]
+    Mismatch { expected: String, found: String },
+    /// Checked mutation only: the element or attribute name is not a legal qualified name.
+    #[error("invalid name `{0}`")]
+    InvalidName(String),
+    /// Checked mutation only: the prefix of the name has no namespace declaration in scope.
+    #[error("unbound namespace prefix `{0}`")]
+    UnboundPrefix(String),
+    /// Checked mutation only: the element already has an attribute with the same name, or the
+    /// same local name and namespace.
+    #[error("duplicate attribute `{0}`")]
+    DuplicateAttr(String),
+}
+
+impl DomError {
+    fn read(source: &str, err: ControlFlow<ReadError<&[u8]>>) -> Self {
+        match err {
+            // keep the tree level errors for failures of the reader's tag balance check.
+            ControlFlow::Fatal(ReadError::Mismatch {
+                expected, found, ..
+            }) => DomError::Mismatch {
+                expected: to_string(expected),
+                found: to_string(found),
+            },
+            ControlFlow::Fatal(ReadError::Unclosed(_)) => DomError::Incomplete,
+            ControlFlow::Fatal(err) | ControlFlow::Recovable(err) => {
+                DomError::Read(Diagnostic::new(source.as_bytes(), &err))
+            }
+            ControlFlow::Incomplete(_) => DomError::Incomplete,
+        }
+    }
+}
+
+fn to_string(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+impl Document {
+    /// Parse `source` into a tree.
+    pub fn parse(source: &str) -> Result<Self, DomError> {
+        Self::parse_reader(source, XmlReader::from(source.as_bytes()), None)
+    }
+
+    /// Parse `source` into a tree, sharing repeated attribute values through `interner`.
+    pub fn parse_with_interner(source: &str, interner: &mut Interner) -> Result<Self, DomError> {
+        Self::parse_reader(source, XmlReader::from(source.as_bytes()), Some(interner))
+    }
+
+    /// Parse a fragment without xml declaration, e.g. `<g><rect/></g>`, completing it with `prolog`.
+    pub fn parse_fragment(source: &str, prolog: Prolog) -> Result<Self, DomError> {
+        let mut document = Self::parse_reader(
+            source,
+            XmlReader::new(ReadState::RootElement, source.as_bytes()),
+            None,
+        )?;
+
+        document.set_prolog(prolog);
+
+        Ok(document)
+    }
+
+    /// Build a tree from a configured `reader` over `source`.
+    pub fn parse_reader<'a>(
+        source: &'a str,
+        mut reader: XmlReader<&'a [u8]>,
+        mut interner: Option<&mut Interner>,
+    ) -> Result<Self, DomError> {
+        let mut document = Document::new();
+        let mut current = document.document_node();
+
+        loop {
+            let node = match reader.read_next() {
+                Ok(Some(node)) => node,
+                Ok(None) => break,
+                Err(err) => return Err(DomError::read(source, err)),
+            };
+
+            match node {
+                XmlNode::XmlDecl(decl) => {
+                    document.version = decl.version;
+                    document.encoding = decl.encoding.map(to_string);
+                    document.standalone = decl.standalone;
+                }
+                XmlNode::DocType(doctype) => document.doctype = Some(to_string(doctype.0)),
+                XmlNode::PI(pi) => {
+                    document.append(
+                        current,
+                        Node::PI {
+                            target: to_string(pi.name),
+                            data: to_string(pi.unparsed),
+                        },
+                    );
+                }
+                XmlNode::Comment(comment) => {
+                    document.append(current, Node::Comment(to_string(comment.0)));
+                }
+                XmlNode::Start(start) => {
+                    let mut element = Element::new(to_string(start.name));
+
+                    for attr in start.attrs() {
+                        let attr = attr.map_err(|err| DomError::read(source, err))?;
+
+                        let value = reader
+                            .attr_value(&attr)
+                            .map_err(|err| DomError::read(source, err))?;
+
+                        let value = match interner.as_mut() {
+                            Some(interner) => interner.intern(&value),
+                            None => Arc::from(value.as_ref()),
+                        };
+
+                        element.attrs.push(Attribute {
+                            name: to_string(attr.name),
+                            value,
+                        });
+                    }
+
+                    let id = document.append(current, Node::Element(element));
+
+                    if !start.is_empty {
+                        current = id;
+                    }
+                }
+                XmlNode::End(end) => {
+                    let expected = document.element(current).map(|el| el.name.as_str());
+
+                    if expected.map(str::as_bytes) != Some(end.name) {
+                        return Err(DomError::Mismatch {
+                            expected: expected.unwrap_or_default().to_string(),
+                            found: to_string(end.name),
+                        });
+                    }
+
+                    current = document.parent(current).unwrap_or(current);
+                }
+                XmlNode::CharData(chardata) => {
+                    let text = reader
+                        .text(&chardata)
+                        .map_err(|err| DomError::read(source, err))?;
+
+                    document.append(current, Node::Text(text.into_owned()));
+                }
+                XmlNode::CData(cdata) => {
+                    let content = reader
+                        .cdata(&cdata)
+                        .map_err(|err| DomError::read(source, err))?;
+
+                    document.append(current, Node::CData(content.into_owned()));
+                }
+                XmlNode::Text(text) => {
+                    document.append(current, Node::Text(text));
+                }
+                XmlNode::S(_) | XmlNode::Unknown(_) => {}
+            }
+        }
+
+        if current != document.document_node() {
+            return Err(DomError::Incomplete);
+        }
+
+        Ok(document)
+    }
+}
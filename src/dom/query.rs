@@ -0,0 +1,503 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::chars::is_name_char;
+
+use super::{Document, NodeId};
+
+/// Error returned by [`Query::parse`].
+#[derive(Debug, thiserror::Error, PartialEq, Eq, Clone)]
+#[error("{message} at offset {offset}")]
+pub struct QueryError {
+    /// byte offset in the expression.
+    pub offset: usize,
+    pub message: &'static str,
+}
+
+/// A compiled path expression, a practical subset of [`XPath 1.0`](https://www.w3.org/TR/xpath-10/).
+///
+/// Supported are absolute and relative paths over the child (`/`) and descendant (`//`) axes,
+/// name tests (`rect`, `svg:rect`, `*`, `svg:*`), `text()`, `node()`, `.` and `..`, and
+/// predicates combining with `and`/`or`:
+///
+/// - positions, `[1]`,
+/// - attributes, `[@fill]`, `[@fill='red']` and `[@fill!='red']`,
+/// - text content, `[text()='hello']`,
+/// - child elements, `[title]` and `[title='hello']`.
+///
+/// Prefixed name tests match by namespace if the prefix is bound with [`namespace`](Self::namespace),
+/// otherwise the qualified name is compared literally. Attribute names are always compared literally.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Query {
+    absolute: bool,
+    steps: Vec<Step>,
+    namespaces: Vec<(String, String)>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct Step {
+    /// true for steps following `//`.
+    descendant: bool,
+    test: NodeTest,
+    predicates: Vec<Predicate>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum NodeTest {
+    /// `prefix:local`, `local` is `*` for wildcards.
+    Name(Option<String>, String),
+    Text,
+    Node,
+    SelfNode,
+    Parent,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Op {
+    Eq,
+    Ne,
+}
+
+impl Op {
+    fn test(self, left: &str, right: &str) -> bool {
+        match self {
+            Op::Eq => left == right,
+            Op::Ne => left != right,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Predicate {
+    Position(usize),
+    Attr(String, Option<(Op, String)>),
+    Text(Op, String),
+    Child(NodeTest, Option<(Op, String)>),
+    And(Box<Predicate>, Box<Predicate>),
+    Or(Box<Predicate>, Box<Predicate>),
+}
+
+struct Parser<'a> {
+    expr: &'a str,
+    offset: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.expr[self.offset..]
+    }
+
+    fn error<T>(&self, message: &'static str) -> Result<T, QueryError> {
+        Err(QueryError {
+            offset: self.offset,
+            message,
+        })
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = self.rest();
+        self.offset += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_ws();
+
+        if self.rest().starts_with(token) {
+            self.offset += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns true if the keyword `word` follows, not followed by another name char.
+    fn eat_word(&mut self, word: &str) -> bool {
+        self.skip_ws();
+
+        let rest = self.rest();
+
+        if rest.starts_with(word)
+            && !rest[word.len()..]
+                .chars()
+                .next()
+                .is_some_and(|c| is_name_char(c) && c != ':')
+        {
+            self.offset += word.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn name(&mut self) -> Result<&'a str, QueryError> {
+        self.skip_ws();
+
+        let rest = self.rest();
+        let len = rest
+            .char_indices()
+            .find(|(_, c)| !is_name_char(*c) || *c == ':')
+            .map_or(rest.len(), |(at, _)| at);
+
+        if len == 0 {
+            return self.error("expect name");
+        }
+
+        self.offset += len;
+
+        Ok(&rest[..len])
+    }
+
+    /// Parse `*`, `local`, `prefix:local` or `prefix:*`.
+    fn name_test(&mut self) -> Result<NodeTest, QueryError> {
+        if self.eat("*") {
+            return Ok(NodeTest::Name(None, "*".to_string()));
+        }
+
+        let name = self.name()?;
+
+        if self.rest().starts_with(':') {
+            self.offset += 1;
+
+            let local = if self.rest().starts_with('*') {
+                self.offset += 1;
+                "*"
+            } else {
+                self.name()?
+            };
+
+            return Ok(NodeTest::Name(Some(name.to_string()), local.to_string()));
+        }
+
+        Ok(NodeTest::Name(None, name.to_string()))
+    }
+
+    /// Parse a qualified attribute name.
+    fn qname(&mut self) -> Result<String, QueryError> {
+        let mut name = self.name()?.to_string();
+
+        if self.rest().starts_with(':') {
+            self.offset += 1;
+            name.push(':');
+            name.push_str(self.name()?);
+        }
+
+        Ok(name)
+    }
+
+    fn literal(&mut self) -> Result<String, QueryError> {
+        self.skip_ws();
+
+        let rest = self.rest();
+
+        let Some(quote) = rest.chars().next().filter(|c| matches!(c, '"' | '\'')) else {
+            return self.error("expect string literal");
+        };
+
+        let Some(len) = rest[1..].find(quote) else {
+            return self.error("unterminated string literal");
+        };
+
+        self.offset += len + 2;
+
+        Ok(rest[1..len + 1].to_string())
+    }
+
+    fn comparison(&mut self) -> Result<Option<(Op, String)>, QueryError> {
+        let op = if self.eat("!=") {
+            Op::Ne
+        } else if self.eat("=") {
+            Op::Eq
+        } else {
+            return Ok(None);
+        };
+
+        Ok(Some((op, self.literal()?)))
+    }
+
+    fn path(&mut self) -> Result<Query, QueryError> {
+        let mut steps = vec![];
+
+        let absolute = self.rest().starts_with('/');
+        let mut descendant = false;
+
+        if absolute {
+            descendant = self.eat("//") || !self.eat("/");
+
+            self.skip_ws();
+
+            // `/` alone selects the document node.
+            if !descendant && self.rest().is_empty() {
+                return Ok(Query {
+                    absolute,
+                    steps,
+                    namespaces: vec![],
+                });
+            }
+        }
+
+        loop {
+            steps.push(self.step(descendant)?);
+
+            if self.eat("//") {
+                descendant = true;
+            } else if self.eat("/") {
+                descendant = false;
+            } else {
+                break;
+            }
+        }
+
+        self.skip_ws();
+
+        if !self.rest().is_empty() {
+            return self.error("unexpected character");
+        }
+
+        Ok(Query {
+            absolute,
+            steps,
+            namespaces: vec![],
+        })
+    }
+
+    fn step(&mut self, descendant: bool) -> Result<Step, QueryError> {
+        let test = if self.eat("..") {
+            NodeTest::Parent
+        } else if self.eat(".") {
+            NodeTest::SelfNode
+        } else if self.eat("text()") {
+            NodeTest::Text
+        } else if self.eat("node()") {
+            NodeTest::Node
+        } else {
+            self.name_test()?
+        };
+
+        let mut predicates = vec![];
+
+        while self.eat("[") {
+            predicates.push(self.or()?);
+
+            if !self.eat("]") {
+                return self.error("expect `]`");
+            }
+        }
+
+        Ok(Step {
+            descendant,
+            test,
+            predicates,
+        })
+    }
+
+    fn or(&mut self) -> Result<Predicate, QueryError> {
+        let mut predicate = self.and()?;
+
+        while self.eat_word("or") {
+            predicate = Predicate::Or(Box::new(predicate), Box::new(self.and()?));
+        }
+
+        Ok(predicate)
+    }
+
+    fn and(&mut self) -> Result<Predicate, QueryError> {
+        let mut predicate = self.condition()?;
+
+        while self.eat_word("and") {
+            predicate = Predicate::And(Box::new(predicate), Box::new(self.condition()?));
+        }
+
+        Ok(predicate)
+    }
+
+    fn condition(&mut self) -> Result<Predicate, QueryError> {
+        self.skip_ws();
+
+        let rest = self.rest();
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+
+        if digits > 0 {
+            let position = rest[..digits].parse().unwrap_or(usize::MAX);
+            self.offset += digits;
+
+            if position == 0 {
+                return self.error("positions start at 1");
+            }
+
+            return Ok(Predicate::Position(position));
+        }
+
+        if self.eat("@") {
+            let name = self.qname()?;
+            return Ok(Predicate::Attr(name, self.comparison()?));
+        }
+
+        if self.eat("text()") {
+            let Some((op, value)) = self.comparison()? else {
+                return self.error("expect `=` or `!=`");
+            };
+
+            return Ok(Predicate::Text(op, value));
+        }
+
+        let test = self.name_test()?;
+
+        Ok(Predicate::Child(test, self.comparison()?))
+    }
+}
+
+impl Query {
+    /// Compile the expression `expr`.
+    pub fn parse(expr: &str) -> Result<Self, QueryError> {
+        Parser { expr, offset: 0 }.path()
+    }
+
+    /// Bind `prefix` to the namespace `uri` for prefixed name tests.
+    pub fn namespace<P, U>(mut self, prefix: P, uri: U) -> Self
+    where
+        P: Into<String>,
+        U: Into<String>,
+    {
+        self.namespaces.push((prefix.into(), uri.into()));
+        self
+    }
+
+    /// Returns the nodes selected from `context` in document order.
+    ///
+    /// Absolute paths start at the document node whatever `context` is.
+    pub fn select(&self, document: &Document, context: NodeId) -> Vec<NodeId> {
+        let mut current = vec![if self.absolute {
+            document.document_node()
+        } else {
+            context
+        }];
+
+        for step in &self.steps {
+            let mut seen = HashSet::new();
+            let mut next = vec![];
+
+            for id in current {
+                let origins = if step.descendant {
+                    document.descendants(id).collect()
+                } else {
+                    vec![id]
+                };
+
+                for origin in origins {
+                    let mut candidates = match &step.test {
+                        NodeTest::SelfNode => vec![origin],
+                        NodeTest::Parent => document.parent(origin).into_iter().collect(),
+                        test => document
+                            .children(origin)
+                            .iter()
+                            .copied()
+                            .filter(|child| self.matches(document, test, *child))
+                            .collect::<Vec<_>>(),
+                    };
+
+                    for predicate in &step.predicates {
+                        candidates = candidates
+                            .into_iter()
+                            .enumerate()
+                            .filter(|(i, id)| self.eval(document, predicate, i + 1, *id))
+                            .map(|(_, id)| id)
+                            .collect();
+                    }
+
+                    next.extend(candidates.into_iter().filter(|id| seen.insert(*id)));
+                }
+            }
+
+            // nested origins are visited before their following siblings.
+            if next.len() > 1 {
+                let order = document
+                    .descendants(document.document_node())
+                    .enumerate()
+                    .map(|(i, id)| (id, i))
+                    .collect::<HashMap<_, _>>();
+
+                next.sort_by_key(|id| order.get(id).copied());
+            }
+
+            current = next;
+        }
+
+        current
+    }
+
+    fn matches(&self, document: &Document, test: &NodeTest, id: NodeId) -> bool {
+        let node = document.node(id);
+
+        match test {
+            NodeTest::Name(prefix, local) => {
+                let Some(el) = node.as_element() else {
+                    return false;
+                };
+
+                let (el_prefix, el_local) = match el.name.split_once(':') {
+                    Some((prefix, local)) => (Some(prefix), local),
+                    None => (None, el.name.as_str()),
+                };
+
+                if local != "*" && local != el_local {
+                    return false;
+                }
+
+                // unprefixed tests match unprefixed names, `*` matches any element.
+                let Some(prefix) = prefix else {
+                    return local == "*" || el_prefix.is_none();
+                };
+
+                match self.namespaces.iter().find(|(bound, _)| bound == prefix) {
+                    Some((_, uri)) => {
+                        document.lookup_namespace(id, el_prefix) == Some(uri.as_str())
+                    }
+                    None => el_prefix == Some(prefix.as_str()),
+                }
+            }
+            NodeTest::Text => node.as_text().is_some(),
+            NodeTest::Node => true,
+            NodeTest::SelfNode | NodeTest::Parent => false,
+        }
+    }
+
+    fn eval(
+        &self,
+        document: &Document,
+        predicate: &Predicate,
+        position: usize,
+        id: NodeId,
+    ) -> bool {
+        match predicate {
+            Predicate::Position(expected) => position == *expected,
+            Predicate::Attr(name, comparison) => {
+                let value = document.element(id).and_then(|el| el.get_attr(name));
+
+                match (value, comparison) {
+                    (Some(value), Some((op, expected))) => op.test(value, expected),
+                    (value, None) => value.is_some(),
+                    (None, Some(_)) => false,
+                }
+            }
+            Predicate::Text(op, expected) => op.test(&document.text(id), expected),
+            Predicate::Child(test, comparison) => document.children(id).iter().any(|child| {
+                self.matches(document, test, *child)
+                    && comparison
+                        .as_ref()
+                        .is_none_or(|(op, expected)| op.test(&document.text(*child), expected))
+            }),
+            Predicate::And(left, right) => {
+                self.eval(document, left, position, id) && self.eval(document, right, position, id)
+            }
+            Predicate::Or(left, right) => {
+                self.eval(document, left, position, id) || self.eval(document, right, position, id)
+            }
+        }
+    }
+}
+
+impl Document {
+    /// Select nodes with the path expression `expr`, see [`Query`].
+    pub fn select(&self, expr: &str) -> Result<Vec<NodeId>, QueryError> {
+        Ok(Query::parse(expr)?.select(self, self.document_node()))
+    }
+}
@@ -0,0 +1,137 @@
+use std::sync::Arc;
+
+use crate::chars::is_name;
+
+use super::{Document, DomError, Element, Node, NodeId};
+
+/// The namespace bound to the `xml` prefix.
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+impl Document {
+    /// Append `node` as the last child of `parent`, checking it first; see [`try_insert`](Self::try_insert).
+    pub fn try_append(&mut self, parent: NodeId, node: Node) -> Result<NodeId, DomError> {
+        let index = self.children(parent).len();
+        self.try_insert(parent, index, node)
+    }
+
+    /// Insert `node` as the `index`th child of `parent`, if it is well-formed in place.
+    ///
+    /// Element and attribute names must be legal qualified names, their prefixes must be bound
+    /// by the element itself or its new ancestors, and attributes must be unique, also by local
+    /// name and namespace. Nothing is inserted on error, so it points at the offending call rather
+    /// than at the serialization of the whole tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the child count of `parent`.
+    pub fn try_insert(
+        &mut self,
+        parent: NodeId,
+        index: usize,
+        node: Node,
+    ) -> Result<NodeId, DomError> {
+        if let Node::Element(el) = &node {
+            self.check_element(Some(parent), el)?;
+        }
+
+        Ok(self.insert(parent, index, node))
+    }
+
+    /// Set the attribute `name` of element `id`, if the element stays well-formed; see
+    /// [`try_insert`](Self::try_insert). Returns the previous value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not an element.
+    pub fn try_set_attr<N, V>(
+        &mut self,
+        id: NodeId,
+        name: N,
+        value: V,
+    ) -> Result<Option<Arc<str>>, DomError>
+    where
+        N: Into<String>,
+        V: Into<Arc<str>>,
+    {
+        let mut el = self.element(id).expect("element node").clone();
+        let previous = el.set_attr(name, value);
+
+        self.check_element(self.parent(id), &el)?;
+
+        *self.element_mut(id).unwrap() = el;
+
+        Ok(previous)
+    }
+
+    /// Check `el` as a child of `parent`.
+    fn check_element(&self, parent: Option<NodeId>, el: &Element) -> Result<(), DomError> {
+        check_qname(&el.name)?;
+
+        let lookup = |prefix: &str| -> Result<String, DomError> {
+            if prefix == "xml" {
+                return Ok(XML_NAMESPACE.to_string());
+            }
+
+            let declared = el
+                .attrs
+                .iter()
+                .find(|attr| attr.name.strip_prefix("xmlns:") == Some(prefix))
+                .map(|attr| attr.value.as_ref());
+
+            declared
+                .or_else(|| parent.and_then(|parent| self.lookup_namespace(parent, Some(prefix))))
+                .filter(|namespace| !namespace.is_empty())
+                .map(str::to_string)
+                .ok_or_else(|| DomError::UnboundPrefix(prefix.to_string()))
+        };
+
+        if let Some((prefix, _)) = el.name.split_once(':') {
+            lookup(prefix)?;
+        }
+
+        let mut expanded: Vec<(String, &str)> = vec![];
+
+        for (i, attr) in el.attrs.iter().enumerate() {
+            check_qname(&attr.name)?;
+
+            if el.attrs[..i].iter().any(|prev| prev.name == attr.name) {
+                return Err(DomError::DuplicateAttr(attr.name.clone()));
+            }
+
+            let Some((prefix, local)) = attr.name.split_once(':') else {
+                continue;
+            };
+
+            if prefix == "xmlns" {
+                continue;
+            }
+
+            let namespace = lookup(prefix)?;
+
+            if expanded
+                .iter()
+                .any(|(prev, prev_local)| *prev == namespace && *prev_local == local)
+            {
+                return Err(DomError::DuplicateAttr(attr.name.clone()));
+            }
+
+            expanded.push((namespace, local));
+        }
+
+        Ok(())
+    }
+}
+
+/// Check `name` matches the [`QName`](https://www.w3.org/TR/xml-names11/#NT-QName) production.
+fn check_qname(name: &str) -> Result<(), DomError> {
+    let legal = match name.split_once(':') {
+        Some((prefix, local)) => is_name(prefix) && is_name(local) && !local.contains(':'),
+        None => is_name(name),
+    };
+
+    if legal {
+        Ok(())
+    } else {
+        Err(DomError::InvalidName(name.to_string()))
+    }
+}
@@ -0,0 +1,118 @@
+//! Escaping tables used by the [`writer`](crate::writer).
+//!
+//! Embedders writing markup through their own fast paths can use these tables and functions to
+//! stay byte-for-byte consistent with [`XmlWriter::write_text`](crate::writer::XmlWriter::write_text)
+//! and [`ElemStart::write_escaped_attr`](crate::writer::ElemStart::write_escaped_attr).
+
+use std::borrow::Cow;
+
+use crate::chars::ByteSet;
+
+/// Bytes escaped in text content.
+///
+/// `>` is escaped too, so the text can never contain `]]>`, and carriage returns, as readers
+/// [`normalize`](https://www.w3.org/TR/xml11/#sec-line-ends) line breaks otherwise.
+pub const TEXT_ESCAPE: ByteSet = ByteSet::EMPTY.with(b'<').with(b'>').with(b'&').with(b'\r');
+
+/// Bytes escaped in attribute values, which are always written in double quotes.
+///
+/// Tab, line feed and carriage return are escaped as character references, as readers
+/// [`normalize`](https://www.w3.org/TR/xml11/#AVNormalize) them to spaces otherwise.
+pub const ATTR_ESCAPE: ByteSet = TEXT_ESCAPE.with(b'"').with(b'\t').with(b'\n');
+
+/// Returns true if `c` is escaped in text content.
+#[inline(always)]
+pub const fn needs_escaping_text(c: u8) -> bool {
+    TEXT_ESCAPE.contains(c)
+}
+
+/// Returns true if `c` is escaped in attribute values.
+#[inline(always)]
+pub const fn needs_escaping_attr(c: u8) -> bool {
+    ATTR_ESCAPE.contains(c)
+}
+
+/// Returns the replacement written for the escaped byte `c`, `None` if `c` is written as is.
+#[inline(always)]
+pub const fn escape_byte(c: u8) -> Option<&'static str> {
+    match c {
+        b'<' => Some("&lt;"),
+        b'>' => Some("&gt;"),
+        b'&' => Some("&amp;"),
+        b'"' => Some("&quot;"),
+        b'\t' => Some("&#9;"),
+        b'\n' => Some("&#10;"),
+        b'\r' => Some("&#13;"),
+        _ => None,
+    }
+}
+
+/// Escape `value` for text content.
+pub fn escape_text(value: &str) -> Cow<'_, str> {
+    escape(value, TEXT_ESCAPE)
+}
+
+/// Escape `value` for a double quoted attribute value.
+pub fn escape_attr(value: &str) -> Cow<'_, str> {
+    escape(value, ATTR_ESCAPE)
+}
+
+/// Escape `value` for the content of a CDATA section, splitting each `]]>` across two sections
+/// as `]]]]><![CDATA[>`.
+pub fn escape_cdata(value: &str) -> Cow<'_, str> {
+    if value.contains("]]>") {
+        Cow::Owned(value.replace("]]>", "]]]]><![CDATA[>"))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+fn escape(value: &str, set: ByteSet) -> Cow<'_, str> {
+    let bytes = value.as_bytes();
+
+    let Some(first) = bytes.iter().position(|c| set.contains(*c)) else {
+        return Cow::Borrowed(value);
+    };
+
+    let mut escaped = String::with_capacity(value.len() + 8);
+    let mut offset = 0;
+
+    for (i, c) in bytes.iter().enumerate().skip(first) {
+        if set.contains(*c)
+            && let Some(replacement) = escape_byte(*c)
+        {
+            // escaped bytes are ascii, so `i` is a char boundary.
+            escaped.push_str(&value[offset..i]);
+            escaped.push_str(replacement);
+            offset = i + 1;
+        }
+    }
+
+    escaped.push_str(&value[offset..]);
+
+    Cow::Owned(escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape_text("plain"), Cow::Borrowed("plain"));
+        assert_eq!(
+            escape_text("a < b & \"c\" ]]>\r\n"),
+            "a &lt; b &amp; \"c\" ]]&gt;&#13;\n"
+        );
+        assert_eq!(escape_attr("é\"\t\n"), "é&quot;&#9;&#10;");
+        assert_eq!(escape_cdata("a]]>b"), "a]]]]><![CDATA[>b");
+        assert_eq!(escape_cdata("]]"), Cow::Borrowed("]]"));
+
+        for c in 0..=255u8 {
+            assert_eq!(needs_escaping_attr(c), escape_byte(c).is_some());
+            assert!(!needs_escaping_text(c) || needs_escaping_attr(c));
+        }
+    }
+}
@@ -0,0 +1,558 @@
+//! Rough schema inference from sample documents.
+//!
+//! [`SchemaInference`] collects the elements of one or more documents, their attributes,
+//! children and the data types of their text, e.g. to explore an undocumented feed. The result
+//! is a [`report`](SchemaInference#impl-Display-for-SchemaInference) or a skeleton XSD to start
+//! a real schema from.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{Debug, Display, Write},
+};
+
+use parserc::{AsBytes, ControlFlow, Input};
+
+use crate::{
+    escape::escape_attr,
+    reader::{ReadError, XmlNode, XmlReader},
+};
+
+/// The narrowest data type of all values seen.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ValueKind {
+    /// `true` or `false`.
+    Boolean,
+    /// An optionally signed run of digits.
+    Integer,
+    /// A decimal number with fraction, e.g. `-1.5`.
+    Decimal,
+    /// A date like `2024-01-31`.
+    Date,
+    /// A date with time like `2024-01-31T12:00:00Z`.
+    DateTime,
+    /// Anything else.
+    String,
+}
+
+impl ValueKind {
+    /// Returns the narrowest kind of `value`.
+    pub fn of(value: &str) -> Self {
+        let value = value.trim();
+
+        let digits = |s: &str| !s.is_empty() && s.bytes().all(|c| c.is_ascii_digit());
+        let unsigned = value.strip_prefix(['-', '+']).unwrap_or(value);
+
+        if value == "true" || value == "false" {
+            Self::Boolean
+        } else if digits(unsigned) {
+            Self::Integer
+        } else if let Some((int, frac)) = unsigned.split_once('.')
+            && (digits(int) || int.is_empty())
+            && digits(frac)
+        {
+            Self::Decimal
+        } else if is_date(value) {
+            Self::Date
+        } else if value.len() > 11 && is_date(&value[..10]) && value.as_bytes()[10] == b'T' {
+            Self::DateTime
+        } else {
+            Self::String
+        }
+    }
+
+    /// Returns the narrowest kind covering `self` and `other`.
+    pub fn merge(self, other: Self) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Self::Integer, Self::Decimal) | (Self::Decimal, Self::Integer) => Self::Decimal,
+            (Self::Date, Self::DateTime) | (Self::DateTime, Self::Date) => Self::DateTime,
+            _ => Self::String,
+        }
+    }
+
+    /// Returns the matching XSD built-in type.
+    pub fn xsd_type(self) -> &'static str {
+        match self {
+            Self::Boolean => "xs:boolean",
+            Self::Integer => "xs:integer",
+            Self::Decimal => "xs:decimal",
+            Self::Date => "xs:date",
+            Self::DateTime => "xs:dateTime",
+            Self::String => "xs:string",
+        }
+    }
+}
+
+impl Display for ValueKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Boolean => "boolean",
+            Self::Integer => "integer",
+            Self::Decimal => "decimal",
+            Self::Date => "date",
+            Self::DateTime => "datetime",
+            Self::String => "string",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// `YYYY-MM-DD`.
+fn is_date(value: &str) -> bool {
+    let bytes = value.as_bytes();
+
+    bytes.len() == 10
+        && bytes.iter().enumerate().all(|(i, c)| match i {
+            4 | 7 => *c == b'-',
+            _ => c.is_ascii_digit(),
+        })
+}
+
+/// Statistics of one attribute of an element.
+#[derive(Debug, PartialEq, Clone)]
+pub struct AttrStats {
+    /// number of elements carrying it.
+    pub count: usize,
+    /// the kind of its values.
+    pub kind: ValueKind,
+}
+
+/// Statistics of one child element name of an element.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ChildStats {
+    /// fewest occurrences in one parent.
+    pub min: usize,
+    /// most occurrences in one parent.
+    pub max: usize,
+    /// occurrences in all parents.
+    pub total: usize,
+}
+
+/// Statistics of one element name.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ElementStats {
+    /// number of occurrences.
+    pub count: usize,
+    /// attributes by name, `xmlns` declarations excluded.
+    pub attrs: BTreeMap<String, AttrStats>,
+    /// child elements in the order they were first seen.
+    pub children: Vec<(String, ChildStats)>,
+    /// the kind of the non-whitespace text, `None` if there was none.
+    pub text: Option<ValueKind>,
+    /// true if text and child elements were seen in the same element.
+    pub mixed: bool,
+}
+
+impl ElementStats {
+    /// Returns the statistics of the child element `name`.
+    pub fn child(&self, name: &str) -> Option<&ChildStats> {
+        self.children
+            .iter()
+            .find(|(child, _)| child == name)
+            .map(|(_, stats)| stats)
+    }
+}
+
+/// An element open while scanning.
+struct Frame {
+    name: String,
+    /// child counts in the order of their first occurrence.
+    children: Vec<(String, usize)>,
+    text: String,
+}
+
+/// Schema statistics collected from sample documents, see the [module](self) docs.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SchemaInference {
+    documents: usize,
+    roots: BTreeMap<String, usize>,
+    elements: BTreeMap<String, ElementStats>,
+}
+
+impl SchemaInference {
+    /// Create an empty inference.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read all nodes of `reader` and add the statistics of its document.
+    pub fn add_document<I>(
+        &mut self,
+        reader: &mut XmlReader<I>,
+    ) -> Result<(), ControlFlow<ReadError<I>>>
+    where
+        I: Input<Item = u8> + AsBytes + Clone + Debug,
+    {
+        let mut stack: Vec<Frame> = vec![];
+
+        while let Some(node) = reader.read_next()? {
+            match node {
+                XmlNode::Start(start) => {
+                    let name = lossy(&start.name);
+
+                    match stack.last_mut() {
+                        Some(parent) => {
+                            match parent.children.iter_mut().find(|(child, _)| *child == name) {
+                                Some((_, count)) => *count += 1,
+                                None => parent.children.push((name.clone(), 1)),
+                            }
+                        }
+                        None => *self.roots.entry(name.clone()).or_default() += 1,
+                    }
+
+                    let stats = self.elements.entry(name.clone()).or_default();
+
+                    for attr in start.attrs() {
+                        let attr = attr?;
+                        let attr_name = lossy(&attr.name);
+
+                        if attr_name == "xmlns" || attr_name.starts_with("xmlns:") {
+                            continue;
+                        }
+
+                        let kind = ValueKind::of(&reader.attr_value(&attr)?);
+
+                        stats
+                            .attrs
+                            .entry(attr_name)
+                            .and_modify(|stats| {
+                                stats.count += 1;
+                                stats.kind = stats.kind.merge(kind);
+                            })
+                            .or_insert(AttrStats { count: 1, kind });
+                    }
+
+                    let frame = Frame {
+                        name,
+                        children: vec![],
+                        text: String::new(),
+                    };
+
+                    if start.is_empty {
+                        self.close(frame);
+                    } else {
+                        stack.push(frame);
+                    }
+                }
+                XmlNode::End(_) => {
+                    if let Some(frame) = stack.pop() {
+                        self.close(frame);
+                    }
+                }
+                XmlNode::CharData(chardata) => {
+                    if let Some(frame) = stack.last_mut() {
+                        frame.text.push_str(&reader.text(&chardata)?);
+                    }
+                }
+                XmlNode::CData(cdata) => {
+                    if let Some(frame) = stack.last_mut() {
+                        frame.text.push_str(&reader.cdata(&cdata)?);
+                    }
+                }
+                XmlNode::Text(text) => {
+                    if let Some(frame) = stack.last_mut() {
+                        frame.text.push_str(&text);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.documents += 1;
+
+        Ok(())
+    }
+
+    /// Merge the statistics of a closed element.
+    fn close(&mut self, frame: Frame) {
+        let stats = self.elements.get_mut(&frame.name).expect("opened element");
+        let first = stats.count == 0;
+
+        stats.count += 1;
+
+        for (name, child) in stats.children.iter_mut() {
+            let count = frame
+                .children
+                .iter()
+                .find(|(child, _)| child == name)
+                .map_or(0, |(_, count)| *count);
+
+            child.min = child.min.min(count);
+            child.max = child.max.max(count);
+            child.total += count;
+        }
+
+        for (name, count) in &frame.children {
+            if stats.child(name).is_none() {
+                stats.children.push((
+                    name.clone(),
+                    ChildStats {
+                        // missing in the elements before.
+                        min: if first { *count } else { 0 },
+                        max: *count,
+                        total: *count,
+                    },
+                ));
+            }
+        }
+
+        let text = frame.text.trim();
+
+        if !text.is_empty() {
+            let kind = ValueKind::of(text);
+
+            stats.text = Some(stats.text.map_or(kind, |prev| prev.merge(kind)));
+            stats.mixed |= !frame.children.is_empty();
+        }
+    }
+
+    /// Returns the number of documents added.
+    pub fn documents(&self) -> usize {
+        self.documents
+    }
+
+    /// Returns the root element names and how many documents they are the root of.
+    pub fn roots(&self) -> &BTreeMap<String, usize> {
+        &self.roots
+    }
+
+    /// Returns the statistics of the element `name`.
+    pub fn element(&self, name: &str) -> Option<&ElementStats> {
+        self.elements.get(name)
+    }
+
+    /// Create an iterator over all elements by name.
+    pub fn elements(&self) -> impl Iterator<Item = (&str, &ElementStats)> {
+        self.elements
+            .iter()
+            .map(|(name, stats)| (name.as_str(), stats))
+    }
+
+    /// Returns a skeleton XSD declaring each element globally.
+    ///
+    /// Children are listed as a sequence in the order they were first seen, attributes present on
+    /// every element are `required`. The guesses are rough: review them before use.
+    pub fn to_xsd(&self) -> String {
+        let mut xsd = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<xs:schema xmlns:xs=\"http://www.w3.org/2001/XMLSchema\">\n",
+        );
+
+        for (name, stats) in &self.elements {
+            let name = escape_attr(name);
+            let text = stats.text.map_or("xs:string", ValueKind::xsd_type);
+
+            if stats.children.is_empty() && stats.attrs.is_empty() {
+                let _ = match stats.text {
+                    Some(_) => writeln!(xsd, "  <xs:element name=\"{}\" type=\"{}\"/>", name, text),
+                    None => writeln!(xsd, "  <xs:element name=\"{}\"/>", name),
+                };
+
+                continue;
+            }
+
+            let _ = writeln!(xsd, "  <xs:element name=\"{}\">", name);
+
+            let attr_indent = if stats.children.is_empty() && stats.text.is_some() {
+                let _ = writeln!(
+                    xsd,
+                    "    <xs:complexType>\n      <xs:simpleContent>\n        <xs:extension base=\"{}\">",
+                    text
+                );
+
+                "          "
+            } else {
+                let _ = match stats.mixed {
+                    true => writeln!(xsd, "    <xs:complexType mixed=\"true\">"),
+                    false => writeln!(xsd, "    <xs:complexType>"),
+                };
+
+                if !stats.children.is_empty() {
+                    xsd.push_str("      <xs:sequence>\n");
+
+                    for (child, occurs) in &stats.children {
+                        let max = if occurs.max > 1 { "unbounded" } else { "1" };
+
+                        let _ = writeln!(
+                            xsd,
+                            "        <xs:element ref=\"{}\" minOccurs=\"{}\" maxOccurs=\"{}\"/>",
+                            escape_attr(child),
+                            occurs.min.min(1),
+                            max
+                        );
+                    }
+
+                    xsd.push_str("      </xs:sequence>\n");
+                }
+
+                "      "
+            };
+
+            for (attr, attr_stats) in &stats.attrs {
+                let required = match attr_stats.count == stats.count {
+                    true => " use=\"required\"",
+                    false => "",
+                };
+
+                let _ = writeln!(
+                    xsd,
+                    "{}<xs:attribute name=\"{}\" type=\"{}\"{}/>",
+                    attr_indent,
+                    escape_attr(attr),
+                    attr_stats.kind.xsd_type(),
+                    required
+                );
+            }
+
+            if stats.children.is_empty() && stats.text.is_some() {
+                xsd.push_str("        </xs:extension>\n      </xs:simpleContent>\n");
+            }
+
+            xsd.push_str("    </xs:complexType>\n  </xs:element>\n");
+        }
+
+        xsd.push_str("</xs:schema>\n");
+
+        xsd
+    }
+}
+
+/// A human-readable report, one block per element.
+impl Display for SchemaInference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "documents: {}", self.documents)?;
+
+        for (name, count) in &self.roots {
+            writeln!(f, "root {}: {}", name, count)?;
+        }
+
+        for (name, stats) in &self.elements {
+            writeln!(f, "element {} ({}x)", name, stats.count)?;
+
+            for (attr, attr_stats) in &stats.attrs {
+                writeln!(
+                    f,
+                    "  @{}: {}, {}/{}",
+                    attr, attr_stats.kind, attr_stats.count, stats.count
+                )?;
+            }
+
+            for (child, occurs) in &stats.children {
+                writeln!(
+                    f,
+                    "  {}: {}..{}, {} total",
+                    child, occurs.min, occurs.max, occurs.total
+                )?;
+            }
+
+            if let Some(text) = stats.text {
+                writeln!(
+                    f,
+                    "  text: {}{}",
+                    text,
+                    if stats.mixed { ", mixed" } else { "" }
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn lossy<I>(input: &I) -> String
+where
+    I: AsBytes,
+{
+    String::from_utf8_lossy(input.as_bytes()).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reader::XmlReader;
+
+    use super::{ChildStats, SchemaInference, ValueKind};
+
+    #[test]
+    fn test_value_kind() {
+        assert_eq!(ValueKind::of("true"), ValueKind::Boolean);
+        assert_eq!(ValueKind::of(" -12 "), ValueKind::Integer);
+        assert_eq!(ValueKind::of("1.50"), ValueKind::Decimal);
+        assert_eq!(ValueKind::of("2024-01-31"), ValueKind::Date);
+        assert_eq!(ValueKind::of("2024-01-31T12:00:00Z"), ValueKind::DateTime);
+        assert_eq!(ValueKind::of("1.2.3"), ValueKind::String);
+        assert_eq!(
+            ValueKind::Integer.merge(ValueKind::Decimal),
+            ValueKind::Decimal
+        );
+        assert_eq!(
+            ValueKind::Boolean.merge(ValueKind::Integer),
+            ValueKind::String
+        );
+    }
+
+    #[test]
+    fn test_infer() {
+        let mut inference = SchemaInference::new();
+
+        for doc in [
+            r#"<?xml version="1.0"?><feed><item id="1" at="2024-01-31"><price>1</price><tag>a</tag><tag>b</tag></item></feed>"#,
+            r#"<?xml version="1.0"?><feed xmlns="urn:f"><item id="2"><price>2.5</price></item><item id="x"><price>3</price><note>see <b>this</b></note></item></feed>"#,
+        ] {
+            inference
+                .add_document(&mut XmlReader::from(doc.as_bytes()))
+                .unwrap();
+        }
+
+        assert_eq!(inference.documents(), 2);
+        assert_eq!(inference.roots().get("feed"), Some(&2));
+
+        let feed = inference.element("feed").unwrap();
+
+        assert!(feed.attrs.is_empty());
+        assert_eq!(
+            feed.child("item"),
+            Some(&ChildStats {
+                min: 1,
+                max: 2,
+                total: 3
+            })
+        );
+
+        let item = inference.element("item").unwrap();
+
+        assert_eq!(item.count, 3);
+        assert_eq!(item.attrs["id"].kind, ValueKind::String);
+        assert_eq!(item.attrs["at"].count, 1);
+        assert_eq!(
+            item.children
+                .iter()
+                .map(|(name, stats)| (name.as_str(), stats.min, stats.max))
+                .collect::<Vec<_>>(),
+            [("price", 1, 1), ("tag", 0, 2), ("note", 0, 1)]
+        );
+
+        assert_eq!(
+            inference.element("price").unwrap().text,
+            Some(ValueKind::Decimal)
+        );
+        assert!(inference.element("note").unwrap().mixed);
+        assert_eq!(
+            inference.element("tag").unwrap().text,
+            Some(ValueKind::String)
+        );
+
+        let report = inference.to_string();
+
+        assert!(report.contains("element item (3x)\n  @at: date, 1/3\n  @id: string, 3/3\n"));
+
+        let xsd = inference.to_xsd();
+
+        assert!(xsd.contains(r#"<xs:element name="price" type="xs:decimal"/>"#));
+        assert!(xsd.contains(r#"<xs:element ref="tag" minOccurs="0" maxOccurs="unbounded"/>"#));
+        assert!(xsd.contains(r#"<xs:attribute name="id" type="xs:string" use="required"/>"#));
+        assert!(xsd.contains(r#"<xs:complexType mixed="true">"#));
+
+        // the skeleton is well-formed itself.
+        assert!(XmlReader::from(xsd.as_bytes()).all(|node| node.is_ok()));
+    }
+}
@@ -0,0 +1,85 @@
+//! Deduplication of repeated strings.
+//!
+//! Documents like SVG repeat the same attribute values (`fill="none"`, `stroke-width="1"`)
+//! over and over; an [`Interner`] shared while materializing owned nodes stores each
+//! distinct value once.
+
+use std::{collections::HashSet, sync::Arc};
+
+/// A set of shared, immutable strings.
+#[derive(Debug, Default, Clone)]
+pub struct Interner {
+    /// distinct values.
+    values: HashSet<Arc<str>>,
+    /// total bytes passed to [`intern`](Self::intern).
+    requested: usize,
+}
+
+impl Interner {
+    /// Create an empty interner.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the shared copy of `value`, inserting it on first use.
+    pub fn intern(&mut self, value: &str) -> Arc<str> {
+        self.requested += value.len();
+
+        if let Some(value) = self.values.get(value) {
+            return value.clone();
+        }
+
+        let value: Arc<str> = Arc::from(value);
+
+        self.values.insert(value.clone());
+
+        value
+    }
+
+    /// Returns the number of distinct values.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns true if nothing was interned yet.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the bytes held by the distinct values.
+    pub fn interned_bytes(&self) -> usize {
+        self.values.iter().map(|value| value.len()).sum()
+    }
+
+    /// Returns the bytes that would be held without deduplication.
+    pub fn requested_bytes(&self) -> usize {
+        self.requested
+    }
+
+    /// Drop all values, values handed out stay valid.
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.requested = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::Interner;
+
+    #[test]
+    fn test_intern() {
+        let mut interner = Interner::new();
+
+        let a = interner.intern("none");
+        let b = interner.intern("none");
+        interner.intern("red");
+
+        assert!(Arc::ptr_eq(&a, &b));
+        assert_eq!(interner.len(), 2);
+        assert_eq!(interner.interned_bytes(), 7);
+        assert_eq!(interner.requested_bytes(), 11);
+    }
+}
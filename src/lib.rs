@@ -1,12 +1,57 @@
 //! A pure rust xml implementation, based-on event stream api.
+//!
+//! # Features
+//!
+//! - `reader-core`: the event `reader`, entities, diagnostics and schema `infer`ence.
+//! - `ns`: namespace resolution and rule-based validation, implies `reader-core`.
+//! - `dtd`: parsing of doctype internal subsets, implies `reader-core`.
+//! - `reader`: `reader-core`, `ns` and `dtd`.
+//! - `writer`: the event `writer`.
+//! - `async`: the tokio based writer, implies `writer`.
+//! - `dom`: the `dom` tree, implies `reader-core`.
+//! - `xpath`: path queries over the `dom`, implies `dom`.
+//! - `serde`: deserializing from the `dom`, implies `dom`.
+//! - `instrument`: per-construct parse statistics, implies `reader-core`.
+//! - `rayon`: parallel reading of the records of a document, implies `reader-core`.
+//! - `svg`: tokenizers of the path data, `points` and `transform` attribute values of SVG.
+//! - `test-helpers`: the `assert_xml_eq!` macro comparing normalized documents and the
+//!   `arbitrary_document` generator of round-trip tests, implies `reader-core` and `writer`.
+//!
+//! `reader` and `writer` are enabled by default.
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
 pub mod types;
 
-#[cfg(feature = "reader")]
-#[cfg_attr(docsrs, doc(cfg(feature = "reader")))]
+pub mod chars;
+
+pub mod intern;
+
+pub mod escape;
+
+#[cfg(feature = "reader-core")]
+#[cfg_attr(docsrs, doc(cfg(feature = "reader-core")))]
 pub mod reader;
 
 #[cfg(feature = "writer")]
 #[cfg_attr(docsrs, doc(cfg(feature = "writer")))]
 pub mod writer;
+
+#[cfg(feature = "reader-core")]
+#[cfg_attr(docsrs, doc(cfg(feature = "reader-core")))]
+pub mod infer;
+
+#[cfg(feature = "dom")]
+#[cfg_attr(docsrs, doc(cfg(feature = "dom")))]
+pub mod dom;
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub mod de;
+
+#[cfg(feature = "svg")]
+#[cfg_attr(docsrs, doc(cfg(feature = "svg")))]
+pub mod svg;
+
+#[cfg(feature = "test-helpers")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-helpers")))]
+pub mod testing;
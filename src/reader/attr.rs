@@ -1,21 +1,69 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, ops::Range};
 
-use parserc::{ControlFlow, Input, Parse, Parser, ParserExt};
+use parserc::{AsBytes, ControlFlow, Input, Parse, Parser, ParserExt};
 
 use crate::reader::{Name, ReadKind, parse_eq, parse_quote, parse_ws};
 
-use super::ReadError;
+use super::{ReadError, position::offset_in};
 
 /// Attribute value pair.
 #[derive(Debug, PartialEq, Clone)]
 pub struct Attr<I> {
     pub name: I,
+    /// the value without quotes.
     pub value: I,
+    /// the `=` between name and value.
+    pub eq: I,
+    /// the value including its quotes.
+    pub quoted: I,
+}
+
+/// Byte ranges of the parts of an [`Attr`] in its source document, see [`Attr::spans`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AttrSpans {
+    pub name: Range<usize>,
+    pub eq: Range<usize>,
+    /// the value including its quotes.
+    pub value: Range<usize>,
+}
+
+impl<I> Attr<I>
+where
+    I: AsBytes,
+{
+    /// Split into the name and the unquoted value.
+    pub fn into_parts(self) -> (I, I) {
+        (self.name, self.value)
+    }
+
+    /// Returns the quote char of the value, `"` or `'`.
+    pub fn quote(&self) -> u8 {
+        self.quoted.as_bytes()[0]
+    }
+
+    /// Returns the byte offset of this attribute, i.e. of its name, in the `source` document it was parsed from.
+    pub fn offset(&self, source: &[u8]) -> usize {
+        offset_in(source, self.name.as_bytes())
+    }
+
+    /// Resolve the parts of this attribute against the `source` document it was parsed from.
+    pub fn spans(&self, source: &[u8]) -> AttrSpans {
+        let span = |part: &I| {
+            let start = offset_in(source, part.as_bytes());
+            start..start + part.as_bytes().len()
+        };
+
+        AttrSpans {
+            name: span(&self.name),
+            eq: span(&self.eq),
+            value: span(&self.quoted),
+        }
+    }
 }
 
 impl<I> Parse<I> for Attr<I>
 where
-    I: Input<Item = u8> + Debug + Clone,
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
 {
     type Error = ReadError<I>;
 
@@ -32,14 +80,26 @@ where
 
         let (name, input) = Name::into_parser().parse(input)?;
 
+        let mut eq = input.clone();
+
         let (_, input) = parse_eq.fatal().parse(input)?;
 
+        // `parse_eq` consumes whitespace around the `=`.
+        let mut quoted = input.clone();
+        eq.split_off(eq.len() - input.len());
+        eq.split_to(memchr::memchr(b'=', eq.as_bytes()).unwrap_or_default());
+        eq.split_off(1);
+
         let (value, input) = parse_quote.fatal().parse(input)?;
 
+        quoted.split_off(quoted.len() - input.len());
+
         Ok((
             Self {
                 name: name.0,
                 value,
+                eq,
+                quoted,
             },
             input,
         ))
@@ -50,7 +110,7 @@ where
 mod tests {
     use parserc::Parse;
 
-    use crate::reader::Attr;
+    use crate::reader::{Attr, AttrSpans};
 
     #[test]
     fn test_attr() {
@@ -60,9 +120,28 @@ mod tests {
                 Attr {
                     name: b"value".as_slice(),
                     value: b"hello world".as_slice(),
+                    eq: b"=".as_slice(),
+                    quoted: b"'hello world'".as_slice(),
                 },
                 b"".as_slice()
             ))
         );
     }
+
+    #[test]
+    fn test_spans() {
+        let source = br#" href = "a.svg""#.as_slice();
+
+        let (attr, _) = Attr::parse(source).unwrap();
+
+        assert_eq!(attr.quote(), b'"');
+        assert_eq!(
+            attr.spans(source),
+            AttrSpans {
+                name: 1..5,
+                eq: 6..7,
+                value: 8..15
+            }
+        );
+    }
 }
@@ -0,0 +1,163 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    hash::{BuildHasher, RandomState},
+};
+
+use parserc::{AsBytes, ControlFlow, Input};
+
+use super::{Attr, ElemStart, ReadError};
+
+/// A reusable lookup table over the attributes of one start tag.
+///
+/// Small tags are searched linearly; once a tag has more than `threshold` attributes a hash
+/// index is built. Buffers are kept between [`load`](Self::load) calls, so reusing one map
+/// across elements doesn't allocate per element.
+#[derive(Debug, Clone)]
+pub struct AttrMap<I> {
+    /// parsed attributes of the loaded start tag.
+    attrs: Vec<Attr<I>>,
+    /// name hash to attribute index, only filled above `threshold`.
+    index: HashMap<u64, usize>,
+    /// true if two names of the loaded tag share a hash, lookups fall back to a linear scan.
+    collided: bool,
+    /// attribute count above which the index is built.
+    threshold: usize,
+    hasher: RandomState,
+}
+
+impl<I> Default for AttrMap<I> {
+    fn default() -> Self {
+        Self::with_threshold(Self::DEFAULT_THRESHOLD)
+    }
+}
+
+impl<I> AttrMap<I> {
+    /// The default attribute count above which the hash index is built.
+    pub const DEFAULT_THRESHOLD: usize = 8;
+
+    /// Create an empty map with the default threshold.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty map that indexes tags with more than `threshold` attributes.
+    pub fn with_threshold(threshold: usize) -> Self {
+        Self {
+            attrs: vec![],
+            index: Default::default(),
+            collided: false,
+            threshold,
+            hasher: Default::default(),
+        }
+    }
+
+    /// Returns the indexing threshold.
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Returns the number of loaded attributes.
+    pub fn len(&self) -> usize {
+        self.attrs.len()
+    }
+
+    /// Returns true if no attributes are loaded.
+    pub fn is_empty(&self) -> bool {
+        self.attrs.is_empty()
+    }
+
+    /// Returns true if lookups use the hash index.
+    pub fn is_indexed(&self) -> bool {
+        !self.index.is_empty() && !self.collided
+    }
+
+    /// Returns an iterator over the loaded attributes in document order.
+    pub fn iter(&self) -> std::slice::Iter<'_, Attr<I>> {
+        self.attrs.iter()
+    }
+
+    /// Drop the loaded attributes, keeping the allocated buffers.
+    pub fn clear(&mut self) {
+        self.attrs.clear();
+        self.index.clear();
+        self.collided = false;
+    }
+}
+
+impl<I> AttrMap<I>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    /// Parse the attributes of `start`, replacing the loaded ones.
+    pub fn load(&mut self, start: &ElemStart<I>) -> Result<(), ControlFlow<ReadError<I>>> {
+        self.clear();
+
+        for attr in start.attrs() {
+            self.attrs.push(attr?);
+        }
+
+        if self.attrs.len() > self.threshold {
+            for (i, attr) in self.attrs.iter().enumerate() {
+                let hash = self.hasher.hash_one(attr.name.as_bytes());
+
+                if self.index.insert(hash, i).is_some() {
+                    self.collided = true;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the value of the attribute `name`.
+    pub fn get(&self, name: &[u8]) -> Option<&I> {
+        if self.is_indexed() {
+            let attr = &self.attrs[*self.index.get(&self.hasher.hash_one(name))?];
+
+            return (attr.name.as_bytes() == name).then_some(&attr.value);
+        }
+
+        self.attrs
+            .iter()
+            .find(|attr| attr.name.as_bytes() == name)
+            .map(|attr| &attr.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parserc::Parse;
+
+    use crate::reader::ElemStart;
+
+    use super::AttrMap;
+
+    #[test]
+    fn test_attr_map() {
+        let input = (0..20)
+            .map(|i| format!(" a{}='{}'", i, i))
+            .collect::<String>();
+
+        let input = format!("<el{}/>", input);
+
+        let (start, _) = ElemStart::parse(input.as_bytes()).unwrap();
+
+        let mut map = AttrMap::new();
+
+        map.load(&start).unwrap();
+
+        assert!(map.is_indexed());
+        assert_eq!(map.len(), 20);
+        assert_eq!(map.get(b"a13"), Some(&b"13".as_slice()));
+        assert_eq!(map.get(b"a20"), None);
+
+        let (start, _) = ElemStart::parse(br#"<el a="1" b="2"/>"#.as_slice()).unwrap();
+
+        map.load(&start).unwrap();
+
+        assert!(!map.is_indexed());
+        assert_eq!(map.get(b"b"), Some(&b"2".as_slice()));
+        assert_eq!(map.get(b"a13"), None);
+    }
+}
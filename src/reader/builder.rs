@@ -0,0 +1,301 @@
+use std::fmt::Debug;
+
+use parserc::{AsBytes, Input};
+
+use super::{
+    AttrMap, Entities, EntityLimits, InvalidCharRef, ReadState, ReaderLimits, UnknownEntity,
+    XmlReader,
+};
+
+/// Controls how [`XmlReader`] checks that the input is valid utf-8.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum Utf8Policy {
+    /// The caller guarantees the input is valid utf-8, e.g. it came from a `&str`; no checks are performed.
+    Trusted,
+    /// Validate the whole input once, before the first event is returned.
+    ValidateOnce,
+    /// Validate the bytes of each event as it is read.
+    ///
+    /// Errors surface at the offending event instead of up front, and documents abandoned
+    /// half way through never pay for the unread tail.
+    #[default]
+    ValidateLazy,
+}
+
+/// A builder for [`XmlReader`] with non-default options.
+#[derive(Debug, Clone)]
+pub struct XmlReaderBuilder {
+    pub(super) state: ReadState,
+    pub(super) utf8: Utf8Policy,
+    pub(super) attr_index_threshold: usize,
+    pub(super) entities: Entities,
+    pub(super) lenient: bool,
+    pub(super) strict: bool,
+    pub(super) trim_text: bool,
+    pub(super) skip_comments: bool,
+    pub(super) coalesce_text: bool,
+    pub(super) limits: ReaderLimits,
+    pub(super) check_names: bool,
+    pub(super) check_chars: bool,
+    pub(super) attach_ws: bool,
+    pub(super) fragment: bool,
+}
+
+impl Default for XmlReaderBuilder {
+    fn default() -> Self {
+        Self {
+            state: ReadState::XmlDecl,
+            utf8: Default::default(),
+            attr_index_threshold: AttrMap::<()>::DEFAULT_THRESHOLD,
+            entities: Default::default(),
+            lenient: false,
+            strict: true,
+            trim_text: false,
+            skip_comments: false,
+            coalesce_text: false,
+            limits: Default::default(),
+            check_names: false,
+            check_chars: false,
+            attach_ws: false,
+            fragment: false,
+        }
+    }
+}
+
+impl XmlReaderBuilder {
+    /// Create a builder with default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the [`ReadState`] the reader starts in, defaults to [`ReadState::XmlDecl`].
+    pub fn state(mut self, state: ReadState) -> Self {
+        self.state = state;
+        self
+    }
+
+    /// Set the utf-8 validation strategy, defaults to [`Utf8Policy::ValidateLazy`].
+    pub fn utf8(mut self, policy: Utf8Policy) -> Self {
+        self.utf8 = policy;
+        self
+    }
+
+    /// Set the attribute count above which [`XmlReader::get_attr`] builds a hash index,
+    /// defaults to [`AttrMap::DEFAULT_THRESHOLD`].
+    pub fn attr_index_threshold(mut self, threshold: usize) -> Self {
+        self.attr_index_threshold = threshold;
+        self
+    }
+
+    /// Register the entity `name` expanded by [`XmlReader::expand`].
+    pub fn entity<N, V>(mut self, name: N, value: V) -> Self
+    where
+        N: Into<String>,
+        V: Into<String>,
+    {
+        self.entities.insert(name, value);
+        self
+    }
+
+    /// Set a callback resolving entities that were not registered with [`entity`](Self::entity).
+    pub fn entity_resolver<F>(mut self, resolver: F) -> Self
+    where
+        F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+    {
+        self.entities.resolver(resolver);
+        self
+    }
+
+    /// Set the behavior for unknown entities, defaults to [`UnknownEntity::Error`].
+    pub fn unknown_entity(mut self, unknown: UnknownEntity) -> Self {
+        self.entities.unknown(unknown);
+        self
+    }
+
+    /// Set the behavior for character references to illegal code points, defaults to [`InvalidCharRef::Error`].
+    pub fn invalid_char_ref(mut self, policy: InvalidCharRef) -> Self {
+        self.entities.invalid_char_ref(policy);
+        self
+    }
+
+    /// Set the expansion limits of text nodes, defaults to [`EntityLimits::TEXT`].
+    pub fn text_entity_limits(mut self, limits: EntityLimits) -> Self {
+        self.entities.text_limits(limits);
+        self
+    }
+
+    /// Set the expansion limits of attribute values, defaults to [`EntityLimits::ATTR`].
+    pub fn attr_entity_limits(mut self, limits: EntityLimits) -> Self {
+        self.entities.attr_limits(limits);
+        self
+    }
+
+    /// Set whether line breaks are normalized in decoded text, defaults to `true`; see
+    /// [`Entities::normalize_newlines`].
+    ///
+    /// Turn it off for byte-exact processing, carriage returns are then kept as written.
+    pub fn normalize_newlines(mut self, normalize: bool) -> Self {
+        self.entities.normalize_newlines(normalize);
+        self
+    }
+
+    /// Replace the whole entity table.
+    pub fn entities(mut self, entities: Entities) -> Self {
+        self.entities = entities;
+        self
+    }
+
+    /// Yield [`XmlNode::Unknown`](super::XmlNode::Unknown) for markup the reader can't classify
+    /// instead of failing, defaults to `false`.
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Check that end tags match their start tags, that no element is left open at the end
+    /// of the input and that comments contain no `--`, defaults to `true`.
+    ///
+    /// Readers of fragments or damaged documents can turn this off; they then only count tags.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Drop whitespace around text and whitespace-only text, and the whitespace
+    /// [`XmlNode::S`](super::XmlNode::S) outside the root element, defaults to `false`.
+    ///
+    /// CDATA sections are kept as is unless coalesced.
+    pub fn trim_text(mut self, trim: bool) -> Self {
+        self.trim_text = trim;
+        self
+    }
+
+    /// Drop comments, defaults to `false`.
+    ///
+    /// Comments are then skipped by scanning for their end, so neither their length, their
+    /// content nor their utf-8 is checked.
+    pub fn skip_comments(mut self, skip: bool) -> Self {
+        self.skip_comments = skip;
+        self
+    }
+
+    /// Merge each run of text and cdata sections, and of skipped comments between them, into one
+    /// [`XmlNode::Text`](super::XmlNode::Text) with references expanded, defaults to `false`.
+    pub fn coalesce_text(mut self, coalesce: bool) -> Self {
+        self.coalesce_text = coalesce;
+        self
+    }
+
+    /// Check element, attribute and PI target names against the `NameStartChar` and `NameChar`
+    /// productions, defaults to `false`; see [`Name::validate`](super::Name::validate).
+    ///
+    /// Otherwise names only end at delimiters, so e.g. `1abc` is accepted.
+    pub fn check_names(mut self, check: bool) -> Self {
+        self.check_names = check;
+        self
+    }
+
+    /// Reject characters the xml version of the document doesn't allow literally with
+    /// [`ReadError::IllegalChar`](super::ReadError::IllegalChar), defaults to `false`.
+    ///
+    /// In xml 1.0 these are control characters other than tab, line feed and carriage return,
+    /// in xml 1.1 the `RestrictedChar`s, which may only appear as character references. Both
+    /// versions reject `U+0000`, `U+FFFE` and `U+FFFF`.
+    pub fn check_chars(mut self, check: bool) -> Self {
+        self.check_chars = check;
+        self
+    }
+
+    /// Set the caps on the resources of one document, defaults to [`ReaderLimits::UNLIMITED`].
+    ///
+    /// Set it after [`entities`](Self::entities), which replaces the total expansion cap.
+    pub fn limits(mut self, limits: ReaderLimits) -> Self {
+        self.entities
+            .total_expansion_limit(limits.max_total_expansion);
+        self.limits = limits;
+        self
+    }
+
+    /// Drop the whitespace [`XmlNode::S`](super::XmlNode::S) outside the root element and attach
+    /// it to the adjacent node instead, defaults to `false`.
+    ///
+    /// Formatting-preserving tools find it with [`XmlReader::leading_whitespace`] of the next
+    /// node, or [`XmlReader::trailing_whitespace`] of the last node of the document. Whitespace
+    /// inside elements is text and not affected.
+    pub fn attach_whitespace(mut self, attach: bool) -> Self {
+        self.attach_ws = attach;
+        self
+    }
+
+    /// Read a fragment: content without prolog, with any number of top-level elements and text,
+    /// e.g. a template snippet or an XHTML island, defaults to `false`.
+    ///
+    /// Starts the reader in [`ReadState::Element`], see also [`XmlReader::fragment`].
+    pub fn fragment(mut self, fragment: bool) -> Self {
+        self.fragment = fragment;
+
+        if fragment {
+            self.state = ReadState::Element;
+        }
+
+        self
+    }
+
+    /// Create a reader over `input` with these options.
+    pub fn build<I>(self, input: I) -> XmlReader<I>
+    where
+        I: Input<Item = u8> + AsBytes + Clone + Debug,
+    {
+        XmlReader::with_builder(self, input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parserc::ControlFlow;
+
+    use crate::reader::{ReadError, XmlNode, XmlReader, XmlReaderBuilder};
+
+    use super::Utf8Policy;
+
+    const DOC: &[u8] = b"<?xml version=\"1.0\"?><a>\xFF</a>";
+
+    #[test]
+    fn test_validate_once() {
+        let mut reader = XmlReaderBuilder::new()
+            .utf8(Utf8Policy::ValidateOnce)
+            .build(DOC);
+
+        assert_eq!(
+            reader.read_next(),
+            Err(ControlFlow::Fatal(ReadError::InvalidUtf8(
+                b"\xFF</a>".as_slice()
+            )))
+        );
+    }
+
+    #[test]
+    fn test_validate_lazy() {
+        let mut reader = XmlReader::from(DOC);
+
+        assert!(matches!(reader.read_next(), Ok(Some(XmlNode::XmlDecl(_)))));
+        assert!(matches!(reader.read_next(), Ok(Some(XmlNode::Start(_)))));
+        assert_eq!(
+            reader.read_next(),
+            Err(ControlFlow::Fatal(ReadError::InvalidUtf8(
+                b"\xFF".as_slice()
+            )))
+        );
+    }
+
+    #[test]
+    fn test_trusted() {
+        let nodes = XmlReaderBuilder::new()
+            .utf8(Utf8Policy::Trusted)
+            .build(DOC)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(nodes.len(), 4);
+    }
+}
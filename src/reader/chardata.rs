@@ -4,7 +4,7 @@ use parserc::{
     AsBytes, ControlFlow, Input, Kind, Parse, Parser, ParserExt, keyword, take_till, take_until,
 };
 
-use super::{ReadError, ReadKind};
+use super::{ReadError, ReadKind, find_bad_ref};
 
 /// See [`chardata`](https://www.w3.org/TR/xml11/#NT-CharData)
 #[derive(Debug, PartialEq, Clone)]
@@ -12,12 +12,14 @@ pub struct CharData<I>(pub I);
 
 impl<I> Parse<I> for CharData<I>
 where
-    I: Input<Item = u8> + AsBytes + Debug,
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
 {
     type Error = ReadError<I>;
 
     #[inline(always)]
     fn parse(input: I) -> parserc::Result<Self, I, Self::Error> {
+        let start = input.clone();
+
         let (content, input) = take_till(|c| c == b'<').parse(input)?;
 
         if content.is_empty() {
@@ -27,6 +29,13 @@ where
             )));
         }
 
+        if let Some(at) = find_bad_ref(content.as_bytes()) {
+            return Err(ControlFlow::Fatal(ReadError::Expect(
+                ReadKind::Reference,
+                start.clone().split_off(at),
+            )));
+        }
+
         Ok((CharData(content), input))
     }
 }
@@ -0,0 +1,422 @@
+use std::{fmt::Display, ops::Range};
+
+use parserc::{AsBytes, Input};
+
+use super::{Position, ReadError, ReadKind, position::offset_in};
+
+/// A machine-readable edit that fixes a parse error.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FixIt {
+    /// Short description of the edit, e.g. "insert `&amp;`".
+    pub title: String,
+    /// Byte range of the source replaced by this edit, empty for insertions.
+    pub range: Range<usize>,
+    /// The replacement text.
+    pub replacement: String,
+}
+
+impl FixIt {
+    fn replace(range: Range<usize>, replacement: &str, title: String) -> Self {
+        Self {
+            title,
+            range,
+            replacement: replacement.to_string(),
+        }
+    }
+
+    fn insert(offset: usize, text: &str) -> Self {
+        Self::replace(offset..offset, text, format!("insert `{}`", text))
+    }
+}
+
+/// A parse error resolved against its source document, with optional fix-it hints.
+///
+/// Editor integrations can offer each [`FixIt`] as a quick fix.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Diagnostic {
+    /// Byte offset of the error, `None` for whole-document errors.
+    pub offset: Option<usize>,
+    /// Line and column of the error, `None` for whole-document errors.
+    pub position: Option<Position>,
+    /// Human readable description.
+    pub message: String,
+    /// Suggested edits, applied together they fix the error.
+    pub fixits: Vec<FixIt>,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.position {
+            Some(position) => write!(f, "{} at {}", self.message, position),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl Diagnostic {
+    /// Resolve `err` against the `source` document it was returned for.
+    ///
+    /// The offset is computed from the input slice carried by `err`, so `source` must be the
+    /// whole input passed to the reader.
+    pub fn new<I>(source: &[u8], err: &ReadError<I>) -> Self
+    where
+        I: Input + AsBytes,
+    {
+        // attribute errors carry a slice of the start tag rather than the remaining input.
+        let offset = |rest: &I| offset_in(source, rest.as_bytes());
+
+        let mut diagnostic = match err {
+            ReadError::Parserc(kind) => Self::message(None, format!("{:?}", kind)),
+            ReadError::Expect(ReadKind::Reference, rest) => {
+                let offset = offset(rest);
+
+                Self {
+                    offset: Some(offset),
+                    position: None,
+                    message: "unescaped `&`".to_string(),
+                    fixits: vec![FixIt::replace(
+                        offset..offset + 1,
+                        "&amp;",
+                        "replace with `&amp;`".to_string(),
+                    )],
+                }
+            }
+            ReadError::Unexpect(ReadKind::Lt, rest) => {
+                let offset = offset(rest);
+
+                Self {
+                    offset: Some(offset),
+                    position: None,
+                    message: "`<` in attribute value".to_string(),
+                    fixits: vec![FixIt::replace(
+                        offset..offset + 1,
+                        "&lt;",
+                        "replace with `&lt;`".to_string(),
+                    )],
+                }
+            }
+            ReadError::Expect(ReadKind::Quote, rest) => {
+                let offset = offset(rest);
+
+                Self {
+                    offset: Some(offset),
+                    position: None,
+                    message: "missing quote".to_string(),
+                    fixits: quote_fixits(source, offset),
+                }
+            }
+            ReadError::Expect(kind, rest) => {
+                Self::message(Some(offset(rest)), format!("expect {}", kind))
+            }
+            ReadError::Unexpect(kind, rest) => {
+                Self::message(Some(offset(rest)), format!("unexpect {}", kind))
+            }
+            ReadError::InvalidEncodingName(rest) => {
+                Self::message(Some(offset(rest)), "invalid encoding name".to_string())
+            }
+            ReadError::EncodingMismatch { detected, declared } => Self::message(
+                None,
+                format!(
+                    "detected {} encoding, but the declaration claims `{}`",
+                    detected, declared
+                ),
+            ),
+            ReadError::UnsupportedEncoding(detected) => {
+                Self::message(None, format!("unsupported {} encoding", detected))
+            }
+            ReadError::InvalidUtf8(rest) => {
+                Self::message(Some(offset(rest)), "invalid utf-8 sequence".to_string())
+            }
+            ReadError::UnknownEntity(rest) => {
+                Self::message(Some(offset(rest)), "unknown entity".to_string())
+            }
+            ReadError::InvalidCharRef(rest) => Self::message(
+                Some(offset(rest)),
+                "character reference to an illegal code point".to_string(),
+            ),
+            ReadError::UnboundPrefix(prefix) => {
+                Self::message(Some(offset(prefix)), "unbound namespace prefix".to_string())
+            }
+            ReadError::DuplicateAttr(name) => {
+                Self::message(Some(offset(name)), "duplicate attribute".to_string())
+            }
+            ReadError::TextEntityLimit(limit, rest) => Self::message(
+                Some(offset(rest)),
+                format!("{} limit of text exceeded", limit),
+            ),
+            ReadError::AttrEntityLimit(limit, rest) => Self::message(
+                Some(offset(rest)),
+                format!("{} limit of attribute value exceeded", limit),
+            ),
+            ReadError::InvalidComment(dashes) => Self::message(
+                Some(offset(dashes)),
+                "comments must not contain `--` or end with `-`".to_string(),
+            ),
+            ReadError::InvalidName(c) => {
+                Self::message(Some(offset(c)), "illegal name character".to_string())
+            }
+            ReadError::IllegalChar {
+                codepoint,
+                position,
+            } => Self::message(
+                Some(offset(position)),
+                format!("illegal character U+{:04X}", codepoint),
+            ),
+            ReadError::LimitExceeded(limit, rest) => {
+                Self::message(Some(offset(rest)), format!("{} limit exceeded", limit))
+            }
+            ReadError::MalformedAttr(span) => {
+                let offset = offset(span);
+
+                // attributes must be separated by whitespace.
+                let fixits = match source[..offset].last() {
+                    Some(b'"' | b'\'') => vec![FixIt::insert(offset, " ")],
+                    _ => vec![],
+                };
+
+                Self {
+                    offset: Some(offset),
+                    position: None,
+                    message: "malformed attribute".to_string(),
+                    fixits,
+                }
+            }
+            ReadError::MisplacedDecl(rest) | ReadError::MisplacedBom(rest) => {
+                let offset = offset(rest);
+
+                let what = match err {
+                    ReadError::MisplacedDecl(_) => "xml declaration",
+                    _ => "byte order mark",
+                };
+
+                Self {
+                    offset: Some(offset),
+                    position: None,
+                    message: format!("{} not at the start of the document", what),
+                    fixits: vec![FixIt::replace(
+                        0..offset,
+                        "",
+                        format!("remove content before the {}", what),
+                    )],
+                }
+            }
+            ReadError::Mismatch {
+                expected, found, ..
+            } => {
+                let offset = offset(found);
+                let expected = String::from_utf8_lossy(expected.as_bytes());
+
+                Self {
+                    offset: Some(offset),
+                    position: None,
+                    message: format!("end tag doesn't match start tag `{}`", expected),
+                    fixits: vec![FixIt::replace(
+                        offset..offset + found.as_bytes().len(),
+                        &expected,
+                        format!("close tag `{}`", expected),
+                    )],
+                }
+            }
+            ReadError::Unclosed(name) => {
+                let name = String::from_utf8_lossy(name.as_bytes());
+
+                Self {
+                    offset: Some(source.len()),
+                    position: None,
+                    message: format!("unclosed element `{}`", name),
+                    fixits: vec![FixIt::insert(source.len(), &format!("</{}>", name))],
+                }
+            }
+        };
+
+        diagnostic.position = diagnostic
+            .offset
+            .map(|offset| Position::locate(source, offset));
+
+        diagnostic
+    }
+
+    fn message(offset: Option<usize>, message: String) -> Self {
+        Self {
+            offset,
+            position: None,
+            message,
+            fixits: vec![],
+        }
+    }
+
+    /// Apply all fix-its to `source`.
+    ///
+    /// Fix-its not fitting `source`, e.g. of a diagnostic of another document, are skipped.
+    pub fn apply(&self, source: &str) -> String {
+        let mut fixits = self.fixits.iter().collect::<Vec<_>>();
+
+        fixits.sort_by_key(|fixit| std::cmp::Reverse(fixit.range.start));
+
+        let mut fixed = source.to_string();
+
+        for fixit in fixits {
+            let range = &fixit.range;
+
+            if range.start > range.end
+                || !fixed.is_char_boundary(range.start)
+                || !fixed.is_char_boundary(range.end)
+            {
+                continue;
+            }
+
+            fixed.replace_range(fixit.range.clone(), &fixit.replacement);
+        }
+
+        fixed
+    }
+}
+
+/// Returns the edits for a missing quote at `offset`.
+fn quote_fixits(source: &[u8], offset: usize) -> Vec<FixIt> {
+    let is_end = |c: &u8| crate::chars::is_ws(*c) || matches!(c, b'/' | b'>');
+
+    if source.get(offset).is_some_and(|c| !is_end(c)) {
+        // the opening quote is missing, quote the whole unquoted value.
+        let end = source[offset..]
+            .iter()
+            .position(is_end)
+            .map_or(source.len(), |len| offset + len);
+
+        return vec![FixIt::insert(offset, "\""), FixIt::insert(end, "\"")];
+    }
+
+    // the closing quote is missing, reuse the opening one.
+    let quote = source[..offset]
+        .iter()
+        .rev()
+        .find(|c| matches!(c, b'"' | b'\''))
+        .map_or("\"", |c| if *c == b'"' { "\"" } else { "'" });
+
+    vec![FixIt::insert(offset, quote)]
+}
+
+#[cfg(test)]
+mod tests {
+    use parserc::ControlFlow;
+
+    use crate::reader::{XmlNode, XmlReader};
+
+    use super::Diagnostic;
+
+    fn fix(source: &str) -> (String, String) {
+        // attributes are parsed lazily, so walk them too.
+        let err = XmlReader::from(source.as_bytes()).find_map(|node| match node {
+            Ok(XmlNode::Start(start)) => start.attrs().find_map(|attr| attr.err()),
+            Ok(_) => None,
+            Err(err) => Some(err),
+        });
+
+        let err = match err {
+            Some(ControlFlow::Fatal(err)) | Some(ControlFlow::Recovable(err)) => err,
+            err => panic!("unexpected {:?}", err),
+        };
+
+        let diagnostic = Diagnostic::new(source.as_bytes(), &err);
+
+        (diagnostic.message.clone(), diagnostic.apply(source))
+    }
+
+    #[test]
+    fn test_position() {
+        let source = "<?xml version=\"1.0\"?>\n<a>\n  fish & chips</a>";
+
+        let err = XmlReader::from(source.as_bytes())
+            .find_map(Result::err)
+            .unwrap();
+
+        let ControlFlow::Fatal(err) = err else {
+            panic!("unexpected {:?}", err);
+        };
+
+        assert_eq!(
+            Diagnostic::new(source.as_bytes(), &err).to_string(),
+            "unescaped `&` at line 3, column 8"
+        );
+    }
+
+    #[test]
+    fn test_fixits() {
+        assert_eq!(
+            fix(r#"<?xml version="1.0"?><a>fish & chips</a>"#),
+            (
+                "unescaped `&`".to_string(),
+                r#"<?xml version="1.0"?><a>fish &amp; chips</a>"#.to_string()
+            )
+        );
+
+        assert_eq!(
+            fix(r#"<?xml version="1.0"?><a b="1 < 2"/>"#),
+            (
+                "`<` in attribute value".to_string(),
+                r#"<?xml version="1.0"?><a b="1 &lt; 2"/>"#.to_string()
+            )
+        );
+
+        assert_eq!(
+            fix(r#"<?xml version="1.0"?><a b=c/>"#),
+            (
+                "missing quote".to_string(),
+                r#"<?xml version="1.0"?><a b="c"/>"#.to_string()
+            )
+        );
+
+        assert_eq!(
+            fix(r#"<?xml version="1.0"?><a b='c><d/></a>"#),
+            (
+                "missing quote".to_string(),
+                r#"<?xml version="1.0"?><a b='c'><d/></a>"#.to_string()
+            )
+        );
+
+        assert_eq!(
+            fix(r#"<?xml version="1.0"?><a b="1"c="2"/>"#),
+            (
+                "malformed attribute".to_string(),
+                r#"<?xml version="1.0"?><a b="1" c="2"/>"#.to_string()
+            )
+        );
+
+        assert_eq!(
+            fix(r#"<?xml version="1.0"?><a><b></c></a>"#),
+            (
+                "end tag doesn't match start tag `b`".to_string(),
+                r#"<?xml version="1.0"?><a><b></b></a>"#.to_string()
+            )
+        );
+
+        assert_eq!(
+            fix(r#"<?xml version="1.0"?><a><b/>"#),
+            (
+                "unclosed element `a`".to_string(),
+                r#"<?xml version="1.0"?><a><b/></a>"#.to_string()
+            )
+        );
+
+        let err = XmlReader::from(r#"<?xml version="1.0"?><a>é & </a>"#.as_bytes())
+            .find_map(Result::err)
+            .unwrap();
+
+        let ControlFlow::Fatal(err) = err else {
+            panic!("unexpected {:?}", err);
+        };
+
+        let diagnostic = Diagnostic::new(r#"<?xml version="1.0"?><a>é & </a>"#.as_bytes(), &err);
+
+        assert_eq!(diagnostic.apply("<a>é</a>"), "<a>é</a>");
+        assert_eq!(diagnostic.apply(""), "");
+
+        assert_eq!(
+            fix("\n<?xml version=\"1.0\"?><a/>"),
+            (
+                "xml declaration not at the start of the document".to_string(),
+                r#"<?xml version="1.0"?><a/>"#.to_string()
+            )
+        );
+    }
+}
@@ -0,0 +1,899 @@
+use std::fmt::Debug;
+
+use parserc::{AsBytes, ControlFlow, Input, Parse, Parser, take_till};
+
+use crate::reader::{Comment, PI, ReadError, ReadKind, ensure_ws, is_ws, parse_literal, parse_ws};
+
+use super::DocType;
+
+/// A parsed [`doctypedecl`](https://www.w3.org/TR/xml11/#NT-doctypedecl), see [`DocType::parse_decl`].
+#[derive(Debug, PartialEq, Clone)]
+pub struct DocTypeDecl<I> {
+    /// name of the root element.
+    pub name: I,
+    /// location of the external subset.
+    pub external_id: Option<ExternalId<I>>,
+    /// declarations of the internal subset, in document order.
+    pub subset: Vec<MarkupDecl<I>>,
+}
+
+/// See [`ExternalID`](https://www.w3.org/TR/xml11/#NT-ExternalID).
+#[derive(Debug, PartialEq, Clone)]
+pub enum ExternalId<I> {
+    /// `SYSTEM "uri"`
+    System(I),
+    /// `PUBLIC "id" "uri"`, the system literal is only optional in notation declarations.
+    Public { public: I, system: Option<I> },
+}
+
+/// One item of the internal subset, see [`markupdecl`](https://www.w3.org/TR/xml11/#NT-markupdecl).
+#[derive(Debug, PartialEq, Clone)]
+pub enum MarkupDecl<I> {
+    Element(ElementDecl<I>),
+    AttList(AttListDecl<I>),
+    Entity(EntityDecl<I>),
+    Notation(NotationDecl<I>),
+    PI(PI<I>),
+    Comment(Comment<I>),
+    /// A parameter entity reference `%name;`, not expanded.
+    PERef(I),
+}
+
+/// See [`elementdecl`](https://www.w3.org/TR/xml11/#NT-elementdecl).
+#[derive(Debug, PartialEq, Clone)]
+pub struct ElementDecl<I> {
+    pub name: I,
+    pub content: ContentSpec<I>,
+}
+
+/// See [`contentspec`](https://www.w3.org/TR/xml11/#NT-contentspec).
+#[derive(Debug, PartialEq, Clone)]
+pub enum ContentSpec<I> {
+    Empty,
+    Any,
+    /// `(#PCDATA | a | b)*`, with the names of the allowed elements.
+    Mixed(Vec<I>),
+    /// Element content.
+    Children(ContentParticle<I>),
+}
+
+/// The occurrence suffix of a content particle.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Repeat {
+    /// no suffix, exactly once.
+    One,
+    /// `?`
+    Optional,
+    /// `*`
+    ZeroOrMore,
+    /// `+`
+    OneOrMore,
+}
+
+/// See [`cp`](https://www.w3.org/TR/xml11/#NT-cp).
+#[derive(Debug, PartialEq, Clone)]
+pub enum ContentParticle<I> {
+    Name(I, Repeat),
+    /// `(a, b)`
+    Seq(Vec<ContentParticle<I>>, Repeat),
+    /// `(a | b)`
+    Choice(Vec<ContentParticle<I>>, Repeat),
+}
+
+/// See [`AttlistDecl`](https://www.w3.org/TR/xml11/#NT-AttlistDecl).
+#[derive(Debug, PartialEq, Clone)]
+pub struct AttListDecl<I> {
+    /// name of the element the attributes belong to.
+    pub element: I,
+    pub attrs: Vec<AttDef<I>>,
+}
+
+/// See [`AttDef`](https://www.w3.org/TR/xml11/#NT-AttDef).
+#[derive(Debug, PartialEq, Clone)]
+pub struct AttDef<I> {
+    pub name: I,
+    pub ty: AttType<I>,
+    pub default: DefaultDecl<I>,
+}
+
+/// See [`AttType`](https://www.w3.org/TR/xml11/#NT-AttType).
+#[derive(Debug, PartialEq, Clone)]
+pub enum AttType<I> {
+    CData,
+    Id,
+    IdRef,
+    IdRefs,
+    Entity,
+    Entities,
+    NmToken,
+    NmTokens,
+    /// `NOTATION (a | b)`
+    Notation(Vec<I>),
+    /// `(a | b)`
+    Enumeration(Vec<I>),
+}
+
+/// See [`DefaultDecl`](https://www.w3.org/TR/xml11/#NT-DefaultDecl).
+#[derive(Debug, PartialEq, Clone)]
+pub enum DefaultDecl<I> {
+    Required,
+    Implied,
+    /// `#FIXED "value"`
+    Fixed(I),
+    /// `"value"`
+    Value(I),
+}
+
+/// See [`EntityDecl`](https://www.w3.org/TR/xml11/#NT-EntityDecl).
+#[derive(Debug, PartialEq, Clone)]
+pub struct EntityDecl<I> {
+    pub name: I,
+    /// true for parameter entities, `<!ENTITY % name ...>`.
+    pub parameter: bool,
+    pub def: EntityDef<I>,
+}
+
+/// See [`EntityDef`](https://www.w3.org/TR/xml11/#NT-EntityDef).
+#[derive(Debug, PartialEq, Clone)]
+pub enum EntityDef<I> {
+    /// The raw replacement text, references are not expanded.
+    Internal(I),
+    /// An external entity, `ndata` is the notation of unparsed entities.
+    External { id: ExternalId<I>, ndata: Option<I> },
+}
+
+/// See [`NotationDecl`](https://www.w3.org/TR/xml11/#NT-NotationDecl).
+#[derive(Debug, PartialEq, Clone)]
+pub struct NotationDecl<I> {
+    pub name: I,
+    pub id: ExternalId<I>,
+}
+
+impl<I> DocType<I>
+where
+    I: Input<Item = u8> + AsBytes + Clone + Debug,
+{
+    /// Parse the name, external id and internal subset of this doctype.
+    pub fn parse_decl(&self) -> Result<DocTypeDecl<I>, ControlFlow<ReadError<I>>> {
+        let (_, input) = require_ws(self.0.clone())?;
+        let (name, input) = parse_word(input)?;
+        let (_, input) = parse_ws(input)?;
+        let (external_id, input) = parse_external_id(input)?;
+        let (_, mut input) = parse_ws(input)?;
+
+        let mut subset = vec![];
+
+        if input.as_bytes().first() == Some(&b'[') {
+            input.split_to(1);
+            (subset, input) = parse_subset(input)?;
+            (_, input) = expect(input, b']', "]")?;
+            (_, input) = parse_ws(input)?;
+        }
+
+        if !input.is_empty() {
+            return Err(ControlFlow::Fatal(ReadError::Expect(
+                ReadKind::Keyword(">"),
+                input,
+            )));
+        }
+
+        Ok(DocTypeDecl {
+            name,
+            external_id,
+            subset,
+        })
+    }
+}
+
+/// Turn a recoverable error into a fatal one, used once a declaration keyword is matched.
+#[inline(always)]
+fn fatal<T, I>(result: parserc::Result<T, I, ReadError<I>>) -> parserc::Result<T, I, ReadError<I>>
+where
+    I: Debug,
+{
+    match result {
+        Err(ControlFlow::Recovable(err)) => Err(ControlFlow::Fatal(err)),
+        result => result,
+    }
+}
+
+#[inline(always)]
+fn require_ws<I>(input: I) -> parserc::Result<I, I, ReadError<I>>
+where
+    I: Input<Item = u8> + Debug + Clone,
+{
+    fatal(ensure_ws(input))
+}
+
+#[inline(always)]
+fn expect<I>(mut input: I, c: u8, keyword: &'static str) -> parserc::Result<(), I, ReadError<I>>
+where
+    I: Input<Item = u8> + AsBytes + Debug,
+{
+    if input.as_bytes().first() != Some(&c) {
+        return Err(ControlFlow::Fatal(ReadError::Expect(
+            ReadKind::Keyword(keyword),
+            input,
+        )));
+    }
+
+    input.split_to(1);
+
+    Ok(((), input))
+}
+
+/// Returns true if `input` starts with `keyword`, consuming it.
+#[inline(always)]
+fn eat<I>(input: &mut I, keyword: &[u8]) -> bool
+where
+    I: Input<Item = u8> + AsBytes,
+{
+    if input.as_bytes().starts_with(keyword) {
+        input.split_to(keyword.len());
+        true
+    } else {
+        false
+    }
+}
+
+/// Parse a name, nmtoken or keyword.
+#[inline(always)]
+fn parse_word<I>(input: I) -> parserc::Result<I, I, ReadError<I>>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    let (word, rest) = take_till(|c: u8| {
+        is_ws(c)
+            || matches!(
+                c,
+                b'<' | b'>'
+                    | b'('
+                    | b')'
+                    | b'['
+                    | b']'
+                    | b'|'
+                    | b','
+                    | b'*'
+                    | b'+'
+                    | b'?'
+                    | b'%'
+                    | b';'
+                    | b'"'
+                    | b'\''
+            )
+    })
+    .parse(input.clone())?;
+
+    if word.is_empty() {
+        return Err(ControlFlow::Fatal(ReadError::Expect(ReadKind::Name, input)));
+    }
+
+    Ok((word, rest))
+}
+
+/// Parse `S? '>'`.
+#[inline(always)]
+fn parse_end<I>(input: I) -> parserc::Result<(), I, ReadError<I>>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    let (_, input) = parse_ws(input)?;
+
+    expect(input, b'>', ">")
+}
+
+fn parse_external_id<I>(mut input: I) -> parserc::Result<Option<ExternalId<I>>, I, ReadError<I>>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    if eat(&mut input, b"SYSTEM") {
+        let (_, input) = require_ws(input)?;
+        let (system, input) = fatal(parse_literal(input))?;
+
+        return Ok((Some(ExternalId::System(system)), input));
+    }
+
+    if eat(&mut input, b"PUBLIC") {
+        let (_, input) = require_ws(input)?;
+        let (public, input) = fatal(parse_literal(input))?;
+        let (s, rest) = parse_ws(input.clone())?;
+
+        if !s.is_empty() && matches!(rest.as_bytes().first(), Some(b'"' | b'\'')) {
+            let (system, input) = fatal(parse_literal(rest))?;
+
+            return Ok((
+                Some(ExternalId::Public {
+                    public,
+                    system: Some(system),
+                }),
+                input,
+            ));
+        }
+
+        return Ok((
+            Some(ExternalId::Public {
+                public,
+                system: None,
+            }),
+            input,
+        ));
+    }
+
+    Ok((None, input))
+}
+
+/// Parse markup declarations up to the closing `]` of the internal subset.
+fn parse_subset<I>(mut input: I) -> parserc::Result<Vec<MarkupDecl<I>>, I, ReadError<I>>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    let mut decls = vec![];
+
+    loop {
+        (_, input) = parse_ws(input)?;
+
+        let decl;
+
+        (decl, input) = match input.as_bytes() {
+            [] | [b']', ..] => return Ok((decls, input)),
+            bytes if bytes.starts_with(b"<!ELEMENT") => parse_element(input)?,
+            bytes if bytes.starts_with(b"<!ATTLIST") => parse_attlist(input)?,
+            bytes if bytes.starts_with(b"<!ENTITY") => parse_entity(input)?,
+            bytes if bytes.starts_with(b"<!NOTATION") => parse_notation(input)?,
+            bytes if bytes.starts_with(b"<!--") => {
+                let (comment, input) = fatal(Comment::parse(input))?;
+                (MarkupDecl::Comment(comment), input)
+            }
+            bytes if bytes.starts_with(b"<?") => {
+                let (pi, input) = fatal(PI::parse(input))?;
+                (MarkupDecl::PI(pi), input)
+            }
+            [b'%', ..] => {
+                input.split_to(1);
+                let (name, input) = parse_word(input)?;
+                let (_, input) = expect(input, b';', ";")?;
+                (MarkupDecl::PERef(name), input)
+            }
+            _ => {
+                return Err(ControlFlow::Fatal(ReadError::Unexpect(
+                    ReadKind::MarkupDecl,
+                    input,
+                )));
+            }
+        };
+
+        decls.push(decl);
+    }
+}
+
+fn parse_element<I>(mut input: I) -> parserc::Result<MarkupDecl<I>, I, ReadError<I>>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    input.split_to(b"<!ELEMENT".len());
+
+    let (_, input) = require_ws(input)?;
+    let (name, input) = parse_word(input)?;
+    let (_, mut input) = require_ws(input)?;
+
+    let content = if eat(&mut input, b"EMPTY") {
+        ContentSpec::Empty
+    } else if eat(&mut input, b"ANY") {
+        ContentSpec::Any
+    } else {
+        (_, input) = expect(input, b'(', "(")?;
+        (_, input) = parse_ws(input)?;
+
+        if eat(&mut input, b"#PCDATA") {
+            let names;
+            (names, input) = parse_mixed(input)?;
+            ContentSpec::Mixed(names)
+        } else {
+            let model;
+            (model, input) = parse_group(input)?;
+            ContentSpec::Children(model)
+        }
+    };
+
+    let (_, input) = parse_end(input)?;
+
+    Ok((MarkupDecl::Element(ElementDecl { name, content }), input))
+}
+
+/// Parse the rest of [`Mixed`](https://www.w3.org/TR/xml11/#NT-Mixed) after `#PCDATA`.
+fn parse_mixed<I>(mut input: I) -> parserc::Result<Vec<I>, I, ReadError<I>>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    let mut names = vec![];
+
+    loop {
+        (_, input) = parse_ws(input)?;
+
+        if eat(&mut input, b"|") {
+            let name;
+            (_, input) = parse_ws(input)?;
+            (name, input) = parse_word(input)?;
+            names.push(name);
+        } else {
+            (_, input) = expect(input, b')', ")")?;
+
+            // `*` is only optional for `(#PCDATA)`.
+            if !eat(&mut input, b"*") && !names.is_empty() {
+                return Err(ControlFlow::Fatal(ReadError::Expect(
+                    ReadKind::Keyword("*"),
+                    input,
+                )));
+            }
+
+            return Ok((names, input));
+        }
+    }
+}
+
+/// Parse a `choice` or `seq` after its opening `(`.
+fn parse_group<I>(mut input: I) -> parserc::Result<ContentParticle<I>, I, ReadError<I>>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    let mut items = vec![];
+    let mut sep = None;
+
+    loop {
+        let item;
+        (_, input) = parse_ws(input)?;
+        (item, input) = parse_cp(input)?;
+        (_, input) = parse_ws(input)?;
+
+        items.push(item);
+
+        match input.as_bytes().first() {
+            Some(b')') => {
+                input.split_to(1);
+                break;
+            }
+            Some(&c @ (b',' | b'|')) if sep.is_none_or(|sep| sep == c) => {
+                sep = Some(c);
+                input.split_to(1);
+            }
+            _ => {
+                return Err(ControlFlow::Fatal(ReadError::Expect(
+                    ReadKind::Keyword(")"),
+                    input,
+                )));
+            }
+        }
+    }
+
+    let (repeat, input) = parse_repeat(input);
+
+    if sep == Some(b'|') {
+        Ok((ContentParticle::Choice(items, repeat), input))
+    } else {
+        Ok((ContentParticle::Seq(items, repeat), input))
+    }
+}
+
+fn parse_cp<I>(mut input: I) -> parserc::Result<ContentParticle<I>, I, ReadError<I>>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    if eat(&mut input, b"(") {
+        return parse_group(input);
+    }
+
+    let (name, input) = parse_word(input)?;
+    let (repeat, input) = parse_repeat(input);
+
+    Ok((ContentParticle::Name(name, repeat), input))
+}
+
+#[inline(always)]
+fn parse_repeat<I>(mut input: I) -> (Repeat, I)
+where
+    I: Input<Item = u8> + AsBytes,
+{
+    let repeat = match input.as_bytes().first() {
+        Some(b'?') => Repeat::Optional,
+        Some(b'*') => Repeat::ZeroOrMore,
+        Some(b'+') => Repeat::OneOrMore,
+        _ => return (Repeat::One, input),
+    };
+
+    input.split_to(1);
+
+    (repeat, input)
+}
+
+fn parse_attlist<I>(mut input: I) -> parserc::Result<MarkupDecl<I>, I, ReadError<I>>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    input.split_to(b"<!ATTLIST".len());
+
+    let (_, input) = require_ws(input)?;
+    let (element, mut input) = parse_word(input)?;
+
+    let mut attrs = vec![];
+
+    loop {
+        let (s, mut rest) = parse_ws(input)?;
+
+        if eat(&mut rest, b">") {
+            return Ok((MarkupDecl::AttList(AttListDecl { element, attrs }), rest));
+        }
+
+        if s.is_empty() {
+            return Err(ControlFlow::Fatal(ReadError::Expect(ReadKind::S, rest)));
+        }
+
+        let (name, rest) = parse_word(rest)?;
+        let (_, rest) = require_ws(rest)?;
+        let (ty, rest) = parse_att_type(rest)?;
+        let (_, rest) = require_ws(rest)?;
+        let (default, rest) = parse_default(rest)?;
+
+        attrs.push(AttDef { name, ty, default });
+
+        input = rest;
+    }
+}
+
+fn parse_att_type<I>(mut input: I) -> parserc::Result<AttType<I>, I, ReadError<I>>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    if eat(&mut input, b"(") {
+        let (names, input) = parse_names(input)?;
+        return Ok((AttType::Enumeration(names), input));
+    }
+
+    let (word, input) = parse_word(input)?;
+
+    let ty = match word.as_bytes() {
+        b"CDATA" => AttType::CData,
+        b"ID" => AttType::Id,
+        b"IDREF" => AttType::IdRef,
+        b"IDREFS" => AttType::IdRefs,
+        b"ENTITY" => AttType::Entity,
+        b"ENTITIES" => AttType::Entities,
+        b"NMTOKEN" => AttType::NmToken,
+        b"NMTOKENS" => AttType::NmTokens,
+        b"NOTATION" => {
+            let (_, input) = require_ws(input)?;
+            let (_, input) = expect(input, b'(', "(")?;
+            let (names, input) = parse_names(input)?;
+            return Ok((AttType::Notation(names), input));
+        }
+        _ => {
+            return Err(ControlFlow::Fatal(ReadError::Unexpect(
+                ReadKind::Name,
+                word,
+            )));
+        }
+    };
+
+    Ok((ty, input))
+}
+
+/// Parse `Name (S? '|' S? Name)* S? ')'` after the opening `(`.
+fn parse_names<I>(mut input: I) -> parserc::Result<Vec<I>, I, ReadError<I>>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    let mut names = vec![];
+
+    loop {
+        let name;
+        (_, input) = parse_ws(input)?;
+        (name, input) = parse_word(input)?;
+        (_, input) = parse_ws(input)?;
+
+        names.push(name);
+
+        if !eat(&mut input, b"|") {
+            let (_, input) = expect(input, b')', ")")?;
+            return Ok((names, input));
+        }
+    }
+}
+
+fn parse_default<I>(mut input: I) -> parserc::Result<DefaultDecl<I>, I, ReadError<I>>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    if eat(&mut input, b"#REQUIRED") {
+        return Ok((DefaultDecl::Required, input));
+    }
+
+    if eat(&mut input, b"#IMPLIED") {
+        return Ok((DefaultDecl::Implied, input));
+    }
+
+    if eat(&mut input, b"#FIXED") {
+        let (_, input) = require_ws(input)?;
+        let (value, input) = fatal(parse_literal(input))?;
+        return Ok((DefaultDecl::Fixed(value), input));
+    }
+
+    let (value, input) = fatal(parse_literal(input))?;
+
+    Ok((DefaultDecl::Value(value), input))
+}
+
+fn parse_entity<I>(mut input: I) -> parserc::Result<MarkupDecl<I>, I, ReadError<I>>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    input.split_to(b"<!ENTITY".len());
+
+    let (_, mut input) = require_ws(input)?;
+
+    let parameter = eat(&mut input, b"%");
+
+    if parameter {
+        (_, input) = require_ws(input)?;
+    }
+
+    let (name, input) = parse_word(input)?;
+    let (_, input) = require_ws(input)?;
+
+    let (def, input) = if matches!(input.as_bytes().first(), Some(b'"' | b'\'')) {
+        let (value, input) = fatal(parse_literal(input))?;
+        (EntityDef::Internal(value), input)
+    } else {
+        let (id, input) = parse_required_id(input)?;
+
+        if let ExternalId::Public { system: None, .. } = id {
+            return Err(ControlFlow::Fatal(ReadError::Expect(
+                ReadKind::Quote,
+                input,
+            )));
+        }
+
+        let (s, mut rest) = parse_ws(input.clone())?;
+
+        if !parameter && !s.is_empty() && eat(&mut rest, b"NDATA") {
+            let (_, rest) = require_ws(rest)?;
+            let (ndata, rest) = parse_word(rest)?;
+
+            (
+                EntityDef::External {
+                    id,
+                    ndata: Some(ndata),
+                },
+                rest,
+            )
+        } else {
+            (EntityDef::External { id, ndata: None }, input)
+        }
+    };
+
+    let (_, input) = parse_end(input)?;
+
+    Ok((
+        MarkupDecl::Entity(EntityDecl {
+            name,
+            parameter,
+            def,
+        }),
+        input,
+    ))
+}
+
+fn parse_notation<I>(mut input: I) -> parserc::Result<MarkupDecl<I>, I, ReadError<I>>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    input.split_to(b"<!NOTATION".len());
+
+    let (_, input) = require_ws(input)?;
+    let (name, input) = parse_word(input)?;
+    let (_, input) = require_ws(input)?;
+    let (id, input) = parse_required_id(input)?;
+    let (_, input) = parse_end(input)?;
+
+    Ok((MarkupDecl::Notation(NotationDecl { name, id }), input))
+}
+
+#[inline(always)]
+fn parse_required_id<I>(input: I) -> parserc::Result<ExternalId<I>, I, ReadError<I>>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    match parse_external_id(input.clone())? {
+        (Some(id), input) => Ok((id, input)),
+        (None, _) => Err(ControlFlow::Fatal(ReadError::Expect(
+            ReadKind::Keyword("SYSTEM"),
+            input,
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parserc::{ControlFlow, Parse};
+
+    use crate::reader::{
+        AttDef, AttType, ContentParticle, ContentSpec, DefaultDecl, DocType, EntityDef, ExternalId,
+        MarkupDecl, ReadError, ReadKind, Repeat, XmlNode, XmlReader,
+    };
+
+    fn parse(input: &str) -> Vec<MarkupDecl<&[u8]>> {
+        let (doctype, _) = DocType::parse(input.as_bytes()).unwrap();
+        doctype.parse_decl().unwrap().subset
+    }
+
+    #[test]
+    fn test_parse_decl() {
+        let (doctype, _) = DocType::parse(
+            br#"<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Strict//EN" "xhtml1-strict.dtd">"#
+                .as_slice(),
+        )
+        .unwrap();
+
+        let decl = doctype.parse_decl().unwrap();
+
+        assert_eq!(decl.name, b"html");
+        assert_eq!(
+            decl.external_id,
+            Some(ExternalId::Public {
+                public: b"-//W3C//DTD XHTML 1.0 Strict//EN".as_slice(),
+                system: Some(b"xhtml1-strict.dtd".as_slice())
+            })
+        );
+        assert!(decl.subset.is_empty());
+    }
+
+    #[test]
+    fn test_element_decl() {
+        let decls = parse(
+            r#"<!DOCTYPE doc [
+                <!ELEMENT br EMPTY>
+                <!ELEMENT p (#PCDATA | em)*>
+                <!ELEMENT doc (head, (p | list)+, foot?)>
+            ]>"#,
+        );
+
+        let contents = decls
+            .into_iter()
+            .map(|decl| match decl {
+                MarkupDecl::Element(el) => el.content,
+                decl => panic!("unexpected {:?}", decl),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            contents,
+            [
+                ContentSpec::Empty,
+                ContentSpec::Mixed(vec![b"em".as_slice()]),
+                ContentSpec::Children(ContentParticle::Seq(
+                    vec![
+                        ContentParticle::Name(b"head".as_slice(), Repeat::One),
+                        ContentParticle::Choice(
+                            vec![
+                                ContentParticle::Name(b"p".as_slice(), Repeat::One),
+                                ContentParticle::Name(b"list".as_slice(), Repeat::One)
+                            ],
+                            Repeat::OneOrMore
+                        ),
+                        ContentParticle::Name(b"foot".as_slice(), Repeat::Optional),
+                    ],
+                    Repeat::One
+                ))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_attlist_entity_notation() {
+        let decls = parse(
+            r#"<!DOCTYPE doc [
+                <!-- attributes -->
+                <!ATTLIST img src CDATA #REQUIRED kind (a|b) "a" format NOTATION (gif) #IMPLIED v CDATA #FIXED '1'>
+                <!ENTITY h "hardcover">
+                <!ENTITY % p SYSTEM "p.ent">
+                <!ENTITY pic SYSTEM "pic.gif" NDATA gif>
+                <!NOTATION gif PUBLIC "image/gif">
+                %p;
+            ]>"#,
+        );
+
+        assert!(matches!(decls[0], MarkupDecl::Comment(_)));
+
+        let MarkupDecl::AttList(attlist) = &decls[1] else {
+            panic!("unexpected {:?}", decls[1]);
+        };
+
+        assert_eq!(attlist.element, b"img");
+        assert_eq!(
+            attlist.attrs,
+            [
+                AttDef {
+                    name: b"src".as_slice(),
+                    ty: AttType::CData,
+                    default: DefaultDecl::Required
+                },
+                AttDef {
+                    name: b"kind".as_slice(),
+                    ty: AttType::Enumeration(vec![b"a".as_slice(), b"b".as_slice()]),
+                    default: DefaultDecl::Value(b"a".as_slice())
+                },
+                AttDef {
+                    name: b"format".as_slice(),
+                    ty: AttType::Notation(vec![b"gif".as_slice()]),
+                    default: DefaultDecl::Implied
+                },
+                AttDef {
+                    name: b"v".as_slice(),
+                    ty: AttType::CData,
+                    default: DefaultDecl::Fixed(b"1".as_slice())
+                }
+            ]
+        );
+
+        assert!(matches!(
+            &decls[2],
+            MarkupDecl::Entity(entity) if !entity.parameter && entity.def == EntityDef::Internal(b"hardcover".as_slice())
+        ));
+        assert!(matches!(&decls[3], MarkupDecl::Entity(entity) if entity.parameter));
+        assert!(matches!(
+            &decls[4],
+            MarkupDecl::Entity(entity) if matches!(entity.def, EntityDef::External { ndata: Some(b"gif"), .. })
+        ));
+        assert!(matches!(
+            &decls[5],
+            MarkupDecl::Notation(notation) if notation.id == ExternalId::Public { public: b"image/gif".as_slice(), system: None }
+        ));
+        assert_eq!(decls[6], MarkupDecl::PERef(b"p".as_slice()));
+    }
+
+    #[test]
+    fn test_invalid_decl() {
+        let (doctype, _) =
+            DocType::parse(br#"<!DOCTYPE doc [ <!ELEMENT a (b, c | d)> ]>"#.as_slice()).unwrap();
+
+        assert_eq!(
+            doctype.parse_decl(),
+            Err(ControlFlow::Fatal(ReadError::Expect(
+                ReadKind::Keyword(")"),
+                b"| d)> ]".as_slice()
+            )))
+        );
+
+        let (doctype, _) = DocType::parse(br#"<!DOCTYPE doc [ <!FOO> ]>"#.as_slice()).unwrap();
+
+        assert!(matches!(
+            doctype.parse_decl(),
+            Err(ControlFlow::Fatal(ReadError::Unexpect(
+                ReadKind::MarkupDecl,
+                _
+            )))
+        ));
+    }
+
+    #[test]
+    fn test_register_entities() {
+        let input =
+            br#"<?xml version="1.0"?><!DOCTYPE book [<!ENTITY h "hardcover">]><book>&h;</book>"#;
+
+        let mut reader = XmlReader::from(input.as_slice());
+        let mut texts = vec![];
+
+        while let Some(node) = reader.read_next().unwrap() {
+            match node {
+                XmlNode::DocType(doctype) => {
+                    let decl = doctype.parse_decl().unwrap();
+                    reader.entities_mut().insert_decls(&decl.subset);
+                }
+                XmlNode::CharData(chardata) => {
+                    texts.push(reader.text(&chardata).unwrap().into_owned());
+                }
+                _ => {}
+            }
+        }
+
+        assert_eq!(texts, ["hardcover"]);
+    }
+}
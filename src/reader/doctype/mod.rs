@@ -0,0 +1,298 @@
+use std::fmt::Debug;
+
+use parserc::{AsBytes, ControlFlow, Input, Parse, Parser, keyword, take_till};
+
+use crate::reader::{ensure_ws, is_ws, parse_literal, parse_ws};
+
+use super::{ReadError, ReadKind};
+
+#[cfg(feature = "dtd")]
+mod decl;
+#[cfg(feature = "dtd")]
+pub use decl::*;
+
+#[cfg(feature = "dtd")]
+mod valid;
+#[cfg(feature = "dtd")]
+pub use valid::*;
+
+/// See [`doctype`](https://www.w3.org/TR/xml11/#NT-doctypedecl)
+#[derive(Debug, PartialEq, Clone)]
+pub struct DocType<I>(pub I);
+
+impl<I> Parse<I> for DocType<I>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    type Error = ReadError<I>;
+
+    #[inline(always)]
+    fn parse(input: I) -> parserc::Result<Self, I, Self::Error> {
+        let (_, mut input) = keyword("<!DOCTYPE").parse(input)?;
+
+        let mut content = input.clone();
+
+        let mut len = 0;
+
+        let mut count = 1usize;
+
+        loop {
+            let seg;
+            (seg, input) =
+                take_till(|c: u8| matches!(c, b'>' | b'<' | b'"' | b'\'')).parse(input)?;
+
+            len += seg.len();
+
+            match input.iter().next() {
+                Some(b'"') | Some(b'\'') => {
+                    let quote;
+                    (quote, input) = parse_literal(input)?;
+                    len += quote.len() + 2;
+                }
+                Some(b'<') => {
+                    input.split_to(1);
+                    len += 1;
+                    count += 1;
+                }
+                Some(b'>') => {
+                    input.split_to(1);
+                    count -= 1;
+
+                    if count == 0 {
+                        return Ok((Self(content.split_to(len)), input));
+                    } else {
+                        len += 1;
+                    }
+                }
+                _ => {
+                    break;
+                }
+            }
+        }
+
+        Err(ControlFlow::Fatal(ReadError::Expect(
+            ReadKind::Keyword(">"),
+            input,
+        )))
+    }
+}
+
+/// The parts of a doctype declaration, see [`DocType::head`].
+struct Head<I> {
+    name: I,
+    public_id: Option<I>,
+    system_id: Option<I>,
+    subset: Option<I>,
+}
+
+impl<I> DocType<I>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    /// Returns the name of the root element.
+    pub fn root_name(&self) -> Result<I, ControlFlow<ReadError<I>>> {
+        Ok(self.head()?.name)
+    }
+
+    /// Returns the public identifier of the external subset, `PUBLIC "id" "uri"`.
+    pub fn public_id(&self) -> Result<Option<I>, ControlFlow<ReadError<I>>> {
+        Ok(self.head()?.public_id)
+    }
+
+    /// Returns the system identifier, i.e. the uri, of the external subset.
+    pub fn system_id(&self) -> Result<Option<I>, ControlFlow<ReadError<I>>> {
+        Ok(self.head()?.system_id)
+    }
+
+    /// Returns the raw internal subset between `[` and `]`.
+    ///
+    /// The declarations are not parsed, see `parse_decl` of the `dtd` feature.
+    pub fn internal_subset(&self) -> Result<Option<I>, ControlFlow<ReadError<I>>> {
+        Ok(self.head()?.subset)
+    }
+
+    /// Split the declaration into its parts, without looking into the internal subset.
+    fn head(&self) -> Result<Head<I>, ControlFlow<ReadError<I>>> {
+        let (_, input) = ensure_ws(self.0.clone()).map_err(fatal)?;
+        let (name, input) = take_till(|c: u8| is_ws(c) || c == b'[').parse(input)?;
+
+        if name.is_empty() {
+            return Err(ControlFlow::Fatal(ReadError::Expect(ReadKind::Name, input)));
+        }
+
+        let (_, input) = parse_ws(input)?;
+        let ((public_id, system_id), input) = external_id(input)?;
+        let (_, input) = parse_ws(input)?;
+
+        let bytes = input.as_bytes();
+
+        let subset = match bytes.first() {
+            None => None,
+            Some(b'[') => {
+                // the subset ends at the last `]`, only whitespace may follow it.
+                let end = bytes
+                    .iter()
+                    .rposition(|c| *c == b']')
+                    .filter(|end| bytes[end + 1..].iter().all(|c| is_ws(*c)))
+                    .ok_or_else(|| {
+                        ControlFlow::Fatal(ReadError::Expect(
+                            ReadKind::Keyword("]"),
+                            input.clone().split_off(input.len()),
+                        ))
+                    })?;
+
+                Some(input.clone().split_off(1).split_to(end - 1))
+            }
+            Some(_) => {
+                return Err(ControlFlow::Fatal(ReadError::Expect(
+                    ReadKind::Keyword(">"),
+                    input,
+                )));
+            }
+        };
+
+        Ok(Head {
+            name,
+            public_id,
+            system_id,
+            subset,
+        })
+    }
+}
+
+/// Parse an optional external id into its public and system literal.
+fn external_id<I>(mut input: I) -> parserc::Result<(Option<I>, Option<I>), I, ReadError<I>>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    let public = if input.as_bytes().starts_with(b"PUBLIC") {
+        true
+    } else if input.as_bytes().starts_with(b"SYSTEM") {
+        false
+    } else {
+        return Ok(((None, None), input));
+    };
+
+    input.split_to(6);
+
+    let (_, input) = ensure_ws(input).map_err(fatal)?;
+    let (literal, input) = parse_literal(input).map_err(fatal)?;
+
+    if !public {
+        return Ok(((None, Some(literal)), input));
+    }
+
+    let (s, rest) = parse_ws(input.clone())?;
+
+    if s.is_empty() || !matches!(rest.as_bytes().first(), Some(b'"' | b'\'')) {
+        return Ok(((Some(literal), None), input));
+    }
+
+    let (system, input) = parse_literal(rest).map_err(fatal)?;
+
+    Ok(((Some(literal), Some(system)), input))
+}
+
+/// Malformed parts of a captured declaration can't be parsed otherwise.
+fn fatal<I>(err: ControlFlow<ReadError<I>>) -> ControlFlow<ReadError<I>>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    match err {
+        ControlFlow::Recovable(err) => ControlFlow::Fatal(err),
+        err => err,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parserc::{ControlFlow, Parse};
+
+    use crate::reader::{ReadError, ReadKind};
+
+    use super::DocType;
+
+    #[test]
+    fn test_doc_type() {
+        assert_eq!(
+            DocType::parse(br#"<!DOCTYPE greeting SYSTEM "hello.dtd">"#.as_slice()),
+            Ok((
+                DocType(br#" greeting SYSTEM "hello.dtd""#.as_slice()),
+                br#""#.as_slice()
+            ))
+        );
+
+        assert_eq!(
+            DocType::parse(
+                br#"<!DOCTYPE greeting [
+                    <!ELEMENT greeting (#PCDATA)>
+                    <!ELEMENT greeting (#PCDATA)>
+                    <!ELEMENT greeting (#PCDATA)>
+                    ]>"#
+                .as_slice()
+            ),
+            Ok((
+                DocType(
+                    br#" greeting [
+                    <!ELEMENT greeting (#PCDATA)>
+                    <!ELEMENT greeting (#PCDATA)>
+                    <!ELEMENT greeting (#PCDATA)>
+                    ]"#
+                    .as_slice()
+                ),
+                br#""#.as_slice()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_accessors() {
+        let doctype =
+            DocType(br#" html PUBLIC "-//W3C//DTD XHTML 1.0 Strict//EN" 'x.dtd'"#.as_slice());
+
+        assert_eq!(doctype.root_name(), Ok(b"html".as_slice()));
+        assert_eq!(
+            doctype.public_id(),
+            Ok(Some(b"-//W3C//DTD XHTML 1.0 Strict//EN".as_slice()))
+        );
+        assert_eq!(doctype.system_id(), Ok(Some(b"x.dtd".as_slice())));
+        assert_eq!(doctype.internal_subset(), Ok(None));
+
+        let doctype = DocType(br#" a SYSTEM "a.dtd" [ <!ENTITY b "]"> ] "#.as_slice());
+
+        assert_eq!(doctype.root_name(), Ok(b"a".as_slice()));
+        assert_eq!(doctype.public_id(), Ok(None));
+        assert_eq!(doctype.system_id(), Ok(Some(b"a.dtd".as_slice())));
+        assert_eq!(
+            doctype.internal_subset(),
+            Ok(Some(br#" <!ENTITY b "]"> "#.as_slice()))
+        );
+
+        let doctype = DocType(b" a[]".as_slice());
+
+        assert_eq!(doctype.root_name(), Ok(b"a".as_slice()));
+        assert_eq!(doctype.internal_subset(), Ok(Some(b"".as_slice())));
+
+        assert_eq!(
+            DocType(b"".as_slice()).root_name(),
+            Err(ControlFlow::Fatal(ReadError::Expect(
+                ReadKind::S,
+                b"".as_slice()
+            )))
+        );
+        assert_eq!(
+            DocType(b" a SYSTEM".as_slice()).system_id(),
+            Err(ControlFlow::Fatal(ReadError::Expect(
+                ReadKind::S,
+                b"".as_slice()
+            )))
+        );
+        assert_eq!(
+            DocType(b" a b".as_slice()).root_name(),
+            Err(ControlFlow::Fatal(ReadError::Expect(
+                ReadKind::Keyword(">"),
+                b"b".as_slice()
+            )))
+        );
+    }
+}
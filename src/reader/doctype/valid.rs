@@ -0,0 +1,537 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Debug,
+};
+
+use parserc::{AsBytes, ControlFlow, Input};
+
+use crate::{
+    chars::is_ws,
+    reader::{
+        AttDef, AttType, Attr, ContentParticle, ContentSpec, DefaultDecl, DocTypeDecl, EventSource,
+        MarkupDecl, Position, ReadError, Repeat, XmlNode, position::offset_in,
+    },
+};
+
+/// A validity constraint of the document type violated by the document, see [`DtdValidator`].
+#[derive(Debug, PartialEq, Clone)]
+pub struct ValidationError<I> {
+    /// human readable description.
+    pub message: String,
+    /// the input slice the error refers to, e.g. an element or attribute name.
+    pub at: I,
+}
+
+impl<I> ValidationError<I>
+where
+    I: AsBytes,
+{
+    /// Returns the position of this error in the `source` document passed to the reader.
+    pub fn position(&self, source: &[u8]) -> Position {
+        Position::locate(source, offset_in(source, self.at.as_bytes()))
+    }
+}
+
+/// An open element and the content seen so far.
+struct Open<I> {
+    name: I,
+    children: Vec<I>,
+    /// the first non-whitespace text of the content.
+    text: Option<I>,
+}
+
+/// Checks the events of a document against the declarations of its internal subset.
+///
+/// Checked are element content models, required attributes, `#FIXED` values and
+/// enumerated and notation attribute values. Parameter entity references and the external
+/// subset are not loaded, so documents relying on them report undeclared elements.
+pub struct DtdValidator<I> {
+    root: I,
+    elements: HashMap<Vec<u8>, ContentSpec<I>>,
+    attlists: HashMap<Vec<u8>, Vec<AttDef<I>>>,
+    stack: Vec<Open<I>>,
+    seen_root: bool,
+}
+
+impl<I> DtdValidator<I>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    /// Create a validator for the declarations of `decl`.
+    ///
+    /// Repeated declarations of an element or attribute are ignored, the first one is binding.
+    pub fn new(decl: &DocTypeDecl<I>) -> Self {
+        let mut elements = HashMap::new();
+        let mut attlists: HashMap<Vec<u8>, Vec<AttDef<I>>> = HashMap::new();
+
+        for markup in &decl.subset {
+            match markup {
+                MarkupDecl::Element(el) => {
+                    elements
+                        .entry(el.name.as_bytes().to_vec())
+                        .or_insert_with(|| el.content.clone());
+                }
+                MarkupDecl::AttList(attlist) => {
+                    let defs = attlists
+                        .entry(attlist.element.as_bytes().to_vec())
+                        .or_default();
+
+                    for def in &attlist.attrs {
+                        if !defs
+                            .iter()
+                            .any(|d| d.name.as_bytes() == def.name.as_bytes())
+                        {
+                            defs.push(def.clone());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            root: decl.name.clone(),
+            elements,
+            attlists,
+            stack: vec![],
+            seen_root: false,
+        }
+    }
+
+    /// Check `node`, pushing any problem found to `errors`.
+    ///
+    /// Malformed attributes are left to the reader and skipped here.
+    pub fn check(&mut self, node: &XmlNode<I>, errors: &mut Vec<ValidationError<I>>) {
+        match node {
+            XmlNode::Start(start) => {
+                if let Some(parent) = self.stack.last_mut() {
+                    parent.children.push(start.name.clone());
+                } else if !self.seen_root {
+                    self.seen_root = true;
+
+                    if start.name.as_bytes() != self.root.as_bytes() {
+                        errors.push(ValidationError {
+                            message: format!(
+                                "root element `{}` does not match the doctype name `{}`",
+                                String::from_utf8_lossy(start.name.as_bytes()),
+                                String::from_utf8_lossy(self.root.as_bytes())
+                            ),
+                            at: start.name.clone(),
+                        });
+                    }
+                }
+
+                if !self.elements.contains_key(start.name.as_bytes()) {
+                    errors.push(ValidationError {
+                        message: format!(
+                            "element `{}` is not declared",
+                            String::from_utf8_lossy(start.name.as_bytes())
+                        ),
+                        at: start.name.clone(),
+                    });
+                }
+
+                let attrs = start.attrs().flatten().collect::<Vec<_>>();
+
+                self.check_attrs(&start.name, &attrs, errors);
+
+                let open = Open {
+                    name: start.name.clone(),
+                    children: vec![],
+                    text: None,
+                };
+
+                if start.is_empty {
+                    self.check_content(open, errors);
+                } else {
+                    self.stack.push(open);
+                }
+            }
+            XmlNode::End(_) => {
+                if let Some(open) = self.stack.pop() {
+                    self.check_content(open, errors);
+                }
+            }
+            XmlNode::CharData(text) => self.push_text(&text.0),
+            XmlNode::CData(cdata) => self.push_text(&cdata.0),
+            XmlNode::Text(text) if !text.bytes().all(is_ws) => {
+                if let Some(open) = self.stack.last_mut()
+                    && open.text.is_none()
+                {
+                    open.text = Some(open.name.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn push_text(&mut self, text: &I) {
+        if let Some(open) = self.stack.last_mut()
+            && open.text.is_none()
+            && !text.as_bytes().iter().all(|c| is_ws(*c))
+        {
+            open.text = Some(text.clone());
+        }
+    }
+
+    fn check_attrs(&self, element: &I, attrs: &[Attr<I>], errors: &mut Vec<ValidationError<I>>) {
+        let defs = self
+            .attlists
+            .get(element.as_bytes())
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        for attr in attrs {
+            let Some(def) = defs
+                .iter()
+                .find(|def| def.name.as_bytes() == attr.name.as_bytes())
+            else {
+                errors.push(ValidationError {
+                    message: format!(
+                        "attribute `{}` is not declared for `{}`",
+                        String::from_utf8_lossy(attr.name.as_bytes()),
+                        String::from_utf8_lossy(element.as_bytes())
+                    ),
+                    at: attr.name.clone(),
+                });
+                continue;
+            };
+
+            let value = match def.ty {
+                AttType::CData => attr.value.as_bytes(),
+                // tokenized types are compared after whitespace normalization.
+                _ => attr.value.as_bytes().trim_ascii(),
+            };
+
+            if let AttType::Enumeration(allowed) | AttType::Notation(allowed) = &def.ty
+                && !allowed.iter().any(|name| name.as_bytes() == value)
+            {
+                errors.push(ValidationError {
+                    message: format!(
+                        "value `{}` of attribute `{}` is not one of the declared values",
+                        String::from_utf8_lossy(value),
+                        String::from_utf8_lossy(attr.name.as_bytes())
+                    ),
+                    at: attr.value.clone(),
+                });
+            }
+
+            if let DefaultDecl::Fixed(fixed) = &def.default
+                && fixed.as_bytes() != value
+            {
+                errors.push(ValidationError {
+                    message: format!(
+                        "attribute `{}` must have the fixed value `{}`",
+                        String::from_utf8_lossy(attr.name.as_bytes()),
+                        String::from_utf8_lossy(fixed.as_bytes())
+                    ),
+                    at: attr.value.clone(),
+                });
+            }
+        }
+
+        for def in defs {
+            if matches!(def.default, DefaultDecl::Required)
+                && !attrs
+                    .iter()
+                    .any(|attr| attr.name.as_bytes() == def.name.as_bytes())
+            {
+                errors.push(ValidationError {
+                    message: format!(
+                        "required attribute `{}` is missing",
+                        String::from_utf8_lossy(def.name.as_bytes())
+                    ),
+                    at: element.clone(),
+                });
+            }
+        }
+    }
+
+    fn check_content(&self, open: Open<I>, errors: &mut Vec<ValidationError<I>>) {
+        let Some(spec) = self.elements.get(open.name.as_bytes()) else {
+            return;
+        };
+
+        let name = String::from_utf8_lossy(open.name.as_bytes());
+
+        match spec {
+            ContentSpec::Any => {}
+            ContentSpec::Empty => {
+                if let Some(at) = open.children.first().or(open.text.as_ref()) {
+                    errors.push(ValidationError {
+                        message: format!("element `{}` is declared EMPTY", name),
+                        at: at.clone(),
+                    });
+                }
+            }
+            ContentSpec::Mixed(allowed) => {
+                for child in &open.children {
+                    if !allowed
+                        .iter()
+                        .any(|allowed| allowed.as_bytes() == child.as_bytes())
+                    {
+                        errors.push(ValidationError {
+                            message: format!(
+                                "element `{}` is not allowed in `{}`",
+                                String::from_utf8_lossy(child.as_bytes()),
+                                name
+                            ),
+                            at: child.clone(),
+                        });
+                    }
+                }
+            }
+            ContentSpec::Children(cp) => {
+                if let Some(text) = open.text {
+                    errors.push(ValidationError {
+                        message: format!("character data is not allowed in `{}`", name),
+                        at: text,
+                    });
+                }
+
+                let ends = match_particle(cp, &open.children, BTreeSet::from([0]));
+
+                if !ends.contains(&open.children.len()) {
+                    errors.push(ValidationError {
+                        message: format!("content of `{}` does not match the declared model", name),
+                        at: open.name,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Returns the positions in `children` reachable by matching `cp` from any of `starts`.
+fn match_particle<I>(
+    cp: &ContentParticle<I>,
+    children: &[I],
+    starts: BTreeSet<usize>,
+) -> BTreeSet<usize>
+where
+    I: AsBytes,
+{
+    let once = |starts: &BTreeSet<usize>| -> BTreeSet<usize> {
+        match cp {
+            ContentParticle::Name(name, _) => starts
+                .iter()
+                .filter(|at| {
+                    children
+                        .get(**at)
+                        .is_some_and(|child| child.as_bytes() == name.as_bytes())
+                })
+                .map(|at| at + 1)
+                .collect(),
+            ContentParticle::Seq(items, _) => items.iter().fold(starts.clone(), |ends, item| {
+                match_particle(item, children, ends)
+            }),
+            ContentParticle::Choice(items, _) => items
+                .iter()
+                .flat_map(|item| match_particle(item, children, starts.clone()))
+                .collect(),
+        }
+    };
+
+    // repeat `once` until no new position is reached.
+    let closure = |mut ends: BTreeSet<usize>| {
+        let mut frontier = ends.clone();
+
+        while !frontier.is_empty() {
+            frontier = once(&frontier)
+                .into_iter()
+                .filter(|at| !ends.contains(at))
+                .collect();
+            ends.extend(frontier.iter().copied());
+        }
+
+        ends
+    };
+
+    let repeat = match cp {
+        ContentParticle::Name(_, repeat)
+        | ContentParticle::Seq(_, repeat)
+        | ContentParticle::Choice(_, repeat) => *repeat,
+    };
+
+    match repeat {
+        Repeat::One => once(&starts),
+        Repeat::Optional => {
+            let mut ends = once(&starts);
+            ends.extend(starts);
+            ends
+        }
+        Repeat::ZeroOrMore => closure(starts),
+        Repeat::OneOrMore => closure(once(&starts)),
+    }
+}
+
+/// Result of [`validate_dtd`], fails only if the document or its doctype can't be read.
+pub type DtdValidation<I> = Result<Vec<ValidationError<I>>, ControlFlow<ReadError<I>>>;
+
+/// Validate all events of `source` against the internal subset of its doctype, returning the
+/// errors in document order.
+///
+/// A document without doctype is reported once, at its root element.
+pub fn validate_dtd<S>(mut source: S) -> DtdValidation<S::Input>
+where
+    S: EventSource,
+    S::Input: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    let mut validator: Option<DtdValidator<_>> = None;
+    let mut errors = vec![];
+
+    while let Some(node) = source.next_event()? {
+        if let Some(validator) = &mut validator {
+            validator.check(&node, &mut errors);
+            continue;
+        }
+
+        match node {
+            XmlNode::DocType(doctype) => {
+                validator = Some(DtdValidator::new(&doctype.parse_decl()?));
+            }
+            XmlNode::Start(start) => {
+                errors.push(ValidationError {
+                    message: "document has no doctype".to_string(),
+                    at: start.name,
+                });
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reader::{Position, XmlReader};
+
+    use super::validate_dtd;
+
+    fn errors(input: &str) -> Vec<(String, &[u8])> {
+        validate_dtd(XmlReader::from(input.as_bytes()))
+            .unwrap()
+            .into_iter()
+            .map(|err| (err.message, err.at))
+            .collect()
+    }
+
+    const DTD: &str = r#"<!DOCTYPE doc [
+        <!ELEMENT doc (head, (p | list)+, foot?)>
+        <!ELEMENT head (#PCDATA)>
+        <!ELEMENT p (#PCDATA | em)*>
+        <!ELEMENT em (#PCDATA)>
+        <!ELEMENT list ANY>
+        <!ELEMENT foot EMPTY>
+        <!ATTLIST doc version CDATA #FIXED "1" lang (en | de) #IMPLIED id ID #REQUIRED>
+    ]>"#;
+
+    #[test]
+    fn test_valid() {
+        assert_eq!(
+            errors(&format!(
+                r#"{DTD}<doc id="a" lang=" de "><head>t</head><p>a<em>b</em></p><list><em/></list><p/>
+                <foot/></doc>"#
+            )),
+            []
+        );
+    }
+
+    #[test]
+    fn test_content_model() {
+        assert_eq!(
+            errors(&format!(
+                "{DTD}<doc id='a'>text<p/><head/><foot>x</foot><p><head/></p></doc>"
+            )),
+            [
+                (
+                    "element `foot` is declared EMPTY".to_string(),
+                    b"x".as_slice()
+                ),
+                (
+                    "element `head` is not allowed in `p`".to_string(),
+                    b"head".as_slice()
+                ),
+                (
+                    "character data is not allowed in `doc`".to_string(),
+                    b"text".as_slice()
+                ),
+                (
+                    "content of `doc` does not match the declared model".to_string(),
+                    b"doc".as_slice()
+                ),
+            ]
+        );
+
+        assert_eq!(
+            errors(&format!("{DTD}<doc id='a'><head/></doc>")),
+            [(
+                "content of `doc` does not match the declared model".to_string(),
+                b"doc".as_slice()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_attributes() {
+        assert_eq!(
+            errors(&format!(
+                "{DTD}<doc version='2' lang='fr' x=''><head/><p/></doc>"
+            )),
+            [
+                (
+                    "attribute `version` must have the fixed value `1`".to_string(),
+                    b"2".as_slice()
+                ),
+                (
+                    "value `fr` of attribute `lang` is not one of the declared values".to_string(),
+                    b"fr".as_slice()
+                ),
+                (
+                    "attribute `x` is not declared for `doc`".to_string(),
+                    b"x".as_slice()
+                ),
+                (
+                    "required attribute `id` is missing".to_string(),
+                    b"doc".as_slice()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_root_and_position() {
+        let input = "<!DOCTYPE doc [<!ELEMENT doc EMPTY>]>\n<root/>";
+
+        let errors = validate_dtd(XmlReader::from(input.as_bytes())).unwrap();
+
+        assert_eq!(
+            errors
+                .iter()
+                .map(|err| err.message.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "root element `root` does not match the doctype name `doc`",
+                "element `root` is not declared"
+            ]
+        );
+
+        assert_eq!(
+            errors[0].position(input.as_bytes()),
+            Position {
+                offset: 39,
+                line: 2,
+                column: 2
+            }
+        );
+
+        assert_eq!(
+            validate_dtd(XmlReader::from(b"<a/>".as_slice()))
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+}
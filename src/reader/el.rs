@@ -4,9 +4,12 @@ use parserc::{
     AsBytes, ControlFlow, Input, Kind, Parse, Parser, ParserExt, keyword, next, take_till,
 };
 
-use crate::reader::{Name, parse_quote, parse_ws};
+use crate::{
+    chars::is_ws,
+    reader::{Name, parse_quote, parse_ws},
+};
 
-use super::{Attr, ReadError, ReadKind};
+use super::{Attr, ReadError, ReadKind, position::offset_in};
 
 /// The start tag of an element.
 ///
@@ -14,6 +17,10 @@ use super::{Attr, ReadError, ReadKind};
 #[derive(Debug, PartialEq, Clone)]
 pub struct ElemStart<I> {
     pub name: I,
+    /// The raw attribute list between the name and `>` or `/>`, as written in the source.
+    ///
+    /// It keeps the whitespace and quoting the parsed [`attrs`](Self::attrs) lose, so
+    /// lossless editors can copy it unchanged, see also [`raw_attr`](Self::raw_attr).
     pub unparsed: I,
     pub is_empty: bool,
 }
@@ -25,13 +32,161 @@ where
     /// Create an iterator over attribute list.
     #[inline(always)]
     pub fn attrs(&self) -> Attrs<I> {
-        Attrs(self.unparsed.clone())
+        Attrs::new(self.unparsed.clone())
+    }
+
+    /// Split into the name, the attribute iterator and the `is_empty` flag without copying input.
+    ///
+    /// The parts are [`Send`] if `I` is, e.g. to decode the collected attributes of a huge
+    /// start tag on several threads.
+    pub fn into_parts(self) -> (I, Attrs<I>, bool) {
+        (self.name, Attrs::new(self.unparsed), self.is_empty)
+    }
+
+    /// Returns true if the element name is `name`.
+    pub fn has_name(&self, name: &str) -> bool {
+        self.name.as_bytes() == name.as_bytes()
+    }
+
+    /// Returns the value of the attribute `name` by a linear scan.
+    ///
+    /// Use [`XmlReader::get_attr`](super::XmlReader::get_attr) for repeated lookups on tags with many attributes.
+    pub fn get_attr(&self, name: &[u8]) -> Result<Option<I>, ControlFlow<ReadError<I>>> {
+        for attr in self.attrs() {
+            let attr = attr?;
+
+            if attr.name.as_bytes() == name {
+                return Ok(Some(attr.value));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the attribute `name` by a linear scan, see [`Attrs::get`].
+    pub fn get(&self, name: &[u8]) -> Result<Option<Attr<I>>, ControlFlow<ReadError<I>>> {
+        self.attrs().get(name)
+    }
+
+    /// Returns the attribute `prefix:local` by a linear scan, see [`Attrs::get_ns`].
+    pub fn get_ns(
+        &self,
+        prefix: Option<&[u8]>,
+        local: &[u8],
+    ) -> Result<Option<Attr<I>>, ControlFlow<ReadError<I>>> {
+        self.attrs().get_ns(prefix, local)
+    }
+
+    /// Returns the source of the attribute `name` by a linear scan, from its name up to and
+    /// including the closing quote of its value, with references unexpanded.
+    pub fn raw_attr(&self, name: &[u8]) -> Result<Option<I>, ControlFlow<ReadError<I>>> {
+        let unparsed = self.unparsed.as_bytes();
+
+        for attr in self.attrs() {
+            let attr = attr?;
+
+            if attr.name.as_bytes() == name {
+                let start = offset_in(unparsed, attr.name.as_bytes());
+                let end = offset_in(unparsed, attr.quoted.as_bytes()) + attr.quoted.len();
+
+                let mut raw = self.unparsed.clone();
+                raw.split_off(end);
+                raw.split_to(start);
+
+                return Ok(Some(raw));
+            }
+        }
+
+        Ok(None)
     }
 }
 
 /// Attribute list.
+///
+/// Malformed attributes are returned as errors, e.g. [`ReadError::MalformedAttr`] for a region
+/// that is no attribute at all. The iterator ends after the first error, unless it
+/// [`resynchronizes`](Self::resync).
 #[derive(Debug, PartialEq, Clone)]
-pub struct Attrs<I>(I);
+pub struct Attrs<I> {
+    input: I,
+    /// continue after errors, see [`resync`](Self::resync).
+    resync: bool,
+}
+
+impl<I> Attrs<I>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    pub(super) fn new(input: I) -> Self {
+        Self {
+            input,
+            resync: false,
+        }
+    }
+
+    /// Continue after an error at the next whitespace, so the attributes behind a malformed one
+    /// are still yielded.
+    pub fn resync(mut self) -> Self {
+        self.resync = true;
+        self
+    }
+
+    /// Returns the attribute `name`, consuming the iterator up to it.
+    ///
+    /// The [`spans`](Attr::spans) of the returned attribute locate it in the source document.
+    pub fn get(&mut self, name: &[u8]) -> Result<Option<Attr<I>>, ControlFlow<ReadError<I>>> {
+        for attr in self.by_ref() {
+            let attr = attr?;
+
+            if attr.name.as_bytes() == name {
+                return Ok(Some(attr));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the attribute with the lexical `prefix` and `local` name, e.g. `(Some(b"xlink"), b"href")`
+    /// for `xlink:href`, consuming the iterator up to it.
+    ///
+    /// Prefixes are compared as written, use a [`NsReader`](super::NsReader) to match namespaces.
+    pub fn get_ns(
+        &mut self,
+        prefix: Option<&[u8]>,
+        local: &[u8],
+    ) -> Result<Option<Attr<I>>, ControlFlow<ReadError<I>>> {
+        for attr in self.by_ref() {
+            let attr = attr?;
+
+            let name = attr.name.as_bytes();
+
+            let matched = match (prefix, memchr::memchr(b':', name)) {
+                (None, None) => name == local,
+                (Some(prefix), Some(at)) => &name[..at] == prefix && &name[at + 1..] == local,
+                _ => false,
+            };
+
+            if matched {
+                return Ok(Some(attr));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Skip the input up to the first whitespace from `offset`, or all of it without resync.
+    fn skip(&mut self, offset: usize) {
+        let len = match self.resync {
+            true => self.input.as_bytes()[offset..]
+                .iter()
+                .position(|c| is_ws(*c))
+                .map_or(self.input.len(), |len| offset + len),
+            false => self.input.len(),
+        };
+
+        self.input.split_to(len);
+    }
+}
 
 impl<I> Iterator for Attrs<I>
 where
@@ -41,14 +196,44 @@ where
 
     #[inline(always)]
     fn next(&mut self) -> Option<Self::Item> {
-        let (next, input) = match Attr::into_parser().ok().parse(self.0.clone()) {
-            Ok(v) => v,
-            Err(err) => return Some(Err(err)),
-        };
+        // only whitespace is left after the last attribute.
+        let lead = self.input.as_bytes().iter().position(|c| !is_ws(*c))?;
+
+        match Attr::parse(self.input.clone()) {
+            Ok((attr, input)) => {
+                self.input = input;
+                Some(Ok(attr))
+            }
+            Err(ControlFlow::Recovable(_)) => {
+                // no attribute starts here, report the region up to the next whitespace.
+                let mut span = self.input.clone();
+                span.split_to(lead);
+
+                let len = span
+                    .as_bytes()
+                    .iter()
+                    .position(|c| is_ws(*c))
+                    .unwrap_or(span.len());
+
+                span.split_off(len);
+
+                self.skip(lead);
+
+                Some(Err(ControlFlow::Fatal(ReadError::MalformedAttr(span))))
+            }
+            Err(err) => {
+                let offset = match &err {
+                    ControlFlow::Fatal(err) => err.input().map(|input| {
+                        offset_in(self.input.as_bytes(), input.as_bytes()).min(self.input.len())
+                    }),
+                    _ => None,
+                };
 
-        self.0 = input;
+                self.skip(offset.unwrap_or(lead));
 
-        next.map(|v| Ok(v))
+                Some(Err(err))
+            }
+        }
     }
 }
 
@@ -62,6 +247,14 @@ where
     fn parse(input: I) -> parserc::Result<Self, I, Self::Error> {
         let (_, input) = next(b'<').parse(input)?;
 
+        // `<!` and `<?` open other markup.
+        if matches!(input.iter().next(), Some(b'!') | Some(b'?')) {
+            return Err(ControlFlow::Recovable(ReadError::Expect(
+                ReadKind::Name,
+                input,
+            )));
+        }
+
         let (name, mut input) = Name::into_parser().fatal().parse(input)?;
 
         let mut content = input.clone();
@@ -148,9 +341,9 @@ where
 }
 #[cfg(test)]
 mod tests {
-    use parserc::Parse;
+    use parserc::{ControlFlow, Parse};
 
-    use crate::reader::{ElemEnd, ElemStart};
+    use crate::reader::{Attr, Attrs, ElemEnd, ElemStart, Entities, ReadError, ReadKind};
 
     #[test]
     fn test_el_start() {
@@ -182,6 +375,146 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_raw_attr() {
+        let (start, _) =
+            ElemStart::parse(br#"<a  x = 'a &amp; b'	y="&quot;"/>"#.as_slice()).unwrap();
+
+        assert_eq!(
+            start.raw_attr(b"x"),
+            Ok(Some(b"x = 'a &amp; b'".as_slice()))
+        );
+        assert_eq!(start.raw_attr(b"y"), Ok(Some(br#"y="&quot;""#.as_slice())));
+        assert_eq!(start.raw_attr(b"z"), Ok(None));
+        assert_eq!(
+            start.unparsed,
+            br#"  x = 'a &amp; b'	y="&quot;""#.as_slice()
+        );
+    }
+
+    #[test]
+    fn test_get() {
+        let source = br#"<a href="x" xlink:href='y' svg:href="z"/>"#.as_slice();
+
+        let (start, _) = ElemStart::parse(source).unwrap();
+
+        let attr = start.get(b"xlink:href").unwrap().unwrap();
+
+        assert_eq!(attr.value, b"y");
+        assert_eq!(attr.offset(source), 12);
+        assert_eq!(start.get(b"title"), Ok(None));
+
+        assert_eq!(
+            start.get_ns(None, b"href").unwrap().map(|attr| attr.value),
+            Some(b"x".as_slice())
+        );
+        assert_eq!(
+            start
+                .get_ns(Some(b"svg"), b"href")
+                .unwrap()
+                .map(|attr| attr.value),
+            Some(b"z".as_slice())
+        );
+        assert_eq!(start.get_ns(Some(b"xml"), b"href"), Ok(None));
+
+        let mut attrs = start.attrs();
+
+        assert!(attrs.get(b"xlink:href").unwrap().is_some());
+        assert_eq!(
+            attrs.next().map(|attr| attr.unwrap().name),
+            Some(b"svg:href".as_slice())
+        );
+    }
+
+    #[test]
+    fn test_malformed_attrs() {
+        let (start, _) = ElemStart::parse(br#"<a x="1"y="2" "z"="4" w='5' >"#.as_slice()).unwrap();
+
+        let names = |attrs: Attrs<&'static [u8]>| {
+            attrs
+                .map(|attr| attr.map(|attr| attr.name))
+                .collect::<Vec<_>>()
+        };
+
+        let malformed =
+            |span: &'static [u8]| Err(ControlFlow::Fatal(ReadError::MalformedAttr(span)));
+
+        assert_eq!(
+            names(start.attrs()),
+            [Ok(b"x".as_slice()), malformed(br#"y="2""#)]
+        );
+
+        assert_eq!(
+            names(start.attrs().resync()),
+            [
+                Ok(b"x".as_slice()),
+                malformed(br#"y="2""#),
+                malformed(br#""z"="4""#),
+                Ok(b"w".as_slice())
+            ]
+        );
+
+        let (start, _) = ElemStart::parse(br#"<a x=1 y="2">"#.as_slice()).unwrap();
+
+        assert_eq!(
+            names(start.attrs().resync()),
+            [
+                Err(ControlFlow::Fatal(ReadError::Expect(
+                    ReadKind::Quote,
+                    br#"1 y="2""#.as_slice()
+                ))),
+                Ok(b"y".as_slice())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_into_parts() {
+        fn assert_send<T: Send>(_: &T) {}
+
+        let input = (0..64)
+            .map(|i| format!(" a{}='{} &amp; {}'", i, i, i))
+            .collect::<String>();
+
+        let input = format!("<el{}/>", input);
+
+        let (start, _) = ElemStart::parse(input.as_bytes()).unwrap();
+
+        let (name, attrs, is_empty) = start.into_parts();
+
+        assert_eq!((name, is_empty), (b"el".as_slice(), true));
+
+        let attrs = attrs.collect::<Result<Vec<Attr<&[u8]>>, _>>().unwrap();
+
+        assert_send(&attrs);
+
+        let entities = Entities::new();
+
+        let values = std::thread::scope(|scope| {
+            let handles = attrs
+                .chunks(16)
+                .map(|chunk| {
+                    let entities = &entities;
+
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|attr| entities.expand_attr(&attr.value).unwrap().into_owned())
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        assert_eq!(values.len(), 64);
+        assert_eq!(values[42], "42 & 42");
+    }
+
     #[test]
     fn test_el_end() {
         assert_eq!(
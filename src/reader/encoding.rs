@@ -0,0 +1,221 @@
+use std::fmt::Display;
+
+use parserc::Parse;
+
+use super::XmlDecl;
+
+/// Encoding family detected from the first bytes of a document.
+///
+/// See [`Autodetection of Character Encodings`](https://www.w3.org/TR/xml11/#sec-guessing).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DetectedEncoding {
+    /// utf-8 with byte order mark.
+    Utf8Bom,
+    /// utf-8 or another ascii-compatible encoding.
+    Utf8,
+    /// utf-16, big-endian.
+    Utf16Be,
+    /// utf-16, little-endian.
+    Utf16Le,
+    /// ucs-4/utf-32, big-endian.
+    Utf32Be,
+    /// ucs-4/utf-32, little-endian.
+    Utf32Le,
+}
+
+impl Display for DetectedEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DetectedEncoding::Utf8Bom => write!(f, "UTF-8 (BOM)"),
+            DetectedEncoding::Utf8 => write!(f, "UTF-8"),
+            DetectedEncoding::Utf16Be => write!(f, "UTF-16BE"),
+            DetectedEncoding::Utf16Le => write!(f, "UTF-16LE"),
+            DetectedEncoding::Utf32Be => write!(f, "UTF-32BE"),
+            DetectedEncoding::Utf32Le => write!(f, "UTF-32LE"),
+        }
+    }
+}
+
+impl DetectedEncoding {
+    /// Detect the encoding family from the leading bytes, returns the family and the byte order mark length.
+    pub fn detect(bytes: &[u8]) -> Option<(Self, usize)> {
+        match bytes {
+            [0x00, 0x00, 0xFE, 0xFF, ..] => Some((DetectedEncoding::Utf32Be, 4)),
+            [0xFF, 0xFE, 0x00, 0x00, ..] => Some((DetectedEncoding::Utf32Le, 4)),
+            [0xFE, 0xFF, ..] => Some((DetectedEncoding::Utf16Be, 2)),
+            [0xFF, 0xFE, ..] => Some((DetectedEncoding::Utf16Le, 2)),
+            [0xEF, 0xBB, 0xBF, ..] => Some((DetectedEncoding::Utf8Bom, 3)),
+            [0x00, 0x00, 0x00, 0x3C, ..] => Some((DetectedEncoding::Utf32Be, 0)),
+            [0x3C, 0x00, 0x00, 0x00, ..] => Some((DetectedEncoding::Utf32Le, 0)),
+            [0x00, 0x3C, 0x00, 0x3F, ..] => Some((DetectedEncoding::Utf16Be, 0)),
+            [0x3C, 0x00, 0x3F, 0x00, ..] => Some((DetectedEncoding::Utf16Le, 0)),
+            [0x3C, 0x3F, 0x78, 0x6D, ..] => Some((DetectedEncoding::Utf8, 0)),
+            _ => None,
+        }
+    }
+
+    /// Returns true if this is an ascii-compatible family the reader can parse.
+    pub fn is_utf8(&self) -> bool {
+        matches!(self, DetectedEncoding::Utf8 | DetectedEncoding::Utf8Bom)
+    }
+
+    /// Returns true if the declared encoding name `name` belongs to this family.
+    ///
+    /// For the utf-8 families every encoding name except the utf-16/ucs-4 ones is accepted,
+    /// since the reader can't tell ascii-compatible encodings apart.
+    pub fn matches(&self, name: &[u8]) -> bool {
+        let name = name.to_ascii_uppercase();
+
+        let utf16 = matches!(
+            name.as_slice(),
+            b"UTF-16" | b"UTF-16LE" | b"UTF-16BE" | b"UCS-2" | b"ISO-10646-UCS-2"
+        );
+
+        let utf32 = matches!(
+            name.as_slice(),
+            b"UTF-32" | b"UTF-32LE" | b"UTF-32BE" | b"UCS-4" | b"ISO-10646-UCS-4"
+        );
+
+        match self {
+            DetectedEncoding::Utf8Bom => name == b"UTF-8",
+            DetectedEncoding::Utf8 => !utf16 && !utf32,
+            DetectedEncoding::Utf16Be | DetectedEncoding::Utf16Le => utf16,
+            DetectedEncoding::Utf32Be | DetectedEncoding::Utf32Le => utf32,
+        }
+    }
+
+    /// Decode the ascii text of a xml declaration encoded in this family and returns its encoding name.
+    pub(super) fn sniff_encoding(&self, bytes: &[u8]) -> Option<String> {
+        let width = match self {
+            DetectedEncoding::Utf8 | DetectedEncoding::Utf8Bom => 1,
+            DetectedEncoding::Utf16Be | DetectedEncoding::Utf16Le => 2,
+            DetectedEncoding::Utf32Be | DetectedEncoding::Utf32Le => 4,
+        };
+
+        let mut decl = Vec::new();
+
+        for unit in bytes.chunks_exact(width).take(256) {
+            let c = match self {
+                DetectedEncoding::Utf8 | DetectedEncoding::Utf8Bom => unit[0] as u32,
+                DetectedEncoding::Utf16Be => u16::from_be_bytes([unit[0], unit[1]]) as u32,
+                DetectedEncoding::Utf16Le => u16::from_le_bytes([unit[0], unit[1]]) as u32,
+                DetectedEncoding::Utf32Be => {
+                    u32::from_be_bytes([unit[0], unit[1], unit[2], unit[3]])
+                }
+                DetectedEncoding::Utf32Le => {
+                    u32::from_le_bytes([unit[0], unit[1], unit[2], unit[3]])
+                }
+            };
+
+            if c >= 0x80 {
+                return None;
+            }
+
+            decl.push(c as u8);
+
+            if c == b'>' as u32 {
+                break;
+            }
+        }
+
+        let (decl, _) = XmlDecl::parse(decl.as_slice()).ok()?;
+
+        decl.encoding
+            .map(|encoding| String::from_utf8_lossy(encoding).into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parserc::ControlFlow;
+
+    use crate::reader::{DetectedEncoding, ReadError, XmlNode, XmlReader};
+
+    fn utf16le(s: &str, bom: bool) -> Vec<u8> {
+        let mut bytes = if bom { vec![0xFF, 0xFE] } else { vec![] };
+
+        for unit in s.encode_utf16() {
+            bytes.extend_from_slice(&unit.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            DetectedEncoding::detect(&utf16le("<?xml", true)),
+            Some((DetectedEncoding::Utf16Le, 2))
+        );
+        assert_eq!(
+            DetectedEncoding::detect(&utf16le("<?xml", false)),
+            Some((DetectedEncoding::Utf16Le, 0))
+        );
+        assert_eq!(
+            DetectedEncoding::detect(b"\xEF\xBB\xBF<?xml"),
+            Some((DetectedEncoding::Utf8Bom, 3))
+        );
+        assert_eq!(
+            DetectedEncoding::detect(b"<?xml"),
+            Some((DetectedEncoding::Utf8, 0))
+        );
+        assert_eq!(DetectedEncoding::detect(b"<svg"), None);
+    }
+
+    #[test]
+    fn test_mismatch() {
+        let doc = utf16le(r#"<?xml version="1.0" encoding="UTF-8"?><a/>"#, false);
+
+        assert_eq!(
+            XmlReader::from(doc.as_slice()).read_next(),
+            Err(ControlFlow::Fatal(ReadError::EncodingMismatch {
+                detected: DetectedEncoding::Utf16Le,
+                declared: "UTF-8".to_string()
+            }))
+        );
+
+        let doc = br#"<?xml version="1.0" encoding="utf-16"?><a/>"#;
+
+        assert_eq!(
+            XmlReader::from(doc.as_slice()).read_next(),
+            Err(ControlFlow::Fatal(ReadError::EncodingMismatch {
+                detected: DetectedEncoding::Utf8,
+                declared: "utf-16".to_string()
+            }))
+        );
+
+        let doc = b"\xEF\xBB\xBF<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><a/>";
+
+        assert_eq!(
+            XmlReader::from(doc.as_slice()).read_next(),
+            Err(ControlFlow::Fatal(ReadError::EncodingMismatch {
+                detected: DetectedEncoding::Utf8Bom,
+                declared: "ISO-8859-1".to_string()
+            }))
+        );
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let doc = utf16le(r#"<?xml version="1.0" encoding="UTF-16"?><a/>"#, true);
+
+        assert_eq!(
+            XmlReader::from(doc.as_slice()).read_next(),
+            Err(ControlFlow::Fatal(ReadError::UnsupportedEncoding(
+                DetectedEncoding::Utf16Le
+            )))
+        );
+    }
+
+    #[test]
+    fn test_utf8_bom() {
+        let doc = b"\xEF\xBB\xBF<?xml version=\"1.0\" encoding=\"UTF-8\"?><a/>";
+
+        let nodes = XmlReader::from(doc.as_slice())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(nodes.len(), 2);
+        assert!(matches!(nodes[0], XmlNode::XmlDecl(_)));
+    }
+}
@@ -0,0 +1,622 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt::Debug,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use parserc::{AsBytes, ControlFlow, Input};
+
+use crate::{
+    chars::{is_char, normalize_newlines},
+    types::XmlVersion,
+};
+
+use super::{ReadError, ReadKind, ReaderLimit};
+
+#[cfg(feature = "dtd")]
+use super::{EntityDecl, EntityDef, MarkupDecl};
+
+/// What to do with a reference to an entity that is neither predefined nor registered.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub enum UnknownEntity {
+    /// Fail with [`ReadError::UnknownEntity`].
+    #[default]
+    Error,
+    /// Keep the reference text, e.g. `&h;`, unchanged.
+    KeepLiteral,
+    /// Replace the reference with the given text.
+    Replace(String),
+}
+
+/// What to do with a character reference to a code point that is not a legal
+/// [`Char`](https://www.w3.org/TR/xml11/#NT-Char), e.g. the surrogate `&#xD800;` or the noncharacter `&#xFFFE;`.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum InvalidCharRef {
+    /// Fail with [`ReadError::InvalidCharRef`].
+    #[default]
+    Error,
+    /// Replace the reference with the given char, usually `U+FFFD`.
+    Replace(char),
+}
+
+/// The cap exceeded by an entity expansion, see [`EntityLimits`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, thiserror::Error)]
+pub enum EntityLimit {
+    /// Too many nested references.
+    #[error("entity nesting depth")]
+    Depth,
+    /// Too much replacement text.
+    #[error("entity expansion size")]
+    Expansion,
+}
+
+/// Caps on the expansion of registered entities in one text node or attribute value.
+///
+/// Replacement text may reference further entities, which are expanded in turn. The caps
+/// protect readers from recursive and exponentially growing ("billion laughs") definitions.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct EntityLimits {
+    /// Maximum nesting of entity references, `1` expands entities but no references in their values.
+    pub max_depth: usize,
+    /// Maximum number of replacement text bytes inserted, summed over all nesting levels.
+    pub max_expansion: usize,
+}
+
+impl EntityLimits {
+    /// Default limits of text nodes.
+    pub const TEXT: Self = Self {
+        max_depth: 8,
+        max_expansion: 1 << 20,
+    };
+
+    /// Default limits of attribute values.
+    pub const ATTR: Self = Self {
+        max_depth: 4,
+        max_expansion: 64 << 10,
+    };
+}
+
+/// Callback type of [`Entities::resolver`].
+pub type EntityResolverFn = Arc<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+/// Entity table used to expand references in text and attribute values.
+///
+/// The [`predefined entities`](https://www.w3.org/TR/xml11/#sec-predefined-ent) and character
+/// references are always expanded. References inside values of registered entities are expanded
+/// too, within the [`EntityLimits`] of text and attribute values.
+#[derive(Clone)]
+pub struct Entities {
+    map: HashMap<String, String>,
+    resolver: Option<EntityResolverFn>,
+    unknown: UnknownEntity,
+    invalid_char_ref: InvalidCharRef,
+    /// version whose `Char` production character references are checked against.
+    version: XmlVersion,
+    text_limits: EntityLimits,
+    attr_limits: EntityLimits,
+    /// cap of `expanded`, see [`ReaderLimits`](super::ReaderLimits).
+    max_total_expansion: usize,
+    /// replacement text bytes inserted so far, shared by clones.
+    expanded: Arc<AtomicUsize>,
+    /// replace `\r\n` and `\r` with `\n` in decoded text.
+    newlines: bool,
+}
+
+impl Default for Entities {
+    fn default() -> Self {
+        Self {
+            map: Default::default(),
+            resolver: None,
+            unknown: Default::default(),
+            invalid_char_ref: Default::default(),
+            version: XmlVersion::Ver10,
+            text_limits: EntityLimits::TEXT,
+            attr_limits: EntityLimits::ATTR,
+            max_total_expansion: usize::MAX,
+            expanded: Default::default(),
+            newlines: true,
+        }
+    }
+}
+
+impl Debug for Entities {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Entities")
+            .field("map", &self.map)
+            .field("resolver", &self.resolver.is_some())
+            .field("unknown", &self.unknown)
+            .field("invalid_char_ref", &self.invalid_char_ref)
+            .field("version", &self.version)
+            .field("text_limits", &self.text_limits)
+            .field("attr_limits", &self.attr_limits)
+            .field("max_total_expansion", &self.max_total_expansion)
+            .field("expanded", &self.expanded())
+            .field("newlines", &self.newlines)
+            .finish()
+    }
+}
+
+impl Entities {
+    /// Create a table with only the predefined entities.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the entity `name` with replacement text `value`.
+    pub fn insert<N, V>(&mut self, name: N, value: V)
+    where
+        N: Into<String>,
+        V: Into<String>,
+    {
+        self.map.insert(name.into(), value.into());
+    }
+
+    /// Register the internal general entities declared in `decls`, e.g. [`DocTypeDecl::subset`](super::DocTypeDecl::subset).
+    ///
+    /// Parameter and external entities are skipped. An entity already registered keeps its value,
+    /// as the first declaration of an entity is binding.
+    #[cfg(feature = "dtd")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dtd")))]
+    pub fn insert_decls<I>(&mut self, decls: &[MarkupDecl<I>])
+    where
+        I: AsBytes,
+    {
+        for decl in decls {
+            if let MarkupDecl::Entity(EntityDecl {
+                name,
+                parameter: false,
+                def: EntityDef::Internal(value),
+            }) = decl
+            {
+                self.map
+                    .entry(String::from_utf8_lossy(name.as_bytes()).into_owned())
+                    .or_insert_with(|| String::from_utf8_lossy(value.as_bytes()).into_owned());
+            }
+        }
+    }
+
+    /// Set a callback consulted for entities missing from the table.
+    pub fn resolver<F>(&mut self, resolver: F)
+    where
+        F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+    {
+        self.resolver = Some(Arc::new(resolver));
+    }
+
+    /// Set the behavior for unknown entities.
+    pub fn unknown(&mut self, unknown: UnknownEntity) {
+        self.unknown = unknown;
+    }
+
+    /// Set the behavior for character references to illegal code points.
+    pub fn invalid_char_ref(&mut self, policy: InvalidCharRef) {
+        self.invalid_char_ref = policy;
+    }
+
+    /// Set the xml version character references are checked against, defaults to `1.0`.
+    ///
+    /// [`XmlReader`](super::XmlReader) sets it from the xml declaration.
+    pub fn version(&mut self, version: XmlVersion) {
+        self.version = version;
+    }
+
+    /// Returns the xml version character references are checked against.
+    pub(super) fn xml_version(&self) -> XmlVersion {
+        self.version
+    }
+
+    /// Set whether line breaks `\r\n` and `\r` are normalized to `\n` in decoded text, cdata
+    /// and attribute values, defaults to `true`; see
+    /// [`End-of-Line Handling`](https://www.w3.org/TR/xml11/#sec-line-ends).
+    ///
+    /// Character references such as `&#13;` always expand to the referenced character.
+    pub fn normalize_newlines(&mut self, normalize: bool) {
+        self.newlines = normalize;
+    }
+
+    /// Returns the replacement text of the entity `name`.
+    pub fn get(&self, name: &str) -> Option<Cow<'_, str>> {
+        if let Some(predefined) = predefined(name) {
+            return Some(Cow::Borrowed(predefined));
+        }
+
+        if let Some(value) = self.map.get(name) {
+            return Some(Cow::Borrowed(value));
+        }
+
+        self.resolver
+            .as_ref()
+            .and_then(|resolver| resolver(name))
+            .map(Cow::Owned)
+    }
+
+    /// Set the limits for expanding references in text, defaults to [`EntityLimits::TEXT`].
+    pub fn text_limits(&mut self, limits: EntityLimits) {
+        self.text_limits = limits;
+    }
+
+    /// Set the limits for expanding references in attribute values, defaults to [`EntityLimits::ATTR`].
+    pub fn attr_limits(&mut self, limits: EntityLimits) {
+        self.attr_limits = limits;
+    }
+
+    /// Set the cap of replacement text bytes inserted by all expansions, defaults to no cap.
+    ///
+    /// Clones of this table share the running total, so events expanded on other threads count
+    /// too. Exceeding it fails with [`ReadError::LimitExceeded`].
+    pub fn total_expansion_limit(&mut self, max: usize) {
+        self.max_total_expansion = max;
+    }
+
+    /// Returns the replacement text bytes inserted so far.
+    pub fn expanded(&self) -> usize {
+        self.expanded.load(Ordering::Relaxed)
+    }
+
+    /// Restart the running total of [`expanded`](Self::expanded), e.g. for a new document.
+    pub(super) fn reset_expanded(&self) {
+        self.expanded.store(0, Ordering::Relaxed);
+    }
+
+    /// Expand all references in `raw` text.
+    ///
+    /// Returns the borrowed text if it contains no references. Fails with
+    /// [`ReadError::TextEntityLimit`] if the [`text limits`](Self::text_limits) are exceeded.
+    pub fn expand<'a, I>(&self, raw: &'a I) -> Result<Cow<'a, str>, ControlFlow<ReadError<I>>>
+    where
+        I: Input<Item = u8> + AsBytes + Clone + Debug,
+    {
+        self.expand_with(raw, &self.text_limits, ReadError::TextEntityLimit)
+    }
+
+    /// Expand all references in `raw` attribute value.
+    ///
+    /// Returns the borrowed value if it contains no references. Fails with
+    /// [`ReadError::AttrEntityLimit`] if the [`attribute limits`](Self::attr_limits) are exceeded.
+    pub fn expand_attr<'a, I>(&self, raw: &'a I) -> Result<Cow<'a, str>, ControlFlow<ReadError<I>>>
+    where
+        I: Input<Item = u8> + AsBytes + Clone + Debug,
+    {
+        self.expand_with(raw, &self.attr_limits, ReadError::AttrEntityLimit)
+    }
+
+    /// Returns the content of a cdata section `raw`, with line breaks normalized.
+    pub fn cdata<'a, I>(&self, raw: &'a I) -> Result<Cow<'a, str>, ControlFlow<ReadError<I>>>
+    where
+        I: Input<Item = u8> + AsBytes + Clone + Debug,
+    {
+        let text = Self::utf8(raw)?;
+
+        match self.newlines {
+            true => Ok(normalize_newlines(text)),
+            false => Ok(Cow::Borrowed(text)),
+        }
+    }
+
+    fn utf8<I>(raw: &I) -> Result<&str, ControlFlow<ReadError<I>>>
+    where
+        I: Input<Item = u8> + AsBytes + Clone + Debug,
+    {
+        std::str::from_utf8(raw.as_bytes()).map_err(|err| {
+            ControlFlow::Fatal(ReadError::InvalidUtf8(
+                raw.clone().split_off(err.valid_up_to()),
+            ))
+        })
+    }
+
+    fn expand_with<'a, I>(
+        &self,
+        raw: &'a I,
+        limits: &EntityLimits,
+        limit_err: fn(EntityLimit, I) -> ReadError<I>,
+    ) -> Result<Cow<'a, str>, ControlFlow<ReadError<I>>>
+    where
+        I: Input<Item = u8> + AsBytes + Clone + Debug,
+    {
+        let bytes = raw.as_bytes();
+
+        let text = Self::utf8(raw)?;
+
+        if memchr::memchr(b'&', bytes).is_none() {
+            return match self.newlines {
+                true => Ok(normalize_newlines(text)),
+                false => Ok(Cow::Borrowed(text)),
+            };
+        }
+
+        let mut expansion = Expansion {
+            expanded: String::with_capacity(text.len()),
+            budget: limits.max_expansion,
+            max_depth: limits.max_depth,
+        };
+
+        self.expand_into(text, 0, &mut expansion)
+            .map_err(|(start, err)| {
+                let input = raw.clone().split_off(start);
+
+                ControlFlow::Fatal(match err {
+                    Invalid::Unterminated => ReadError::Expect(ReadKind::Reference, input),
+                    Invalid::CharRef => ReadError::Unexpect(ReadKind::Reference, input),
+                    Invalid::CodePoint => ReadError::InvalidCharRef(input),
+                    Invalid::Unknown => ReadError::UnknownEntity(input),
+                    Invalid::Limit(limit) => limit_err(limit, input),
+                })
+            })?;
+
+        let inserted = limits.max_expansion - expansion.budget;
+
+        if inserted > 0 {
+            let total = self.expanded.fetch_add(inserted, Ordering::Relaxed) + inserted;
+
+            if total > self.max_total_expansion {
+                return Err(ControlFlow::Fatal(ReadError::LimitExceeded(
+                    ReaderLimit::TotalExpansion,
+                    raw.clone(),
+                )));
+            }
+        }
+
+        Ok(Cow::Owned(expansion.expanded))
+    }
+
+    /// Expand `text` at nesting `depth`, errors carry the offset of the outermost reference.
+    fn expand_into(
+        &self,
+        text: &str,
+        depth: usize,
+        expansion: &mut Expansion,
+    ) -> Result<(), (usize, Invalid)> {
+        let bytes = text.as_bytes();
+        let mut offset = 0;
+        let mut next = memchr::memchr(b'&', bytes);
+
+        while let Some(start) = next {
+            self.push_literal(&text[offset..start], expansion);
+
+            // errors of nested values are reported at the reference that started the expansion.
+            let at = |err| if depth == 0 { (start, err) } else { (0, err) };
+
+            let end = memchr::memchr(b';', &bytes[start..])
+                .map(|len| start + len)
+                .ok_or_else(|| at(Invalid::Unterminated))?;
+
+            let name = &text[start + 1..end];
+
+            if let Some(code) = name.strip_prefix('#') {
+                let code = match code.strip_prefix('x') {
+                    Some(hex) => u32::from_str_radix(hex, 16),
+                    None => code.parse::<u32>(),
+                }
+                .map_err(|_| at(Invalid::CharRef))?;
+
+                // surrogates are no chars at all, noncharacters fail the `Char` production.
+                match char::from_u32(code).filter(|c| is_char(self.version, *c)) {
+                    Some(c) => expansion.expanded.push(c),
+                    None => match self.invalid_char_ref {
+                        InvalidCharRef::Error => return Err(at(Invalid::CodePoint)),
+                        InvalidCharRef::Replace(c) => expansion.expanded.push(c),
+                    },
+                }
+            } else if let Some(predefined) = predefined(name) {
+                expansion.expanded.push_str(predefined);
+            } else if let Some(value) = self.get(name) {
+                if depth == expansion.max_depth {
+                    return Err(at(Invalid::Limit(EntityLimit::Depth)));
+                }
+
+                expansion.budget = expansion
+                    .budget
+                    .checked_sub(value.len())
+                    .ok_or_else(|| at(Invalid::Limit(EntityLimit::Expansion)))?;
+
+                self.expand_into(&value, depth + 1, expansion)
+                    .map_err(|(inner, err)| {
+                        if depth == 0 {
+                            (start, err)
+                        } else {
+                            (inner, err)
+                        }
+                    })?;
+            } else {
+                match &self.unknown {
+                    UnknownEntity::Error => return Err(at(Invalid::Unknown)),
+                    UnknownEntity::KeepLiteral => expansion.expanded.push_str(&text[start..=end]),
+                    UnknownEntity::Replace(value) => expansion.expanded.push_str(value),
+                }
+            }
+
+            offset = end + 1;
+            next = memchr::memchr(b'&', &bytes[offset..]).map(|len| offset + len);
+        }
+
+        self.push_literal(&text[offset..], expansion);
+
+        Ok(())
+    }
+
+    /// Append the literal `text` between references, normalizing its line breaks.
+    fn push_literal(&self, text: &str, expansion: &mut Expansion) {
+        match self.newlines {
+            true => expansion.expanded.push_str(&normalize_newlines(text)),
+            false => expansion.expanded.push_str(text),
+        }
+    }
+}
+
+/// Returns the replacement text of a [`predefined entity`](https://www.w3.org/TR/xml11/#sec-predefined-ent).
+fn predefined(name: &str) -> Option<&'static str> {
+    match name {
+        "lt" => Some("<"),
+        "gt" => Some(">"),
+        "amp" => Some("&"),
+        "apos" => Some("'"),
+        "quot" => Some("\""),
+        _ => None,
+    }
+}
+
+/// State of one [`Entities::expand_with`] call.
+struct Expansion {
+    expanded: String,
+    /// replacement text bytes left.
+    budget: usize,
+    max_depth: usize,
+}
+
+/// Reasons a reference can't be expanded.
+enum Invalid {
+    Unterminated,
+    CharRef,
+    CodePoint,
+    Unknown,
+    Limit(EntityLimit),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use parserc::ControlFlow;
+
+    use crate::{
+        reader::{ReadError, UnknownEntity},
+        types::XmlVersion,
+    };
+
+    use super::{Entities, EntityLimit, EntityLimits, InvalidCharRef};
+
+    #[test]
+    fn test_expand() {
+        let mut entities = Entities::new();
+
+        entities.insert("h", "hardcover");
+        entities.resolver(|name| (name == "p").then(|| "paperback".to_string()));
+
+        assert_eq!(
+            entities.expand(&b"plain".as_slice()),
+            Ok(Cow::Borrowed("plain"))
+        );
+        assert_eq!(
+            entities
+                .expand(&b"&h; or &p; &lt;&#x20AC;&#65;&gt;".as_slice())
+                .unwrap(),
+            "hardcover or paperback <\u{20AC}A>"
+        );
+        assert_eq!(
+            entities.expand(&b"a &e; b".as_slice()),
+            Err(ControlFlow::Fatal(ReadError::UnknownEntity(
+                b"&e; b".as_slice()
+            )))
+        );
+
+        entities.unknown(UnknownEntity::KeepLiteral);
+        assert_eq!(entities.expand(&b"a &e; b".as_slice()).unwrap(), "a &e; b");
+
+        entities.unknown(UnknownEntity::Replace("?".to_string()));
+        assert_eq!(entities.expand(&b"a &e; b".as_slice()).unwrap(), "a ? b");
+    }
+
+    #[test]
+    fn test_normalize_newlines() {
+        let mut entities = Entities::new();
+
+        assert_eq!(
+            entities.expand(&b"a\r\nb\rc\n".as_slice()).unwrap(),
+            "a\nb\nc\n"
+        );
+        assert_eq!(
+            entities
+                .expand_attr(&b"a\r\n&amp;&#13;\r".as_slice())
+                .unwrap(),
+            "a\n&\r\n"
+        );
+        assert_eq!(entities.cdata(&b"a\r\n".as_slice()).unwrap(), "a\n");
+
+        entities.normalize_newlines(false);
+
+        assert_eq!(
+            entities.expand(&b"a\r\nb".as_slice()),
+            Ok(Cow::Borrowed("a\r\nb"))
+        );
+        assert_eq!(entities.cdata(&b"a\r".as_slice()).unwrap(), "a\r");
+    }
+
+    #[test]
+    fn test_nested() {
+        let mut entities = Entities::new();
+
+        entities.insert("a", "&b;&b;");
+        entities.insert("b", "[&lt;]");
+        entities.insert("loop", "x&loop;");
+
+        assert_eq!(
+            entities.expand(&b"&a; &a;".as_slice()).unwrap(),
+            "[<][<] [<][<]"
+        );
+
+        assert_eq!(
+            entities.expand(&b"1 &loop;".as_slice()),
+            Err(ControlFlow::Fatal(ReadError::TextEntityLimit(
+                EntityLimit::Depth,
+                b"&loop;".as_slice()
+            )))
+        );
+
+        assert_eq!(
+            entities.expand_attr(&b"&loop;".as_slice()),
+            Err(ControlFlow::Fatal(ReadError::AttrEntityLimit(
+                EntityLimit::Depth,
+                b"&loop;".as_slice()
+            )))
+        );
+
+        entities.attr_limits(EntityLimits {
+            max_depth: 8,
+            max_expansion: 20,
+        });
+
+        assert_eq!(entities.expand_attr(&b"&a;".as_slice()).unwrap(), "[<][<]");
+        assert_eq!(
+            entities.expand_attr(&b"&a;&a;".as_slice()),
+            Err(ControlFlow::Fatal(ReadError::AttrEntityLimit(
+                EntityLimit::Expansion,
+                b"&a;".as_slice()
+            )))
+        );
+        assert_eq!(
+            entities.expand(&b"&a;&a;".as_slice()).unwrap(),
+            "[<][<][<][<]"
+        );
+    }
+
+    #[test]
+    fn test_invalid_char_ref() {
+        let mut entities = Entities::new();
+
+        for reference in ["&#xD800;", "&#xFFFE;", "&#65535;", "&#x1;", "&#x110000;"] {
+            let raw = format!("a {}", reference);
+
+            assert_eq!(
+                entities.expand(&raw.as_bytes()),
+                Err(ControlFlow::Fatal(ReadError::InvalidCharRef(
+                    reference.as_bytes()
+                ))),
+                "{}",
+                reference
+            );
+        }
+
+        entities.version(XmlVersion::Ver11);
+        assert_eq!(entities.expand(&b"&#x1;".as_slice()).unwrap(), "\u{1}");
+
+        entities.invalid_char_ref(InvalidCharRef::Replace('\u{FFFD}'));
+        assert_eq!(
+            entities.expand_attr(&b"a&#xD800;b".as_slice()).unwrap(),
+            "a\u{FFFD}b"
+        );
+    }
+}
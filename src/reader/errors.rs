@@ -1,5 +1,15 @@
 use std::fmt::Debug;
 
+use super::{DetectedEncoding, EntityLimit, Position, ReaderLimit};
+
+/// Errors of [`XmlReader`](super::XmlReader).
+///
+/// Most variants carry the input where the error occurred instead of a [`Position`]: they are
+/// created by parsers that only see the remaining input, and recoverable errors are created and
+/// dropped while trying alternatives, so counting lines for each would cost more than the parse.
+/// [`XmlReader::error_position`](super::XmlReader::error_position) turns the carried input into
+/// a line and column on demand, errors detected by the reader itself like
+/// [`Mismatch`](Self::Mismatch) carry their position.
 #[derive(Debug, thiserror::Error, PartialEq, Clone)]
 pub enum ReadError<I> {
     #[error(transparent)]
@@ -9,6 +19,124 @@ pub enum ReadError<I> {
 
     #[error("unexpect {0} {1}")]
     Unexpect(ReadKind, I),
+
+    /// The encoding declaration value does not match [`EncName`](https://www.w3.org/TR/xml11/#NT-EncName).
+    #[error("invalid encoding name {0}")]
+    InvalidEncodingName(I),
+
+    /// The byte stream encoding conflicts with the encoding declaration.
+    #[error("detected {detected} encoding, but the declaration claims `{declared}`")]
+    EncodingMismatch {
+        detected: DetectedEncoding,
+        declared: String,
+    },
+
+    /// The document is encoded in a family this reader can't parse.
+    #[error("unsupported {0} encoding")]
+    UnsupportedEncoding(DetectedEncoding),
+
+    /// The input is not valid utf-8, starting at the carried position.
+    #[error("invalid utf-8 sequence {0}")]
+    InvalidUtf8(I),
+
+    /// A reference to an entity that is neither predefined nor registered.
+    #[error("unknown entity {0}")]
+    UnknownEntity(I),
+
+    /// A character reference to a code point that is not a legal `Char`, e.g. a surrogate.
+    #[error("invalid character reference {0}")]
+    InvalidCharRef(I),
+
+    /// A qualified name uses a prefix without namespace declaration in scope.
+    #[error("unbound namespace prefix {0}")]
+    UnboundPrefix(I),
+
+    /// Two attributes of a start tag have the same name, or the same local name and namespace.
+    #[error("duplicate attribute {0}")]
+    DuplicateAttr(I),
+
+    /// The carried region of an attribute list is no attribute, e.g. a name starting with a
+    /// digit, or an attribute not separated from the previous one by whitespace.
+    #[error("malformed attribute {0}")]
+    MalformedAttr(I),
+
+    /// An end tag doesn't close the innermost open element, `found` is the end tag name at
+    /// `position`.
+    #[error("end tag {found} doesn't match start tag {expected} at {position}")]
+    Mismatch {
+        expected: I,
+        found: I,
+        position: Position,
+    },
+
+    /// Expanding the entity reference at the carried position in a text node exceeded a limit.
+    #[error("{0} limit of text exceeded {1}")]
+    TextEntityLimit(EntityLimit, I),
+
+    /// Expanding the entity reference at the carried position in an attribute value exceeded a limit.
+    #[error("{0} limit of attribute value exceeded {1}")]
+    AttrEntityLimit(EntityLimit, I),
+
+    /// Strict mode only: a comment contains the carried `--`, or ends with the carried `-`.
+    #[error("illegal `--` in comment {0}")]
+    InvalidComment(I),
+
+    /// The carried character is not allowed at its position in a name, see [`Name::validate`](super::Name::validate).
+    #[error("illegal name character {0}")]
+    InvalidName(I),
+
+    /// A character not allowed literally by the xml version of the document, e.g. a control
+    /// character in xml 1.0, see [`XmlReaderBuilder::check_chars`](super::XmlReaderBuilder::check_chars).
+    #[error("illegal character U+{codepoint:04X} {position}")]
+    IllegalChar { codepoint: u32, position: I },
+
+    /// The node at the carried position exceeded a [`ReaderLimits`](super::ReaderLimits) cap.
+    #[error("{0} limit exceeded {1}")]
+    LimitExceeded(ReaderLimit, I),
+
+    /// The xml declaration at the carried position is preceded by whitespace or comments.
+    #[error("xml declaration not at the start of the document {0}")]
+    MisplacedDecl(I),
+
+    /// A byte order mark at the carried position is preceded by whitespace or comments.
+    #[error("byte order mark not at the start of the document {0}")]
+    MisplacedBom(I),
+
+    /// The input ended inside the element with the carried name.
+    #[error("unclosed element {0}")]
+    Unclosed(I),
+}
+
+impl<I> ReadError<I> {
+    /// Returns the input position carried by this error, if any.
+    pub fn input(&self) -> Option<&I> {
+        match self {
+            ReadError::Expect(_, input)
+            | ReadError::Unexpect(_, input)
+            | ReadError::InvalidEncodingName(input)
+            | ReadError::InvalidUtf8(input)
+            | ReadError::UnknownEntity(input)
+            | ReadError::InvalidCharRef(input)
+            | ReadError::UnboundPrefix(input)
+            | ReadError::DuplicateAttr(input)
+            | ReadError::TextEntityLimit(_, input)
+            | ReadError::AttrEntityLimit(_, input)
+            | ReadError::MalformedAttr(input)
+            | ReadError::LimitExceeded(_, input)
+            | ReadError::InvalidName(input)
+            | ReadError::InvalidComment(input)
+            | ReadError::MisplacedDecl(input)
+            | ReadError::MisplacedBom(input)
+            | ReadError::IllegalChar {
+                position: input, ..
+            }
+            | ReadError::Mismatch { found: input, .. }
+            | ReadError::Unclosed(input) => Some(input),
+            ReadError::Parserc(_)
+            | ReadError::EncodingMismatch { .. }
+            | ReadError::UnsupportedEncoding(_) => None,
+        }
+    }
 }
 
 #[derive(Debug, thiserror::Error, PartialEq, Clone)]
@@ -29,4 +157,14 @@ pub enum ReadKind {
     Encoding,
     #[error("`chardata`")]
     CharData,
+    #[error("`reference`")]
+    Reference,
+    #[error("quote")]
+    Quote,
+    #[error("`<`")]
+    Lt,
+    #[error("markup declaration")]
+    MarkupDecl,
+    #[error("element")]
+    Element,
 }
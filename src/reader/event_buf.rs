@@ -0,0 +1,134 @@
+use std::fmt::Debug;
+
+use parserc::{AsBytes, ControlFlow, Input};
+
+use super::{Attr, ReadError, XmlNode, XmlReader};
+
+/// Reusable storage of the event read by [`XmlReader::read_next_into`].
+///
+/// The attribute list and decoded text of each event are kept in buffers that are cleared,
+/// not freed, between events, so a long running reader stops allocating once they have grown
+/// to the largest event.
+#[derive(Debug)]
+pub struct EventBuf<I> {
+    node: Option<XmlNode<I>>,
+    attrs: Vec<Attr<I>>,
+    text: String,
+}
+
+impl<I> Default for EventBuf<I> {
+    fn default() -> Self {
+        Self {
+            node: None,
+            attrs: vec![],
+            text: String::new(),
+        }
+    }
+}
+
+impl<I> EventBuf<I> {
+    /// Create an empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the event read last, `None` before the first read and at the end of the document.
+    pub fn node(&self) -> Option<&XmlNode<I>> {
+        self.node.as_ref()
+    }
+
+    /// Returns the attributes of a start tag event, empty for other events.
+    pub fn attrs(&self) -> &[Attr<I>] {
+        &self.attrs
+    }
+
+    /// Returns the expanded content of a text or cdata event, empty for other events.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn clear(&mut self) {
+        self.node = None;
+        self.attrs.clear();
+        self.text.clear();
+    }
+}
+
+impl<I> XmlReader<I>
+where
+    I: Input<Item = u8> + AsBytes + Clone + Debug,
+{
+    /// Read the next event into `buf`, returning false at the end of the document.
+    ///
+    /// Unlike [`read_next`](Self::read_next), the attributes of start tags are parsed and the
+    /// content of text events is expanded, into buffers reused across calls.
+    pub fn read_next_into(
+        &mut self,
+        buf: &mut EventBuf<I>,
+    ) -> Result<bool, ControlFlow<ReadError<I>>> {
+        buf.clear();
+
+        let Some(node) = self.read_next()? else {
+            return Ok(false);
+        };
+
+        match &node {
+            XmlNode::Start(start) => {
+                for attr in start.attrs() {
+                    buf.attrs.push(attr?);
+                }
+            }
+            XmlNode::CharData(chardata) => buf.text.push_str(&self.text(chardata)?),
+            XmlNode::CData(cdata) => buf.text.push_str(&self.cdata(cdata)?),
+            XmlNode::Text(text) => buf.text.push_str(text),
+            _ => {}
+        }
+
+        buf.node = Some(node);
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reader::{XmlNode, XmlReader};
+
+    use super::EventBuf;
+
+    #[test]
+    fn test_read_next_into() {
+        let mut reader = XmlReader::from(
+            br#"<?xml version="1.0"?><a x="1" y='2'>b &amp; c<![CDATA[<d>]]></a>"#.as_slice(),
+        );
+
+        let mut buf = EventBuf::new();
+        let mut events = vec![];
+
+        while reader.read_next_into(&mut buf).unwrap() {
+            events.push((
+                buf.attrs().iter().map(|attr| attr.name).collect::<Vec<_>>(),
+                buf.text().to_string(),
+            ));
+        }
+
+        assert_eq!(
+            events,
+            [
+                (vec![], "".to_string()),
+                (vec![b"x".as_slice(), b"y".as_slice()], "".to_string()),
+                (vec![], "b & c".to_string()),
+                (vec![], "<d>".to_string()),
+                (vec![], "".to_string()),
+            ]
+        );
+
+        assert!(buf.node().is_none());
+        assert!(buf.attrs.capacity() >= 2);
+
+        let mut reader = XmlReader::from(b"<a/>".as_slice());
+
+        assert!(reader.read_next_into(&mut buf).unwrap());
+        assert!(matches!(buf.node(), Some(XmlNode::Start(start)) if start.is_empty));
+    }
+}
@@ -0,0 +1,165 @@
+use std::{fmt::Display, time::Duration};
+
+use super::XmlNode;
+
+/// Construct kinds tracked by [`ParseReport`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ConstructKind {
+    /// The xml declaration.
+    XmlDecl,
+    /// The doctype declaration, including internal DTD subset.
+    DocType,
+    /// Processing instructions.
+    PI,
+    /// Whitespace between markup.
+    S,
+    /// Comments.
+    Comment,
+    /// Element start and end tags.
+    Tag,
+    /// Character data.
+    Text,
+    /// CDATA sections.
+    CData,
+    /// Unclassified markup, see [`Unknown`](super::Unknown).
+    Unknown,
+}
+
+impl ConstructKind {
+    /// All tracked kinds, in report order.
+    pub const ALL: [ConstructKind; 9] = [
+        ConstructKind::XmlDecl,
+        ConstructKind::DocType,
+        ConstructKind::PI,
+        ConstructKind::S,
+        ConstructKind::Comment,
+        ConstructKind::Tag,
+        ConstructKind::Text,
+        ConstructKind::CData,
+        ConstructKind::Unknown,
+    ];
+
+    /// Returns the construct kind of one xml node.
+    pub fn of<I>(node: &XmlNode<I>) -> Self {
+        match node {
+            XmlNode::XmlDecl(_) => ConstructKind::XmlDecl,
+            XmlNode::DocType(_) => ConstructKind::DocType,
+            XmlNode::PI(_) => ConstructKind::PI,
+            XmlNode::S(_) => ConstructKind::S,
+            XmlNode::Comment(_) => ConstructKind::Comment,
+            XmlNode::Start(_) | XmlNode::End(_) => ConstructKind::Tag,
+            XmlNode::CharData(_) | XmlNode::Text(_) => ConstructKind::Text,
+            XmlNode::CData(_) => ConstructKind::CData,
+            XmlNode::Unknown(_) => ConstructKind::Unknown,
+        }
+    }
+}
+
+impl Display for ConstructKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConstructKind::XmlDecl => write!(f, "xmldecl"),
+            ConstructKind::DocType => write!(f, "doctype"),
+            ConstructKind::PI => write!(f, "pi"),
+            ConstructKind::S => write!(f, "whitespace"),
+            ConstructKind::Comment => write!(f, "comment"),
+            ConstructKind::Tag => write!(f, "tag"),
+            ConstructKind::Text => write!(f, "text"),
+            ConstructKind::CData => write!(f, "cdata"),
+            ConstructKind::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Counters collected for one [`ConstructKind`].
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct ConstructStats {
+    /// number of parsed nodes.
+    pub count: usize,
+    /// number of consumed input bytes.
+    pub bytes: usize,
+    /// time spent parsing these nodes.
+    pub elapsed: Duration,
+}
+
+/// Per-construct parse statistics collected by [`XmlReader`](super::XmlReader).
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct ParseReport {
+    stats: [ConstructStats; ConstructKind::ALL.len()],
+}
+
+impl ParseReport {
+    /// Returns the counters of one construct kind.
+    pub fn get(&self, kind: ConstructKind) -> &ConstructStats {
+        &self.stats[kind as usize]
+    }
+
+    /// Returns the sum of all counters.
+    pub fn total(&self) -> ConstructStats {
+        self.stats
+            .iter()
+            .fold(ConstructStats::default(), |acc, stats| ConstructStats {
+                count: acc.count + stats.count,
+                bytes: acc.bytes + stats.bytes,
+                elapsed: acc.elapsed + stats.elapsed,
+            })
+    }
+
+    /// Create an iterator over all counters, in [`ConstructKind::ALL`] order.
+    pub fn iter(&self) -> impl Iterator<Item = (ConstructKind, &ConstructStats)> {
+        ConstructKind::ALL.into_iter().zip(self.stats.iter())
+    }
+
+    /// Reset all counters to zero.
+    pub fn clear(&mut self) {
+        self.stats = Default::default();
+    }
+
+    /// Record one parsed node.
+    pub(super) fn record(&mut self, kind: ConstructKind, bytes: usize, elapsed: Duration) {
+        let stats = &mut self.stats[kind as usize];
+
+        stats.count += 1;
+        stats.bytes += bytes;
+        stats.elapsed += elapsed;
+    }
+}
+
+impl Display for ParseReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (kind, stats) in self.iter() {
+            writeln!(
+                f,
+                "{:<10} count={} bytes={} elapsed={:?}",
+                kind, stats.count, stats.bytes, stats.elapsed
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reader::{ConstructKind, XmlReader};
+
+    #[test]
+    fn test_report() {
+        let input = br#"<?xml version="1.0"?><!-- c --><a x="1"><b/>hello</a>"#.as_slice();
+
+        let mut reader = XmlReader::from(input);
+
+        for node in &mut reader {
+            node.unwrap();
+        }
+
+        let report = reader.report();
+
+        assert_eq!(report.get(ConstructKind::XmlDecl).count, 1);
+        assert_eq!(report.get(ConstructKind::Comment).count, 1);
+        assert_eq!(report.get(ConstructKind::Comment).bytes, 10);
+        assert_eq!(report.get(ConstructKind::Tag).count, 3);
+        assert_eq!(report.get(ConstructKind::Text).bytes, 5);
+        assert_eq!(report.total().bytes, input.len());
+    }
+}
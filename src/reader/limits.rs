@@ -0,0 +1,75 @@
+/// The cap exceeded by a document, see [`ReaderLimits`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, thiserror::Error)]
+pub enum ReaderLimit {
+    /// Too deeply nested elements.
+    #[error("element nesting depth")]
+    Depth,
+    /// Too many attributes on one element.
+    #[error("attribute count")]
+    Attrs,
+    /// Too long markup or text node.
+    #[error("token length")]
+    TokenLength,
+    /// Too long comment.
+    #[error("comment length")]
+    CommentLength,
+    /// Too long processing instruction.
+    #[error("processing instruction length")]
+    PILength,
+    /// Too much replacement text, summed over the whole document.
+    #[error("total entity expansion")]
+    TotalExpansion,
+}
+
+/// Caps on the resources one document may use, to defend readers against malicious input.
+///
+/// The caps are enforced by [`XmlReader`](super::XmlReader), which fails with
+/// [`ReadError::LimitExceeded`](super::ReadError::LimitExceeded) at the offending node.
+/// [`EntityLimits`](super::EntityLimits) cap single text nodes and attribute values, the total
+/// expansion cap here catches many of them which each stay within those.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ReaderLimits {
+    /// Maximum nesting of elements, `1` allows only the root element.
+    pub max_depth: usize,
+    /// Maximum number of attributes of one element, including namespace declarations.
+    pub max_attrs: usize,
+    /// Maximum length in bytes of one node, e.g. a text node or start tag.
+    pub max_token_len: usize,
+    /// Maximum length in bytes of one comment including its delimiters.
+    ///
+    /// Comments dropped by [`skip_comments`](super::XmlReaderBuilder::skip_comments) are
+    /// discarded while scanning for their end and are not capped.
+    pub max_comment_len: usize,
+    /// Maximum length in bytes of one processing instruction including its delimiters.
+    pub max_pi_len: usize,
+    /// Maximum number of replacement text bytes inserted by all expansions of the document.
+    pub max_total_expansion: usize,
+}
+
+impl ReaderLimits {
+    /// No caps, the default.
+    pub const UNLIMITED: Self = Self {
+        max_depth: usize::MAX,
+        max_attrs: usize::MAX,
+        max_token_len: usize::MAX,
+        max_comment_len: usize::MAX,
+        max_pi_len: usize::MAX,
+        max_total_expansion: usize::MAX,
+    };
+
+    /// Caps for documents from untrusted sources, generous for any legitimate document.
+    pub const UNTRUSTED: Self = Self {
+        max_depth: 256,
+        max_attrs: 1024,
+        max_token_len: 16 << 20,
+        max_comment_len: 1 << 20,
+        max_pi_len: 1 << 20,
+        max_total_expansion: 16 << 20,
+    };
+}
+
+impl Default for ReaderLimits {
+    fn default() -> Self {
+        Self::UNLIMITED
+    }
+}
@@ -0,0 +1,178 @@
+use std::fmt::Debug;
+
+use parserc::{AsBytes, ControlFlow, Input};
+
+use super::{ElemStart, ReadError, ReadKind, XmlNode, XmlReader};
+
+impl<I> XmlReader<I>
+where
+    I: Input<Item = u8> + AsBytes + Clone + Debug,
+{
+    /// Read up to the next start tag and check its name is `name`.
+    ///
+    /// Text, comments and PIs before it are skipped. Fails with [`ReadKind::Element`] as
+    /// [`ReadError::Unexpect`] if the element has another name, or as [`ReadError::Expect`] if an
+    /// end tag or the end of input comes first.
+    pub fn expect_element(
+        &mut self,
+        name: &str,
+    ) -> Result<ElemStart<I>, ControlFlow<ReadError<I>>> {
+        loop {
+            match self.read_next()? {
+                Some(XmlNode::Start(start)) if start.has_name(name) => {
+                    return Ok(start);
+                }
+                Some(XmlNode::Start(start)) => {
+                    return Err(ControlFlow::Fatal(ReadError::Unexpect(
+                        ReadKind::Element,
+                        start.name,
+                    )));
+                }
+                Some(XmlNode::End(end)) => {
+                    return Err(ControlFlow::Fatal(ReadError::Expect(
+                        ReadKind::Element,
+                        end.name,
+                    )));
+                }
+                Some(_) => {}
+                None => {
+                    return Err(ControlFlow::Fatal(ReadError::Expect(
+                        ReadKind::Element,
+                        self.remaining().clone(),
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Call `f` with each child element of the element opened by `start`, up to its end tag.
+    ///
+    /// Call it right after `start` was returned. Other child nodes are skipped, and so is the
+    /// rest of a child element `f` doesn't read up to its end tag, so `f` only reads what it needs.
+    /// See [`match_xml!`](crate::match_xml) for dispatching children by name.
+    pub fn read_children<F>(
+        &mut self,
+        start: &ElemStart<I>,
+        mut f: F,
+    ) -> Result<(), ControlFlow<ReadError<I>>>
+    where
+        F: FnMut(&mut Self, ElemStart<I>) -> Result<(), ControlFlow<ReadError<I>>>,
+    {
+        if start.is_empty {
+            return Ok(());
+        }
+
+        let depth = self.depth();
+
+        loop {
+            match self.read_next()? {
+                Some(XmlNode::Start(child)) => {
+                    f(self, child)?;
+
+                    while self.depth() > depth {
+                        if self.read_next()?.is_none() {
+                            return Ok(());
+                        }
+                    }
+                }
+                Some(XmlNode::End(_)) | None => return Ok(()),
+                Some(_) => {}
+            }
+        }
+    }
+}
+
+/// Dispatch the child elements of a start tag by name, see [`XmlReader::read_children`].
+///
+/// Each arm binds the child start tag and runs a block that may use the reader and `?`;
+/// children without matching arm are skipped, unless a final `_` arm handles them.
+///
+/// ```
+/// use rexml::{match_xml, reader::XmlReader};
+///
+/// let mut reader = XmlReader::from(
+///     br#"<?xml version="1.0"?><book id="7"><title>Dune</title><isbn/><author>Herbert</author></book>"#
+///         .as_slice(),
+/// );
+///
+/// let book = reader.expect_element("book")?;
+/// let id = reader.get_attr(&book, b"id")?;
+/// let (mut title, mut authors) = (None, vec![]);
+///
+/// match_xml!(reader, book,
+///     "title" => |start| { title = Some(reader.read_text(&start, Default::default())?); },
+///     "author" => |start| { authors.push(reader.read_text(&start, Default::default())?); },
+/// )?;
+///
+/// assert_eq!(id, Some(b"7".as_slice()));
+/// assert_eq!(title.as_deref(), Some("Dune"));
+/// assert_eq!(authors, ["Herbert"]);
+/// # Ok::<(), parserc::ControlFlow<rexml::reader::ReadError<&[u8]>>>(())
+/// ```
+#[macro_export]
+macro_rules! match_xml {
+    (
+        $reader:ident, $start:expr,
+        $( $name:literal => |$child:pat_param| $body:block , )*
+        $( _ => |$other:pat_param| $fallback:block $(,)? )?
+    ) => {
+        $reader.read_children(&$start, |$reader, child| {
+            match child {
+                $(
+                    child if child.has_name($name) => {
+                        let $child = child;
+                        $body
+                    }
+                )*
+                _ => {
+                    $(
+                        let $other = child;
+                        $fallback
+                    )?
+                }
+            }
+
+            Ok(())
+        })
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reader::{ReadError, ReadKind, XmlReader};
+
+    #[test]
+    fn test_match_xml() {
+        let source = br#"<?xml version="1.0"?><shelf><!--c--><book><title>A</title><x><title>no</title></x></book>text<book><title>B</title></book><box/></shelf>"#;
+
+        let mut reader = XmlReader::from(source.as_slice());
+
+        let shelf = reader.expect_element("shelf").unwrap();
+        let mut titles = vec![];
+        let mut others = vec![];
+
+        crate::match_xml!(reader, shelf,
+            "book" => |book| {
+                crate::match_xml!(reader, book,
+                    "title" => |title| { titles.push(reader.read_text(&title, Default::default())?); },
+                )?;
+            },
+            _ => |other| { others.push(other.name); },
+        )
+        .unwrap();
+
+        assert_eq!(titles, ["A", "B"]);
+        assert_eq!(others, [b"box".as_slice()]);
+        assert_eq!(reader.depth(), 0);
+
+        let mut reader = XmlReader::from(br#"<?xml version="1.0"?><a/>"#.as_slice());
+
+        assert_eq!(
+            reader.expect_element("b").map(|_| ()),
+            Err(parserc::ControlFlow::Fatal(ReadError::Unexpect(
+                ReadKind::Element,
+                b"a".as_slice()
+            )))
+        );
+    }
+}
@@ -1,9 +1,12 @@
 use std::fmt::Debug;
 
-use parserc::{AsBytes, ControlFlow, Input, Kind, Parse, Parser, ParserExt, keyword, take_until};
+use parserc::{
+    AsBytes, ControlFlow, Input, Kind, Parse, Parser, ParserExt, keyword, take_till, take_until,
+};
 
 use crate::{
-    reader::{Attr, Name, ReadKind, parse_ws},
+    chars::illegal_comment_offset,
+    reader::{Attr, Attrs, Name, ReadKind, is_ws, parse_literal, parse_ws},
     types::XmlVersion,
 };
 
@@ -18,6 +21,24 @@ pub struct XmlDecl<I> {
     pub encoding: Option<I>,
     /// optional standalone flag.
     pub standalone: Option<bool>,
+    /// the declaration as written, from `<?xml` to `?>`, e.g. to rewrite a file keeping its
+    /// quote style and whitespace.
+    pub raw: I,
+}
+
+impl<I> XmlDecl<I>
+where
+    I: Input<Item = u8> + AsBytes + Clone + Debug,
+{
+    /// Create an iterator over the pseudo-attributes as written, e.g. to find their quote chars,
+    /// see [`Attr::quote`].
+    pub fn pseudo_attrs(&self) -> Attrs<I> {
+        let mut content = self.raw.clone();
+        content.split_to(b"<?xml".len());
+        content.split_off(content.len() - b"?>".len());
+
+        Attrs::new(content)
+    }
 }
 
 impl<I> Parse<I> for XmlDecl<I>
@@ -27,9 +48,28 @@ where
     type Error = ReadError<I>;
     #[inline(always)]
     fn parse(input: I) -> parserc::Result<Self, I, Self::Error> {
+        let mut raw = input.clone();
+
         let (_, input) = keyword(b"<?xml".as_slice()).parse(input)?;
 
-        let (version, input) = Attr::into_parser()
+        // `<?xml-stylesheet ...?>` and friends are processing instructions, not a declaration.
+        match input.iter().next() {
+            Some(c) if is_ws(c) => {}
+            Some(b'?') => {
+                return Err(ControlFlow::Fatal(ReadError::Expect(
+                    ReadKind::Version,
+                    input,
+                )));
+            }
+            _ => {
+                return Err(ControlFlow::Recovable(ReadError::Expect(
+                    ReadKind::S,
+                    input,
+                )));
+            }
+        }
+
+        let (version, rest) = Attr::into_parser()
             .map_err(|_| ReadError::Expect(ReadKind::Version, input.clone()))
             .fatal()
             .parse(input.clone())?;
@@ -47,82 +87,43 @@ where
             _ => {
                 return Err(ControlFlow::Fatal(ReadError::Unexpect(
                     ReadKind::Version,
-                    input,
+                    version.value,
                 )));
             }
         };
 
-        let (attr, input) = Attr::into_parser().ok().parse(input)?;
-
-        let encoding = if let Some(attr) = attr {
-            match attr.name.as_bytes() {
-                b"encoding" => Some(attr.value),
-                b"standalone" => {
-                    let standalone = match attr.value.as_bytes() {
-                        b"yes" => true,
-                        b"no" => false,
-                        _ => {
-                            return Err(ControlFlow::Fatal(ReadError::Expect(
-                                ReadKind::YesNo,
-                                input,
-                            )));
-                        }
-                    };
-
-                    let (_, input) = parse_ws(input)?;
-
-                    let (_, input) = keyword(b"?>".as_slice())
-                        .fatal()
-                        .map_err(|_: Kind| {
-                            ReadError::Expect(ReadKind::Keyword("?>"), input.clone())
-                        })
-                        .parse(input.clone())?;
-
-                    return Ok((
-                        XmlDecl {
-                            version,
-                            encoding: None,
-                            standalone: Some(standalone),
-                        },
-                        input,
-                    ));
-                }
-                _ => {
-                    return Err(ControlFlow::Fatal(ReadError::Expect(
-                        ReadKind::Encoding,
-                        input,
-                    )));
-                }
-            }
-        } else {
-            None
-        };
+        let mut encoding = None;
+        let mut standalone = None;
 
-        let (attr, input) = Attr::into_parser().ok().parse(input)?;
-
-        let standalone = if let Some(attr) = attr {
-            if attr.name.as_bytes() == b"standalone" {
-                let standalone = match attr.value.as_bytes() {
-                    b"yes" => true,
-                    b"no" => false,
-                    _ => {
-                        return Err(ControlFlow::Fatal(ReadError::Expect(
-                            ReadKind::YesNo,
-                            input,
-                        )));
-                    }
-                };
-
-                Some(standalone)
-            } else {
-                return Err(ControlFlow::Fatal(ReadError::Expect(
-                    ReadKind::Keyword("standalone"),
-                    input,
+        let (mut attr, mut input) = parse_pseudo_attr(rest)?;
+
+        if let Some(next) = attr.take_if(|attr| attr.name.as_bytes() == b"encoding") {
+            if !is_enc_name(next.value.as_bytes()) {
+                return Err(ControlFlow::Fatal(ReadError::InvalidEncodingName(
+                    next.value,
                 )));
             }
-        } else {
-            None
-        };
+
+            encoding = Some(next.value);
+            (attr, input) = parse_pseudo_attr(input)?;
+        }
+
+        if let Some(next) = attr.take_if(|attr| attr.name.as_bytes() == b"standalone") {
+            standalone = Some(parse_yes_no(next.value)?);
+            (attr, input) = parse_pseudo_attr(input)?;
+        }
+
+        // the pseudo-attributes are out of order, duplicated or unknown.
+        if let Some(attr) = attr {
+            let kind = match attr.name.as_bytes() {
+                b"version" => ReadKind::Keyword("version"),
+                b"encoding" => ReadKind::Keyword("encoding"),
+                b"standalone" => ReadKind::Keyword("standalone"),
+                _ => ReadKind::Name,
+            };
+
+            return Err(ControlFlow::Fatal(ReadError::Unexpect(kind, attr.name)));
+        }
 
         let (_, input) = parse_ws(input)?;
 
@@ -131,14 +132,60 @@ where
             .map_err(|_: Kind| ReadError::Expect(ReadKind::Keyword("?>"), input.clone()))
             .parse(input.clone())?;
 
-        return Ok((
+        Ok((
             XmlDecl {
                 version,
                 encoding,
                 standalone,
+                raw: raw.split_to(raw.len() - input.len()),
             },
             input,
-        ));
+        ))
+    }
+}
+
+/// Parse one optional pseudo-attribute of the xml declaration.
+///
+/// A pseudo-attribute directly following the previous value without `S` is an error.
+#[inline(always)]
+fn parse_pseudo_attr<I>(input: I) -> parserc::Result<Option<Attr<I>>, I, ReadError<I>>
+where
+    I: Input<Item = u8> + AsBytes + Clone + Debug,
+{
+    match input.iter().next() {
+        Some(c) if is_ws(c) => Attr::into_parser().ok().parse(input),
+        Some(b'?') => Ok((None, input)),
+        _ => Err(ControlFlow::Fatal(ReadError::Expect(ReadKind::S, input))),
+    }
+}
+
+/// Check if `value` matches [`EncName`](https://www.w3.org/TR/xml11/#NT-EncName).
+#[inline(always)]
+fn is_enc_name(value: &[u8]) -> bool {
+    match value.split_first() {
+        Some((first, rest)) => {
+            first.is_ascii_alphabetic()
+                && rest
+                    .iter()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, b'.' | b'_' | b'-'))
+        }
+        None => false,
+    }
+}
+
+/// Parse the value of [`SDDecl`](https://www.w3.org/TR/xml11/#NT-SDDecl)
+#[inline(always)]
+fn parse_yes_no<I>(value: I) -> Result<bool, ControlFlow<ReadError<I>>>
+where
+    I: Input<Item = u8> + AsBytes + Debug,
+{
+    match value.as_bytes() {
+        b"yes" => Ok(true),
+        b"no" => Ok(false),
+        _ => Err(ControlFlow::Fatal(ReadError::Expect(
+            ReadKind::YesNo,
+            value,
+        ))),
     }
 }
 
@@ -150,6 +197,26 @@ pub struct PI<I> {
     pub unparsed: I,
 }
 
+impl<I> PI<I> {
+    /// Split into the target name and the unparsed content.
+    pub fn into_parts(self) -> (I, I) {
+        (self.name, self.unparsed)
+    }
+}
+
+impl<I> PI<I>
+where
+    I: Input<Item = u8> + AsBytes + Clone + Debug,
+{
+    /// Create an iterator over the pseudo-attributes of the content, e.g. `href` and `type` of
+    /// `<?xml-stylesheet href="a.css" type="text/css"?>`.
+    ///
+    /// Content not written in attribute syntax is yielded as an error.
+    pub fn pseudo_attrs(&self) -> Attrs<I> {
+        Attrs::new(self.unparsed.clone())
+    }
+}
+
 impl<I> Parse<I> for PI<I>
 where
     I: Input<Item = u8> + AsBytes + Clone + Debug,
@@ -199,13 +266,98 @@ where
     }
 }
 
+impl<I> Comment<I>
+where
+    I: Input<Item = u8> + AsBytes + Clone + Debug,
+{
+    /// Check the content contains no `--` and doesn't end with `-`, see [`ReadError::InvalidComment`].
+    pub fn validate(&self) -> Result<(), ControlFlow<ReadError<I>>> {
+        match illegal_comment_offset(self.0.as_bytes()) {
+            Some(offset) => {
+                let mut dashes = self.0.clone().split_off(offset);
+                dashes.split_off(dashes.len().min(2));
+
+                Err(ControlFlow::Fatal(ReadError::InvalidComment(dashes)))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Markup the reader can't classify, e.g. `<!ENTITY` outside the doctype or `<![INCLUDE[`.
+///
+/// Only yielded in lenient mode, see [`XmlReaderBuilder::lenient`](super::XmlReaderBuilder::lenient).
+/// The content is the raw markup, including the delimiters.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Unknown<I>(pub I);
+
+impl<I> Parse<I> for Unknown<I>
+where
+    I: Input<Item = u8> + AsBytes + Clone + Debug,
+{
+    type Error = ReadError<I>;
+
+    fn parse(input: I) -> parserc::Result<Self, I, Self::Error> {
+        let mut raw = input.clone();
+
+        let (_, mut input) = keyword("<!").parse(input)?;
+
+        let body = input.as_bytes();
+
+        if body.starts_with(b"--") || body.starts_with(b"[CDATA[") || body.starts_with(b"DOCTYPE") {
+            return Err(ControlFlow::Recovable(ReadError::Unexpect(
+                ReadKind::Keyword("<!"),
+                input,
+            )));
+        }
+
+        if body.starts_with(b"[") {
+            let (content, mut rest) = take_until("]]>")
+                .fatal()
+                .map_err(|_: Kind| ReadError::Expect(ReadKind::Keyword("]]>"), input.clone()))
+                .parse(input.clone())?;
+
+            rest.split_to(3);
+
+            return Ok((Unknown(raw.split_to(content.len() + 5)), rest));
+        }
+
+        let mut len = 2;
+
+        loop {
+            let seg;
+            (seg, input) = take_till(|c: u8| matches!(c, b'>' | b'"' | b'\'')).parse(input)?;
+
+            len += seg.len();
+
+            match input.iter().next() {
+                Some(b'>') => {
+                    input.split_to(1);
+                    return Ok((Unknown(raw.split_to(len + 1)), input));
+                }
+                Some(_) => {
+                    let literal;
+                    (literal, input) = parse_literal(input)?;
+                    len += literal.len() + 2;
+                }
+                None => {
+                    return Err(ControlFlow::Fatal(ReadError::Expect(
+                        ReadKind::Keyword(">"),
+                        input,
+                    )));
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use parserc::Parse;
+    use parserc::{ControlFlow, Parse};
 
     use crate::{
-        reader::{Comment, PI, XmlDecl},
-        types::XmlVersion,
+        reader::{Attr, Comment, PI, ReadError, ReadKind, Unknown, XmlDecl},
+        types::{XmlStylesheet, XmlVersion},
     };
 
     #[test]
@@ -216,7 +368,8 @@ mod tests {
                 XmlDecl {
                     version: XmlVersion::Ver11,
                     encoding: None,
-                    standalone: None
+                    standalone: None,
+                    raw: br#"<?xml version="1.1"?>"#.as_slice()
                 },
                 b"".as_slice()
             ))
@@ -228,7 +381,8 @@ mod tests {
                 XmlDecl {
                     version: XmlVersion::Ver11,
                     encoding: None,
-                    standalone: Some(true)
+                    standalone: Some(true),
+                    raw: br#"<?xml version="1.1" standalone='yes'?>"#.as_slice()
                 },
                 b"".as_slice()
             ))
@@ -240,7 +394,8 @@ mod tests {
                 XmlDecl {
                     version: XmlVersion::Ver11,
                     encoding: Some(b"UTF-8".as_slice()),
-                    standalone: None
+                    standalone: None,
+                    raw: br#"<?xml version="1.1" encoding="UTF-8" ?>"#.as_slice()
                 },
                 b"".as_slice()
             ))
@@ -252,13 +407,183 @@ mod tests {
                 XmlDecl {
                     version: XmlVersion::Ver11,
                     encoding: Some(b"UTF-8".as_slice()),
-                    standalone: Some(false)
+                    standalone: Some(false),
+                    raw: br#"<?xml version="1.1" encoding="UTF-8" standalone='no'?>"#.as_slice()
                 },
                 b"".as_slice()
             ))
         );
     }
 
+    #[test]
+    fn test_xmldecl_raw() {
+        let source = br#"<?xml  version = '1.0'  encoding="UTF-8" ?><a/>"#;
+
+        let (decl, _) = XmlDecl::parse(source.as_slice()).unwrap();
+
+        assert_eq!(decl.raw, &source[..43]);
+
+        let attrs = decl
+            .pseudo_attrs()
+            .map(|attr| attr.map(|attr| (attr.name, attr.eq, attr.quoted)))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            attrs,
+            [
+                (b"version".as_slice(), b"=".as_slice(), b"'1.0'".as_slice()),
+                (b"encoding", b"=", br#""UTF-8""#)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_xmldecl_order() {
+        assert_eq!(
+            XmlDecl::parse(
+                br#"<?xml  version = '1.0'  encoding="UTF-8"  standalone="yes"  ?>"#.as_slice()
+            ),
+            Ok((
+                XmlDecl {
+                    version: XmlVersion::Ver10,
+                    encoding: Some(b"UTF-8".as_slice()),
+                    standalone: Some(true),
+                    raw: br#"<?xml  version = '1.0'  encoding="UTF-8"  standalone="yes"  ?>"#
+                        .as_slice()
+                },
+                b"".as_slice()
+            ))
+        );
+
+        assert_eq!(
+            XmlDecl::parse(
+                br#"<?xml version="1.0" standalone="yes" encoding="UTF-8"?>"#.as_slice()
+            ),
+            Err(ControlFlow::Fatal(ReadError::Unexpect(
+                ReadKind::Keyword("encoding"),
+                br#"encoding"#.as_slice()
+            )))
+        );
+
+        assert_eq!(
+            XmlDecl::parse(br#"<?xml encoding="UTF-8" version="1.0"?>"#.as_slice()),
+            Err(ControlFlow::Fatal(ReadError::Expect(
+                ReadKind::Version,
+                br#" encoding="UTF-8" version="1.0"?>"#.as_slice()
+            )))
+        );
+
+        assert_eq!(
+            XmlDecl::parse(
+                br#"<?xml version="1.0" encoding="UTF-8" encoding="UTF-8"?>"#.as_slice()
+            ),
+            Err(ControlFlow::Fatal(ReadError::Unexpect(
+                ReadKind::Keyword("encoding"),
+                br#"encoding"#.as_slice()
+            )))
+        );
+
+        assert_eq!(
+            XmlDecl::parse(br#"<?xml version="1.0" version="1.0"?>"#.as_slice()),
+            Err(ControlFlow::Fatal(ReadError::Unexpect(
+                ReadKind::Keyword("version"),
+                br#"version"#.as_slice()
+            )))
+        );
+
+        assert_eq!(
+            XmlDecl::parse(br#"<?xml version="1.0" hello="world"?>"#.as_slice()),
+            Err(ControlFlow::Fatal(ReadError::Unexpect(
+                ReadKind::Name,
+                br#"hello"#.as_slice()
+            )))
+        );
+    }
+
+    #[test]
+    fn test_xmldecl_ws() {
+        assert_eq!(
+            XmlDecl::parse(br#"<?xml version="1.0"encoding="UTF-8"?>"#.as_slice()),
+            Err(ControlFlow::Fatal(ReadError::Expect(
+                ReadKind::S,
+                br#"encoding="UTF-8"?>"#.as_slice()
+            )))
+        );
+
+        assert_eq!(
+            XmlDecl::parse(br#"<?xml version="1.0" encoding="UTF-8"standalone="no"?>"#.as_slice()),
+            Err(ControlFlow::Fatal(ReadError::Expect(
+                ReadKind::S,
+                br#"standalone="no"?>"#.as_slice()
+            )))
+        );
+
+        assert_eq!(
+            XmlDecl::parse(br#"<?xml?>"#.as_slice()),
+            Err(ControlFlow::Fatal(ReadError::Expect(
+                ReadKind::Version,
+                br#"?>"#.as_slice()
+            )))
+        );
+
+        assert_eq!(
+            XmlDecl::parse(br#"<?xml-stylesheet href="a.css"?>"#.as_slice()),
+            Err(ControlFlow::Recovable(ReadError::Expect(
+                ReadKind::S,
+                br#"-stylesheet href="a.css"?>"#.as_slice()
+            )))
+        );
+
+        assert_eq!(
+            XmlDecl::parse(br#"<?xml version="1.0" standalone="maybe"?>"#.as_slice()),
+            Err(ControlFlow::Fatal(ReadError::Expect(
+                ReadKind::YesNo,
+                br#"maybe"#.as_slice()
+            )))
+        );
+    }
+
+    #[test]
+    fn test_encoding_name() {
+        assert_eq!(
+            XmlDecl::parse(br#"<?xml version="1.0" encoding="ISO-8859-1"?>"#.as_slice()),
+            Ok((
+                XmlDecl {
+                    version: XmlVersion::Ver10,
+                    encoding: Some(b"ISO-8859-1".as_slice()),
+                    standalone: None,
+                    raw: br#"<?xml version="1.0" encoding="ISO-8859-1"?>"#.as_slice()
+                },
+                b"".as_slice()
+            ))
+        );
+
+        assert_eq!(
+            XmlDecl::parse(br#"<?xml version="1.0" encoding="x.y_z-1"?>"#.as_slice()),
+            Ok((
+                XmlDecl {
+                    version: XmlVersion::Ver10,
+                    encoding: Some(b"x.y_z-1".as_slice()),
+                    standalone: None,
+                    raw: br#"<?xml version="1.0" encoding="x.y_z-1"?>"#.as_slice()
+                },
+                b"".as_slice()
+            ))
+        );
+
+        for name in ["", "8859-1", "-utf8", "utf 8", "utf/8", "utf\u{e9}"] {
+            let decl = format!(r#"<?xml version="1.0" encoding="{}"?>"#, name);
+
+            assert_eq!(
+                XmlDecl::parse(decl.as_bytes()),
+                Err(ControlFlow::Fatal(ReadError::InvalidEncodingName(
+                    name.as_bytes()
+                )))
+            );
+        }
+    }
+
     #[test]
     fn test_pi() {
         assert_eq!(
@@ -284,6 +609,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_pseudo_attrs() {
+        let (pi, _) =
+            PI::parse(br#"<?xml-stylesheet href="a.css" type='text/css' ?>"#.as_slice()).unwrap();
+
+        assert_eq!(
+            pi.pseudo_attrs()
+                .map(|attr| attr.map(Attr::into_parts))
+                .collect::<Vec<_>>(),
+            [
+                Ok((b"href".as_slice(), b"a.css".as_slice())),
+                Ok((b"type".as_slice(), b"text/css".as_slice()))
+            ]
+        );
+
+        let (pi, _) = PI::parse(b"<?target free text?>".as_slice()).unwrap();
+
+        assert!(matches!(
+            pi.pseudo_attrs().next(),
+            Some(Err(ControlFlow::Fatal(_)))
+        ));
+    }
+
+    #[test]
+    fn test_stylesheet() {
+        let (pi, _) = PI::parse(
+            br#"<?xml-stylesheet type="text/xsl" href="a.xsl?x=1&amp;y=2" alternate="no"?>"#
+                .as_slice(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            XmlStylesheet::from_pi(&pi),
+            Ok(Some(XmlStylesheet {
+                alternate: Some(false),
+                ..XmlStylesheet::new("a.xsl?x=1&y=2", "text/xsl")
+            }))
+        );
+
+        let (pi, _) = PI::parse(br#"<?xml-stylesheet type="text/css"?>"#.as_slice()).unwrap();
+
+        assert_eq!(XmlStylesheet::from_pi(&pi), Ok(None));
+    }
+
     #[test]
     fn test_comment() {
         assert_eq!(
@@ -291,4 +660,33 @@ mod tests {
             Ok((Comment(br#" >?? <? "#.as_slice()), b"".as_slice()))
         );
     }
+
+    #[test]
+    fn test_unknown() {
+        assert_eq!(
+            Unknown::parse(br#"<!ENTITY h "a > b">x"#.as_slice()),
+            Ok((
+                Unknown(br#"<!ENTITY h "a > b">"#.as_slice()),
+                b"x".as_slice()
+            ))
+        );
+
+        assert_eq!(
+            Unknown::parse(b"<![INCLUDE[ <!ELEMENT a ANY> ]]>x".as_slice()),
+            Ok((
+                Unknown(b"<![INCLUDE[ <!ELEMENT a ANY> ]]>".as_slice()),
+                b"x".as_slice()
+            ))
+        );
+
+        assert!(matches!(
+            Unknown::parse(b"<!-- c -->".as_slice()),
+            Err(ControlFlow::Recovable(_))
+        ));
+
+        assert!(matches!(
+            Unknown::parse(b"<!ENTITY h".as_slice()),
+            Err(ControlFlow::Fatal(_))
+        ));
+    }
 }
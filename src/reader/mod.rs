@@ -12,9 +12,18 @@ pub use utils::*;
 mod misc;
 pub use misc::*;
 
+mod encoding;
+pub use encoding::*;
+
 mod attr;
 pub use attr::*;
 
+mod entity;
+pub use entity::*;
+
+mod attr_map;
+pub use attr_map::*;
+
 mod chardata;
 pub use chardata::*;
 
@@ -24,5 +33,62 @@ pub use doctype::*;
 mod el;
 pub use el::*;
 
+#[cfg(feature = "instrument")]
+mod instrument;
+#[cfg(feature = "instrument")]
+pub use instrument::*;
+
+mod position;
+pub use position::*;
+
+mod diagnostic;
+pub use diagnostic::*;
+
+mod limits;
+pub use limits::*;
+
+mod builder;
+pub use builder::*;
+
+#[allow(clippy::module_inception)]
 mod reader;
 pub use reader::*;
+
+mod matcher;
+
+mod event_buf;
+pub use event_buf::*;
+
+mod owned;
+pub use owned::{Event, OwnedEvent};
+
+mod node_ids;
+pub use node_ids::*;
+
+mod pipeline;
+pub use pipeline::*;
+
+mod record;
+pub use record::*;
+
+#[cfg(feature = "rayon")]
+mod par;
+
+mod push;
+pub use push::*;
+
+mod str_reader;
+pub use str_reader::*;
+
+mod source;
+pub use source::*;
+
+#[cfg(feature = "ns")]
+mod ns;
+#[cfg(feature = "ns")]
+pub use ns::*;
+
+#[cfg(feature = "ns")]
+mod validate;
+#[cfg(feature = "ns")]
+pub use validate::*;
@@ -1,8 +1,11 @@
 use std::fmt::Debug;
 
-use parserc::{ControlFlow, Input, Parse, Parser, take_till};
+use parserc::{AsBytes, ControlFlow, Input, Parse, Parser, take_till};
 
-use crate::reader::utils::{is_markup_char, is_ws};
+use crate::{
+    chars::{is_name_char, is_name_start_char},
+    reader::utils::{is_markup_char, is_ws},
+};
 
 use super::{ReadError, ReadKind};
 
@@ -33,11 +36,46 @@ where
     }
 }
 
+impl<I> Name<I>
+where
+    I: Input<Item = u8> + AsBytes + Clone + Debug,
+{
+    /// Check this name against the [`NameStartChar`](https://www.w3.org/TR/xml11/#NT-NameStartChar)
+    /// and [`NameChar`](https://www.w3.org/TR/xml11/#NT-NameChar) productions, which are the
+    /// same for xml 1.0 and 1.1.
+    ///
+    /// Fails with [`ReadError::InvalidName`] carrying the first illegal character. Names that are
+    /// no utf-8 are left to the utf-8 checks of the reader.
+    pub fn validate(&self) -> Result<(), ControlFlow<ReadError<I>>> {
+        let Ok(name) = std::str::from_utf8(self.0.as_bytes()) else {
+            return Ok(());
+        };
+
+        let illegal = name.char_indices().find(|(i, c)| {
+            if *i == 0 {
+                !is_name_start_char(*c)
+            } else {
+                !is_name_char(*c)
+            }
+        });
+
+        match illegal {
+            Some((i, c)) => {
+                let mut span = self.0.clone().split_off(i);
+                span.split_off(c.len_utf8());
+
+                Err(ControlFlow::Fatal(ReadError::InvalidName(span)))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use parserc::Parse;
+    use parserc::{ControlFlow, Parse};
 
-    use crate::reader::Name;
+    use crate::reader::{Name, ReadError};
 
     #[test]
     fn test_name() {
@@ -56,4 +94,29 @@ mod tests {
             Ok((Name(b"12dfdd".as_slice()), b"=".as_slice()))
         );
     }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(Name("a:b-1.\u{b7}\u{e9}".as_bytes()).validate(), Ok(()));
+        assert_eq!(Name("_\u{10000}".as_bytes()).validate(), Ok(()));
+
+        let name = "1abc".as_bytes();
+
+        assert_eq!(
+            Name(name).validate(),
+            Err(ControlFlow::Fatal(ReadError::InvalidName(&name[..1])))
+        );
+
+        let name = "ab\u{d7}c".as_bytes();
+
+        assert_eq!(
+            Name(name).validate(),
+            Err(ControlFlow::Fatal(ReadError::InvalidName(&name[2..4])))
+        );
+
+        assert_eq!(
+            Name(b"a;b".as_slice()).validate(),
+            Err(ControlFlow::Fatal(ReadError::InvalidName(b";".as_slice())))
+        );
+    }
 }
@@ -0,0 +1,189 @@
+use std::fmt::Debug;
+
+use parserc::{AsBytes, ControlFlow, Input};
+
+use super::{ReadError, XmlNode, XmlReader};
+
+/// Id of an element, counting start tags in document order from `0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(pub usize);
+
+/// A node read by [`NodeIds`], with the ids of the elements it belongs to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeEvent<I> {
+    pub node: XmlNode<I>,
+    /// the element started or ended by this node, `None` for other nodes.
+    pub id: Option<NodeId>,
+    /// the element containing this node, or the started or ended element, `None` at the top level.
+    pub parent: Option<NodeId>,
+}
+
+/// A reader assigning ids to elements, see [`XmlReader::with_node_ids`].
+///
+/// ```
+/// use rexml::reader::{NodeId, XmlNode, XmlReader};
+///
+/// let mut reader = XmlReader::from(b"<a><b/>text<c></c></a>".as_slice()).with_node_ids();
+///
+/// let mut rows = vec![];
+///
+/// while let Some(event) = reader.read_next().unwrap() {
+///     if let XmlNode::Start(start) = event.node {
+///         rows.push((event.id.unwrap(), event.parent, start.name));
+///     }
+/// }
+///
+/// assert_eq!(
+///     rows,
+///     [
+///         (NodeId(0), None, b"a".as_slice()),
+///         (NodeId(1), Some(NodeId(0)), b"b"),
+///         (NodeId(2), Some(NodeId(0)), b"c"),
+///     ]
+/// );
+/// ```
+pub struct NodeIds<I>
+where
+    I: Debug,
+{
+    reader: XmlReader<I>,
+    /// the id of the next start tag.
+    next: usize,
+    /// ids of the open elements, outermost first.
+    open: Vec<NodeId>,
+}
+
+impl<I> XmlReader<I>
+where
+    I: Input<Item = u8> + AsBytes + Clone + Debug,
+{
+    /// Returns a reader assigning each element a document-order [`NodeId`], and passing the id
+    /// of its parent element with every node, e.g. to fill flat tables or custom indexes without
+    /// keeping a stack of open elements.
+    pub fn with_node_ids(self) -> NodeIds<I> {
+        NodeIds {
+            reader: self,
+            next: 0,
+            open: vec![],
+        }
+    }
+}
+
+impl<I> NodeIds<I>
+where
+    I: Input<Item = u8> + AsBytes + Clone + Debug,
+{
+    /// Read the next node, see [`XmlReader::read_next`].
+    pub fn read_next(&mut self) -> Result<Option<NodeEvent<I>>, ControlFlow<ReadError<I>>> {
+        let Some(node) = self.reader.read_next()? else {
+            return Ok(None);
+        };
+
+        let parent = self.open.last().copied();
+
+        let (id, parent) = match &node {
+            XmlNode::Start(start) => {
+                let id = NodeId(self.next);
+                self.next += 1;
+
+                if !start.is_empty {
+                    self.open.push(id);
+                }
+
+                (Some(id), parent)
+            }
+            XmlNode::End(_) => (self.open.pop(), self.open.last().copied()),
+            _ => (None, parent),
+        };
+
+        Ok(Some(NodeEvent { node, id, parent }))
+    }
+
+    /// Returns the id of the element containing the next node, `None` at the top level.
+    pub fn current(&self) -> Option<NodeId> {
+        self.open.last().copied()
+    }
+
+    /// Returns the wrapped reader, e.g. to expand text.
+    pub fn reader(&self) -> &XmlReader<I> {
+        &self.reader
+    }
+
+    /// Returns the wrapped reader, reading from it directly breaks the ids.
+    pub fn reader_mut(&mut self) -> &mut XmlReader<I> {
+        &mut self.reader
+    }
+
+    /// Returns the wrapped reader.
+    pub fn into_inner(self) -> XmlReader<I> {
+        self.reader
+    }
+}
+
+impl<I> Iterator for NodeIds<I>
+where
+    I: Input<Item = u8> + AsBytes + Clone + Debug,
+{
+    type Item = Result<NodeEvent<I>, ControlFlow<ReadError<I>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_next().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reader::{XmlNode, XmlReader};
+
+    use super::NodeId;
+
+    #[test]
+    fn test_node_ids() {
+        let source = br#"<?xml version="1.0"?>
+<a><b>x<c/></b><!--y--><d></d></a>"#;
+
+        let events = XmlReader::from(source.as_slice())
+            .with_node_ids()
+            .map(|event| {
+                let event = event.unwrap();
+
+                let kind = match event.node {
+                    XmlNode::Start(_) => "start",
+                    XmlNode::End(_) => "end",
+                    XmlNode::CharData(_) => "text",
+                    XmlNode::Comment(_) => "comment",
+                    _ => "other",
+                };
+
+                (kind, event.id.map(|id| id.0), event.parent.map(|id| id.0))
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            events,
+            [
+                ("other", None, None),
+                ("other", None, None),
+                ("start", Some(0), None),
+                ("start", Some(1), Some(0)),
+                ("text", None, Some(1)),
+                ("start", Some(2), Some(1)),
+                ("end", Some(1), Some(0)),
+                ("comment", None, Some(0)),
+                ("start", Some(3), Some(0)),
+                ("end", Some(3), Some(0)),
+                ("end", Some(0), None),
+            ]
+        );
+
+        let mut reader = XmlReader::fragment(b"<b/><c>x</c>".as_slice()).with_node_ids();
+
+        assert_eq!(reader.next().unwrap().unwrap().id, Some(NodeId(0)));
+        assert_eq!(reader.current(), None);
+        assert_eq!(reader.next().unwrap().unwrap().id, Some(NodeId(1)));
+        assert_eq!(reader.current(), Some(NodeId(1)));
+        assert_eq!(reader.next().unwrap().unwrap().parent, Some(NodeId(1)));
+        assert_eq!(reader.next().unwrap().unwrap().id, Some(NodeId(1)));
+        assert!(reader.next().is_none());
+    }
+}
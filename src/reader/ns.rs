@@ -0,0 +1,463 @@
+use std::sync::Arc;
+
+use parserc::{AsBytes, ControlFlow, Input};
+
+use super::{Attr, EventSource, ReadError, XmlNode, source::NextEvent};
+
+/// The namespace bound to the `xml` prefix.
+pub const XML_NAMESPACE: &[u8] = b"http://www.w3.org/XML/1998/namespace";
+
+/// The namespace bound to the `xmlns` prefix.
+pub const XMLNS_NAMESPACE: &[u8] = b"http://www.w3.org/2000/xmlns/";
+
+/// A qualified name resolved against the namespace declarations in scope.
+///
+/// See [`Namespaces in XML`](https://www.w3.org/TR/xml-names11/#ns-qualnames).
+#[derive(Debug, PartialEq, Clone)]
+pub struct ResolvedName<'a, I> {
+    /// the prefix part, if any.
+    pub prefix: Option<I>,
+    /// the local part.
+    pub local: I,
+    /// the namespace name, `None` if the name is in no namespace.
+    pub namespace: Option<&'a [u8]>,
+}
+
+/// One declaration of a [`NsScope`] chain.
+#[derive(Debug)]
+struct Binding<I> {
+    prefix: Option<I>,
+    namespace: I,
+    parent: Option<Arc<Binding<I>>>,
+}
+
+/// A snapshot of the namespace bindings in scope, see [`NsReader::scope`].
+///
+/// Declarations are shared between snapshots as a linked chain, so taking one is a reference
+/// count increment and it stays valid after the reader has moved on.
+#[derive(Debug)]
+pub struct NsScope<I> {
+    head: Option<Arc<Binding<I>>>,
+}
+
+impl<I> Default for NsScope<I> {
+    fn default() -> Self {
+        Self { head: None }
+    }
+}
+
+impl<I> Clone for NsScope<I> {
+    fn clone(&self) -> Self {
+        Self {
+            head: self.head.clone(),
+        }
+    }
+}
+
+impl<I> NsScope<I> {
+    /// Returns true if no declarations are in scope.
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    /// Returns an iterator over the `(prefix, namespace)` declarations in scope, innermost first.
+    ///
+    /// Shadowed declarations are included.
+    pub fn iter(&self) -> impl Iterator<Item = (Option<&I>, &I)> {
+        std::iter::successors(self.head.as_deref(), |binding| binding.parent.as_deref())
+            .map(|binding| (binding.prefix.as_ref(), &binding.namespace))
+    }
+
+    fn push(&mut self, prefix: Option<I>, namespace: I) {
+        self.head = Some(Arc::new(Binding {
+            prefix,
+            namespace,
+            parent: self.head.take(),
+        }));
+    }
+}
+
+impl<I> NsScope<I>
+where
+    I: AsBytes,
+{
+    /// Returns the namespace bound to `prefix`, `None` prefix is the default namespace.
+    pub fn lookup(&self, prefix: Option<&[u8]>) -> Option<&[u8]> {
+        match prefix {
+            Some(b"xml") => return Some(XML_NAMESPACE),
+            Some(b"xmlns") => return Some(XMLNS_NAMESPACE),
+            _ => {}
+        }
+
+        self.iter()
+            .find(|(bound, _)| bound.map(|bound| bound.as_bytes()) == prefix)
+            .map(|(_, namespace)| namespace.as_bytes())
+            .filter(|namespace| !namespace.is_empty())
+    }
+}
+
+/// A namespace-aware reader over any [`EventSource`].
+///
+/// Tracks `xmlns`/`xmlns:prefix` declarations of the elements in scope. The scope of an element is
+/// kept until the event following its end tag (or its empty start tag), so names of the event just
+/// returned can always be resolved.
+pub struct NsReader<S>
+where
+    S: EventSource,
+{
+    source: S,
+    /// declared `(prefix, namespace)` bindings, innermost last.
+    bindings: Vec<(Option<S::Input>, S::Input)>,
+    /// `bindings` length and snapshot at the start of each open element.
+    scopes: Vec<(usize, NsScope<S::Input>)>,
+    /// `bindings` as a shared chain.
+    scope: NsScope<S::Input>,
+    /// true if the last event closed an element whose scope is still active.
+    pending_pop: bool,
+}
+
+impl<S> NsReader<S>
+where
+    S: EventSource,
+    S::Input: Input<Item = u8> + AsBytes + Clone,
+{
+    /// Wrap `source`.
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            bindings: vec![],
+            scopes: vec![],
+            scope: NsScope::default(),
+            pending_pop: false,
+        }
+    }
+
+    /// Returns the wrapped source.
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+
+    /// Returns the number of elements in scope.
+    pub fn depth(&self) -> usize {
+        self.scopes.len()
+    }
+
+    /// Returns the namespace bound to `prefix` in the current scope, `None` prefix is the default namespace.
+    pub fn lookup(&self, prefix: Option<&[u8]>) -> Option<&[u8]> {
+        lookup_in(&self.bindings, prefix)
+    }
+
+    /// Returns the `(prefix, namespace)` declarations in scope, innermost last.
+    pub fn bindings(&self) -> &[(Option<S::Input>, S::Input)] {
+        &self.bindings
+    }
+
+    /// Returns a snapshot of the bindings in scope, including those declared by the last start tag.
+    pub fn scope(&self) -> NsScope<S::Input> {
+        self.scope.clone()
+    }
+
+    /// Resolve an element name, unprefixed names use the default namespace.
+    pub fn resolve_element(
+        &self,
+        name: &S::Input,
+    ) -> Result<ResolvedName<'_, S::Input>, ReadError<S::Input>> {
+        self.resolve(name, true)
+    }
+
+    /// Resolve an attribute name, unprefixed names are in no namespace.
+    pub fn resolve_attr(
+        &self,
+        name: &S::Input,
+    ) -> Result<ResolvedName<'_, S::Input>, ReadError<S::Input>> {
+        self.resolve(name, false)
+    }
+
+    fn resolve(
+        &self,
+        name: &S::Input,
+        use_default: bool,
+    ) -> Result<ResolvedName<'_, S::Input>, ReadError<S::Input>> {
+        let (prefix, local) = split_qname(name);
+
+        let namespace = match &prefix {
+            Some(prefix) => Some(
+                self.lookup(Some(prefix.as_bytes()))
+                    .ok_or_else(|| ReadError::UnboundPrefix(prefix.clone()))?,
+            ),
+            None if use_default => self.lookup(None),
+            None => None,
+        };
+
+        Ok(ResolvedName {
+            prefix,
+            local,
+            namespace,
+        })
+    }
+}
+
+impl<S> NsReader<S>
+where
+    S: EventSource,
+    S::Input: Input<Item = u8> + AsBytes + Clone,
+{
+    /// Reject two attributes with the same local name and namespace, even if written with
+    /// different prefixes, see [`Namespaces in XML`](https://www.w3.org/TR/xml-names11/#uniqAttrs).
+    ///
+    /// Attributes with unbound prefixes are left to [`resolve_attr`](Self::resolve_attr).
+    fn check_unique(
+        &self,
+        attrs: &[Attr<S::Input>],
+    ) -> Result<(), ControlFlow<ReadError<S::Input>>> {
+        let mut seen: Vec<ResolvedName<'_, S::Input>> = Vec::with_capacity(attrs.len());
+
+        for attr in attrs {
+            let Ok(name) = self.resolve_attr(&attr.name) else {
+                continue;
+            };
+
+            if seen.iter().any(|prev| {
+                prev.namespace == name.namespace && prev.local.as_bytes() == name.local.as_bytes()
+            }) {
+                return Err(ControlFlow::Fatal(ReadError::DuplicateAttr(
+                    attr.name.clone(),
+                )));
+            }
+
+            seen.push(name);
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the namespace bound to `prefix` by `bindings`, innermost last.
+pub(super) fn lookup_in<'a, I>(
+    bindings: &'a [(Option<I>, I)],
+    prefix: Option<&[u8]>,
+) -> Option<&'a [u8]>
+where
+    I: AsBytes,
+{
+    match prefix {
+        Some(b"xml") => return Some(XML_NAMESPACE),
+        Some(b"xmlns") => return Some(XMLNS_NAMESPACE),
+        _ => {}
+    }
+
+    bindings
+        .iter()
+        .rev()
+        .find(|(bound, _)| bound.as_ref().map(|bound| bound.as_bytes()) == prefix)
+        .map(|(_, namespace)| namespace.as_bytes())
+        // `xmlns=""` undeclares the default namespace.
+        .filter(|namespace| !namespace.is_empty())
+}
+
+/// Split a qualified name at its first `:`.
+fn split_qname<I>(name: &I) -> (Option<I>, I)
+where
+    I: Input<Item = u8> + AsBytes + Clone,
+{
+    match memchr::memchr(b':', name.as_bytes()) {
+        Some(at) => {
+            let mut local = name.clone();
+            let prefix = local.split_to(at);
+            local.split_to(1);
+            (Some(prefix), local)
+        }
+        None => (None, name.clone()),
+    }
+}
+
+impl<S> EventSource for NsReader<S>
+where
+    S: EventSource,
+    S::Input: Input<Item = u8> + AsBytes + Clone,
+{
+    type Input = S::Input;
+
+    fn next_event(&mut self) -> NextEvent<Self::Input> {
+        if self.pending_pop {
+            self.pending_pop = false;
+
+            if let Some((len, scope)) = self.scopes.pop() {
+                self.bindings.truncate(len);
+                self.scope = scope;
+            }
+        }
+
+        let node = self.source.next_event()?;
+
+        match &node {
+            Some(XmlNode::Start(start)) => {
+                self.scopes.push((self.bindings.len(), self.scope.clone()));
+                self.pending_pop = start.is_empty;
+
+                let attrs = start.attrs().collect::<Result<Vec<_>, _>>()?;
+
+                for attr in &attrs {
+                    let name = attr.name.as_bytes();
+
+                    let prefix = if name == b"xmlns" {
+                        None
+                    } else if name.starts_with(b"xmlns:") {
+                        let mut prefix = attr.name.clone();
+                        prefix.split_to(6);
+                        Some(prefix)
+                    } else {
+                        continue;
+                    };
+
+                    self.bindings.push((prefix.clone(), attr.value.clone()));
+                    self.scope.push(prefix, attr.value.clone());
+                }
+
+                self.check_unique(&attrs)?;
+            }
+            Some(XmlNode::End(_)) => {
+                self.pending_pop = true;
+            }
+            _ => {}
+        }
+
+        Ok(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parserc::ControlFlow;
+
+    use crate::reader::{EventSource, ReadError, XmlNode, XmlReader};
+
+    use super::{NsReader, NsScope, XML_NAMESPACE, XMLNS_NAMESPACE};
+
+    #[test]
+    fn test_resolve() {
+        let input = br##"<?xml version="1.0"?><svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink"><use xlink:href="#a" xml:lang="en" x="1"/><g xmlns=""><p:c/></g></svg>"##.as_slice();
+
+        let mut reader = NsReader::new(XmlReader::from(input));
+
+        let mut resolved = vec![];
+
+        while let Some(node) = reader.next_event().unwrap() {
+            let XmlNode::Start(start) = node else {
+                continue;
+            };
+
+            match reader.resolve_element(&start.name) {
+                Ok(name) => resolved.push((name.local, name.namespace.map(<[u8]>::to_vec))),
+                Err(err) => {
+                    assert_eq!(err, ReadError::UnboundPrefix(b"p".as_slice()));
+                    continue;
+                }
+            }
+
+            for attr in start.attrs() {
+                let attr = attr.unwrap();
+                let name = reader.resolve_attr(&attr.name).unwrap();
+                resolved.push((name.local, name.namespace.map(<[u8]>::to_vec)));
+            }
+        }
+
+        let svg = Some(b"http://www.w3.org/2000/svg".to_vec());
+        let xlink = Some(b"http://www.w3.org/1999/xlink".to_vec());
+        let xmlns = Some(XMLNS_NAMESPACE.to_vec());
+
+        assert_eq!(
+            resolved,
+            vec![
+                (b"svg".as_slice(), svg.clone()),
+                (b"xmlns".as_slice(), None),
+                (b"xlink".as_slice(), xmlns),
+                (b"use".as_slice(), svg),
+                (b"href".as_slice(), xlink),
+                (b"lang".as_slice(), Some(XML_NAMESPACE.to_vec())),
+                (b"x".as_slice(), None),
+                (b"g".as_slice(), None),
+                (b"xmlns".as_slice(), None),
+            ]
+        );
+
+        assert_eq!(reader.depth(), 0);
+    }
+
+    #[test]
+    fn test_unique_attrs() {
+        let read = |input: &'static str| {
+            let mut reader = NsReader::new(XmlReader::from(input.as_bytes()));
+
+            loop {
+                match reader.next_event() {
+                    Ok(Some(_)) => {}
+                    Ok(None) => return Ok(()),
+                    Err(err) => return Err(err),
+                }
+            }
+        };
+
+        assert!(
+            read(r#"<?xml version="1.0"?><a xmlns:p="u" xmlns:q="v" p:x="1" q:x="2" x="3"/>"#)
+                .is_ok()
+        );
+
+        assert_eq!(
+            read(r#"<?xml version="1.0"?><a xmlns:p="u" xmlns:q="u" p:x="1" q:x="2"/>"#),
+            Err(ControlFlow::Fatal(ReadError::DuplicateAttr(
+                b"q:x".as_slice()
+            )))
+        );
+
+        assert_eq!(
+            read(r#"<?xml version="1.0"?><a x="1" x="2"/>"#),
+            Err(ControlFlow::Fatal(ReadError::DuplicateAttr(
+                b"x".as_slice()
+            )))
+        );
+    }
+
+    #[test]
+    fn test_scope() {
+        let input =
+            br#"<?xml version="1.0"?><a xmlns="u"><b xmlns:p="v"><c xmlns="w"/></b><d/></a>"#
+                .as_slice();
+
+        let mut reader = NsReader::new(XmlReader::from(input));
+
+        let mut scopes: Vec<NsScope<&[u8]>> = vec![];
+
+        while let Some(node) = reader.next_event().unwrap() {
+            if let XmlNode::Start(_) = node {
+                scopes.push(reader.scope());
+            }
+        }
+
+        assert!(reader.scope().is_empty());
+
+        let lookup = |scope: &NsScope<&[u8]>, prefix: Option<&[u8]>| {
+            scope.lookup(prefix).map(<[u8]>::to_vec)
+        };
+
+        assert_eq!(lookup(&scopes[0], None), Some(b"u".to_vec()));
+        assert_eq!(lookup(&scopes[0], Some(b"p")), None);
+        assert_eq!(lookup(&scopes[1], Some(b"p")), Some(b"v".to_vec()));
+        assert_eq!(lookup(&scopes[2], None), Some(b"w".to_vec()));
+        assert_eq!(
+            lookup(&scopes[2], Some(b"xml")),
+            Some(XML_NAMESPACE.to_vec())
+        );
+        assert_eq!(lookup(&scopes[3], None), Some(b"u".to_vec()));
+        assert_eq!(lookup(&scopes[3], Some(b"p")), None);
+
+        assert_eq!(
+            scopes[2].iter().collect::<Vec<_>>(),
+            [
+                (None, &b"w".as_slice()),
+                (Some(&b"p".as_slice()), &b"v".as_slice()),
+                (None, &b"u".as_slice())
+            ]
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use parserc::AsBytes;
+
+use super::{
+    Attr, CData, CharData, Comment, DocType, ElemEnd, ElemStart, PI, Unknown, XmlDecl, XmlNode,
+};
+
+/// An event borrowing the document, as returned by a reader over a byte slice.
+pub type Event<'a> = XmlNode<&'a [u8]>;
+
+/// An event owning its content, see [`XmlNode::into_owned`]; write it with
+/// [`XmlWriter::write_owned_event`](crate::writer::XmlWriter::write_owned_event).
+pub type OwnedEvent = XmlNode<Vec<u8>>;
+
+impl<I> XmlNode<I> {
+    fn map_input<'a, O>(&'a self, f: &mut impl FnMut(&'a I) -> O) -> XmlNode<O> {
+        match self {
+            XmlNode::XmlDecl(decl) => XmlNode::XmlDecl(decl.map_input(f)),
+            XmlNode::DocType(doctype) => XmlNode::DocType(doctype.map_input(f)),
+            XmlNode::PI(pi) => XmlNode::PI(pi.map_input(f)),
+            XmlNode::S(s) => XmlNode::S(f(s)),
+            XmlNode::Comment(comment) => XmlNode::Comment(comment.map_input(f)),
+            XmlNode::Start(start) => XmlNode::Start(start.map_input(f)),
+            XmlNode::End(end) => XmlNode::End(end.map_input(f)),
+            XmlNode::CharData(chardata) => XmlNode::CharData(chardata.map_input(f)),
+            XmlNode::CData(cdata) => XmlNode::CData(cdata.map_input(f)),
+            XmlNode::Unknown(unknown) => XmlNode::Unknown(unknown.map_input(f)),
+            XmlNode::Text(text) => XmlNode::Text(text.clone()),
+        }
+    }
+}
+
+impl<I> XmlDecl<I> {
+    fn map_input<'a, O>(&'a self, f: &mut impl FnMut(&'a I) -> O) -> XmlDecl<O> {
+        XmlDecl {
+            version: self.version,
+            encoding: self.encoding.as_ref().map(&mut *f),
+            standalone: self.standalone,
+            raw: f(&self.raw),
+        }
+    }
+}
+
+impl<I> PI<I> {
+    fn map_input<'a, O>(&'a self, f: &mut impl FnMut(&'a I) -> O) -> PI<O> {
+        PI {
+            name: f(&self.name),
+            unparsed: f(&self.unparsed),
+        }
+    }
+}
+
+impl<I> ElemStart<I> {
+    fn map_input<'a, O>(&'a self, f: &mut impl FnMut(&'a I) -> O) -> ElemStart<O> {
+        ElemStart {
+            name: f(&self.name),
+            unparsed: f(&self.unparsed),
+            is_empty: self.is_empty,
+        }
+    }
+}
+
+impl<I> ElemEnd<I> {
+    fn map_input<'a, O>(&'a self, f: &mut impl FnMut(&'a I) -> O) -> ElemEnd<O> {
+        ElemEnd {
+            name: f(&self.name),
+        }
+    }
+}
+
+impl<I> Attr<I> {
+    fn map_input<'a, O>(&'a self, f: &mut impl FnMut(&'a I) -> O) -> Attr<O> {
+        Attr {
+            name: f(&self.name),
+            value: f(&self.value),
+            eq: f(&self.eq),
+            quoted: f(&self.quoted),
+        }
+    }
+}
+
+/// Implement `map_input` for the tuple structs wrapping one input.
+macro_rules! map_wrapped {
+    ($($ty:ident),*) => {
+        $(
+            impl<I> $ty<I> {
+                fn map_input<'a, O>(&'a self, f: &mut impl FnMut(&'a I) -> O) -> $ty<O> {
+                    $ty(f(&self.0))
+                }
+            }
+        )*
+    };
+}
+
+map_wrapped!(DocType, Comment, CharData, CData, Unknown);
+
+/// Implement `into_owned` and `as_slice` on top of `map_input`.
+macro_rules! owned {
+    ($($ty:ident),*) => {
+        $(
+            impl<I> $ty<I>
+            where
+                I: AsBytes,
+            {
+                /// Returns a copy owning its input, which doesn't borrow the document, e.g. to
+                /// store it or send it to another thread.
+                pub fn into_owned(self) -> $ty<Vec<u8>> {
+                    self.map_input(&mut |input| input.as_bytes().to_vec())
+                }
+            }
+
+            impl $ty<Vec<u8>> {
+                /// Returns a view over byte slices, e.g. to parse attributes of an owned start tag.
+                pub fn as_slice(&self) -> $ty<&[u8]> {
+                    self.map_input(&mut |input| input.as_slice())
+                }
+            }
+        )*
+    };
+}
+
+owned!(
+    XmlNode, XmlDecl, DocType, PI, Comment, ElemStart, ElemEnd, CharData, CData, Unknown, Attr
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::reader::{XmlNode, XmlReader};
+
+    #[test]
+    fn test_into_owned() {
+        let source =
+            br#"<?xml version="1.0"?><!DOCTYPE a><a x="1"><?pi x?><!--c-->b<![CDATA[c]]></a>"#;
+
+        let nodes = XmlReader::from(source.as_slice())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let owned = nodes
+            .iter()
+            .cloned()
+            .map(XmlNode::into_owned)
+            .collect::<Vec<_>>();
+
+        let owned = std::thread::spawn(move || owned).join().unwrap();
+
+        assert_eq!(
+            owned.iter().map(XmlNode::as_slice).collect::<Vec<_>>(),
+            nodes
+        );
+
+        let Some(XmlNode::Start(start)) = owned.get(2) else {
+            panic!("expect start tag");
+        };
+
+        let attr = start.as_slice().attrs().next().unwrap().unwrap();
+
+        assert_eq!(attr.value, b"1");
+        assert_eq!(attr.into_owned().as_slice().quoted, b"\"1\"");
+    }
+}
@@ -0,0 +1,140 @@
+use std::fmt::Debug;
+
+use parserc::{AsBytes, ControlFlow, Input};
+use rayon::prelude::*;
+
+use super::{ReadError, XmlNode, XmlReader, XmlReaderBuilder, position::offset_in};
+
+impl<I> XmlReader<I>
+where
+    I: Input<Item = u8> + AsBytes + Clone + Debug + Send + Sync,
+{
+    /// Read the children of the root element named `name`, e.g. the `<record>`s of a huge export,
+    /// in parallel on the rayon thread pool, returning the events of each in document order.
+    ///
+    /// See [`par_split_with`](Self::par_split_with).
+    pub fn par_split(self, name: &str) -> Result<Vec<Vec<XmlNode<I>>>, ControlFlow<ReadError<I>>> {
+        self.par_split_with(name, |reader| reader.collect())
+    }
+
+    /// Split the children of the root element named `name` off the rest of the document, and
+    /// call `parse` for each of them in parallel on the rayon thread pool, returning the results
+    /// in document order.
+    ///
+    /// The records are found by reading the document with this reader and skipping their
+    /// content, see [`skip_subtree`](Self::skip_subtree), so the rest of the document is still
+    /// checked. `parse` is passed a [`fragment`](XmlReaderBuilder::fragment) reader over one
+    /// record, sharing the entity table of this reader. The first error in document order is
+    /// returned.
+    pub fn par_split_with<T, F>(
+        mut self,
+        name: &str,
+        parse: F,
+    ) -> Result<Vec<T>, ControlFlow<ReadError<I>>>
+    where
+        T: Send,
+        F: Fn(XmlReader<I>) -> Result<T, ControlFlow<ReadError<I>>> + Sync,
+    {
+        let records = self.split_records(name.as_bytes())?;
+        let entities = self.entities().clone();
+
+        records
+            .into_par_iter()
+            .map(|record| {
+                parse(
+                    XmlReaderBuilder::new()
+                        .fragment(true)
+                        .entities(entities.clone())
+                        .build(record),
+                )
+            })
+            .collect()
+    }
+
+    /// Read the rest of the document, returning the children of the root element named `name`.
+    fn split_records(&mut self, name: &[u8]) -> Result<Vec<I>, ControlFlow<ReadError<I>>> {
+        let rest = self.remaining().clone();
+        let offset = |input: &I| offset_in(rest.as_bytes(), input.as_bytes());
+
+        let mut records = vec![];
+
+        while let Some(node) = self.read_next()? {
+            let XmlNode::Start(start) = node else {
+                continue;
+            };
+
+            // open elements are counted already, empty ones are not.
+            if start.name.as_bytes() != name || self.depth() != 1 + usize::from(!start.is_empty) {
+                continue;
+            }
+
+            // the name follows the `<`.
+            let begin = offset(&start.name) - 1;
+
+            if !start.is_empty {
+                self.skip_subtree(&start)?;
+            }
+
+            let end = offset(self.remaining());
+
+            records.push(rest.clone().split_off(begin).split_to(end - begin));
+        }
+
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parserc::ControlFlow;
+
+    use crate::reader::{Entities, ReadError, XmlNode, XmlReader, XmlReaderBuilder};
+
+    #[test]
+    fn test_par_split() {
+        let source = format!(
+            r#"<?xml version="1.0"?><a><meta/>{}<b n="last"/></a>"#,
+            (0..100)
+                .map(|i| format!(r#"<b n="{}"><c>&e;</c><b/></b>"#, i))
+                .collect::<String>()
+        );
+
+        let records = XmlReader::from(source.as_bytes()).par_split("b").unwrap();
+
+        assert_eq!(records.len(), 101);
+        assert_eq!(records[7].len(), 6);
+
+        let XmlNode::Start(start) = &records[7][0] else {
+            panic!("expect start tag");
+        };
+
+        assert_eq!(start.get_attr(b"n").unwrap(), Some(b"7".as_slice()));
+
+        let mut entities = Entities::new();
+        entities.insert("e", "x");
+
+        let texts = XmlReaderBuilder::new()
+            .entities(entities)
+            .build(source.as_bytes())
+            .par_split_with("b", |mut reader| {
+                let mut text = String::new();
+
+                while let Some(node) = reader.read_next()? {
+                    if let XmlNode::CharData(chardata) = node {
+                        text.push_str(&reader.text(&chardata)?);
+                    }
+                }
+
+                Ok(text)
+            })
+            .unwrap();
+
+        assert_eq!(texts[0], "x");
+        assert_eq!(texts[100], "");
+
+        assert_eq!(
+            XmlReader::from(br#"<?xml version="1.0"?><a><b><c/>"#.as_slice()).par_split("b"),
+            Err(ControlFlow::Fatal(ReadError::Unclosed(b"b".as_slice())))
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    fmt::Debug,
+    sync::mpsc::{IntoIter, Receiver, sync_channel},
+    thread,
+};
+
+use parserc::{AsBytes, ControlFlow, Input};
+
+use crate::types::XmlVersion;
+
+use super::{Entities, ReadError, XmlNode, XmlReader};
+
+/// Error returned by [`XmlReader::pipeline`].
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum PipelineError<I, E>
+where
+    I: Debug,
+    E: Debug,
+{
+    /// The producer failed to read the next event, the consumer saw the events before it.
+    #[error("read error: {0:?}")]
+    Read(ControlFlow<ReadError<I>>),
+    /// The consumer failed, the producer stopped reading.
+    #[error("consumer error: {0:?}")]
+    Consumer(E),
+}
+
+/// The events received by the consumer of a [`pipeline`](XmlReader::pipeline), in document order.
+///
+/// Iteration ends at the end of the document, or at the first read error.
+pub struct PipelineEvents<I> {
+    /// events and the version the producer's entity table switched to while reading them.
+    events: IntoIter<(XmlNode<I>, Option<XmlVersion>)>,
+    entities: Entities,
+}
+
+impl<I> PipelineEvents<I> {
+    /// Returns the entity table of the producing reader as of the event returned last, e.g. to
+    /// expand text.
+    pub fn entities(&self) -> &Entities {
+        &self.entities
+    }
+}
+
+impl<I> Iterator for PipelineEvents<I> {
+    type Item = XmlNode<I>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node, version) = self.events.next()?;
+
+        if let Some(version) = version {
+            self.entities.version(version);
+        }
+
+        Some(node)
+    }
+}
+
+impl<I> XmlReader<I>
+where
+    I: Input<Item = u8> + AsBytes + Clone + Debug + Send,
+{
+    /// Read events on a new thread while `consumer` transforms them on the calling thread.
+    ///
+    /// At most `capacity` events are buffered, the reader waits for the consumer beyond that.
+    /// Errors stop the other side: a read error ends the consumer's events, and returning from
+    /// `consumer` early stops the reader. A read error is returned in preference to the result of
+    /// `consumer`, as it likely caused it.
+    pub fn pipeline<T, E, F>(self, capacity: usize, consumer: F) -> Result<T, PipelineError<I, E>>
+    where
+        E: Debug,
+        F: FnOnce(&mut PipelineEvents<I>) -> Result<T, E>,
+    {
+        let (sender, receiver) = sync_channel(capacity);
+        let entities = self.entities().clone();
+
+        thread::scope(|scope| {
+            let producer = scope.spawn(move || {
+                let mut reader = self;
+
+                while let Some(node) = reader.read_next()? {
+                    // the declaration sets the version references are checked against.
+                    let version = match node {
+                        XmlNode::XmlDecl(_) => Some(reader.entities().xml_version()),
+                        _ => None,
+                    };
+
+                    // the consumer returned, nobody reads the rest.
+                    if sender.send((node, version)).is_err() {
+                        break;
+                    }
+                }
+
+                Ok(())
+            });
+
+            let mut events = PipelineEvents {
+                events: Receiver::into_iter(receiver),
+                entities,
+            };
+
+            let result = consumer(&mut events);
+
+            // unblock a producer waiting for room.
+            drop(events);
+
+            let read = producer
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+
+            match (read, result) {
+                (Err(err), _) => Err(PipelineError::Read(err)),
+                (Ok(()), result) => result.map_err(PipelineError::Consumer),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parserc::ControlFlow;
+
+    use crate::reader::{ReadError, ReadKind, XmlNode, XmlReader};
+
+    use super::PipelineError;
+
+    #[test]
+    fn test_pipeline() {
+        let source = format!(
+            r#"<?xml version="1.0"?><a>{}</a>"#,
+            "<b>x &amp; y</b>".repeat(1000)
+        );
+
+        let texts = XmlReader::from(source.as_bytes()).pipeline(4, |events| {
+            let mut texts = 0;
+
+            while let Some(node) = events.next() {
+                if let XmlNode::CharData(chardata) = node {
+                    assert_eq!(events.entities().expand(&chardata.0).unwrap(), "x & y");
+                    texts += 1;
+                }
+            }
+
+            Ok::<_, ()>(texts)
+        });
+
+        assert_eq!(texts, Ok(1000));
+
+        // the producer stops once the consumer returns.
+        assert_eq!(
+            XmlReader::from(source.as_bytes())
+                .pipeline(1, |events| events.nth(3).map(|_| ()).ok_or("short")),
+            Ok(())
+        );
+
+        assert_eq!(
+            XmlReader::from(source.as_bytes()).pipeline(1, |_| Err::<(), _>("failed")),
+            Err(PipelineError::Consumer("failed"))
+        );
+
+        let source = br#"<?xml version="1.0"?><a><b>&</b></a>"#;
+
+        let events =
+            XmlReader::from(source.as_slice()).pipeline(1, |events| Ok::<_, ()>(events.count()));
+
+        assert_eq!(
+            events,
+            Err(PipelineError::Read(ControlFlow::Fatal(ReadError::Expect(
+                ReadKind::Reference,
+                b"&</b></a>".as_slice()
+            ))))
+        );
+    }
+
+    #[test]
+    fn test_pipeline_xml11() {
+        let source = br#"<?xml version="1.1"?><a>x&#x1;y</a>"#;
+
+        let texts = XmlReader::from(source.as_slice()).pipeline(1, |events| {
+            let mut texts = vec![];
+
+            while let Some(node) = events.next() {
+                if let XmlNode::CharData(chardata) = node {
+                    texts.push(events.entities().expand(&chardata.0).unwrap().into_owned());
+                }
+            }
+
+            Ok::<_, ()>(texts)
+        });
+
+        assert_eq!(texts, Ok(vec!["x\u{1}y".to_string()]));
+    }
+}
@@ -0,0 +1,122 @@
+use std::fmt::Display;
+
+/// A location in the source document.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Position {
+    /// byte offset, starting at 0.
+    pub offset: usize,
+    /// line number, starting at 1.
+    pub line: usize,
+    /// column in characters, starting at 1.
+    pub column: usize,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+impl Position {
+    /// Returns the position of byte `offset` in `source`.
+    pub fn locate(source: &[u8], offset: usize) -> Self {
+        Self::default().advance(source, offset)
+    }
+
+    /// Move forward to byte `offset` of `source`, `offset` must not be before this position.
+    pub fn advance(self, source: &[u8], offset: usize) -> Self {
+        let offset = offset.min(source.len());
+
+        debug_assert!(offset >= self.offset);
+
+        self.skip(&source[self.offset..offset])
+    }
+
+    /// Move forward past `skipped`, the bytes of the document starting at this position.
+    pub fn skip(self, skipped: &[u8]) -> Self {
+        let offset = self.offset + skipped.len();
+
+        match memchr::memrchr(b'\n', skipped) {
+            Some(at) => Self {
+                offset,
+                line: self.line + memchr::memchr_iter(b'\n', skipped).count(),
+                column: count_chars(&skipped[at + 1..]) + 1,
+            },
+            None => Self {
+                offset,
+                line: self.line,
+                column: self.column + count_chars(skipped),
+            },
+        }
+    }
+}
+
+/// Count the utf-8 characters of `bytes`, by skipping continuation bytes.
+fn count_chars(bytes: &[u8]) -> usize {
+    bytes.iter().filter(|c| (**c as i8) >= -0x40).count()
+}
+
+/// Returns the offset of `rest` in `source`.
+///
+/// `rest` is usually a suffix of `source`; if it points elsewhere, e.g. into a copy of the
+/// input, the offset is computed from its length instead.
+pub(super) fn offset_in(source: &[u8], rest: &[u8]) -> usize {
+    let start = source.as_ptr() as usize;
+    let at = rest.as_ptr() as usize;
+
+    if (start..=start + source.len()).contains(&at) {
+        at - start
+    } else {
+        source.len().saturating_sub(rest.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Position;
+
+    #[test]
+    fn test_locate() {
+        let source = "<a>\n  <b>é\r\n</b></a>".as_bytes();
+
+        assert_eq!(Position::locate(source, 0), Position::default());
+
+        assert_eq!(
+            Position::locate(source, 6),
+            Position {
+                offset: 6,
+                line: 2,
+                column: 3
+            }
+        );
+
+        let end = Position::locate(source, source.len());
+
+        assert_eq!((end.line, end.column), (3, 9));
+        assert_eq!(
+            Position::locate(source, 6).advance(source, source.len()),
+            end
+        );
+
+        assert_eq!(Position::locate(source, 6).skip(&source[6..]), end);
+
+        assert_eq!(
+            Position::locate(source, 11),
+            Position {
+                offset: 11,
+                line: 2,
+                column: 7
+            }
+        );
+    }
+}
@@ -0,0 +1,449 @@
+use memchr::memmem;
+use parserc::ControlFlow;
+
+use crate::chars::is_ws;
+
+use super::{
+    Entities, Position, ReadError, ReadState, XmlNode, XmlReaderBuilder, position::offset_in,
+};
+
+/// The result of [`PushParser::next_event`].
+#[derive(Debug, PartialEq, Clone)]
+pub enum PushEvent<'a> {
+    /// The next completed event.
+    Node(XmlNode<&'a [u8]>),
+    /// The buffered bytes end inside the next event, [`feed`](PushParser::feed) more of them.
+    NeedMoreData,
+    /// The document is finished.
+    Eof,
+}
+
+/// An incremental reader fed with chunks of a document, e.g. by a non-blocking socket.
+///
+/// Chunks may be split anywhere, even inside a multi-byte char. Events are only returned once
+/// all of their bytes arrived, so they are the same as those of an [`XmlReader`](super::XmlReader)
+/// over the whole document; until then [`next_event`](Self::next_event) returns
+/// [`PushEvent::NeedMoreData`]. Call [`finish`](Self::finish) after the last chunk, the rest
+/// of the buffer is then read as is, and truncated markup reported as errors.
+///
+/// Consumed bytes are dropped from the buffer as later chunks are fed. The builder options
+/// filtering and merging events, like [`coalesce_text`](XmlReaderBuilder::coalesce_text),
+/// are not supported and ignored.
+#[derive(Debug)]
+pub struct PushParser {
+    builder: XmlReaderBuilder,
+    entities: Entities,
+    buf: Vec<u8>,
+    /// offset of the first unread byte in `buf`.
+    pos: usize,
+    /// position of the first unread byte in the document.
+    position: Position,
+    state: ReadState,
+    /// names of the open elements, tags are only counted if not strict.
+    names: Vec<Vec<u8>>,
+    depth: usize,
+    finished: bool,
+}
+
+impl Default for PushParser {
+    fn default() -> Self {
+        Self::with_builder(XmlReaderBuilder::new())
+    }
+}
+
+impl PushParser {
+    /// Create a parser with default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a parser with the options of `builder`.
+    pub fn with_builder(mut builder: XmlReaderBuilder) -> Self {
+        let entities = std::mem::take(&mut builder.entities);
+
+        Self {
+            state: builder.state,
+            builder: builder
+                .trim_text(false)
+                .skip_comments(false)
+                .coalesce_text(false)
+                .attach_whitespace(false),
+            entities,
+            buf: vec![],
+            pos: 0,
+            position: Position::default(),
+            names: vec![],
+            depth: 0,
+            finished: false,
+        }
+    }
+
+    /// Append the next `chunk` of the document.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called after [`finish`](Self::finish).
+    pub fn feed(&mut self, chunk: &[u8]) {
+        assert!(!self.finished, "feed after finish");
+
+        // drop consumed bytes once they make up half of the buffer.
+        if self.pos > 0 && self.pos * 2 >= self.buf.len() {
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+        }
+
+        self.buf.extend_from_slice(chunk);
+    }
+
+    /// Mark the end of the document, no more chunks follow.
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    /// Returns the entity table, e.g. to expand text.
+    pub fn entities(&self) -> &Entities {
+        &self.entities
+    }
+
+    /// Returns the number of open elements.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the number of buffered bytes not read yet.
+    pub fn buffered(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    /// Returns the next event, if all of its bytes arrived.
+    ///
+    /// Errors are final as for [`XmlReader::read_next`](super::XmlReader::read_next), the parser
+    /// returns [`PushEvent::Eof`] after them.
+    #[allow(clippy::type_complexity)]
+    pub fn next_event(&mut self) -> Result<PushEvent<'_>, ControlFlow<ReadError<&[u8]>>> {
+        if self.state == ReadState::Eof {
+            return Ok(PushEvent::Eof);
+        }
+
+        let rest = &self.buf[self.pos..];
+
+        let len = if self.finished {
+            if rest.is_empty() && self.builder.strict && !self.names.is_empty() {
+                self.state = ReadState::Eof;
+
+                let name = self.names.last().unwrap();
+                return Err(ControlFlow::Fatal(ReadError::Unclosed(name.as_slice())));
+            }
+
+            rest.len()
+        } else {
+            match node_len(rest, self.state) {
+                Some(len) => len,
+                None => return Ok(PushEvent::NeedMoreData),
+            }
+        };
+
+        let mut reader = self
+            .builder
+            .clone()
+            .state(self.state)
+            .strict(false)
+            .build(&rest[..len])
+            .with_depth(self.depth);
+
+        // lend the entity table, so the declared version and entities outlive the event.
+        std::mem::swap(reader.entities_mut(), &mut self.entities);
+        let next = reader.read_next();
+        std::mem::swap(reader.entities_mut(), &mut self.entities);
+
+        let node = match next {
+            Ok(Some(node)) => node,
+            Ok(None) => {
+                self.state = ReadState::Eof;
+                return Ok(PushEvent::Eof);
+            }
+            Err(err) => {
+                self.state = ReadState::Eof;
+                return Err(err);
+            }
+        };
+
+        let consumed = reader.buffer_position();
+
+        self.pos += consumed;
+        self.state = reader.state();
+        self.depth = reader.depth();
+
+        // the reader of each event can't check what spans events.
+        if self.builder.strict {
+            match &node {
+                XmlNode::Start(start) if !start.is_empty => self.names.push(start.name.to_vec()),
+                XmlNode::Comment(comment) => {
+                    if let Err(err) = comment.validate() {
+                        self.state = ReadState::Eof;
+                        return Err(err);
+                    }
+                }
+                XmlNode::End(end) => {
+                    let mismatch = self
+                        .names
+                        .last()
+                        .is_some_and(|expected| expected.as_slice() != end.name);
+
+                    if mismatch {
+                        self.state = ReadState::Eof;
+
+                        return Err(ControlFlow::Fatal(ReadError::Mismatch {
+                            expected: self.names.last().unwrap().as_slice(),
+                            found: end.name,
+                            position: self.position.skip(&rest[..offset_in(rest, end.name)]),
+                        }));
+                    }
+
+                    self.names.pop();
+                }
+                _ => {}
+            }
+        }
+
+        self.position = self.position.skip(&rest[..consumed]);
+
+        Ok(PushEvent::Node(node))
+    }
+}
+
+/// Returns the length of the next node in `bytes` read in `state`, `None` if it may continue
+/// behind them.
+fn node_len(bytes: &[u8], state: ReadState) -> Option<usize> {
+    let mut lead = 0;
+
+    if state == ReadState::XmlDecl {
+        // a byte order mark and whitespace before the declaration are read with it.
+        if bytes.starts_with(b"\xEF\xBB\xBF") {
+            lead = 3;
+        } else if b"\xEF\xBB\xBF".starts_with(bytes) {
+            return None;
+        }
+
+        lead += bytes[lead..].iter().take_while(|c| is_ws(**c)).count();
+    }
+
+    let rest = &bytes[lead..];
+
+    match rest.first()? {
+        b'<' => markup_len(rest).map(|len| lead + len),
+        // text ends at the next markup.
+        _ if state == ReadState::Element => memchr::memchr(b'<', rest),
+        c if is_ws(*c) => rest.iter().position(|c| !is_ws(*c)),
+        // not well-formed, leave it to the reader.
+        _ => Some(bytes.len()),
+    }
+}
+
+/// Returns the length of the markup `bytes` start with, `None` if it is incomplete.
+fn markup_len(bytes: &[u8]) -> Option<usize> {
+    let find = |from: usize, delim: &[u8]| {
+        memmem::find(bytes.get(from..)?, delim).map(|at| from + at + delim.len())
+    };
+
+    if bytes.starts_with(b"<!--") {
+        find(4, b"-->")
+    } else if bytes.starts_with(b"<![CDATA[") {
+        find(9, b"]]>")
+    } else if bytes.starts_with(b"<!DOCTYPE") {
+        doctype_len(bytes)
+    } else if [b"<!--".as_slice(), b"<![CDATA[", b"<!DOCTYPE"]
+        .iter()
+        .any(|prefix| prefix.starts_with(bytes))
+    {
+        // too short to tell.
+        None
+    } else if bytes.starts_with(b"<?") {
+        find(2, b"?>")
+    } else {
+        // tags end at the first `>` outside quotes.
+        let mut quote = None;
+
+        for (i, c) in bytes.iter().enumerate() {
+            match (quote, *c) {
+                (None, b'"' | b'\'') => quote = Some(*c),
+                (Some(q), c) if q == c => quote = None,
+                (None, b'>') => return Some(i + 1),
+                _ => {}
+            }
+        }
+
+        None
+    }
+}
+
+/// Returns the length of the doctype `bytes` start with, skipping over the internal subset.
+fn doctype_len(bytes: &[u8]) -> Option<usize> {
+    let (mut i, mut subset, mut quote) = (9, false, None);
+
+    while i < bytes.len() {
+        let c = bytes[i];
+
+        if let Some(q) = quote {
+            if c == q {
+                quote = None;
+            }
+
+            i += 1;
+            continue;
+        }
+
+        let rest = &bytes[i..];
+
+        // comments and PIs of the subset may contain any delimiter.
+        if subset
+            && c == b'<'
+            && (rest.starts_with(b"<!--") || rest.starts_with(b"<?") || b"<!--".starts_with(rest))
+        {
+            i += markup_len(rest)?;
+            continue;
+        }
+
+        match c {
+            b'"' | b'\'' => quote = Some(c),
+            b'[' => subset = true,
+            b']' => subset = false,
+            b'>' if !subset => return Some(i + 1),
+            _ => {}
+        }
+
+        i += 1;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use parserc::ControlFlow;
+
+    use crate::{
+        reader::{Position, ReadError, XmlReader, XmlReaderBuilder},
+        types::XmlVersion,
+    };
+
+    use super::{PushEvent, PushParser};
+
+    const DOC: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE a [<!ENTITY e "a > b"><!-- x --><?pi x?>]>
+<a x='>'><b>text &amp; more</b><![CDATA[<c>]]><!-- - --><?pi x?>é<d/></a>
+"#;
+
+    fn push(chunks: &[&[u8]]) -> Result<Vec<String>, String> {
+        let mut parser = PushParser::new();
+        let mut events = vec![];
+
+        for (i, chunk) in chunks.iter().enumerate() {
+            parser.feed(chunk);
+
+            if i + 1 == chunks.len() {
+                parser.finish();
+            }
+
+            loop {
+                match parser.next_event().map_err(|err| format!("{:?}", err))? {
+                    PushEvent::Node(node) => events.push(format!("{:?}", node)),
+                    PushEvent::NeedMoreData => break,
+                    PushEvent::Eof => return Ok(events),
+                }
+            }
+        }
+
+        Ok(events)
+    }
+
+    #[test]
+    fn test_push() {
+        let expected = XmlReader::from(DOC.as_bytes())
+            .map(|node| format!("{:?}", node.unwrap()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(push(&[DOC.as_bytes()]), Ok(expected.clone()));
+
+        let bytes = DOC.as_bytes().chunks(1).collect::<Vec<_>>();
+
+        assert_eq!(push(&bytes), Ok(expected.clone()));
+
+        for len in [2, 3, 7, 16] {
+            assert_eq!(
+                push(&DOC.as_bytes().chunks(len).collect::<Vec<_>>()),
+                Ok(expected.clone())
+            );
+        }
+
+        let mut parser = PushParser::new();
+
+        parser.feed(b"<?xml version=\"1.0\"?>\n<a><b>");
+        parser.feed(b"\n</c>");
+
+        for _ in 0..5 {
+            assert!(matches!(parser.next_event(), Ok(PushEvent::Node(_))));
+        }
+
+        assert_eq!(
+            parser.next_event(),
+            Err(ControlFlow::Fatal(ReadError::Mismatch {
+                expected: b"b".as_slice(),
+                found: b"c".as_slice(),
+                position: Position {
+                    offset: 31,
+                    line: 3,
+                    column: 3
+                }
+            }))
+        );
+        assert_eq!(parser.next_event(), Ok(PushEvent::Eof));
+
+        let mut parser = PushParser::new();
+
+        parser.feed(br#"<?xml version="1.0"?><a>te"#);
+
+        for _ in 0..2 {
+            assert!(matches!(parser.next_event(), Ok(PushEvent::Node(_))));
+        }
+
+        assert_eq!(parser.next_event(), Ok(PushEvent::NeedMoreData));
+        assert_eq!(parser.buffered(), 2);
+
+        parser.finish();
+
+        assert!(matches!(parser.next_event(), Ok(PushEvent::Node(_))));
+        assert_eq!(
+            parser.next_event(),
+            Err(ControlFlow::Fatal(ReadError::Unclosed(b"a".as_slice())))
+        );
+    }
+
+    #[test]
+    fn test_push_xml11() {
+        let source = b"<?xml version=\"1.1\"?><a>x\xC2\x80</a>";
+        let builder = || XmlReaderBuilder::new().check_chars(true);
+
+        let err = builder().build(source.as_slice()).find_map(Result::err);
+
+        assert!(matches!(
+            err,
+            Some(ControlFlow::Fatal(ReadError::IllegalChar {
+                codepoint: 0x80,
+                ..
+            }))
+        ));
+
+        let mut parser = PushParser::with_builder(builder());
+
+        parser.feed(source);
+        parser.finish();
+
+        for _ in 0..2 {
+            assert!(matches!(parser.next_event(), Ok(PushEvent::Node(_))));
+        }
+
+        assert_eq!(parser.next_event().err(), err);
+        assert_eq!(parser.entities().xml_version(), XmlVersion::Ver11);
+    }
+}
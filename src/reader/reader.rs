@@ -1,11 +1,21 @@
-use std::fmt::Debug;
+use std::{borrow::Cow, cell::Cell, fmt::Debug, iter::FusedIterator};
 
-use parserc::{AsBytes, ControlFlow, Input, Parse, Parser, ParserExt};
+use parserc::{AsBytes, ControlFlow, Input, Needed, Parse, Parser, ParserExt};
+
+use crate::{
+    chars::{is_char, is_restricted_char, is_ws},
+    types::{TextCollector, Whitespace, XmlVersion},
+};
 
 use super::{
-    CData, CharData, Comment, DocType, ElemEnd, ElemStart, PI, ReadError, XmlDecl, ensure_ws,
+    Attr, AttrMap, CData, CharData, Comment, DetectedEncoding, DocType, ElemEnd, ElemStart,
+    Entities, Name, PI, Position, ReadError, ReadKind, ReaderLimit, ReaderLimits, Unknown,
+    Utf8Policy, XmlDecl, XmlReaderBuilder, ensure_ws, position::offset_in, utils::find_end_tag,
 };
 
+#[cfg(feature = "instrument")]
+use super::{ConstructKind, ParseReport};
+
 /// Xml node type returns by [`XmlReader`].
 #[derive(Debug, PartialEq, Clone)]
 pub enum XmlNode<I> {
@@ -19,6 +29,11 @@ pub enum XmlNode<I> {
     End(ElemEnd<I>),
     CharData(CharData<I>),
     CData(CData<I>),
+    /// Unclassified markup, only yielded in lenient mode.
+    Unknown(Unknown<I>),
+    /// A run of text and cdata sections with references expanded, only yielded when
+    /// [`coalescing`](XmlReaderBuilder::coalesce_text).
+    Text(String),
 }
 
 /// State of reader.
@@ -34,14 +49,118 @@ pub enum ReadState {
     Eof,
 }
 
+/// A node read ahead by [`XmlReader::peek_next`], with the reader state describing the node
+/// returned last, swapped back in when it is returned.
+struct Peeked<I>
+where
+    I: Debug,
+{
+    node: Result<Option<XmlNode<I>>, ControlFlow<ReadError<I>>>,
+    node_start: usize,
+    leading_ws: Option<I>,
+    trailing_ws: Option<I>,
+    /// `depth`, `buffer_position` and `state` before the node was read.
+    depth: usize,
+    offset: usize,
+    state: ReadState,
+    /// the fields consumed by reading the node, restored when it is discarded.
+    rewind: Rewind<I>,
+}
+
+/// Reader fields changed by reading one node; reading a node pushes or pops at most one name.
+struct Rewind<I> {
+    input: I,
+    starts: usize,
+    names: (usize, Option<I>),
+    state: ReadState,
+    pending: Option<(XmlNode<I>, usize)>,
+    had_error: bool,
+}
+
 /// Xml document reader.
-pub struct XmlReader<I> {
+///
+/// # Event order
+///
+/// The sequence of events for a given input is part of the public contract, and is pinned by the
+/// golden tests in `tests/golden.rs`:
+///
+/// - events follow the document order, one event per construct, none is merged or split.
+/// - `<a/>` yields one [`XmlNode::Start`] with `is_empty` set and no [`XmlNode::End`], `<a></a>`
+///   yields a `Start` directly followed by an `End`.
+/// - text up to the next markup is one [`XmlNode::CharData`] including its references, so
+///   comments, PIs and CDATA sections split text; adjacent CDATA sections, even empty ones, are
+///   separate [`XmlNode::CData`] events.
+/// - whitespace outside the root element is [`XmlNode::S`], inside elements it is `CharData`.
+/// - a start tag ends at the first `>` outside quotes, attributes are parsed lazily on
+///   [`ElemStart::attrs`].
+/// - an error is the last event, see [`read_next`](Self::read_next).
+///
+/// The builder options [`trim_text`](XmlReaderBuilder::trim_text),
+/// [`skip_comments`](XmlReaderBuilder::skip_comments),
+/// [`coalesce_text`](XmlReaderBuilder::coalesce_text) and
+/// [`attach_whitespace`](XmlReaderBuilder::attach_whitespace) drop and merge events of this sequence.
+pub struct XmlReader<I>
+where
+    I: Debug,
+{
+    /// the state this reader was created with, restored by [`reset`](Self::reset).
+    init_state: ReadState,
     /// read state of this reader.
     state: ReadState,
     /// input stream.
     input: I,
+    /// the whole input, positions are computed against it.
+    source: I,
+    /// offset of the node returned last.
+    node_start: usize,
+    /// the position computed last, later positions are counted from it.
+    cursor: Cell<Position>,
     /// start tag counter.
     starts: usize,
+    /// names of the open elements, only tracked in strict mode.
+    names: Vec<I>,
+    /// check tag balance, see [`XmlReaderBuilder::strict`].
+    strict: bool,
+    /// utf-8 validation strategy.
+    utf8: Utf8Policy,
+    /// true if [`Utf8Policy::ValidateOnce`] already checked the input.
+    validated: bool,
+    /// attribute lookup buffer of [`get_attr`](Self::get_attr).
+    attr_map: AttrMap<I>,
+    /// address and length of the start tag loaded into `attr_map`.
+    attr_map_key: Option<(usize, usize)>,
+    /// entity table used by [`expand`](Self::expand).
+    entities: Entities,
+    /// yield [`XmlNode::Unknown`] instead of failing on unclassified markup.
+    lenient: bool,
+    /// true if a read failed, the reader is then exhausted.
+    had_error: bool,
+    /// see [`XmlReaderBuilder::trim_text`].
+    trim_text: bool,
+    /// see [`XmlReaderBuilder::skip_comments`].
+    skip_comments: bool,
+    /// see [`XmlReaderBuilder::coalesce_text`].
+    coalesce_text: bool,
+    /// see [`XmlReaderBuilder::limits`].
+    limits: ReaderLimits,
+    /// see [`XmlReaderBuilder::check_names`].
+    check_names: bool,
+    /// see [`XmlReaderBuilder::check_chars`].
+    check_chars: bool,
+    /// see [`XmlReaderBuilder::attach_whitespace`].
+    attach_ws: bool,
+    /// see [`XmlReaderBuilder::fragment`].
+    fragment: bool,
+    /// whitespace before and after the node returned last, if attached.
+    leading_ws: Option<I>,
+    trailing_ws: Option<I>,
+    /// the node read past the end of coalesced text and its offset.
+    pending: Option<(XmlNode<I>, usize)>,
+    /// the node returned by [`peek_next`](Self::peek_next).
+    peeked: Option<Peeked<I>>,
+    /// per-construct parse statistics.
+    #[cfg(feature = "instrument")]
+    report: ParseReport,
 }
 
 impl<I> XmlReader<I>
@@ -49,20 +168,97 @@ where
     I: Input<Item = u8> + AsBytes + Clone + Debug,
 {
     #[inline(always)]
-    fn read_xml_decl(&mut self) -> Result<XmlNode<I>, ControlFlow<ReadError<I>>> {
-        let (decl, input) = XmlDecl::parse(self.input.clone())?;
+    fn read_xml_decl(&mut self) -> Result<Option<XmlNode<I>>, ControlFlow<ReadError<I>>> {
+        let detected = DetectedEncoding::detect(self.input.as_bytes());
 
-        self.input = input;
+        if let Some((detected, bom)) = detected {
+            if !detected.is_utf8() {
+                let err = match detected.sniff_encoding(&self.input.as_bytes()[bom..]) {
+                    Some(declared) if !detected.matches(declared.as_bytes()) => {
+                        ReadError::EncodingMismatch { detected, declared }
+                    }
+                    _ => ReadError::UnsupportedEncoding(detected),
+                };
+
+                return Err(ControlFlow::Fatal(err));
+            }
+
+            self.input.split_to(bom);
+        }
 
         self.state = ReadState::MiscBeforeDocType;
 
-        Ok(XmlNode::XmlDecl(decl))
+        let (decl, input) = XmlDecl::into_parser().ok().parse(self.input.clone())?;
+
+        let Some(decl) = decl else {
+            return self.read_misplaced_decl();
+        };
+
+        if let (Some((detected, _)), Some(encoding)) = (detected, &decl.encoding)
+            && !detected.matches(encoding.as_bytes())
+        {
+            return Err(ControlFlow::Fatal(ReadError::EncodingMismatch {
+                detected,
+                declared: String::from_utf8_lossy(encoding.as_bytes()).into_owned(),
+            }));
+        }
+
+        self.input = input;
+
+        self.entities.version(decl.version);
+
+        Ok(Some(XmlNode::XmlDecl(decl)))
+    }
+
+    /// The declaration is optional, but must be the very first thing of a document, only
+    /// preceded by a BOM. Look for one behind leading whitespace, comments or a BOM to report it,
+    /// or in lenient mode to skip to it.
+    fn read_misplaced_decl(&mut self) -> Result<Option<XmlNode<I>>, ControlFlow<ReadError<I>>> {
+        let mut rest = self.input.clone();
+
+        loop {
+            let (misc, input) = Comment::into_parser()
+                .map(|_| ())
+                .or(ensure_ws.map(|_| ()))
+                .ok()
+                .parse(rest.clone())?;
+
+            if misc.is_none() {
+                break;
+            }
+
+            rest = input;
+        }
+
+        if rest.as_bytes().starts_with(b"\xEF\xBB\xBF") {
+            if !self.lenient {
+                return Err(ControlFlow::Fatal(ReadError::MisplacedBom(rest)));
+            }
+
+            rest.split_to(3);
+            self.input = rest.clone();
+        }
+
+        let (decl, input) = XmlDecl::into_parser().ok().parse(rest.clone())?;
+
+        let Some(decl) = decl else {
+            return Ok(None);
+        };
+
+        if !self.lenient {
+            return Err(ControlFlow::Fatal(ReadError::MisplacedDecl(rest)));
+        }
+
+        self.input = input;
+        self.entities.version(decl.version);
+
+        Ok(Some(XmlNode::XmlDecl(decl)))
     }
 
     #[inline(always)]
     fn read_doctype(&mut self) -> Result<Option<XmlNode<I>>, ControlFlow<ReadError<I>>> {
         let (doc_type, input) = DocType::into_parser()
-            .map(|v| XmlNode::DocType(v))
+            .map(XmlNode::DocType)
             .ok()
             .parse(self.input.clone())?;
 
@@ -74,17 +270,33 @@ where
     #[inline(always)]
     fn read_misc(&mut self) -> Result<Option<XmlNode<I>>, ControlFlow<ReadError<I>>> {
         let (misc, input) = PI::into_parser()
-            .map(|v| XmlNode::PI(v))
-            .or(Comment::into_parser().map(|v| XmlNode::Comment(v)))
-            .or(ensure_ws.map(|v| XmlNode::S(v)))
+            .map(XmlNode::PI)
+            .or(Comment::into_parser().map(XmlNode::Comment))
+            .or(ensure_ws.map(XmlNode::S))
             .ok()
             .parse(self.input.clone())?;
 
         self.input = input;
 
+        if misc.is_none() && self.lenient {
+            return self.read_unknown();
+        }
+
         Ok(misc)
     }
 
+    #[inline(always)]
+    fn read_unknown(&mut self) -> Result<Option<XmlNode<I>>, ControlFlow<ReadError<I>>> {
+        let (unknown, input) = Unknown::into_parser()
+            .map(XmlNode::Unknown)
+            .ok()
+            .parse(self.input.clone())?;
+
+        self.input = input;
+
+        Ok(unknown)
+    }
+
     #[inline(always)]
     fn read_root_el(&mut self) -> Result<XmlNode<I>, ControlFlow<ReadError<I>>> {
         let (el, input) = ElemStart::parse(self.input.clone())?;
@@ -94,41 +306,84 @@ where
         if el.is_empty {
             self.state = ReadState::MiscAfterElement;
         } else {
+            if self.strict {
+                self.names.push(el.name.clone());
+            }
+
             self.starts += 1;
             self.state = ReadState::Element;
         }
 
-        return Ok(XmlNode::Start(el));
+        Ok(XmlNode::Start(el))
     }
 
     #[inline(always)]
     fn read_el(&mut self) -> Result<XmlNode<I>, ControlFlow<ReadError<I>>> {
-        let (node, input) = ElemEnd::into_parser()
-            .map(|v| XmlNode::End(v))
-            .or(ElemStart::into_parser().map(|v| XmlNode::Start(v)))
-            .or(PI::into_parser().map(|v| XmlNode::PI(v)))
-            .or(Comment::into_parser().map(|v| XmlNode::Comment(v)))
-            .or(CData::into_parser().map(|v| XmlNode::CData(v)))
-            .or(CharData::into_parser().map(|v| XmlNode::CharData(v)))
-            .parse(self.input.clone())?;
+        if self.strict
+            && self.input.is_empty()
+            && let Some(name) = self.names.last()
+        {
+            return Err(ControlFlow::Fatal(ReadError::Unclosed(name.clone())));
+        }
+
+        let (node, input) = match ElemEnd::into_parser()
+            .map(XmlNode::End)
+            .or(PI::into_parser().map(XmlNode::PI))
+            .or(Comment::into_parser().map(XmlNode::Comment))
+            .or(CData::into_parser().map(XmlNode::CData))
+            .or(ElemStart::into_parser().map(XmlNode::Start))
+            .or(CharData::into_parser().map(XmlNode::CharData))
+            .parse(self.input.clone())
+        {
+            Err(ControlFlow::Recovable(err)) if self.lenient => {
+                if let Some(unknown) = self.read_unknown()? {
+                    return Ok(unknown);
+                }
+
+                return Err(ControlFlow::Recovable(err));
+            }
+            result => result?,
+        };
 
         self.input = input;
 
         match &node {
-            XmlNode::Start(_) => {
+            XmlNode::Start(start) if !start.is_empty => {
+                if self.strict {
+                    self.names.push(start.name.clone());
+                }
+
                 self.starts += 1;
             }
-            XmlNode::End(_) => {
+            XmlNode::End(end) => {
+                // only fragments, or readers started inside an element, get here at the top level.
+                if self.starts == 0 {
+                    return Err(ControlFlow::Fatal(ReadError::Unexpect(
+                        ReadKind::Keyword("</"),
+                        end.name.clone(),
+                    )));
+                }
+
+                if let Some(expected) = self.names.pop()
+                    && expected.as_bytes() != end.name.as_bytes()
+                {
+                    return Err(ControlFlow::Fatal(ReadError::Mismatch {
+                        expected,
+                        found: end.name.clone(),
+                        position: self.locate_input(&end.name),
+                    }));
+                }
+
                 self.starts -= 1;
             }
             _ => {}
         }
 
-        if self.starts == 0 {
+        if self.starts == 0 && !self.fragment {
             self.state = ReadState::MiscAfterElement;
         }
 
-        return Ok(node);
+        Ok(node)
     }
 }
 
@@ -147,75 +402,1670 @@ where
 {
     /// Create a new reader.
     pub fn new(state: ReadState, input: I) -> Self {
+        Self::with_builder(XmlReaderBuilder::new().state(state), input)
+    }
+
+    /// Create a reader of a fragment, see [`XmlReaderBuilder::fragment`].
+    pub fn fragment(input: I) -> Self {
+        Self::with_builder(XmlReaderBuilder::new().fragment(true), input)
+    }
+
+    /// Returns a builder to configure a new reader.
+    pub fn builder() -> XmlReaderBuilder {
+        XmlReaderBuilder::new()
+    }
+
+    pub(super) fn with_builder(builder: XmlReaderBuilder, input: I) -> Self {
         Self {
-            state,
+            init_state: builder.state,
+            state: builder.state,
+            source: input.clone(),
             input,
+            node_start: 0,
+            cursor: Cell::new(Position::default()),
             starts: 0,
+            names: vec![],
+            strict: builder.strict,
+            utf8: builder.utf8,
+            validated: false,
+            attr_map: AttrMap::with_threshold(builder.attr_index_threshold),
+            attr_map_key: None,
+            entities: builder.entities,
+            lenient: builder.lenient,
+            had_error: false,
+            trim_text: builder.trim_text,
+            skip_comments: builder.skip_comments,
+            coalesce_text: builder.coalesce_text,
+            limits: builder.limits,
+            check_names: builder.check_names,
+            check_chars: builder.check_chars,
+            attach_ws: builder.attach_ws,
+            fragment: builder.fragment,
+            leading_ws: None,
+            trailing_ws: None,
+            pending: None,
+            peeked: None,
+            #[cfg(feature = "instrument")]
+            report: Default::default(),
         }
     }
 
-    /// read next xml node.
-    #[inline(always)]
-    pub fn read_next(&mut self) -> Result<Option<XmlNode<I>>, ControlFlow<ReadError<I>>> {
-        loop {
-            match self.state {
-                ReadState::XmlDecl => return self.read_xml_decl().map(|v| Some(v)),
-                ReadState::MiscBeforeDocType => {
-                    if let Some(misc) = self.read_misc()? {
-                        return Ok(Some(misc));
-                    } else {
-                        self.state = ReadState::DocType;
-                        continue;
-                    }
-                }
-                ReadState::DocType => {
-                    if let Some(doctype) = self.read_doctype()? {
-                        self.state = ReadState::MiscBeforeElement;
-                        return Ok(Some(doctype));
-                    } else {
-                        self.state = ReadState::RootElement;
-                        continue;
-                    }
-                }
-                ReadState::MiscBeforeElement => {
-                    if let Some(misc) = self.read_misc()? {
-                        return Ok(Some(misc));
-                    } else {
-                        self.state = ReadState::RootElement;
-                        continue;
-                    }
-                }
-                ReadState::RootElement => {
-                    return self.read_root_el().map(|v| Some(v));
+    /// Restart this reader on a new document, keeping its allocated internal buffers.
+    ///
+    /// The reader returns to the [`ReadState`] it was created with. Batch jobs parsing many
+    /// small documents should reuse one reader this way instead of creating a new one per document.
+    /// Instrumentation counters, if enabled, keep accumulating across documents.
+    pub fn reset(&mut self, input: I) {
+        self.state = self.init_state;
+        self.source = input.clone();
+        self.input = input;
+        self.node_start = 0;
+        self.cursor.set(Position::default());
+        self.starts = 0;
+        self.names.clear();
+        self.entities.version(XmlVersion::Ver10);
+        self.entities.reset_expanded();
+        self.validated = false;
+        self.had_error = false;
+        self.pending = None;
+        self.peeked = None;
+        self.leading_ws = None;
+        self.trailing_ws = None;
+        self.attr_map.clear();
+        self.attr_map_key = None;
+    }
+
+    /// Returns the entity table of this reader.
+    pub fn entities(&self) -> &Entities {
+        &self.entities
+    }
+
+    /// Returns a mutable reference to the entity table, e.g. to register internal DTD entities.
+    pub fn entities_mut(&mut self) -> &mut Entities {
+        &mut self.entities
+    }
+
+    /// Expand the entity and character references in `raw`; see [`Entities::expand`].
+    pub fn expand<'a>(&self, raw: &'a I) -> Result<Cow<'a, str>, ControlFlow<ReadError<I>>> {
+        self.entities.expand(raw)
+    }
+
+    /// Returns the expanded content of a text node.
+    pub fn text<'a>(
+        &self,
+        chardata: &'a CharData<I>,
+    ) -> Result<Cow<'a, str>, ControlFlow<ReadError<I>>> {
+        self.expand(&chardata.0)
+    }
+
+    /// Returns the content of a cdata section, see [`Entities::cdata`].
+    pub fn cdata<'a>(
+        &self,
+        cdata: &'a CData<I>,
+    ) -> Result<Cow<'a, str>, ControlFlow<ReadError<I>>> {
+        self.entities.cdata(&cdata.0)
+    }
+
+    /// Returns the expanded value of an attribute.
+    pub fn attr_value<'a>(
+        &self,
+        attr: &'a Attr<I>,
+    ) -> Result<Cow<'a, str>, ControlFlow<ReadError<I>>> {
+        self.entities.expand_attr(&attr.value)
+    }
+
+    /// Fast-forward past the end tag of the element opened by `start`, returning the raw content
+    /// in between.
+    ///
+    /// Call it right after `start` was returned. No events are created for the content: markup is
+    /// only scanned for its delimiters, so it is neither checked for well-formedness nor are
+    /// attributes parsed. The end tag itself is checked in strict mode, and the reader continues
+    /// after it as if the content had been read; empty elements return an empty span.
+    pub fn skip_subtree(&mut self, start: &ElemStart<I>) -> Result<I, ControlFlow<ReadError<I>>> {
+        self.discard_peeked();
+
+        if start.is_empty {
+            return Ok(self.input.clone().split_to(0));
+        }
+
+        let result = self.skip_content(start);
+
+        if result.is_err() {
+            self.had_error = true;
+            self.state = ReadState::Eof;
+        }
+
+        result
+    }
+
+    fn skip_content(&mut self, start: &ElemStart<I>) -> Result<I, ControlFlow<ReadError<I>>> {
+        let end = find_end_tag(self.input.as_bytes())
+            .ok_or_else(|| ControlFlow::Fatal(ReadError::Unclosed(start.name.clone())))?;
+
+        let content = self.input.clone().split_to(end);
+        let node_start = self.source.len() - self.input.len() + end;
+
+        let (el, input) = ElemEnd::parse(self.input.clone().split_off(end))?;
+
+        // `start` was not returned by this reader.
+        if self.starts == 0 {
+            return Err(ControlFlow::Fatal(ReadError::Unexpect(
+                ReadKind::Keyword("</"),
+                el.name,
+            )));
+        }
+
+        if let Some(expected) = self.names.pop()
+            && expected.as_bytes() != el.name.as_bytes()
+        {
+            return Err(ControlFlow::Fatal(ReadError::Mismatch {
+                expected,
+                position: self.locate_input(&el.name),
+                found: el.name,
+            }));
+        }
+
+        self.input = input;
+        self.node_start = node_start;
+        self.starts -= 1;
+
+        if self.starts == 0 {
+            self.state = ReadState::MiscAfterElement;
+        }
+
+        Ok(content)
+    }
+
+    /// Read the text and cdata content of the element opened by `start` up to its end tag,
+    /// applying the whitespace `policy`.
+    ///
+    /// Call it right after `start` was returned; comments and PIs are skipped. For
+    /// [`Whitespace::XmlSpace`] only `start` and its descendants are checked for `xml:space`,
+    /// the content is collapsed unless one of them says `preserve`.
+    pub fn read_text(
+        &mut self,
+        start: &ElemStart<I>,
+        policy: Whitespace,
+    ) -> Result<String, ControlFlow<ReadError<I>>> {
+        let mut collector = TextCollector::default();
+
+        if start.is_empty {
+            return Ok(collector.finish());
+        }
+
+        let mut scopes =
+            vec![policy.preserves(start.get_attr(b"xml:space")?.as_ref().map(I::as_bytes))];
+
+        while let Some(preserve) = scopes.last().copied() {
+            match self.read_next()? {
+                Some(XmlNode::Start(start)) if !start.is_empty => {
+                    let xml_space = start.get_attr(b"xml:space")?;
+
+                    scopes.push(match xml_space {
+                        Some(value) => policy.preserves(Some(value.as_bytes())),
+                        None => preserve,
+                    });
                 }
-                ReadState::Element => {
-                    return self.read_el().map(|v| Some(v));
+                Some(XmlNode::End(_)) => {
+                    scopes.pop();
                 }
-                ReadState::MiscAfterElement => {
-                    if let Some(misc) = self.read_misc()? {
-                        return Ok(Some(misc));
-                    } else {
-                        self.state = ReadState::Eof;
-                        continue;
-                    }
+                Some(node @ (XmlNode::CharData(_) | XmlNode::CData(_))) => {
+                    let mut text = String::new();
+                    self.push_text(&node, &mut text)?;
+                    collector.push(&text, preserve);
                 }
-                ReadState::Eof => return Ok(None),
+                Some(XmlNode::Text(text)) => collector.push(&text, preserve),
+                Some(_) => {}
+                None => break,
             }
         }
+
+        Ok(collector.finish())
     }
-}
 
-impl<I> Iterator for XmlReader<I>
-where
-    I: Input<Item = u8> + AsBytes + Clone + Debug,
-{
-    type Item = Result<XmlNode<I>, ControlFlow<ReadError<I>>>;
+    /// Returns the value of the attribute `name` of `start`.
+    ///
+    /// Repeated lookups on the same start tag reuse the parsed attributes, and tags with many
+    /// attributes are hash indexed; see [`AttrMap`].
+    pub fn get_attr(
+        &mut self,
+        start: &ElemStart<I>,
+        name: &[u8],
+    ) -> Result<Option<I>, ControlFlow<ReadError<I>>> {
+        let unparsed = start.unparsed.as_bytes();
+        let key = Some((unparsed.as_ptr() as usize, unparsed.len()));
+
+        if self.attr_map_key != key {
+            self.attr_map_key = None;
+            self.attr_map.load(start)?;
+            self.attr_map_key = key;
+        }
+
+        Ok(self.attr_map.get(name).cloned())
+    }
+
+    /// Continue a document with `depth` open elements, see [`PushParser`](super::PushParser).
+    pub(super) fn with_depth(mut self, depth: usize) -> Self {
+        self.starts = depth;
+        self
+    }
+
+    /// Returns the unread input.
+    pub(super) fn remaining(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns true if a read returned an error.
+    pub fn had_error(&self) -> bool {
+        self.had_error
+    }
+
+    /// Returns the number of elements open after the node returned last.
+    ///
+    /// A non-empty start tag increments the depth, its end tag decrements it, so the end tag of
+    /// the root element is returned at depth `0`.
+    pub fn depth(&self) -> usize {
+        if let Some(peeked) = &self.peeked {
+            return peeked.depth;
+        }
+
+        match &self.pending {
+            Some((XmlNode::Start(start), _)) if !start.is_empty => self.starts - 1,
+            Some((XmlNode::End(_), _)) => self.starts + 1,
+            _ => self.starts,
+        }
+    }
+
+    /// Returns the byte offset of the next unread byte in the input.
+    pub fn buffer_position(&self) -> usize {
+        if let Some(peeked) = &self.peeked {
+            return peeked.offset;
+        }
+
+        match &self.pending {
+            Some((_, offset)) => *offset,
+            None => self.source.len() - self.input.len(),
+        }
+    }
+
+    /// Returns the read state, i.e. the part of the document the next node is read from.
+    pub fn state(&self) -> ReadState {
+        if let Some(peeked) = &self.peeked {
+            return peeked.state;
+        }
 
+        // nodes are only read ahead while coalescing text inside elements.
+        if self.pending.is_some() {
+            ReadState::Element
+        } else {
+            self.state
+        }
+    }
+
+    /// Returns the position of the next unread byte.
+    pub fn position(&self) -> Position {
+        self.locate(self.buffer_position())
+    }
+
+    /// Returns the position where the node returned last starts.
+    pub fn node_position(&self) -> Position {
+        self.locate(self.node_start)
+    }
+
+    /// Returns the position of `err`, if it carries one, i.e. was returned by this reader.
+    pub fn error_position(&self, err: &ReadError<I>) -> Option<Position> {
+        err.input().map(|input| self.locate_input(input))
+    }
+
+    /// Returns the position of `input`, a part of the source.
+    fn locate_input(&self, input: &I) -> Position {
+        self.locate(offset_in(self.source.as_bytes(), input.as_bytes()))
+    }
+
+    /// Lines are counted incrementally from the last computed position, so requesting the
+    /// position of each node costs `O(n)` for the whole document.
+    fn locate(&self, offset: usize) -> Position {
+        let cursor = self.cursor.get();
+        let source = self.source.as_bytes();
+
+        let position = if offset >= cursor.offset {
+            cursor.advance(source, offset)
+        } else {
+            Position::locate(source, offset)
+        };
+
+        self.cursor.set(position);
+
+        position
+    }
+
+    /// Returns the utf-8 validation strategy of this reader.
+    pub fn utf8_policy(&self) -> Utf8Policy {
+        self.utf8
+    }
+
+    /// Returns the parse statistics collected so far.
+    #[cfg(feature = "instrument")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "instrument")))]
+    pub fn report(&self) -> &ParseReport {
+        &self.report
+    }
+
+    /// read next xml node.
+    ///
+    /// Errors are terminal: after the first error, this fn returns `Ok(None)` until
+    /// [`reset`](Self::reset) is called, and [`had_error`](Self::had_error) returns true.
     #[inline(always)]
-    fn next(&mut self) -> Option<Self::Item> {
-        match self.read_next() {
-            Ok(v) => v.map(|v| Ok(v)),
-            Err(err) => Some(Err(err)),
+    pub fn read_next(&mut self) -> Result<Option<XmlNode<I>>, ControlFlow<ReadError<I>>> {
+        if let Some(peeked) = self.peeked.take() {
+            self.node_start = peeked.node_start;
+            self.leading_ws = peeked.leading_ws;
+            self.trailing_ws = peeked.trailing_ws;
+
+            return peeked.node;
+        }
+
+        if !(self.trim_text || self.skip_comments || self.coalesce_text || self.attach_ws) {
+            return self.read_raw();
+        }
+
+        let mut node = self.read_filtered();
+
+        if self.attach_ws
+            && matches!(node, Ok(Some(_)))
+            && let Err(err) = self.attach_trailing_ws()
+        {
+            node = Err(err);
+        }
+
+        if node.is_err() {
+            self.had_error = true;
+            self.state = ReadState::Eof;
+        }
+
+        node
+    }
+
+    /// Returns the node the next [`read_next`](Self::read_next) returns, without consuming it.
+    ///
+    /// The node is read and kept until then, so repeated calls return the same node and errors
+    /// are returned again by `read_next`. Accessors describing the node returned last, like
+    /// [`depth`](Self::depth) and [`node_position`](Self::node_position), are not affected.
+    /// A peeked node is discarded by [`skip_subtree`](Self::skip_subtree), which continues from
+    /// the input before it.
+    pub fn peek_next(&mut self) -> Result<Option<&XmlNode<I>>, ControlFlow<ReadError<I>>> {
+        let peeked = match self.peeked.take() {
+            Some(peeked) => peeked,
+            None => self.read_ahead(),
+        };
+
+        let peeked = self.peeked.insert(peeked);
+
+        match &peeked.node {
+            Ok(node) => Ok(node.as_ref()),
+            Err(ControlFlow::Fatal(err)) => Err(ControlFlow::Fatal(err.clone())),
+            Err(ControlFlow::Recovable(err)) => Err(ControlFlow::Recovable(err.clone())),
+            Err(ControlFlow::Incomplete(Needed::Unknown)) => {
+                Err(ControlFlow::Incomplete(Needed::Unknown))
+            }
+            Err(ControlFlow::Incomplete(Needed::Size(size))) => {
+                Err(ControlFlow::Incomplete(Needed::Size(*size)))
+            }
+        }
+    }
+
+    /// Drop the node read by [`peek_next`](Self::peek_next), as if it was never read.
+    fn discard_peeked(&mut self) {
+        let Some(Peeked { rewind, .. }) = self.peeked.take() else {
+            return;
+        };
+
+        let (len, top) = rewind.names;
+
+        self.names.truncate(len);
+
+        if self.names.len() < len
+            && let Some(top) = top
+        {
+            self.names.push(top);
+        }
+
+        self.input = rewind.input;
+        self.starts = rewind.starts;
+        self.state = rewind.state;
+        self.pending = rewind.pending;
+        self.had_error = rewind.had_error;
+    }
+
+    /// Read the next node, keeping the state describing the node returned last.
+    fn read_ahead(&mut self) -> Peeked<I> {
+        let depth = self.depth();
+        let offset = self.buffer_position();
+        let state = self.state();
+
+        let node_start = self.node_start;
+        let leading_ws = self.leading_ws.clone();
+        let trailing_ws = self.trailing_ws.clone();
+
+        let rewind = Rewind {
+            input: self.input.clone(),
+            starts: self.starts,
+            names: (self.names.len(), self.names.last().cloned()),
+            state: self.state,
+            pending: self.pending.clone(),
+            had_error: self.had_error,
+        };
+
+        let node = self.read_next();
+
+        Peeked {
+            node,
+            node_start: std::mem::replace(&mut self.node_start, node_start),
+            leading_ws: std::mem::replace(&mut self.leading_ws, leading_ws),
+            trailing_ws: std::mem::replace(&mut self.trailing_ws, trailing_ws),
+            depth,
+            offset,
+            state,
+            rewind,
         }
     }
+
+    /// Returns the whitespace before the node returned last, see
+    /// [`XmlReaderBuilder::attach_whitespace`].
+    pub fn leading_whitespace(&self) -> Option<&I> {
+        self.leading_ws.as_ref()
+    }
+
+    /// Returns the whitespace between the node returned last and the end of the input, see
+    /// [`XmlReaderBuilder::attach_whitespace`].
+    pub fn trailing_whitespace(&self) -> Option<&I> {
+        self.trailing_ws.as_ref()
+    }
+
+    /// Attach the whitespace up to the end of the document to the node read last.
+    fn attach_trailing_ws(&mut self) -> Result<(), ControlFlow<ReadError<I>>> {
+        self.trailing_ws = None;
+
+        if self.pending.is_some()
+            || self.state != ReadState::MiscAfterElement
+            || self.input.is_empty()
+            || !self.input.as_bytes().iter().all(|c| is_ws(*c))
+        {
+            return Ok(());
+        }
+
+        let node_start = self.node_start;
+
+        if let Some(XmlNode::S(ws)) = self.read_raw()? {
+            self.trailing_ws = Some(ws);
+        }
+
+        self.node_start = node_start;
+
+        Ok(())
+    }
+
+    /// Apply the `trim_text`, `skip_comments`, `coalesce_text` and `attach_whitespace` options.
+    fn read_filtered(&mut self) -> Result<Option<XmlNode<I>>, ControlFlow<ReadError<I>>> {
+        self.leading_ws = None;
+
+        loop {
+            let Some(node) = self.read_raw()? else {
+                return Ok(None);
+            };
+
+            match node {
+                XmlNode::S(ws) if self.attach_ws => self.leading_ws = Some(ws),
+                XmlNode::Comment(_) if self.skip_comments => {}
+                XmlNode::S(_) if self.trim_text => {}
+                XmlNode::CharData(_) | XmlNode::CData(_) if self.coalesce_text => {
+                    let start = self.node_start;
+                    let mut text = String::new();
+
+                    self.push_text(&node, &mut text)?;
+
+                    loop {
+                        match self.read_raw()? {
+                            Some(node @ (XmlNode::CharData(_) | XmlNode::CData(_))) => {
+                                self.push_text(&node, &mut text)?;
+                            }
+                            Some(XmlNode::Comment(_)) if self.skip_comments => {}
+                            Some(node) => {
+                                self.pending = Some((node, self.node_start));
+                                break;
+                            }
+                            None => break,
+                        }
+                    }
+
+                    self.node_start = start;
+
+                    if self.trim_text {
+                        let trimmed = text.trim_matches(|c| matches!(c, ' ' | '\t' | '\r' | '\n'));
+
+                        if trimmed.is_empty() {
+                            continue;
+                        }
+
+                        text = trimmed.to_string();
+                    }
+
+                    return Ok(Some(XmlNode::Text(text)));
+                }
+                XmlNode::CharData(chardata) if self.trim_text => {
+                    let bytes = chardata.0.as_bytes();
+
+                    let Some(lead) = bytes.iter().position(|c| !is_ws(*c)) else {
+                        continue;
+                    };
+
+                    let trail = bytes.iter().rev().take_while(|c| is_ws(**c)).count();
+
+                    let mut text = chardata.0;
+                    text.split_to(lead);
+                    text.split_off(text.len() - trail);
+
+                    return Ok(Some(XmlNode::CharData(CharData(text))));
+                }
+                node => return Ok(Some(node)),
+            }
+        }
+    }
+
+    /// Append the expanded content of a text or cdata `node` to `text`.
+    fn push_text(
+        &self,
+        node: &XmlNode<I>,
+        text: &mut String,
+    ) -> Result<(), ControlFlow<ReadError<I>>> {
+        match node {
+            XmlNode::CharData(chardata) => text.push_str(&self.text(chardata)?),
+            XmlNode::CData(cdata) => text.push_str(&self.cdata(cdata)?),
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Returns the next node as parsed.
+    #[inline(always)]
+    fn read_raw(&mut self) -> Result<Option<XmlNode<I>>, ControlFlow<ReadError<I>>> {
+        if let Some((node, offset)) = self.pending.take() {
+            self.node_start = offset;
+            return Ok(Some(node));
+        }
+
+        if self.had_error {
+            return Ok(None);
+        }
+
+        if self.skip_comments {
+            self.discard_comments();
+        }
+
+        let offset = self.source.len() - self.input.len();
+
+        let node = self.read_next_checked();
+
+        if let Ok(Some(_)) = node {
+            self.node_start = offset;
+        }
+
+        if node.is_err() {
+            self.had_error = true;
+            self.state = ReadState::Eof;
+        }
+
+        node
+    }
+
+    /// Skip the comments at the start of the input by scanning for their end, without
+    /// parsing them.
+    ///
+    /// An unterminated comment is left to the parser, which reports it.
+    fn discard_comments(&mut self) {
+        if !matches!(
+            self.state,
+            ReadState::MiscBeforeDocType
+                | ReadState::MiscBeforeElement
+                | ReadState::Element
+                | ReadState::MiscAfterElement
+        ) {
+            return;
+        }
+
+        while let Some(body) = self.input.as_bytes().strip_prefix(b"<!--") {
+            let Some(end) = memchr::memmem::find(body, b"-->") else {
+                return;
+            };
+
+            self.input.split_to(4 + end + 3);
+        }
+    }
+
+    #[inline(always)]
+    fn read_next_checked(&mut self) -> Result<Option<XmlNode<I>>, ControlFlow<ReadError<I>>> {
+        #[cfg(feature = "instrument")]
+        let (len, start) = (self.input.len(), std::time::Instant::now());
+
+        if self.utf8 == Utf8Policy::ValidateOnce && !self.validated {
+            Self::validate_utf8(self.input.clone())?;
+            self.validated = true;
+        }
+
+        let mut consumed = self.input.clone();
+
+        let node = self.read_next_node()?;
+
+        let token = consumed.split_to(consumed.len() - self.input.len());
+
+        if let Some(node) = &node {
+            self.check_limits(node, &token)?;
+
+            if self.check_names {
+                Self::check_names(node)?;
+            }
+
+            if self.check_chars {
+                self.check_chars(&token)?;
+            }
+
+            if self.strict
+                && let XmlNode::Comment(comment) = node
+            {
+                comment.validate()?;
+            }
+        }
+
+        if self.utf8 == Utf8Policy::ValidateLazy {
+            Self::validate_utf8(token)?;
+        }
+
+        #[cfg(feature = "instrument")]
+        if let Some(node) = &node {
+            self.report.record(
+                ConstructKind::of(node),
+                len - self.input.len(),
+                start.elapsed(),
+            );
+        }
+
+        Ok(node)
+    }
+
+    /// Check `node`, read from `token`, against the [`ReaderLimits`].
+    #[inline(always)]
+    fn check_limits(&self, node: &XmlNode<I>, token: &I) -> Result<(), ControlFlow<ReadError<I>>> {
+        let exceeded = |limit| {
+            Err(ControlFlow::Fatal(ReadError::LimitExceeded(
+                limit,
+                token.clone(),
+            )))
+        };
+
+        if token.len() > self.limits.max_token_len {
+            return exceeded(ReaderLimit::TokenLength);
+        }
+
+        match node {
+            XmlNode::Comment(_) if token.len() > self.limits.max_comment_len => {
+                return exceeded(ReaderLimit::CommentLength);
+            }
+            XmlNode::PI(_) if token.len() > self.limits.max_pi_len => {
+                return exceeded(ReaderLimit::PILength);
+            }
+            _ => {}
+        }
+
+        if let XmlNode::Start(start) = node {
+            // open elements are counted already, empty ones nest one level deeper.
+            let depth = if start.is_empty {
+                self.starts + 1
+            } else {
+                self.starts
+            };
+
+            if depth > self.limits.max_depth {
+                return exceeded(ReaderLimit::Depth);
+            }
+
+            if self.limits.max_attrs != usize::MAX && start.attrs().count() > self.limits.max_attrs
+            {
+                return exceeded(ReaderLimit::Attrs);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check the element, attribute and PI target names of `node`.
+    fn check_names(node: &XmlNode<I>) -> Result<(), ControlFlow<ReadError<I>>> {
+        match node {
+            XmlNode::Start(start) => {
+                Name(start.name.clone()).validate()?;
+
+                // malformed attributes are reported when they are read.
+                for attr in start.attrs().flatten() {
+                    Name(attr.name).validate()?;
+                }
+
+                Ok(())
+            }
+            XmlNode::End(end) => Name(end.name.clone()).validate(),
+            XmlNode::PI(pi) => Name(pi.name.clone()).validate(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that `token` only contains characters allowed by the xml version of the document.
+    fn check_chars(&self, token: &I) -> Result<(), ControlFlow<ReadError<I>>> {
+        let version = self.entities.xml_version();
+
+        // invalid utf-8 is reported by the utf-8 validation.
+        let text = match std::str::from_utf8(token.as_bytes()) {
+            Ok(text) => text,
+            Err(err) => std::str::from_utf8(&token.as_bytes()[..err.valid_up_to()]).unwrap(),
+        };
+
+        for (offset, c) in text.char_indices() {
+            if (' '..='~').contains(&c) {
+                continue;
+            }
+
+            let legal = match version {
+                XmlVersion::Ver10 => is_char(version, c),
+                XmlVersion::Ver11 => is_char(version, c) && !is_restricted_char(c),
+            };
+
+            if !legal {
+                return Err(ControlFlow::Fatal(ReadError::IllegalChar {
+                    codepoint: c as u32,
+                    position: token.clone().split_off(offset).split_to(c.len_utf8()),
+                }));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn validate_utf8(input: I) -> Result<(), ControlFlow<ReadError<I>>> {
+        if let Err(err) = std::str::from_utf8(input.as_bytes()) {
+            return Err(ControlFlow::Fatal(ReadError::InvalidUtf8(
+                input.clone().split_off(err.valid_up_to()),
+            )));
+        }
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn read_next_node(&mut self) -> Result<Option<XmlNode<I>>, ControlFlow<ReadError<I>>> {
+        loop {
+            match self.state {
+                ReadState::XmlDecl => {
+                    if let Some(decl) = self.read_xml_decl()? {
+                        return Ok(Some(decl));
+                    }
+
+                    continue;
+                }
+                ReadState::MiscBeforeDocType => {
+                    if let Some(misc) = self.read_misc()? {
+                        return Ok(Some(misc));
+                    } else {
+                        self.state = ReadState::DocType;
+                        continue;
+                    }
+                }
+                ReadState::DocType => {
+                    if let Some(doctype) = self.read_doctype()? {
+                        self.state = ReadState::MiscBeforeElement;
+                        return Ok(Some(doctype));
+                    } else {
+                        self.state = ReadState::RootElement;
+                        continue;
+                    }
+                }
+                ReadState::MiscBeforeElement => {
+                    if let Some(misc) = self.read_misc()? {
+                        return Ok(Some(misc));
+                    } else {
+                        self.state = ReadState::RootElement;
+                        continue;
+                    }
+                }
+                ReadState::RootElement => {
+                    return self.read_root_el().map(Some);
+                }
+                ReadState::Element => {
+                    if self.fragment && self.starts == 0 && self.input.is_empty() {
+                        self.state = ReadState::Eof;
+                        continue;
+                    }
+
+                    return self.read_el().map(Some);
+                }
+                ReadState::MiscAfterElement => {
+                    if let Some(misc) = self.read_misc()? {
+                        return Ok(Some(misc));
+                    } else {
+                        self.state = ReadState::Eof;
+                        continue;
+                    }
+                }
+                ReadState::Eof => return Ok(None),
+            }
+        }
+    }
+}
+
+/// The iterator is fused: it yields at most one error, then `None` forever.
+impl<I> FusedIterator for XmlReader<I> where I: Input<Item = u8> + AsBytes + Clone + Debug {}
+
+impl<I> Iterator for XmlReader<I>
+where
+    I: Input<Item = u8> + AsBytes + Clone + Debug,
+{
+    type Item = Result<XmlNode<I>, ControlFlow<ReadError<I>>>;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_next() {
+            Ok(v) => v.map(Ok),
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parserc::ControlFlow;
+
+    use crate::{
+        reader::{
+            Position, ReadError, ReadKind, ReaderLimit, ReaderLimits, Unknown, XmlReaderBuilder,
+        },
+        types::Whitespace,
+    };
+
+    use super::{ReadState, XmlNode, XmlReader};
+
+    #[test]
+    fn test_empty_element_depth() {
+        let source = br#"<?xml version="1.0"?><a><b/><c></c></a><!--x-->"#;
+
+        let nodes = XmlReader::from(source.as_slice())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(nodes.len(), 7);
+        assert!(matches!(nodes.last(), Some(XmlNode::Comment(_))));
+    }
+
+    #[test]
+    fn test_peek_next() {
+        let source = br#"<?xml version="1.0"?><a><b/>x<!--c-->"#;
+
+        let mut reader = XmlReader::from(source.as_slice());
+
+        reader.read_next().unwrap();
+        reader.read_next().unwrap();
+
+        let (depth, position) = (reader.depth(), reader.node_position());
+
+        assert!(matches!(
+            reader.peek_next(),
+            Ok(Some(XmlNode::Start(start))) if start.is_empty
+        ));
+        assert!(matches!(reader.peek_next(), Ok(Some(XmlNode::Start(_)))));
+        assert_eq!(reader.depth(), depth);
+        assert_eq!(reader.node_position(), position);
+        assert_eq!(reader.state(), ReadState::Element);
+
+        let Ok(Some(XmlNode::Start(start))) = reader.read_next() else {
+            panic!("expect start tag");
+        };
+
+        assert_eq!(start.name, b"b");
+        assert_eq!(reader.node_position().offset, 24);
+
+        assert!(matches!(reader.peek_next(), Ok(Some(XmlNode::CharData(_)))));
+
+        reader.read_next().unwrap();
+        reader.read_next().unwrap();
+
+        let err = || ControlFlow::Fatal(ReadError::Unclosed(b"a".as_slice()));
+
+        assert_eq!(reader.peek_next(), Err(err()));
+        assert_eq!(reader.peek_next(), Err(err()));
+        assert_eq!(reader.read_next(), Err(err()));
+        assert_eq!(reader.peek_next(), Ok(None));
+        assert_eq!(reader.read_next(), Ok(None));
+    }
+
+    #[test]
+    fn test_terminal_error() {
+        let mut reader = XmlReader::from(br#"<?xml version="1.0"?><a>fish & chips</a>"#.as_slice());
+
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_ok());
+        assert!(!reader.had_error());
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.had_error());
+        assert!(reader.next().is_none());
+        assert_eq!(reader.read_next(), Ok(None));
+
+        reader.reset(br#"<?xml version="1.0"?><a/>"#.as_slice());
+
+        assert!(!reader.had_error());
+        assert_eq!(reader.count(), 2);
+    }
+
+    #[test]
+    fn test_position() {
+        let mut reader = XmlReader::from("<?xml version=\"1.0\"?>\n<a>\n  <b/>&x;</a>".as_bytes());
+
+        let mut positions = vec![];
+
+        while let Ok(Some(_)) = reader.read_next() {
+            let position = reader.node_position();
+            positions.push((position.line, position.column));
+        }
+
+        assert_eq!(
+            positions,
+            [(1, 1), (1, 22), (2, 1), (2, 4), (3, 3), (3, 7), (3, 10)]
+        );
+
+        assert_eq!(reader.position().line, 3);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut reader = XmlReader::from(br#"<?xml version="1.0"?><a><b>"#.as_slice());
+
+        // leave the first document unfinished.
+        for _ in 0..3 {
+            reader.read_next().unwrap();
+        }
+
+        reader.reset(br#"<?xml version="1.1"?><c/>"#.as_slice());
+
+        let nodes = reader.collect::<Result<Vec<_>, _>>().unwrap();
+
+        assert_eq!(nodes.len(), 2);
+        assert!(matches!(&nodes[1], XmlNode::Start(start) if start.name == b"c".as_slice()));
+    }
+
+    #[test]
+    fn test_get_attr() {
+        let mut reader = XmlReaderBuilder::new()
+            .attr_index_threshold(1)
+            .build(br#"<?xml version="1.0"?><a x="1" y="2"><b x="3"/></a>"#.as_slice());
+
+        let mut values = vec![];
+
+        while let Some(node) = reader.read_next().unwrap() {
+            if let XmlNode::Start(start) = node {
+                values.push(reader.get_attr(&start, b"x").unwrap());
+                values.push(reader.get_attr(&start, b"y").unwrap());
+            }
+        }
+
+        assert_eq!(
+            values,
+            vec![
+                Some(b"1".as_slice()),
+                Some(b"2".as_slice()),
+                Some(b"3".as_slice()),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_expand() {
+        let mut reader = XmlReaderBuilder::new()
+            .entity("h", "hardcover")
+            .build(br#"<?xml version="1.0"?><a b="&h;">&h; &amp; more</a>"#.as_slice());
+
+        let mut expanded = vec![];
+
+        while let Some(node) = reader.read_next().unwrap() {
+            match node {
+                XmlNode::Start(start) => {
+                    for attr in start.attrs() {
+                        expanded.push(reader.attr_value(&attr.unwrap()).unwrap().into_owned());
+                    }
+                }
+                XmlNode::CharData(chardata) => {
+                    expanded.push(reader.text(&chardata).unwrap().into_owned());
+                }
+                _ => {}
+            }
+        }
+
+        assert_eq!(expanded, vec!["hardcover", "hardcover & more"]);
+    }
+
+    #[test]
+    fn test_lenient() {
+        let input =
+            br#"<?xml version="1.0"?><!ENTITY h "x"><a><![INCLUDE[ y ]]><!--c--></a>"#.as_slice();
+
+        assert!(
+            XmlReader::from(input)
+                .collect::<Result<Vec<_>, _>>()
+                .is_err()
+        );
+
+        let nodes = XmlReaderBuilder::new()
+            .lenient(true)
+            .build(input)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            nodes[1],
+            XmlNode::Unknown(Unknown(br#"<!ENTITY h "x">"#.as_slice()))
+        );
+        assert_eq!(
+            nodes[3],
+            XmlNode::Unknown(Unknown(b"<![INCLUDE[ y ]]>".as_slice()))
+        );
+        assert!(matches!(nodes[4], XmlNode::Comment(_)));
+        assert_eq!(nodes.len(), 6);
+    }
+
+    #[test]
+    fn test_tag_balance() {
+        let read = |strict: bool, input: &'static str| {
+            XmlReaderBuilder::new()
+                .strict(strict)
+                .build(input.as_bytes())
+                .find_map(Result::err)
+        };
+
+        assert_eq!(read(true, r#"<?xml version="1.0"?><a><b/></a>"#), None);
+
+        assert_eq!(
+            read(true, "<?xml version=\"1.0\"?>\n<a>\n  <b></a></b>"),
+            Some(ControlFlow::Fatal(ReadError::Mismatch {
+                expected: b"b".as_slice(),
+                found: b"a".as_slice(),
+                position: Position {
+                    offset: 33,
+                    line: 3,
+                    column: 8
+                }
+            }))
+        );
+
+        assert_eq!(
+            read(true, r#"<?xml version="1.0"?><a><b>"#),
+            Some(ControlFlow::Fatal(ReadError::Unclosed(b"b".as_slice())))
+        );
+
+        assert_eq!(read(false, r#"<?xml version="1.0"?><a><b></a></b>"#), None);
+    }
+
+    #[test]
+    fn test_fragment() {
+        let names = |input: &'static str| {
+            XmlReader::fragment(input.as_bytes())
+                .map(|node| {
+                    node.map(|node| match node {
+                        XmlNode::Start(start) => start.name,
+                        XmlNode::End(end) => end.name,
+                        XmlNode::CharData(chardata) => chardata.0,
+                        node => panic!("unexpected {:?}", node),
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        assert_eq!(
+            names("<p>a</p> b <br/>"),
+            Ok(vec![
+                b"p".as_slice(),
+                b"a".as_slice(),
+                b"p".as_slice(),
+                b" b ".as_slice(),
+                b"br".as_slice()
+            ])
+        );
+
+        assert_eq!(names(""), Ok(vec![]));
+
+        assert_eq!(
+            names("<p></p></p>"),
+            Err(ControlFlow::Fatal(ReadError::Unexpect(
+                ReadKind::Keyword("</"),
+                b"p".as_slice()
+            )))
+        );
+
+        assert_eq!(
+            names("<p>"),
+            Err(ControlFlow::Fatal(ReadError::Unclosed(b"p".as_slice())))
+        );
+
+        let mut reader = XmlReader::fragment(b"<a/><b/>".as_slice());
+
+        reader.read_next().unwrap();
+        reader.reset(b"<c/>".as_slice());
+
+        assert!(
+            matches!(reader.read_next(), Ok(Some(XmlNode::Start(start))) if start.name == b"c")
+        );
+        assert_eq!(reader.read_next(), Ok(None));
+    }
+
+    #[test]
+    fn test_read_text() {
+        let input = br#"<?xml version="1.0"?><a> x <!--c--> y <b xml:space="preserve"> z  <c xml:space="default">  w </c></b><![CDATA[ v ]]></a>"#;
+
+        let read = |policy| {
+            let mut reader = XmlReader::from(input.as_slice());
+
+            reader.read_next().unwrap();
+
+            let Some(XmlNode::Start(start)) = reader.read_next().unwrap() else {
+                panic!("expect start tag");
+            };
+
+            let text = reader.read_text(&start, policy).unwrap();
+
+            assert_eq!(reader.read_next(), Ok(None));
+
+            text
+        };
+
+        assert_eq!(read(Whitespace::Preserve), " x  y  z    w  v ");
+        assert_eq!(read(Whitespace::Collapse), "x y z w v");
+        assert_eq!(read(Whitespace::XmlSpace), "x y  z   w v");
+    }
+
+    #[test]
+    fn test_filters() {
+        let input = "<?xml version=\"1.0\"?>\n<!--c-->\n<a>\n  <b> x &amp; </b>\n  <c>y<!--c--><![CDATA[ <z> ]]>w</c>\n</a>";
+
+        let events = |builder: XmlReaderBuilder| {
+            builder
+                .build(input.as_bytes())
+                .map(|node| match node.unwrap() {
+                    XmlNode::Start(start) => format!("<{}>", String::from_utf8_lossy(start.name)),
+                    XmlNode::End(_) => "</>".to_string(),
+                    XmlNode::CharData(text) => format!("{:?}", String::from_utf8_lossy(text.0)),
+                    XmlNode::CData(cdata) => format!("[{:?}]", String::from_utf8_lossy(cdata.0)),
+                    XmlNode::Text(text) => format!("text {:?}", text),
+                    XmlNode::Comment(_) => "comment".to_string(),
+                    XmlNode::S(_) => "ws".to_string(),
+                    node => format!("{:?}", node),
+                })
+                .skip(1)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            events(XmlReaderBuilder::new().trim_text(true)),
+            [
+                "comment",
+                "<a>",
+                "<b>",
+                r#""x &amp;""#,
+                "</>",
+                "<c>",
+                r#""y""#,
+                "comment",
+                r#"[" <z> "]"#,
+                r#""w""#,
+                "</>",
+                "</>"
+            ]
+        );
+
+        assert_eq!(
+            events(
+                XmlReaderBuilder::new()
+                    .trim_text(true)
+                    .skip_comments(true)
+                    .coalesce_text(true)
+            ),
+            [
+                "<a>",
+                "<b>",
+                r#"text "x &""#,
+                "</>",
+                "<c>",
+                r#"text "y <z> w""#,
+                "</>",
+                "</>"
+            ]
+        );
+
+        assert_eq!(
+            events(XmlReaderBuilder::new().coalesce_text(true)).len(),
+            events(XmlReaderBuilder::new()).len() - 1
+        );
+    }
+
+    #[test]
+    fn test_misplaced_decl() {
+        let read = |lenient: bool, input: &'static [u8]| {
+            XmlReaderBuilder::new()
+                .lenient(lenient)
+                .build(input)
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        assert_eq!(read(false, b"<a/>").unwrap().len(), 1);
+        assert_eq!(read(false, b"<!--c-->\n<a/>").unwrap().len(), 3);
+
+        assert_eq!(
+            read(false, b"\n <?xml version=\"1.0\"?><a/>"),
+            Err(ControlFlow::Fatal(ReadError::MisplacedDecl(
+                b"<?xml version=\"1.0\"?><a/>".as_slice()
+            )))
+        );
+
+        assert_eq!(
+            read(false, b" \xEF\xBB\xBF<?xml version=\"1.0\"?><a/>"),
+            Err(ControlFlow::Fatal(ReadError::MisplacedBom(
+                b"\xEF\xBB\xBF<?xml version=\"1.0\"?><a/>".as_slice()
+            )))
+        );
+
+        let nodes = read(true, b" <!--c--> \xEF\xBB\xBF<?xml version=\"1.1\"?><a/>").unwrap();
+
+        assert!(matches!(
+            &nodes[..],
+            [XmlNode::XmlDecl(_), XmlNode::Start(_)]
+        ));
+    }
+
+    #[test]
+    fn test_progress() {
+        let source = b"<?xml version=\"1.0\"?><a><b>x<!--c-->y</b><c/></a>";
+
+        let progress = |builder: XmlReaderBuilder| {
+            let mut reader = builder.build(source.as_slice());
+            let mut progress = vec![];
+
+            while reader.read_next().unwrap().is_some() {
+                progress.push((reader.depth(), reader.buffer_position(), reader.state()));
+            }
+
+            progress
+        };
+
+        assert_eq!(
+            progress(XmlReaderBuilder::new()),
+            [
+                (0, 21, ReadState::MiscBeforeDocType),
+                (1, 24, ReadState::Element),
+                (2, 27, ReadState::Element),
+                (2, 28, ReadState::Element),
+                (2, 36, ReadState::Element),
+                (2, 37, ReadState::Element),
+                (1, 41, ReadState::Element),
+                (1, 45, ReadState::Element),
+                (0, 49, ReadState::MiscAfterElement),
+            ]
+        );
+
+        assert_eq!(
+            progress(
+                XmlReaderBuilder::new()
+                    .skip_comments(true)
+                    .coalesce_text(true)
+            )[3..5],
+            [(2, 37, ReadState::Element), (1, 41, ReadState::Element)]
+        );
+    }
+
+    #[test]
+    fn test_peek_then_skip_subtree() {
+        let source = br#"<?xml version="1.0"?><a><b><c/></b>x</a>"#;
+
+        let mut reader = XmlReader::from(source.as_slice());
+
+        reader.read_next().unwrap();
+        reader.read_next().unwrap();
+
+        let Ok(Some(XmlNode::Start(b))) = reader.read_next() else {
+            panic!("expect start tag");
+        };
+
+        assert!(matches!(reader.peek_next(), Ok(Some(XmlNode::Start(_)))));
+        assert_eq!(reader.skip_subtree(&b), Ok(b"<c/>".as_slice()));
+        assert_eq!(reader.depth(), 1);
+        assert_eq!(reader.state(), ReadState::Element);
+        assert_eq!(reader.buffer_position(), 35);
+
+        assert!(matches!(reader.read_next(), Ok(Some(XmlNode::CharData(_)))));
+        assert!(matches!(reader.read_next(), Ok(Some(XmlNode::End(_)))));
+        assert_eq!(reader.read_next(), Ok(None));
+        assert!(!reader.had_error());
+    }
+
+    #[test]
+    fn test_skip_subtree() {
+        let source = br#"<?xml version="1.0"?><a><b x="/>"><!--</b>--><c><![CDATA[</b>]]></c><d/><?pi </b>?></b><e/></a>"#;
+
+        let mut reader = XmlReader::from(source.as_slice());
+
+        assert!(matches!(reader.read_next(), Ok(Some(XmlNode::XmlDecl(_)))));
+        assert!(matches!(reader.read_next(), Ok(Some(XmlNode::Start(_)))));
+
+        let Ok(Some(XmlNode::Start(b))) = reader.read_next() else {
+            panic!("expect start tag");
+        };
+
+        assert_eq!(
+            reader.skip_subtree(&b),
+            Ok(br#"<!--</b>--><c><![CDATA[</b>]]></c><d/><?pi </b>?>"#.as_slice())
+        );
+        assert_eq!(reader.depth(), 1);
+        assert_eq!(reader.node_position().offset, 83);
+
+        let Ok(Some(XmlNode::Start(e))) = reader.read_next() else {
+            panic!("expect start tag");
+        };
+
+        assert_eq!(reader.skip_subtree(&e), Ok(b"".as_slice()));
+        assert!(matches!(reader.read_next(), Ok(Some(XmlNode::End(_)))));
+        assert_eq!(reader.read_next(), Ok(None));
+
+        let mut reader = XmlReader::from(br#"<?xml version="1.0"?><a><b></a>"#.as_slice());
+
+        reader.read_next().unwrap();
+
+        let Ok(Some(XmlNode::Start(a))) = reader.read_next() else {
+            panic!("expect start tag");
+        };
+
+        assert_eq!(
+            reader.skip_subtree(&a),
+            Err(ControlFlow::Fatal(ReadError::Unclosed(b"a".as_slice())))
+        );
+        assert!(reader.had_error());
+
+        // a start tag of another reader.
+        let Ok(Some(XmlNode::Start(b))) = XmlReader::fragment(b"<b>".as_slice()).read_next() else {
+            panic!("expect start tag");
+        };
+
+        assert_eq!(
+            XmlReader::fragment(b"x</b>".as_slice()).skip_subtree(&b),
+            Err(ControlFlow::Fatal(ReadError::Unexpect(
+                ReadKind::Keyword("</"),
+                b"b".as_slice()
+            )))
+        );
+    }
+
+    #[test]
+    fn test_limits() {
+        let limits = ReaderLimits {
+            max_depth: 2,
+            max_attrs: 2,
+            max_token_len: 24,
+            ..ReaderLimits::UNLIMITED
+        };
+
+        let mut reader = XmlReaderBuilder::new()
+            .limits(limits)
+            .build(br#"<?xml version="1.0"?><a><b x="1" y="2"/><b><c/></b></a>"#.as_slice());
+
+        reader.by_ref().take(4).for_each(|node| _ = node.unwrap());
+
+        assert_eq!(
+            reader.read_next(),
+            Err(ControlFlow::Fatal(ReadError::LimitExceeded(
+                ReaderLimit::Depth,
+                b"<c/>".as_slice()
+            )))
+        );
+
+        let mut reader = XmlReaderBuilder::new()
+            .limits(limits)
+            .build(br#"<?xml version="1.0"?><a x="1" y="2" z="3"/>"#.as_slice());
+
+        reader.read_next().unwrap();
+
+        assert_eq!(
+            reader.read_next(),
+            Err(ControlFlow::Fatal(ReadError::LimitExceeded(
+                ReaderLimit::Attrs,
+                br#"<a x="1" y="2" z="3"/>"#.as_slice()
+            )))
+        );
+
+        let mut reader = XmlReaderBuilder::new()
+            .limits(limits)
+            .build(br#"<?xml version="1.0"?><a>0123456789abcdefghijklmno</a>"#.as_slice());
+
+        reader.by_ref().take(2).for_each(|node| _ = node.unwrap());
+
+        assert_eq!(
+            reader.read_next(),
+            Err(ControlFlow::Fatal(ReadError::LimitExceeded(
+                ReaderLimit::TokenLength,
+                b"0123456789abcdefghijklmno".as_slice()
+            )))
+        );
+
+        // each text stays within the text limits, but not all of them together.
+        let mut reader = XmlReaderBuilder::new()
+            .entity("e", "0123456789")
+            .limits(ReaderLimits {
+                max_total_expansion: 25,
+                ..ReaderLimits::UNLIMITED
+            })
+            .build(br#"<?xml version="1.0"?><a><b>&e;</b><b>&e;</b><b>&e;</b></a>"#.as_slice());
+
+        let mut texts = vec![];
+
+        while let Some(node) = reader.read_next().unwrap() {
+            if let XmlNode::CharData(chardata) = node {
+                texts.push(reader.text(&chardata).map(|text| text.len()));
+            }
+        }
+
+        assert_eq!(
+            texts,
+            [
+                Ok(10),
+                Ok(10),
+                Err(ControlFlow::Fatal(ReadError::LimitExceeded(
+                    ReaderLimit::TotalExpansion,
+                    b"&e;".as_slice()
+                )))
+            ]
+        );
+        assert_eq!(reader.entities().expanded(), 30);
+
+        reader.reset(br#"<?xml version="1.0"?><a>&e;</a>"#.as_slice());
+
+        assert_eq!(reader.entities().expanded(), 0);
+    }
+
+    #[test]
+    fn test_comment_limits() {
+        let limits = ReaderLimits {
+            max_comment_len: 12,
+            max_pi_len: 12,
+            ..ReaderLimits::UNLIMITED
+        };
+
+        let source = br#"<?xml version="1.0"?><a><!-- 0123456789 --><?pi 0123456789?></a>"#;
+
+        let mut reader = XmlReaderBuilder::new()
+            .limits(limits)
+            .build(source.as_slice());
+
+        reader.by_ref().take(2).for_each(|node| _ = node.unwrap());
+
+        assert_eq!(
+            reader.read_next(),
+            Err(ControlFlow::Fatal(ReadError::LimitExceeded(
+                ReaderLimit::CommentLength,
+                b"<!-- 0123456789 -->".as_slice()
+            )))
+        );
+
+        let mut reader = XmlReaderBuilder::new()
+            .limits(limits)
+            .skip_comments(true)
+            .build(source.as_slice());
+
+        reader.by_ref().take(2).for_each(|node| _ = node.unwrap());
+
+        assert_eq!(
+            reader.read_next(),
+            Err(ControlFlow::Fatal(ReadError::LimitExceeded(
+                ReaderLimit::PILength,
+                b"<?pi 0123456789?>".as_slice()
+            )))
+        );
+
+        // discarded comments are neither parsed nor capped.
+        let source = format!(
+            r#"<?xml version="1.0"?><!--{}--> <a><!--x--><!--y-->t<!--z</a>"#,
+            "-".repeat(1000)
+        );
+
+        let mut reader = XmlReaderBuilder::new()
+            .limits(limits)
+            .skip_comments(true)
+            .build(source.as_bytes());
+
+        assert!(matches!(reader.read_next(), Ok(Some(XmlNode::XmlDecl(_)))));
+        assert!(matches!(reader.read_next(), Ok(Some(XmlNode::S(_)))));
+        assert!(matches!(reader.read_next(), Ok(Some(XmlNode::Start(_)))));
+        assert!(matches!(reader.read_next(), Ok(Some(XmlNode::CharData(_)))));
+        assert!(reader.read_next().is_err());
+    }
+
+    #[test]
+    fn test_check_names() {
+        let source = br#"<?xml version="1.0"?><a><b 1x="1"/></a>"#;
+
+        assert!(XmlReader::from(source.as_slice()).all(|node| node.is_ok()));
+
+        let mut reader = XmlReaderBuilder::new()
+            .check_names(true)
+            .build(source.as_slice());
+
+        reader.by_ref().take(2).for_each(|node| _ = node.unwrap());
+
+        assert_eq!(
+            reader.read_next(),
+            Err(ControlFlow::Fatal(ReadError::InvalidName(&source[27..28])))
+        );
+    }
+
+    #[test]
+    fn test_check_chars() {
+        let read = |source: &'static [u8]| {
+            XmlReaderBuilder::new()
+                .check_chars(true)
+                .build(source)
+                .find_map(Result::err)
+        };
+
+        let source = b"<?xml version=\"1.0\"?><a>x\x01y</a>";
+
+        assert!(XmlReader::from(source.as_slice()).all(|node| node.is_ok()));
+
+        assert_eq!(
+            read(source),
+            Some(ControlFlow::Fatal(ReadError::IllegalChar {
+                codepoint: 1,
+                position: &source[25..26],
+            }))
+        );
+
+        assert_eq!(read(b"<?xml version=\"1.0\"?><a>\t\xC2\x85&#1;</a>"), None);
+        assert_eq!(read(b"<?xml version=\"1.1\"?><a>\xC2\x85&#1;</a>"), None);
+
+        let source = b"<?xml version=\"1.1\"?><a x=\"\x7F\"/>";
+
+        assert_eq!(
+            read(source),
+            Some(ControlFlow::Fatal(ReadError::IllegalChar {
+                codepoint: 0x7F,
+                position: &source[27..28],
+            }))
+        );
+    }
+
+    #[test]
+    fn test_attach_whitespace() {
+        let source = b"<?xml version=\"1.0\"?>\n<!--c-->\n\n<a> </a>\t\n";
+
+        let mut reader = XmlReaderBuilder::new()
+            .attach_whitespace(true)
+            .build(source.as_slice());
+
+        let mut events = vec![];
+
+        while let Some(node) = reader.read_next().unwrap() {
+            events.push((
+                node,
+                reader.leading_whitespace().copied(),
+                reader.trailing_whitespace().copied(),
+            ));
+        }
+
+        assert!(matches!(events[0], (XmlNode::XmlDecl(_), None, None)));
+        assert!(matches!(
+            events[1],
+            (XmlNode::Comment(_), Some(b"\n"), None)
+        ));
+        assert!(matches!(
+            events[2],
+            (XmlNode::Start(_), Some(b"\n\n"), None)
+        ));
+        assert!(matches!(events[3], (XmlNode::CharData(_), None, None)));
+        assert!(matches!(events[4], (XmlNode::End(_), None, Some(b"\t\n"))));
+        assert_eq!(events.len(), 5);
+        assert_eq!(reader.node_position().line, 4);
+    }
+
+    #[test]
+    fn test_invalid_comment() {
+        let source = br#"<?xml version="1.0"?><a><!-- a - b --><!-- a -- b --></a>"#;
+
+        let mut reader = XmlReader::from(source.as_slice());
+
+        reader.by_ref().take(3).for_each(|node| _ = node.unwrap());
+
+        assert_eq!(
+            reader.read_next(),
+            Err(ControlFlow::Fatal(ReadError::InvalidComment(
+                &source[45..47]
+            )))
+        );
+
+        assert!(
+            XmlReaderBuilder::new()
+                .strict(false)
+                .build(source.as_slice())
+                .all(|node| node.is_ok())
+        );
+
+        let mut reader = XmlReader::from(br#"<?xml version="1.0"?><!-- a ---><a/>"#.as_slice());
+
+        reader.read_next().unwrap();
+
+        assert_eq!(
+            reader.read_next(),
+            Err(ControlFlow::Fatal(ReadError::InvalidComment(
+                b"-".as_slice()
+            )))
+        );
+    }
 }
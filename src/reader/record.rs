@@ -0,0 +1,201 @@
+use std::fmt::Debug;
+
+use parserc::{AsBytes, ControlFlow, Input};
+
+use super::{
+    ReadError, ReadKind, XmlReader,
+    utils::{Markup, find_end_tag, is_ws, scan_markup},
+};
+
+/// Reader of the records of a large repetitive document, e.g. the `<page>`s of a wiki dump or
+/// the `<url>`s of a sitemap.
+///
+/// Yields the raw slice of each element named `name`, from its start tag to its end tag, at any
+/// depth. Between records only the markup delimiters are looked at, so comments and CDATA
+/// sections are skipped but the content is not checked; records nested in a record are part of
+/// it. Read a record with [`next_reader`](Self::next_reader) or [`XmlReader::fragment`].
+///
+/// ```
+/// use rexml::reader::RecordReader;
+///
+/// let sitemap = b"<urlset><url><loc>a</loc></url><!-- <url/> --><url><loc>b</loc></url></urlset>";
+///
+/// let records = RecordReader::new(sitemap.as_slice(), "url")
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+///
+/// assert_eq!(records, [b"<url><loc>a</loc></url>", b"<url><loc>b</loc></url>"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RecordReader<I> {
+    /// the unread input.
+    input: I,
+    name: Vec<u8>,
+    /// true if a read failed, the reader is then exhausted.
+    had_error: bool,
+}
+
+impl<I> RecordReader<I>
+where
+    I: Input<Item = u8> + AsBytes + Clone + Debug,
+{
+    /// Create a reader of the elements named `name` in `input`.
+    pub fn new(input: I, name: &str) -> Self {
+        Self {
+            input,
+            name: name.as_bytes().to_vec(),
+            had_error: false,
+        }
+    }
+
+    /// Returns the next record, `None` at the end of the input.
+    ///
+    /// Fails with [`ReadError::Unclosed`] if a record has no end tag, or with [`ReadError::Expect`]
+    /// if markup before it is not terminated. Errors are terminal, as for [`XmlReader::read_next`].
+    pub fn next_record(&mut self) -> Result<Option<I>, ControlFlow<ReadError<I>>> {
+        if self.had_error {
+            return Ok(None);
+        }
+
+        let result = self.find_record();
+
+        if result.is_err() {
+            self.had_error = true;
+        }
+
+        result
+    }
+
+    /// Returns a [`fragment`](XmlReader::fragment) reader over the next record.
+    pub fn next_reader(&mut self) -> Result<Option<XmlReader<I>>, ControlFlow<ReadError<I>>> {
+        Ok(self.next_record()?.map(XmlReader::fragment))
+    }
+
+    fn find_record(&mut self) -> Result<Option<I>, ControlFlow<ReadError<I>>> {
+        let bytes = self.input.as_bytes();
+        let mut offset = 0;
+
+        let (start, end) = loop {
+            let Some(at) = memchr::memchr(b'<', &bytes[offset..]).map(|len| offset + len) else {
+                self.input.split_to(self.input.len());
+                return Ok(None);
+            };
+
+            let Some((markup, len)) = scan_markup(&bytes[at..]) else {
+                return Err(ControlFlow::Fatal(ReadError::Expect(
+                    ReadKind::Keyword(">"),
+                    self.input.clone().split_off(at),
+                )));
+            };
+
+            offset = at + len;
+
+            let Markup::Start { is_empty } = markup else {
+                continue;
+            };
+
+            if !self.is_record(&bytes[at + 1..]) {
+                continue;
+            }
+
+            if is_empty {
+                break (at, offset);
+            }
+
+            let Some(end_tag) = find_end_tag(&bytes[offset..]).map(|len| offset + len) else {
+                return Err(ControlFlow::Fatal(ReadError::Unclosed(
+                    self.input
+                        .clone()
+                        .split_off(at + 1)
+                        .split_to(self.name.len()),
+                )));
+            };
+
+            // the end tag is terminated, or it would not have been found.
+            let (_, len) = scan_markup(&bytes[end_tag..]).unwrap_or((Markup::End, 0));
+
+            break (at, end_tag + len);
+        };
+
+        let record = self.input.clone().split_off(start).split_to(end - start);
+
+        self.input.split_to(end);
+
+        Ok(Some(record))
+    }
+
+    /// Returns true if the start tag `tag`, without its `<`, has the record name.
+    fn is_record(&self, tag: &[u8]) -> bool {
+        tag.starts_with(&self.name)
+            && tag
+                .get(self.name.len())
+                .is_some_and(|c| is_ws(*c) || matches!(c, b'>' | b'/'))
+    }
+}
+
+impl<I> Iterator for RecordReader<I>
+where
+    I: Input<Item = u8> + AsBytes + Clone + Debug,
+{
+    type Item = Result<I, ControlFlow<ReadError<I>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parserc::ControlFlow;
+
+    use crate::reader::{ReadError, XmlNode};
+
+    use super::RecordReader;
+
+    #[test]
+    fn test_records() {
+        let source = br#"<?xml version="1.0"?>
+<mediawiki>
+  <siteinfo><page-count>2</page-count></siteinfo>
+  <!-- <page>commented</page> -->
+  <page id="1"><title>a</title><text><![CDATA[</page>]]></text></page>
+  <pages/>
+  <page id='2'/>
+  <page><page>nested</page></page>
+</mediawiki>"#;
+
+        let mut reader = RecordReader::new(source.as_slice(), "page");
+
+        assert_eq!(
+            reader.next_record(),
+            Ok(Some(
+                br#"<page id="1"><title>a</title><text><![CDATA[</page>]]></text></page>"#
+                    .as_slice()
+            ))
+        );
+
+        let mut record = reader.next_reader().unwrap().unwrap();
+
+        assert!(matches!(
+            record.read_next(),
+            Ok(Some(XmlNode::Start(start))) if start.is_empty
+        ));
+        assert_eq!(record.read_next(), Ok(None));
+
+        assert_eq!(
+            reader.next_record(),
+            Ok(Some(b"<page><page>nested</page></page>".as_slice()))
+        );
+        assert_eq!(reader.next_record(), Ok(None));
+
+        let mut reader = RecordReader::new(b"<a><page><b></page>".as_slice(), "page");
+
+        assert_eq!(
+            reader.next(),
+            Some(Err(ControlFlow::Fatal(ReadError::Unclosed(
+                b"page".as_slice()
+            ))))
+        );
+        assert_eq!(reader.next(), None);
+    }
+}
@@ -0,0 +1,210 @@
+use std::{collections::VecDeque, fmt::Debug};
+
+use parserc::{AsBytes, ControlFlow, Input};
+
+use super::{ReadError, XmlNode, XmlReader};
+
+pub(super) type NextEvent<I> = Result<Option<XmlNode<I>>, ControlFlow<ReadError<I>>>;
+
+/// A pull-based source of xml events.
+///
+/// Deserializers and transformers written against this trait accept [`XmlReader`],
+/// [`EventBuffer`], [`Filter`] or any other source interchangeably.
+pub trait EventSource {
+    /// The input type events borrow from.
+    type Input: Debug;
+
+    /// Returns the next event, or `None` at the end of the stream.
+    fn next_event(&mut self) -> NextEvent<Self::Input>;
+
+    /// Create a source that only yields events matching `predicate`.
+    fn filter_events<F>(self, predicate: F) -> Filter<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(&XmlNode<Self::Input>) -> bool,
+    {
+        Filter {
+            source: self,
+            predicate,
+        }
+    }
+
+    /// Convert this source into an [`Iterator`] of events.
+    fn into_events(self) -> Events<Self>
+    where
+        Self: Sized,
+    {
+        Events(self)
+    }
+}
+
+impl<S> EventSource for &mut S
+where
+    S: EventSource + ?Sized,
+{
+    type Input = S::Input;
+
+    #[inline(always)]
+    fn next_event(&mut self) -> NextEvent<Self::Input> {
+        (**self).next_event()
+    }
+}
+
+impl<I> EventSource for XmlReader<I>
+where
+    I: Input<Item = u8> + AsBytes + Clone + Debug,
+{
+    type Input = I;
+
+    #[inline(always)]
+    fn next_event(&mut self) -> NextEvent<I> {
+        self.read_next()
+    }
+}
+
+/// An in-memory, replayable sequence of events.
+#[derive(Debug, PartialEq, Clone)]
+pub struct EventBuffer<I>(VecDeque<XmlNode<I>>);
+
+impl<I> Default for EventBuffer<I> {
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
+
+impl<I> EventBuffer<I> {
+    /// Create an empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append an event to the end of this buffer.
+    pub fn push(&mut self, node: XmlNode<I>) {
+        self.0.push_back(node);
+    }
+
+    /// Returns the number of buffered events.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if no events are buffered.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Drain all events of `source` into a new buffer.
+    pub fn read_from<S>(mut source: S) -> Result<Self, ControlFlow<ReadError<I>>>
+    where
+        S: EventSource<Input = I>,
+        I: Debug,
+    {
+        let mut buffer = Self::new();
+
+        while let Some(node) = source.next_event()? {
+            buffer.push(node);
+        }
+
+        Ok(buffer)
+    }
+}
+
+impl<I> FromIterator<XmlNode<I>> for EventBuffer<I> {
+    fn from_iter<T: IntoIterator<Item = XmlNode<I>>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl<I> EventSource for EventBuffer<I>
+where
+    I: Debug,
+{
+    type Input = I;
+
+    #[inline(always)]
+    fn next_event(&mut self) -> NextEvent<I> {
+        Ok(self.0.pop_front())
+    }
+}
+
+/// A source adapter created by [`EventSource::filter_events`].
+pub struct Filter<S, F> {
+    source: S,
+    predicate: F,
+}
+
+impl<S, F> EventSource for Filter<S, F>
+where
+    S: EventSource,
+    F: FnMut(&XmlNode<S::Input>) -> bool,
+{
+    type Input = S::Input;
+
+    #[inline(always)]
+    fn next_event(&mut self) -> NextEvent<Self::Input> {
+        while let Some(node) = self.source.next_event()? {
+            if (self.predicate)(&node) {
+                return Ok(Some(node));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// An iterator adapter created by [`EventSource::into_events`].
+pub struct Events<S>(S);
+
+impl<S> Iterator for Events<S>
+where
+    S: EventSource,
+{
+    type Item = Result<XmlNode<S::Input>, ControlFlow<ReadError<S::Input>>>;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_event().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reader::{EventBuffer, EventSource, XmlNode, XmlReader};
+
+    fn count_starts<S: EventSource>(mut source: S) -> usize {
+        let mut count = 0;
+
+        while let Some(node) = source.next_event().unwrap() {
+            if matches!(node, XmlNode::Start(_)) {
+                count += 1;
+            }
+        }
+
+        count
+    }
+
+    #[test]
+    fn test_sources() {
+        let input = br#"<?xml version="1.0"?><a><b/>c<d></d></a>"#.as_slice();
+
+        assert_eq!(count_starts(XmlReader::from(input)), 3);
+
+        let buffer = EventBuffer::read_from(XmlReader::from(input)).unwrap();
+
+        assert_eq!(buffer.len(), 7);
+        assert_eq!(count_starts(buffer.clone()), 3);
+
+        let texts = buffer
+            .filter_events(|node| matches!(node, XmlNode::CharData(_)))
+            .into_events()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(texts.len(), 1);
+
+        let mut reader = XmlReader::from(input);
+
+        assert_eq!(count_starts(&mut reader), 3);
+        assert_eq!(reader.next_event().unwrap(), None);
+    }
+}
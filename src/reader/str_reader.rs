@@ -0,0 +1,231 @@
+use std::borrow::Cow;
+
+use parserc::ControlFlow;
+
+use crate::types::XmlVersion;
+
+use super::{
+    ElemStart, ReadError, Utf8Policy, XmlNode, XmlReader, XmlReaderBuilder, position::offset_in,
+};
+
+/// Error type of [`XmlStrReader`], positions are slices of the source bytes.
+pub type StrReadError<'a> = ControlFlow<ReadError<&'a [u8]>>;
+
+/// Xml node type returned by [`XmlStrReader`].
+#[derive(Debug, PartialEq, Clone)]
+pub enum StrNode<'a> {
+    XmlDecl {
+        version: XmlVersion,
+        encoding: Option<&'a str>,
+        standalone: Option<bool>,
+    },
+    /// raw content of the doctype declaration.
+    DocType(&'a str),
+    PI {
+        target: &'a str,
+        data: &'a str,
+    },
+    /// Whitespace.
+    S(&'a str),
+    Comment(&'a str),
+    Start(StrStart<'a>),
+    End(&'a str),
+    /// Text with references expanded, borrowed if it contains none.
+    Text(Cow<'a, str>),
+    CData(&'a str),
+    /// Unclassified markup, only yielded in lenient mode.
+    Unknown(&'a str),
+}
+
+/// A start tag returned by [`XmlStrReader`].
+#[derive(Debug, PartialEq, Clone)]
+pub struct StrStart<'a> {
+    pub name: &'a str,
+    pub is_empty: bool,
+    start: ElemStart<&'a [u8]>,
+    source: &'a str,
+}
+
+/// An attribute returned by [`StrStart::attrs`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct StrAttr<'a> {
+    pub name: &'a str,
+    /// the value as written, expand it with [`XmlStrReader::attr_value`].
+    pub raw_value: &'a str,
+}
+
+impl<'a> StrStart<'a> {
+    /// Create an iterator over the attributes.
+    pub fn attrs(&self) -> impl Iterator<Item = Result<StrAttr<'a>, StrReadError<'a>>> + 'a {
+        let source = self.source;
+
+        self.start.attrs().map(move |attr| {
+            let attr = attr?;
+
+            Ok(StrAttr {
+                name: slice(source, attr.name),
+                raw_value: slice(source, attr.value),
+            })
+        })
+    }
+
+    /// Returns the underlying byte start tag.
+    pub fn as_bytes(&self) -> &ElemStart<&'a [u8]> {
+        &self.start
+    }
+}
+
+/// Returns the `&str` of `source` at the position of `bytes`, a slice of it.
+///
+/// The reader splits input at ascii delimiters only, so its slices are on char boundaries of a
+/// `&str` source and no utf-8 validation is needed.
+fn slice<'a>(source: &'a str, bytes: &'a [u8]) -> &'a str {
+    let start = offset_in(source.as_bytes(), bytes);
+
+    &source[start..start + bytes.len()]
+}
+
+/// A [`XmlReader`] over a `&str` that yields `&str` names, values and text.
+///
+/// The input is valid utf-8 by type, so events are zero-copy views into it; only text with
+/// references is copied when expanded.
+pub struct XmlStrReader<'a> {
+    reader: XmlReader<&'a [u8]>,
+    source: &'a str,
+}
+
+impl<'a> From<&'a str> for XmlStrReader<'a> {
+    fn from(source: &'a str) -> Self {
+        Self::with_builder(XmlReaderBuilder::new(), source)
+    }
+}
+
+impl<'a> XmlStrReader<'a> {
+    /// Create a reader over `source` with the options of `builder`, its [`Utf8Policy`] is ignored.
+    pub fn with_builder(builder: XmlReaderBuilder, source: &'a str) -> Self {
+        Self {
+            reader: builder.utf8(Utf8Policy::Trusted).build(source.as_bytes()),
+            source,
+        }
+    }
+
+    /// Returns the underlying byte reader, e.g. for positions.
+    pub fn inner(&self) -> &XmlReader<&'a [u8]> {
+        &self.reader
+    }
+
+    /// Returns the expanded value of an attribute.
+    pub fn attr_value(&self, attr: &StrAttr<'a>) -> Result<Cow<'a, str>, StrReadError<'a>> {
+        self.expand_attr(attr.raw_value)
+    }
+
+    fn expand_attr(&self, raw: &'a str) -> Result<Cow<'a, str>, StrReadError<'a>> {
+        Ok(match self.reader.entities().expand_attr(&raw.as_bytes())? {
+            Cow::Borrowed(_) => Cow::Borrowed(raw),
+            Cow::Owned(value) => Cow::Owned(value),
+        })
+    }
+
+    fn expand_text(&self, raw: &'a str) -> Result<Cow<'a, str>, StrReadError<'a>> {
+        Ok(match self.reader.entities().expand(&raw.as_bytes())? {
+            Cow::Borrowed(_) => Cow::Borrowed(raw),
+            Cow::Owned(value) => Cow::Owned(value),
+        })
+    }
+
+    /// Read next xml node, see [`XmlReader::read_next`].
+    pub fn read_next(&mut self) -> Result<Option<StrNode<'a>>, StrReadError<'a>> {
+        let Some(node) = self.reader.read_next()? else {
+            return Ok(None);
+        };
+
+        let source = self.source;
+        let str = |bytes| slice(source, bytes);
+
+        let node = match node {
+            XmlNode::XmlDecl(decl) => StrNode::XmlDecl {
+                version: decl.version,
+                encoding: decl.encoding.map(str),
+                standalone: decl.standalone,
+            },
+            XmlNode::DocType(doctype) => StrNode::DocType(str(doctype.0)),
+            XmlNode::PI(pi) => StrNode::PI {
+                target: str(pi.name),
+                data: str(pi.unparsed),
+            },
+            XmlNode::S(s) => StrNode::S(str(s)),
+            XmlNode::Comment(comment) => StrNode::Comment(str(comment.0)),
+            XmlNode::Start(start) => StrNode::Start(StrStart {
+                name: str(start.name),
+                is_empty: start.is_empty,
+                start,
+                source,
+            }),
+            XmlNode::End(end) => StrNode::End(str(end.name)),
+            XmlNode::CharData(chardata) => StrNode::Text(self.expand_text(str(chardata.0))?),
+            XmlNode::CData(cdata) => StrNode::CData(str(cdata.0)),
+            XmlNode::Unknown(unknown) => StrNode::Unknown(str(unknown.0)),
+            XmlNode::Text(text) => StrNode::Text(Cow::Owned(text)),
+        };
+
+        Ok(Some(node))
+    }
+}
+
+impl<'a> Iterator for XmlStrReader<'a> {
+    type Item = Result<StrNode<'a>, StrReadError<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_next().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::{StrNode, XmlStrReader};
+
+    #[test]
+    fn test_str_reader() {
+        let mut reader = XmlStrReader::from(
+            r#"<?xml version="1.0" encoding="UTF-8"?><prix devise="€ &amp; $">déjà &lt;vu&gt;</prix>"#,
+        );
+
+        assert!(matches!(
+            reader.read_next(),
+            Ok(Some(StrNode::XmlDecl {
+                encoding: Some("UTF-8"),
+                ..
+            }))
+        ));
+
+        let Ok(Some(StrNode::Start(start))) = reader.read_next() else {
+            panic!("expect start tag");
+        };
+
+        assert_eq!(start.name, "prix");
+
+        let attr = start.attrs().next().unwrap().unwrap();
+
+        assert_eq!((attr.name, attr.raw_value), ("devise", "€ &amp; $"));
+        assert_eq!(reader.attr_value(&attr).unwrap(), "€ & $");
+
+        assert_eq!(
+            reader.read_next(),
+            Ok(Some(StrNode::Text(Cow::Owned("déjà <vu>".to_string()))))
+        );
+
+        assert_eq!(reader.read_next(), Ok(Some(StrNode::End("prix"))));
+        assert_eq!(reader.read_next(), Ok(None));
+
+        let texts = XmlStrReader::from(r#"<?xml version="1.0"?><a>plain</a>"#)
+            .filter_map(|node| match node.unwrap() {
+                StrNode::Text(text) => Some(text),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(texts, [Cow::Borrowed("plain")]);
+    }
+}
@@ -1,15 +1,15 @@
 use std::fmt::Debug;
 
-use parserc::{ControlFlow, Input, Parser, ParserExt, next, take_till, take_while};
+use parserc::{AsBytes, ControlFlow, Input, Parser, ParserExt, next, take_till, take_while};
 
-use crate::reader::ReadKind;
+use crate::{chars::is_name, reader::ReadKind};
 
 use super::ReadError;
 
 /// Check if the `c` is whitespace.
 #[inline(always)]
 pub(super) fn is_ws(c: u8) -> bool {
-    matches!(c, b'\x20' | b'\x09' | b'\x0d' | b'\x0a')
+    crate::chars::is_ws(c)
 }
 
 #[inline(always)]
@@ -66,29 +66,204 @@ where
 #[inline(always)]
 pub fn parse_quote<I>(input: I) -> parserc::Result<I, I, ReadError<I>>
 where
-    I: Input<Item = u8> + Debug + Clone,
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
 {
     let (double_quote, input) = next(b'"')
         .map(|_| true)
         .or(next(b'\'').map(|_| false))
-        .parse(input)?;
+        .map_err(|_: ReadError<I>| ReadError::Expect(ReadKind::Quote, input.clone()))
+        .parse(input.clone())?;
+
+    let end = if double_quote { b'"' } else { b'\'' };
+
+    let start = input.clone();
+
+    let (content, mut input) = take_till(|c: u8| c == end || c == b'<').parse(input)?;
+
+    if input.as_bytes().first() != Some(&end) {
+        if input.as_bytes().contains(&end) {
+            return Err(ControlFlow::Fatal(ReadError::Unexpect(ReadKind::Lt, input)));
+        }
+
+        // the closing quote is missing, point at where it probably belongs.
+        let at = start
+            .as_bytes()
+            .iter()
+            .position(|c| is_ws(*c) || matches!(c, b'/' | b'>'))
+            .unwrap_or(content.len());
+
+        return Err(ControlFlow::Fatal(ReadError::Expect(
+            ReadKind::Quote,
+            start.clone().split_off(at),
+        )));
+    }
+
+    if let Some(at) = find_bad_ref(content.as_bytes()) {
+        return Err(ControlFlow::Fatal(ReadError::Expect(
+            ReadKind::Reference,
+            start.clone().split_off(at),
+        )));
+    }
+
+    input.split_to(1);
+
+    Ok((content, input))
+}
+
+/// Parse a quoted literal without checking its content.
+///
+/// See [`SystemLiteral`](https://www.w3.org/TR/xml11/#NT-SystemLiteral) and [`EntityValue`](https://www.w3.org/TR/xml11/#NT-EntityValue).
+#[inline(always)]
+pub fn parse_literal<I>(input: I) -> parserc::Result<I, I, ReadError<I>>
+where
+    I: Input<Item = u8> + AsBytes + Debug + Clone,
+{
+    let (double_quote, input) = next(b'"')
+        .map(|_| true)
+        .or(next(b'\'').map(|_| false))
+        .map_err(|_: ReadError<I>| ReadError::Expect(ReadKind::Quote, input.clone()))
+        .parse(input.clone())?;
 
     let end = if double_quote { b'"' } else { b'\'' };
 
     let (content, mut input) = take_till(|c: u8| c == end).parse(input)?;
 
+    if input.is_empty() {
+        return Err(ControlFlow::Fatal(ReadError::Expect(
+            ReadKind::Quote,
+            input,
+        )));
+    }
+
     input.split_to(1);
 
     Ok((content, input))
 }
 
+/// Returns the offset of the first `&` in `content` that doesn't start a
+/// [`Reference`](https://www.w3.org/TR/xml11/#NT-Reference).
+pub(super) fn find_bad_ref(content: &[u8]) -> Option<usize> {
+    let mut offset = 0;
+
+    while let Some(pos) = memchr::memchr(b'&', &content[offset..]) {
+        let start = offset + pos;
+
+        let Some(end) = memchr::memchr(b';', &content[start + 1..]) else {
+            return Some(start);
+        };
+
+        let body = &content[start + 1..start + 1 + end];
+
+        let valid = if let Some(hex) = body.strip_prefix(b"#x") {
+            !hex.is_empty() && hex.iter().all(u8::is_ascii_hexdigit)
+        } else if let Some(digits) = body.strip_prefix(b"#") {
+            !digits.is_empty() && digits.iter().all(u8::is_ascii_digit)
+        } else {
+            std::str::from_utf8(body).is_ok_and(is_name)
+        };
+
+        if !valid {
+            return Some(start);
+        }
+
+        offset = start + end + 2;
+    }
+
+    None
+}
+
+/// Markup classified by [`scan_markup`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(super) enum Markup {
+    Start {
+        is_empty: bool,
+    },
+    End,
+    /// Comments, CDATA sections, PIs and declarations.
+    Other,
+}
+
+/// Classify the markup starting with the `<` at the start of `markup`, returning its length,
+/// or `None` if it is not terminated.
+///
+/// Only the delimiters are looked at, e.g. a start tag ends at the first `>` outside quotes.
+pub(super) fn scan_markup(markup: &[u8]) -> Option<(Markup, usize)> {
+    let find = |offset: usize, needle: &[u8]| {
+        memchr::memmem::find(&markup[offset..], needle).map(|len| offset + len + needle.len())
+    };
+
+    if markup.starts_with(b"</") {
+        return find(2, b">").map(|len| (Markup::End, len));
+    }
+
+    let other = if markup.starts_with(b"<!--") {
+        find(4, b"-->")
+    } else if markup.starts_with(b"<![CDATA[") {
+        find(9, b"]]>")
+    } else if markup.starts_with(b"<?") {
+        find(2, b"?>")
+    } else if markup.starts_with(b"<!") {
+        find(2, b">")
+    } else {
+        let mut quote = None;
+
+        let len = markup.iter().position(|c| match quote {
+            Some(q) => {
+                if *c == q {
+                    quote = None;
+                }
+
+                false
+            }
+            None if matches!(c, b'"' | b'\'') => {
+                quote = Some(*c);
+                false
+            }
+            None => *c == b'>',
+        })?;
+
+        let is_empty = markup[len - 1] == b'/';
+
+        return Some((Markup::Start { is_empty }, len + 1));
+    };
+
+    other.map(|len| (Markup::Other, len))
+}
+
+/// Returns the offset of the end tag closing the element whose content starts `content`, or
+/// `None` if it is not closed.
+pub(super) fn find_end_tag(content: &[u8]) -> Option<usize> {
+    let mut depth = 1;
+    let mut offset = 0;
+
+    loop {
+        let at = offset + memchr::memchr(b'<', &content[offset..])?;
+
+        let (markup, len) = scan_markup(&content[at..])?;
+
+        match markup {
+            Markup::End => {
+                depth -= 1;
+
+                if depth == 0 {
+                    return Some(at);
+                }
+            }
+            Markup::Start { is_empty: false } => depth += 1,
+            _ => {}
+        }
+
+        offset = at + len;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use parserc::ControlFlow;
 
     use crate::reader::{ReadError, ReadKind, parse_quote};
 
-    use super::parse_eq;
+    use super::{Markup, find_bad_ref, find_end_tag, parse_eq, parse_literal, scan_markup};
 
     #[test]
     fn test_parse_eq() {
@@ -117,4 +292,78 @@ mod tests {
             Ok((b"hello world".as_slice(), b"".as_slice()))
         );
     }
+
+    #[test]
+    fn test_quote_errors() {
+        assert_eq!(
+            parse_quote(b"hello/>".as_slice()),
+            Err(ControlFlow::Recovable(ReadError::Expect(
+                ReadKind::Quote,
+                b"hello/>".as_slice()
+            )))
+        );
+        assert_eq!(
+            parse_quote(br#""a<b" />"#.as_slice()),
+            Err(ControlFlow::Fatal(ReadError::Unexpect(
+                ReadKind::Lt,
+                br#"<b" />"#.as_slice()
+            )))
+        );
+        assert_eq!(
+            parse_quote(br#""hello><b/>"#.as_slice()),
+            Err(ControlFlow::Fatal(ReadError::Expect(
+                ReadKind::Quote,
+                b"><b/>".as_slice()
+            )))
+        );
+        assert_eq!(
+            parse_quote(br#""a & b""#.as_slice()),
+            Err(ControlFlow::Fatal(ReadError::Expect(
+                ReadKind::Reference,
+                br#"& b""#.as_slice()
+            )))
+        );
+    }
+
+    #[test]
+    fn test_literal() {
+        assert_eq!(
+            parse_literal(br#""<b>&x</b>" >"#.as_slice()),
+            Ok((b"<b>&x</b>".as_slice(), b" >".as_slice()))
+        );
+        assert_eq!(
+            parse_literal(br#"'abc"#.as_slice()),
+            Err(ControlFlow::Fatal(ReadError::Expect(
+                ReadKind::Quote,
+                b"".as_slice()
+            )))
+        );
+    }
+
+    #[test]
+    fn test_find_bad_ref() {
+        assert_eq!(find_bad_ref(b"&lt;&#60;&#x3c;&svg:a;"), None);
+        assert_eq!(find_bad_ref(b"a & b;"), Some(2));
+        assert_eq!(find_bad_ref(b"&lt;&"), Some(4));
+        assert_eq!(find_bad_ref(b"&#x;"), Some(0));
+        assert_eq!(find_bad_ref(b"&#12a;"), Some(0));
+    }
+
+    #[test]
+    fn test_scan_markup() {
+        assert_eq!(
+            scan_markup(br#"<a x="/>">"#),
+            Some((Markup::Start { is_empty: false }, 10))
+        );
+        assert_eq!(
+            scan_markup(b"<a/>b"),
+            Some((Markup::Start { is_empty: true }, 4))
+        );
+        assert_eq!(scan_markup(b"</a >"), Some((Markup::End, 5)));
+        assert_eq!(scan_markup(b"<!-- > -->"), Some((Markup::Other, 10)));
+        assert_eq!(scan_markup(b"<![CDATA[x"), None);
+
+        assert_eq!(find_end_tag(b"<b><!--</a>--></b><c/></a>"), Some(22));
+        assert_eq!(find_end_tag(b"<b></a>"), None);
+    }
 }
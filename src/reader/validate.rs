@@ -0,0 +1,172 @@
+use parserc::{AsBytes, ControlFlow, Input};
+
+use super::{EventSource, NsReader, ReadError, XmlNode, ns::lookup_in};
+
+/// A problem reported by a [`Rule`].
+#[derive(Debug, PartialEq, Clone)]
+pub struct Violation<I> {
+    /// name of the rule reporting this violation.
+    pub rule: &'static str,
+    /// human readable description.
+    pub message: String,
+    /// the input slice the violation refers to, e.g. an element or attribute name.
+    pub at: I,
+}
+
+/// The document context of the event passed to [`Rule::check`].
+pub struct RuleContext<'a, I> {
+    path: &'a [I],
+    bindings: &'a [(Option<I>, I)],
+}
+
+impl<I> RuleContext<'_, I>
+where
+    I: AsBytes,
+{
+    /// Returns the number of elements enclosing the event.
+    pub fn depth(&self) -> usize {
+        self.path.len()
+    }
+
+    /// Returns the names of the elements enclosing the event, outermost first.
+    ///
+    /// The element of a start or end tag event is not part of its own path.
+    pub fn path(&self) -> &[I] {
+        self.path
+    }
+
+    /// Returns the namespace bound to `prefix` in scope of the event, see [`NsReader::lookup`].
+    pub fn lookup(&self, prefix: Option<&[u8]>) -> Option<&[u8]> {
+        lookup_in(self.bindings, prefix)
+    }
+}
+
+/// A read-only check run by [`validate_events`] on every event of a document.
+pub trait Rule<I> {
+    /// Returns the name reported in [`Violation::rule`].
+    fn name(&self) -> &'static str;
+
+    /// Check `node`, pushing any problem found to `violations`.
+    fn check(
+        &self,
+        node: &XmlNode<I>,
+        context: &RuleContext<'_, I>,
+        violations: &mut Vec<Violation<I>>,
+    );
+}
+
+/// Result of [`validate_events`], fails only if the document can't be read.
+pub type Validation<I> = Result<Vec<Violation<I>>, ControlFlow<ReadError<I>>>;
+
+/// Run `rules` over all events of `source`, returning the violations in document order.
+///
+/// The document is walked once, however many rules are checked. Namespace declarations
+/// are tracked by a [`NsReader`], so rules can resolve prefixes through [`RuleContext::lookup`].
+pub fn validate_events<S>(source: S, rules: &[&dyn Rule<S::Input>]) -> Validation<S::Input>
+where
+    S: EventSource,
+    S::Input: Input<Item = u8> + AsBytes + Clone,
+{
+    let mut reader = NsReader::new(source);
+    let mut path = vec![];
+    let mut violations = vec![];
+
+    while let Some(node) = reader.next_event()? {
+        if let XmlNode::End(_) = node {
+            path.pop();
+        }
+
+        let context = RuleContext {
+            path: &path,
+            bindings: reader.bindings(),
+        };
+
+        for rule in rules {
+            rule.check(&node, &context, &mut violations);
+        }
+
+        if let XmlNode::Start(start) = node
+            && !start.is_empty
+        {
+            path.push(start.name);
+        }
+    }
+
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reader::{XmlNode, XmlReader};
+
+    use super::{Rule, RuleContext, Violation, validate_events};
+
+    struct MaxDepth(usize);
+
+    impl<'a> Rule<&'a [u8]> for MaxDepth {
+        fn name(&self) -> &'static str {
+            "max-depth"
+        }
+
+        fn check(
+            &self,
+            node: &XmlNode<&'a [u8]>,
+            context: &RuleContext<'_, &'a [u8]>,
+            violations: &mut Vec<Violation<&'a [u8]>>,
+        ) {
+            if let XmlNode::Start(start) = node
+                && context.depth() >= self.0
+            {
+                violations.push(Violation {
+                    rule: self.name(),
+                    message: format!("nested in {} elements", context.depth()),
+                    at: start.name,
+                });
+            }
+        }
+    }
+
+    struct SvgOnly;
+
+    impl<'a> Rule<&'a [u8]> for SvgOnly {
+        fn name(&self) -> &'static str {
+            "svg-only"
+        }
+
+        fn check(
+            &self,
+            node: &XmlNode<&'a [u8]>,
+            context: &RuleContext<'_, &'a [u8]>,
+            violations: &mut Vec<Violation<&'a [u8]>>,
+        ) {
+            if let XmlNode::Start(start) = node
+                && context.lookup(None) != Some(b"http://www.w3.org/2000/svg")
+            {
+                violations.push(Violation {
+                    rule: self.name(),
+                    message: "element outside the svg namespace".to_string(),
+                    at: start.name,
+                });
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_events() {
+        let input = br#"<?xml version="1.0"?><svg xmlns="http://www.w3.org/2000/svg"><g><g><rect/></g></g><a xmlns=""/></svg>"#;
+
+        let violations =
+            validate_events(XmlReader::from(input.as_slice()), &[&MaxDepth(3), &SvgOnly]).unwrap();
+
+        assert_eq!(
+            violations
+                .iter()
+                .map(|violation| (violation.rule, violation.at))
+                .collect::<Vec<_>>(),
+            [
+                ("max-depth", b"rect".as_slice()),
+                ("svg-only", b"a".as_slice())
+            ]
+        );
+    }
+}
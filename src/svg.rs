@@ -0,0 +1,635 @@
+//! Tokenizers of SVG attribute microsyntaxes.
+//!
+//! [`PathData`], [`Points`] and [`Transforms`] iterate over the path `d`, `points` and
+//! `transform` attribute values as read, e.g. the `value` of a [`reader`](crate::reader)
+//! attribute. They don't expand references, values containing `&` must be expanded first.
+//!
+//! ```
+//! use rexml::svg::{PathData, PathSegment};
+//!
+//! let segments = PathData::new(b"M10-20l5.5.5z")
+//!     .collect::<Result<Vec<_>, _>>()
+//!     .unwrap();
+//!
+//! assert_eq!(
+//!     segments,
+//!     [
+//!         PathSegment::MoveTo { abs: true, x: 10.0, y: -20.0 },
+//!         PathSegment::LineTo { abs: false, x: 5.5, y: 0.5 },
+//!         PathSegment::ClosePath { abs: false },
+//!     ]
+//! );
+//! ```
+
+use crate::chars::is_ws;
+
+/// Error returned by the tokenizers of this module, with the byte offset of the failure.
+#[derive(Debug, thiserror::Error, PartialEq, Eq, Clone, Copy)]
+pub enum SvgError {
+    /// A number or an arc flag is missing or malformed.
+    #[error("expect number at offset {0}")]
+    ExpectNumber(usize),
+    /// The path data starts with another command than `M`/`m`, or contains an unknown command.
+    #[error("invalid path command at offset {0}")]
+    InvalidCommand(usize),
+    /// The transform list contains an unknown transform, a wrong number of arguments or a
+    /// missing parenthesis.
+    #[error("invalid transform at offset {0}")]
+    InvalidTransform(usize),
+}
+
+type Result<T> = std::result::Result<T, SvgError>;
+
+/// Cursor over a microsyntax value.
+#[derive(Debug, Clone)]
+struct Lexer<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).copied()
+    }
+
+    fn is_eof(&self) -> bool {
+        self.pos == self.data.len()
+    }
+
+    fn skip_ws(&mut self) {
+        while self.peek().is_some_and(is_ws) {
+            self.pos += 1;
+        }
+    }
+
+    /// Skip whitespace with at most one comma, the separator of numbers.
+    fn skip_comma_ws(&mut self) {
+        self.skip_ws();
+
+        if self.peek() == Some(b',') {
+            self.pos += 1;
+            self.skip_ws();
+        }
+    }
+
+    /// Returns true if a number starts at the cursor.
+    fn at_number(&self) -> bool {
+        self.peek()
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, b'+' | b'-' | b'.'))
+    }
+
+    /// Read a [`number`](https://www.w3.org/TR/SVG2/paths.html#PathDataBNF) and the separator after it.
+    fn number(&mut self) -> Result<f64> {
+        let start = self.pos;
+        let digits = |lexer: &mut Self| {
+            let from = lexer.pos;
+
+            while lexer.peek().is_some_and(|c| c.is_ascii_digit()) {
+                lexer.pos += 1;
+            }
+
+            lexer.pos > from
+        };
+
+        if matches!(self.peek(), Some(b'+' | b'-')) {
+            self.pos += 1;
+        }
+
+        let mut mantissa = digits(self);
+
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            mantissa |= digits(self);
+        }
+
+        if !mantissa {
+            self.pos = start;
+            return Err(SvgError::ExpectNumber(start));
+        }
+
+        // `e` only starts an exponent if digits follow, e.g. not in `1em`.
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            let mark = self.pos;
+            self.pos += 1;
+
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+
+            if !digits(self) {
+                self.pos = mark;
+            }
+        }
+
+        // the scanned bytes are ascii.
+        let number = std::str::from_utf8(&self.data[start..self.pos])
+            .ok()
+            .and_then(|number| number.parse().ok())
+            .ok_or(SvgError::ExpectNumber(start))?;
+
+        self.skip_comma_ws();
+
+        Ok(number)
+    }
+
+    /// Read an arc flag, which needs no separator, e.g. `a1 1 0 01 1 1`.
+    fn flag(&mut self) -> Result<bool> {
+        let flag = match self.peek() {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(SvgError::ExpectNumber(self.pos)),
+        };
+
+        self.pos += 1;
+        self.skip_comma_ws();
+
+        Ok(flag)
+    }
+}
+
+/// A segment of [`PathData`], `abs` is false for the lowercase, relative commands.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PathSegment {
+    MoveTo {
+        abs: bool,
+        x: f64,
+        y: f64,
+    },
+    LineTo {
+        abs: bool,
+        x: f64,
+        y: f64,
+    },
+    HorizontalLineTo {
+        abs: bool,
+        x: f64,
+    },
+    VerticalLineTo {
+        abs: bool,
+        y: f64,
+    },
+    CurveTo {
+        abs: bool,
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+        x: f64,
+        y: f64,
+    },
+    SmoothCurveTo {
+        abs: bool,
+        x2: f64,
+        y2: f64,
+        x: f64,
+        y: f64,
+    },
+    QuadraticTo {
+        abs: bool,
+        x1: f64,
+        y1: f64,
+        x: f64,
+        y: f64,
+    },
+    SmoothQuadraticTo {
+        abs: bool,
+        x: f64,
+        y: f64,
+    },
+    ArcTo {
+        abs: bool,
+        rx: f64,
+        ry: f64,
+        x_axis_rotation: f64,
+        large_arc: bool,
+        sweep: bool,
+        x: f64,
+        y: f64,
+    },
+    ClosePath {
+        abs: bool,
+    },
+}
+
+/// Iterator over the segments of [`path data`](https://www.w3.org/TR/SVG2/paths.html#PathData).
+///
+/// Commands repeated implicitly by extra arguments yield one segment each, the arguments
+/// following a move-to are line-tos. Iteration ends after the first error, as renderers
+/// draw the path up to it.
+#[derive(Debug, Clone)]
+pub struct PathData<'a> {
+    lexer: Lexer<'a>,
+    /// the command repeated by further arguments.
+    prev: Option<u8>,
+    failed: bool,
+}
+
+impl<'a> PathData<'a> {
+    /// Create an iterator over the path data `d`.
+    pub fn new(d: &'a [u8]) -> Self {
+        let mut lexer = Lexer::new(d);
+        lexer.skip_ws();
+
+        Self {
+            lexer,
+            prev: None,
+            failed: false,
+        }
+    }
+
+    fn segment(&mut self) -> Result<PathSegment> {
+        let at = self.lexer.pos;
+
+        let command = match self.lexer.peek() {
+            Some(c) if c.is_ascii_alphabetic() => {
+                self.lexer.pos += 1;
+                self.lexer.skip_ws();
+                c
+            }
+            _ if self.lexer.at_number() => match self.prev {
+                Some(b'M') => b'L',
+                Some(b'm') => b'l',
+                // a close-path takes no arguments, so it can't be repeated.
+                Some(prev) if !matches!(prev, b'Z' | b'z') => prev,
+                _ => return Err(SvgError::InvalidCommand(at)),
+            },
+            _ => return Err(SvgError::InvalidCommand(at)),
+        };
+
+        if self.prev.is_none() && !matches!(command, b'M' | b'm') {
+            return Err(SvgError::InvalidCommand(at));
+        }
+
+        let abs = command.is_ascii_uppercase();
+        let lexer = &mut self.lexer;
+
+        let segment = match command.to_ascii_uppercase() {
+            b'M' => PathSegment::MoveTo {
+                abs,
+                x: lexer.number()?,
+                y: lexer.number()?,
+            },
+            b'L' => PathSegment::LineTo {
+                abs,
+                x: lexer.number()?,
+                y: lexer.number()?,
+            },
+            b'H' => PathSegment::HorizontalLineTo {
+                abs,
+                x: lexer.number()?,
+            },
+            b'V' => PathSegment::VerticalLineTo {
+                abs,
+                y: lexer.number()?,
+            },
+            b'C' => PathSegment::CurveTo {
+                abs,
+                x1: lexer.number()?,
+                y1: lexer.number()?,
+                x2: lexer.number()?,
+                y2: lexer.number()?,
+                x: lexer.number()?,
+                y: lexer.number()?,
+            },
+            b'S' => PathSegment::SmoothCurveTo {
+                abs,
+                x2: lexer.number()?,
+                y2: lexer.number()?,
+                x: lexer.number()?,
+                y: lexer.number()?,
+            },
+            b'Q' => PathSegment::QuadraticTo {
+                abs,
+                x1: lexer.number()?,
+                y1: lexer.number()?,
+                x: lexer.number()?,
+                y: lexer.number()?,
+            },
+            b'T' => PathSegment::SmoothQuadraticTo {
+                abs,
+                x: lexer.number()?,
+                y: lexer.number()?,
+            },
+            b'A' => PathSegment::ArcTo {
+                abs,
+                rx: lexer.number()?,
+                ry: lexer.number()?,
+                x_axis_rotation: lexer.number()?,
+                large_arc: lexer.flag()?,
+                sweep: lexer.flag()?,
+                x: lexer.number()?,
+                y: lexer.number()?,
+            },
+            b'Z' => PathSegment::ClosePath { abs },
+            _ => return Err(SvgError::InvalidCommand(at)),
+        };
+
+        self.prev = Some(command);
+
+        Ok(segment)
+    }
+}
+
+impl<'a> Iterator for PathData<'a> {
+    type Item = Result<PathSegment>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.lexer.is_eof() {
+            return None;
+        }
+
+        let segment = self.segment();
+
+        self.failed = segment.is_err();
+
+        Some(segment)
+    }
+}
+
+/// Iterator over the coordinate pairs of a [`points`](https://www.w3.org/TR/SVG2/shapes.html#DataTypePoints)
+/// value of `polyline` and `polygon`.
+///
+/// Iteration ends after the first error; a trailing odd coordinate is an error too.
+#[derive(Debug, Clone)]
+pub struct Points<'a> {
+    lexer: Lexer<'a>,
+    failed: bool,
+}
+
+impl<'a> Points<'a> {
+    /// Create an iterator over the value `points`.
+    pub fn new(points: &'a [u8]) -> Self {
+        let mut lexer = Lexer::new(points);
+        lexer.skip_ws();
+
+        Self {
+            lexer,
+            failed: false,
+        }
+    }
+}
+
+impl<'a> Iterator for Points<'a> {
+    type Item = Result<(f64, f64)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.lexer.is_eof() {
+            return None;
+        }
+
+        let point = self
+            .lexer
+            .number()
+            .and_then(|x| Ok((x, self.lexer.number()?)));
+
+        self.failed = point.is_err();
+
+        Some(point)
+    }
+}
+
+/// A transform of a [`Transforms`] list, angles are in degrees.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Transform {
+    /// `matrix(a b c d e f)`.
+    Matrix([f64; 6]),
+    /// `translate(tx [ty])`, `ty` defaults to `0`.
+    Translate {
+        tx: f64,
+        ty: f64,
+    },
+    /// `scale(sx [sy])`, `sy` defaults to `sx`.
+    Scale {
+        sx: f64,
+        sy: f64,
+    },
+    /// `rotate(angle [cx cy])`, the center defaults to the origin.
+    Rotate {
+        angle: f64,
+        cx: f64,
+        cy: f64,
+    },
+    SkewX(f64),
+    SkewY(f64),
+}
+
+/// Iterator over a [`transform list`](https://www.w3.org/TR/css-transforms-1/#svg-syntax).
+///
+/// Iteration ends after the first error.
+#[derive(Debug, Clone)]
+pub struct Transforms<'a> {
+    lexer: Lexer<'a>,
+    failed: bool,
+}
+
+impl<'a> Transforms<'a> {
+    /// Create an iterator over the value `transform`.
+    pub fn new(transform: &'a [u8]) -> Self {
+        let mut lexer = Lexer::new(transform);
+        lexer.skip_comma_ws();
+
+        Self {
+            lexer,
+            failed: false,
+        }
+    }
+
+    fn transform(&mut self) -> Result<Transform> {
+        let lexer = &mut self.lexer;
+        let at = lexer.pos;
+        let invalid = SvgError::InvalidTransform(at);
+
+        let name_len = lexer.data[at..]
+            .iter()
+            .position(|c| !c.is_ascii_alphabetic())
+            .unwrap_or(lexer.data.len() - at);
+
+        let name = &lexer.data[at..at + name_len];
+        lexer.pos += name_len;
+        lexer.skip_ws();
+
+        if lexer.peek() != Some(b'(') {
+            return Err(invalid);
+        }
+
+        lexer.pos += 1;
+        lexer.skip_ws();
+
+        let mut args = [0.0; 6];
+        let mut len = 0;
+
+        while lexer.at_number() {
+            if len == args.len() {
+                return Err(invalid);
+            }
+
+            args[len] = lexer.number()?;
+            len += 1;
+        }
+
+        if lexer.peek() != Some(b')') {
+            return Err(invalid);
+        }
+
+        lexer.pos += 1;
+        lexer.skip_comma_ws();
+
+        let transform = match (name, len) {
+            (b"matrix", 6) => Transform::Matrix(args),
+            (b"translate", 1 | 2) => Transform::Translate {
+                tx: args[0],
+                ty: args[1],
+            },
+            (b"scale", 1) => Transform::Scale {
+                sx: args[0],
+                sy: args[0],
+            },
+            (b"scale", 2) => Transform::Scale {
+                sx: args[0],
+                sy: args[1],
+            },
+            (b"rotate", 1 | 3) => Transform::Rotate {
+                angle: args[0],
+                cx: args[1],
+                cy: args[2],
+            },
+            (b"skewX", 1) => Transform::SkewX(args[0]),
+            (b"skewY", 1) => Transform::SkewY(args[0]),
+            _ => return Err(invalid),
+        };
+
+        Ok(transform)
+    }
+}
+
+impl<'a> Iterator for Transforms<'a> {
+    type Item = Result<Transform>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.lexer.is_eof() {
+            return None;
+        }
+
+        let transform = self.transform();
+
+        self.failed = transform.is_err();
+
+        Some(transform)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(d: &str) -> Vec<Result<PathSegment>> {
+        PathData::new(d.as_bytes()).collect()
+    }
+
+    #[test]
+    fn test_numbers() {
+        let mut lexer = Lexer::new(b"-1.5e2,.5.5 +3E-1 1em");
+
+        assert_eq!(lexer.number(), Ok(-150.0));
+        assert_eq!(lexer.number(), Ok(0.5));
+        assert_eq!(lexer.number(), Ok(0.5));
+        assert_eq!(lexer.number(), Ok(0.3));
+        assert_eq!(lexer.number(), Ok(1.0));
+        assert_eq!(lexer.number(), Err(SvgError::ExpectNumber(19)));
+        assert_eq!(Lexer::new(b"-.").number(), Err(SvgError::ExpectNumber(0)));
+    }
+
+    #[test]
+    fn test_path_data() {
+        assert_eq!(
+            path("m1 2 3 4ZH5v6"),
+            [
+                Ok(PathSegment::MoveTo {
+                    abs: false,
+                    x: 1.0,
+                    y: 2.0
+                }),
+                Ok(PathSegment::LineTo {
+                    abs: false,
+                    x: 3.0,
+                    y: 4.0
+                }),
+                Ok(PathSegment::ClosePath { abs: true }),
+                Ok(PathSegment::HorizontalLineTo { abs: true, x: 5.0 }),
+                Ok(PathSegment::VerticalLineTo { abs: false, y: 6.0 }),
+            ]
+        );
+
+        assert_eq!(
+            path("M0,0 a1 1 0 01.5.5")[1],
+            Ok(PathSegment::ArcTo {
+                abs: false,
+                rx: 1.0,
+                ry: 1.0,
+                x_axis_rotation: 0.0,
+                large_arc: false,
+                sweep: true,
+                x: 0.5,
+                y: 0.5
+            })
+        );
+
+        assert_eq!(path(" C1 2 3 4 5 6"), [Err(SvgError::InvalidCommand(1))]);
+        assert_eq!(
+            path("M1 2 Z 3 4"),
+            [
+                Ok(PathSegment::MoveTo {
+                    abs: true,
+                    x: 1.0,
+                    y: 2.0
+                }),
+                Ok(PathSegment::ClosePath { abs: true }),
+                Err(SvgError::InvalidCommand(7)),
+            ]
+        );
+        assert_eq!(path("M1 2 L3")[1], Err(SvgError::ExpectNumber(7)));
+        assert_eq!(path("M1 2 L3").len(), 2);
+    }
+
+    #[test]
+    fn test_points() {
+        assert_eq!(
+            Points::new(b" 1,2 3-4 ").collect::<Vec<_>>(),
+            [Ok((1.0, 2.0)), Ok((3.0, -4.0))]
+        );
+        assert_eq!(
+            Points::new(b"1 2 3").collect::<Vec<_>>(),
+            [Ok((1.0, 2.0)), Err(SvgError::ExpectNumber(5))]
+        );
+    }
+
+    #[test]
+    fn test_transforms() {
+        assert_eq!(
+            Transforms::new(b"translate(10) scale(2),rotate(45 1 1)matrix(1,0,0,1,0,0)")
+                .collect::<Vec<_>>(),
+            [
+                Ok(Transform::Translate { tx: 10.0, ty: 0.0 }),
+                Ok(Transform::Scale { sx: 2.0, sy: 2.0 }),
+                Ok(Transform::Rotate {
+                    angle: 45.0,
+                    cx: 1.0,
+                    cy: 1.0
+                }),
+                Ok(Transform::Matrix([1.0, 0.0, 0.0, 1.0, 0.0, 0.0])),
+            ]
+        );
+        assert_eq!(
+            Transforms::new(b"rotate(1 2)").collect::<Vec<_>>(),
+            [Err(SvgError::InvalidTransform(0))]
+        );
+        assert_eq!(
+            Transforms::new(b"skewX(1").collect::<Vec<_>>(),
+            [Err(SvgError::InvalidTransform(0))]
+        );
+    }
+}
@@ -0,0 +1,507 @@
+//! Helpers for tests of code producing xml, see [`assert_xml_eq!`](crate::assert_xml_eq), and
+//! of round trips through the writer and reader, see [`arbitrary_document`].
+
+use std::io::Write;
+
+use parserc::ControlFlow;
+
+use crate::{
+    escape::{escape_attr, escape_text},
+    reader::{Diagnostic, XmlNode, XmlReader, XmlReaderBuilder},
+    writer::{WriteError, XmlWriter},
+};
+
+/// Returns the normalized form of the document `xml` compared by [`assert_xml_eq!`](crate::assert_xml_eq).
+///
+/// Each node is written on its own line, indented by its depth. The xml declaration and
+/// whitespace outside the root element are dropped, whitespace in text is collapsed, references
+/// are expanded, attributes are sorted by name and empty elements are written as start/end
+/// tag pairs, so `<a y='2' x="1"/>` and `<a x="1" y="2"></a>` have the same form.
+pub fn normalize(xml: &str) -> Result<String, String> {
+    let mut reader = XmlReaderBuilder::new()
+        .trim_text(true)
+        .coalesce_text(true)
+        .build(xml.as_bytes());
+
+    let mut lines = vec![];
+    let mut depth = 0usize;
+
+    let err = |err| match err {
+        ControlFlow::Fatal(err) | ControlFlow::Recovable(err) => {
+            Diagnostic::new(xml.as_bytes(), &err).to_string()
+        }
+        ControlFlow::Incomplete(_) => "incomplete document".to_string(),
+    };
+
+    while let Some(node) = reader.read_next().map_err(err)? {
+        let line = match node {
+            XmlNode::XmlDecl(_) | XmlNode::S(_) => continue,
+            XmlNode::DocType(doctype) => {
+                format!("<!DOCTYPE{}>", String::from_utf8_lossy(doctype.0))
+            }
+            XmlNode::PI(pi) => format!(
+                "<?{} {}?>",
+                String::from_utf8_lossy(pi.name),
+                String::from_utf8_lossy(pi.unparsed.trim_ascii())
+            ),
+            XmlNode::Comment(comment) => {
+                format!("<!--{}-->", String::from_utf8_lossy(comment.0))
+            }
+            XmlNode::Start(start) => {
+                let name = String::from_utf8_lossy(start.name);
+
+                let mut attrs = vec![];
+
+                for attr in start.attrs() {
+                    let attr = attr.map_err(err)?;
+                    let value = reader.attr_value(&attr).map_err(err)?.into_owned();
+
+                    attrs.push((String::from_utf8_lossy(attr.name).into_owned(), value));
+                }
+
+                attrs.sort();
+
+                let mut line = format!("{}<{}", "  ".repeat(depth), name);
+
+                for (name, value) in attrs {
+                    line.push_str(&format!(r#" {}="{}""#, name, escape_attr(&value)));
+                }
+
+                line.push('>');
+                lines.push(line);
+
+                if start.is_empty {
+                    lines.push(format!("{}</{}>", "  ".repeat(depth), name));
+                } else {
+                    depth += 1;
+                }
+
+                continue;
+            }
+            XmlNode::End(end) => {
+                depth = depth.saturating_sub(1);
+                format!("</{}>", String::from_utf8_lossy(end.name))
+            }
+            XmlNode::Text(text) => {
+                escape_text(&text.split_whitespace().collect::<Vec<_>>().join(" ")).into_owned()
+            }
+            XmlNode::CharData(chardata) => String::from_utf8_lossy(chardata.0).into_owned(),
+            XmlNode::CData(cdata) => String::from_utf8_lossy(cdata.0).into_owned(),
+            XmlNode::Unknown(unknown) => String::from_utf8_lossy(unknown.0).into_owned(),
+        };
+
+        lines.push(format!("{}{}", "  ".repeat(depth), line));
+    }
+
+    Ok(lines.join("\n"))
+}
+
+/// Returns a line diff turning `left` into `right`, lines only in `left` are prefixed with `-`,
+/// lines only in `right` with `+`.
+pub fn diff(left: &str, right: &str) -> String {
+    let left = left.lines().collect::<Vec<_>>();
+    let right = right.lines().collect::<Vec<_>>();
+
+    // lcs[i][j] is the length of the longest common subsequence of left[i..] and right[j..].
+    let mut lcs = vec![vec![0usize; right.len() + 1]; left.len() + 1];
+
+    for i in (0..left.len()).rev() {
+        for j in (0..right.len()).rev() {
+            lcs[i][j] = match left[i] == right[j] {
+                true => lcs[i + 1][j + 1] + 1,
+                false => lcs[i + 1][j].max(lcs[i][j + 1]),
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = vec![];
+
+    while i < left.len() || j < right.len() {
+        if i < left.len() && j < right.len() && left[i] == right[j] {
+            lines.push(format!("  {}", left[i]));
+            i += 1;
+            j += 1;
+        } else if j == right.len() || (i < left.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(format!("- {}", left[i]));
+            i += 1;
+        } else {
+            lines.push(format!("+ {}", right[j]));
+            j += 1;
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// Implementation of [`assert_xml_eq!`](crate::assert_xml_eq).
+#[track_caller]
+pub fn assert_xml_eq(left: &str, right: &str) {
+    let normalize = |name, xml| match normalize(xml) {
+        Ok(normalized) => normalized,
+        Err(err) => panic!("{} is not well-formed: {}\n{}", name, err, xml),
+    };
+
+    let (left, right) = (normalize("left", left), normalize("right", right));
+
+    if left != right {
+        panic!(
+            "xml documents are not equal (-left +right):\n{}",
+            diff(&left, &right)
+        );
+    }
+}
+
+/// Asserts that two xml documents are equal after [`normalization`](crate::testing::normalize),
+/// printing a line diff of their normalized forms if not.
+///
+/// ```
+/// rexml::assert_xml_eq!(
+///     r#"<svg height="2" width="1"><rect/></svg>"#,
+///     "<svg width='1' height='2'>\n  <rect></rect>\n</svg>"
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_xml_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::testing::assert_xml_eq(
+            ::core::convert::AsRef::<str>::as_ref(&$left),
+            ::core::convert::AsRef::<str>::as_ref(&$right),
+        )
+    };
+}
+
+/// A node of a document generated by [`arbitrary_document`], with unescaped content.
+#[derive(Debug, PartialEq, Clone)]
+pub enum DocEvent {
+    /// A start tag with its attributes in written order, closed by a matching `End`.
+    Start {
+        name: String,
+        attrs: Vec<(String, String)>,
+    },
+    End(String),
+    Text(String),
+    CData(String),
+    Comment(String),
+    PI {
+        target: String,
+        data: String,
+    },
+}
+
+/// Returns a random well-formed document, the same one for the same `seed`.
+///
+/// Text and attribute values contain markup characters, quotes, tabs, line breaks and non-ascii
+/// chars, and CDATA sections contain `]]>`, so writing the document with [`write_document`] and
+/// reading it back with [`read_document`] checks the escaping of the writer:
+///
+/// ```
+/// use rexml::{
+///     testing::{arbitrary_document, read_document, write_document},
+///     writer::XmlWriter,
+/// };
+///
+/// for seed in 0..100 {
+///     let events = arbitrary_document(seed);
+///
+///     let mut writer = XmlWriter::new(Vec::new());
+///     write_document(&mut writer, &events).unwrap();
+///
+///     let xml = writer.into_inner().unwrap();
+///
+///     assert_eq!(read_document(&xml), Ok(events));
+/// }
+/// ```
+pub fn arbitrary_document(seed: u64) -> Vec<DocEvent> {
+    let mut rng = Rng(seed);
+    let mut events = vec![];
+
+    if rng.chance(3) {
+        events.push(DocEvent::Comment(rng.string(COMMENT_CHARS)));
+    }
+
+    rng.element(0, &mut events);
+
+    if rng.chance(3) {
+        events.push(DocEvent::PI {
+            target: rng.pick(PI_TARGETS).to_string(),
+            data: rng.string(PI_CHARS),
+        });
+    }
+
+    events
+}
+
+/// Write `events`, e.g. those of [`arbitrary_document`], with the escaping methods of `writer`.
+pub fn write_document<W>(writer: &mut XmlWriter<W>, events: &[DocEvent]) -> Result<(), WriteError>
+where
+    W: Write,
+{
+    for event in events {
+        match event {
+            DocEvent::Start { name, attrs } => {
+                let mut el = writer.write_elment_start(name)?;
+
+                for (name, value) in attrs {
+                    el.write_escaped_attr(name, value)?;
+                }
+
+                el.finish()?;
+            }
+            DocEvent::End(name) => writer.write_element_end(name)?,
+            DocEvent::Text(text) => writer.write_text(text)?,
+            DocEvent::CData(content) => writer.write_cdata(content)?,
+            DocEvent::Comment(content) => writer.write_comment(content)?,
+            DocEvent::PI { target, data } => writer.write_pi(target, data)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Read the events of `xml` in the form generated by [`arbitrary_document`].
+///
+/// References are expanded, empty elements yield a `Start` and an `End`, adjacent CDATA
+/// sections are merged, and the data of PIs is trimmed.
+pub fn read_document(xml: &[u8]) -> Result<Vec<DocEvent>, String> {
+    let mut reader = XmlReader::from(xml);
+    let mut events = vec![];
+
+    let err = |err| match err {
+        ControlFlow::Fatal(err) | ControlFlow::Recovable(err) => {
+            Diagnostic::new(xml, &err).to_string()
+        }
+        ControlFlow::Incomplete(_) => "incomplete document".to_string(),
+    };
+
+    let utf8 = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+
+    while let Some(node) = reader.read_next().map_err(err)? {
+        let event = match node {
+            XmlNode::XmlDecl(_) | XmlNode::DocType(_) | XmlNode::S(_) => continue,
+            XmlNode::Start(start) => {
+                let mut attrs = vec![];
+
+                for attr in start.attrs() {
+                    let attr = attr.map_err(err)?;
+                    let value = reader.attr_value(&attr).map_err(err)?.into_owned();
+
+                    attrs.push((utf8(attr.name), value));
+                }
+
+                events.push(DocEvent::Start {
+                    name: utf8(start.name),
+                    attrs,
+                });
+
+                if !start.is_empty {
+                    continue;
+                }
+
+                DocEvent::End(utf8(start.name))
+            }
+            XmlNode::End(end) => DocEvent::End(utf8(end.name)),
+            XmlNode::CharData(chardata) => {
+                DocEvent::Text(reader.text(&chardata).map_err(err)?.into_owned())
+            }
+            XmlNode::Text(text) => DocEvent::Text(text),
+            XmlNode::CData(cdata) => {
+                let content = reader.cdata(&cdata).map_err(err)?;
+
+                if let Some(DocEvent::CData(prev)) = events.last_mut() {
+                    prev.push_str(&content);
+                    continue;
+                }
+
+                DocEvent::CData(content.into_owned())
+            }
+            XmlNode::Comment(comment) => DocEvent::Comment(utf8(comment.0)),
+            XmlNode::PI(pi) => DocEvent::PI {
+                target: utf8(pi.name),
+                data: utf8(pi.unparsed.trim_ascii()),
+            },
+            XmlNode::Unknown(unknown) => return Err(format!("unknown markup {}", utf8(unknown.0))),
+        };
+
+        events.push(event);
+    }
+
+    Ok(events)
+}
+
+const NAMES: &[&str] = &["a", "b", "svg", "g", "x:y", "\u{e9}l\u{e9}ment"];
+const ATTR_NAMES: &[&str] = &["id", "x", "y", "class", "xml:lang", "data-\u{4e2d}"];
+const PI_TARGETS: &[&str] = &["pi", "xml-stylesheet", "php"];
+
+/// Chars of text and attribute values, including all escaped ones.
+const TEXT_CHARS: &[char] = &[
+    'a',
+    'b',
+    ' ',
+    '<',
+    '>',
+    '&',
+    '"',
+    '\'',
+    '\t',
+    '\n',
+    '\r',
+    ']',
+    ';',
+    '#',
+    '\u{e9}',
+    '\u{4e2d}',
+    '\u{1f600}',
+];
+/// Chars of CDATA sections, without carriage returns, which readers may normalize.
+const CDATA_CHARS: &[char] = &['a', ' ', '<', '>', '&', ']', '\u{e9}'];
+/// Chars of comments, without `-`.
+const COMMENT_CHARS: &[char] = &['a', ' ', '<', '>', '&', '\u{e9}'];
+/// Chars of PI data, without `?` and whitespace at the ends.
+const PI_CHARS: &[char] = &['a', '=', '"', '<', '>', '&', '\u{e9}'];
+
+/// A [splitmix64](https://prng.di.unimi.it/splitmix64.c) generator, tests need no better.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    /// Returns true once in `n` times.
+    fn chance(&mut self, n: usize) -> bool {
+        self.below(n) == 0
+    }
+
+    fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())]
+    }
+
+    /// Returns a non-empty string of up to 8 `chars`.
+    fn string(&mut self, chars: &[char]) -> String {
+        (0..1 + self.below(8)).map(|_| self.pick(chars)).collect()
+    }
+
+    /// Push the events of an element at `depth` and its content.
+    fn element(&mut self, depth: usize, events: &mut Vec<DocEvent>) {
+        let name = self.pick(NAMES).to_string();
+
+        let mut attrs: Vec<(String, String)> = vec![];
+
+        for _ in 0..self.below(4) {
+            let name = self.pick(ATTR_NAMES);
+
+            if attrs.iter().all(|(written, _)| written != name) {
+                attrs.push((name.to_string(), self.string(TEXT_CHARS)));
+            }
+        }
+
+        events.push(DocEvent::Start {
+            name: name.clone(),
+            attrs,
+        });
+
+        let children = if depth < 4 { self.below(5) } else { 0 };
+
+        // text and CDATA are not adjacent, the reader would merge or split them.
+        let mut after_text = false;
+
+        for _ in 0..children {
+            let event = match self.below(6) {
+                0 | 1 if !after_text => DocEvent::Text(self.string(TEXT_CHARS)),
+                2 if !after_text => DocEvent::CData(self.string(CDATA_CHARS)),
+                3 => DocEvent::Comment(self.string(COMMENT_CHARS)),
+                4 => DocEvent::PI {
+                    target: self.pick(PI_TARGETS).to_string(),
+                    data: self.string(PI_CHARS),
+                },
+                _ => {
+                    self.element(depth + 1, events);
+                    after_text = false;
+                    continue;
+                }
+            };
+
+            after_text = matches!(event, DocEvent::Text(_) | DocEvent::CData(_));
+            events.push(event);
+        }
+
+        events.push(DocEvent::End(name));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::writer::XmlWriter;
+
+    use super::{DocEvent, arbitrary_document, diff, normalize, read_document, write_document};
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize(
+                "<?xml version=\"1.0\"?>\n<a z='&amp;' b=\"1\">\n  x   y <!--c--><b/>\n</a>\n"
+            ),
+            Ok("<a b=\"1\" z=\"&amp;\">\n  x y\n  <!--c-->\n  <b>\n  </b>\n</a>".to_string())
+        );
+
+        assert!(normalize("<a>").is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for seed in 0..1000 {
+            let events = arbitrary_document(seed);
+
+            assert!(matches!(
+                events.first(),
+                Some(DocEvent::Start { .. } | DocEvent::Comment(_))
+            ));
+
+            let mut writer = XmlWriter::new(Vec::new()).strict();
+            write_document(&mut writer, &events).unwrap();
+
+            let xml = writer.into_inner().unwrap();
+
+            assert_eq!(
+                read_document(&xml),
+                Ok(events),
+                "{}",
+                String::from_utf8_lossy(&xml)
+            );
+        }
+
+        assert_eq!(arbitrary_document(7), arbitrary_document(7));
+    }
+
+    #[test]
+    fn test_diff() {
+        assert_eq!(diff("a\nb\nc", "a\nx\nc"), "  a\n- b\n+ x\n  c");
+    }
+
+    #[test]
+    fn test_assert_xml_eq() {
+        crate::assert_xml_eq!(
+            "<a><b x='1' y='2'/></a>",
+            "<a>\n  <b y=\"2\" x=\"1\"></b>\n</a>"
+        );
+
+        let panic =
+            std::panic::catch_unwind(|| crate::assert_xml_eq!("<a>1</a>", "<a>2</a>")).unwrap_err();
+
+        assert_eq!(
+            panic.downcast_ref::<String>().map(String::as_str),
+            Some("xml documents are not equal (-left +right):\n  <a>\n-   1\n+   2\n  </a>")
+        );
+    }
+}
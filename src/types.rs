@@ -1,7 +1,16 @@
 //! Common types shared by `reader` and `writer`.
 
+#[cfg(feature = "reader-core")]
+use std::fmt::Debug;
 use std::fmt::Display;
 
+#[cfg(feature = "reader-core")]
+use parserc::{AsBytes, ControlFlow, Input};
+
+use crate::escape::escape_attr;
+#[cfg(feature = "reader-core")]
+use crate::reader::{Entities, PI, ReadError};
+
 /// Represents the xml version num: 1.0 or 1.1
 #[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
 pub enum XmlVersion {
@@ -17,3 +26,251 @@ impl Display for XmlVersion {
         }
     }
 }
+
+/// Document metadata written before the root element: the xml declaration and doctype.
+///
+/// Use it to turn a fragment into a complete document, see
+/// [`XmlWriter::write_prolog`](crate::writer::XmlWriter::write_prolog).
+#[derive(Debug, PartialEq, Clone)]
+pub struct Prolog {
+    /// version of the xml declaration.
+    pub version: XmlVersion,
+    /// encoding of the xml declaration.
+    pub encoding: Option<String>,
+    /// standalone flag of the xml declaration.
+    pub standalone: Option<bool>,
+    /// raw content of the doctype declaration, e.g. `svg`, written as `<!DOCTYPE svg>`.
+    pub doctype: Option<String>,
+    /// `(prefix, uri)` namespace declarations written on the root element.
+    pub namespaces: Vec<(Option<String>, String)>,
+}
+
+impl Default for Prolog {
+    fn default() -> Self {
+        Self {
+            version: XmlVersion::Ver10,
+            encoding: None,
+            standalone: None,
+            doctype: None,
+            namespaces: vec![],
+        }
+    }
+}
+
+impl Prolog {
+    /// An xml declaration with `version` and `encoding`, no doctype.
+    pub fn plain(version: XmlVersion, encoding: Option<&str>) -> Self {
+        Self {
+            version,
+            encoding: encoding.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    /// An [`SVG 1.1`](https://www.w3.org/TR/SVG11/) document: doctype, and the svg and xlink namespaces.
+    pub fn svg11() -> Self {
+        Self {
+            encoding: Some("UTF-8".to_string()),
+            standalone: Some(false),
+            doctype: Some(
+                r#"svg PUBLIC "-//W3C//DTD SVG 1.1//EN" "http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd""#
+                    .to_string(),
+            ),
+            namespaces: vec![
+                (None, "http://www.w3.org/2000/svg".to_string()),
+                (
+                    Some("xlink".to_string()),
+                    "http://www.w3.org/1999/xlink".to_string(),
+                ),
+            ],
+            ..Default::default()
+        }
+    }
+
+    /// An [`XHTML 1.0 Strict`](https://www.w3.org/TR/xhtml1/) document: doctype and the xhtml namespace.
+    pub fn xhtml1_strict() -> Self {
+        Self {
+            encoding: Some("UTF-8".to_string()),
+            doctype: Some(
+                r#"html PUBLIC "-//W3C//DTD XHTML 1.0 Strict//EN" "http://www.w3.org/TR/xhtml1/DTD/xhtml1-strict.dtd""#
+                    .to_string(),
+            ),
+            namespaces: vec![(None, "http://www.w3.org/1999/xhtml".to_string())],
+            ..Default::default()
+        }
+    }
+
+    /// Add the namespace declaration `(prefix, uri)` of the root element.
+    pub fn with_namespace<U>(mut self, prefix: Option<&str>, uri: U) -> Self
+    where
+        U: Into<String>,
+    {
+        self.namespaces
+            .push((prefix.map(str::to_string), uri.into()));
+        self
+    }
+}
+
+/// The [`xml-stylesheet`](https://www.w3.org/TR/xml-stylesheet/) processing instruction, e.g.
+/// `<?xml-stylesheet href="style.css" type="text/css"?>`.
+///
+/// Write it with [`XmlWriter::write_stylesheet_pi`](crate::writer::XmlWriter::write_stylesheet_pi).
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct XmlStylesheet {
+    /// uri of the stylesheet, the only required pseudo-attribute.
+    pub href: String,
+    /// media type, e.g. `text/css` or `text/xsl`.
+    pub ty: Option<String>,
+    pub title: Option<String>,
+    pub media: Option<String>,
+    pub charset: Option<String>,
+    /// `alternate="yes"` or `alternate="no"`.
+    pub alternate: Option<bool>,
+}
+
+impl XmlStylesheet {
+    /// The target name of the processing instruction.
+    pub const TARGET: &str = "xml-stylesheet";
+
+    /// A stylesheet located at `href`, with the media type `ty`.
+    pub fn new<H, T>(href: H, ty: T) -> Self
+    where
+        H: Into<String>,
+        T: Into<String>,
+    {
+        Self {
+            href: href.into(),
+            ty: Some(ty.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the pseudo-attributes as written in the body of the processing instruction.
+    pub fn content(&self) -> String {
+        let mut content = format!(r#"href="{}""#, escape_attr(&self.href));
+
+        let optional = [
+            ("type", self.ty.as_deref()),
+            ("title", self.title.as_deref()),
+            ("media", self.media.as_deref()),
+            ("charset", self.charset.as_deref()),
+            (
+                "alternate",
+                self.alternate.map(|alternate| match alternate {
+                    true => "yes",
+                    false => "no",
+                }),
+            ),
+        ];
+
+        for (name, value) in optional {
+            if let Some(value) = value {
+                content.push_str(&format!(r#" {}="{}""#, name, escape_attr(value)));
+            }
+        }
+
+        content
+    }
+}
+
+#[cfg(feature = "reader-core")]
+impl XmlStylesheet {
+    /// Read the pseudo-attributes of `pi`, with references expanded.
+    ///
+    /// Returns `None` if `pi` is no `xml-stylesheet` instruction or has no `href`. Unknown
+    /// pseudo-attributes are ignored.
+    pub fn from_pi<I>(pi: &PI<I>) -> Result<Option<Self>, ControlFlow<ReadError<I>>>
+    where
+        I: Input<Item = u8> + AsBytes + Clone + Debug,
+    {
+        if pi.name.as_bytes() != Self::TARGET.as_bytes() {
+            return Ok(None);
+        }
+
+        let entities = Entities::new();
+        let mut href = None;
+        let mut stylesheet = Self::default();
+
+        for attr in pi.pseudo_attrs() {
+            let attr = attr?;
+            let value = entities.expand_attr(&attr.value)?.into_owned();
+
+            match attr.name.as_bytes() {
+                b"href" => href = Some(value),
+                b"type" => stylesheet.ty = Some(value),
+                b"title" => stylesheet.title = Some(value),
+                b"media" => stylesheet.media = Some(value),
+                b"charset" => stylesheet.charset = Some(value),
+                b"alternate" => stylesheet.alternate = Some(value == "yes"),
+                _ => {}
+            }
+        }
+
+        Ok(href.map(|href| Self { href, ..stylesheet }))
+    }
+}
+
+/// How text extraction treats whitespace, see `XmlReader::read_text` and `Document::text_with`.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Whitespace {
+    /// Keep all whitespace as written.
+    #[default]
+    Preserve,
+    /// Replace each whitespace run with one space, and drop leading and trailing whitespace.
+    Collapse,
+    /// Preserve whitespace inside `xml:space="preserve"` and collapse it elsewhere, see
+    /// [`White Space Handling`](https://www.w3.org/TR/xml11/#sec-white-space).
+    XmlSpace,
+}
+
+impl Whitespace {
+    /// Returns true if text is preserved given the innermost `xml:space` value in scope.
+    pub fn preserves(self, xml_space: Option<&[u8]>) -> bool {
+        match self {
+            Whitespace::Preserve => true,
+            Whitespace::Collapse => false,
+            Whitespace::XmlSpace => xml_space == Some(b"preserve"),
+        }
+    }
+}
+
+/// Concatenates text, collapsing whitespace of the parts pushed with `preserve` off.
+#[cfg(feature = "reader-core")]
+#[derive(Debug, Default)]
+pub(crate) struct TextCollector {
+    text: String,
+    /// true if collapsed whitespace is waiting for the next non-whitespace char.
+    pending: bool,
+}
+
+#[cfg(feature = "reader-core")]
+impl TextCollector {
+    pub(crate) fn push(&mut self, text: &str, preserve: bool) {
+        if preserve {
+            self.flush();
+            self.text.push_str(text);
+            return;
+        }
+
+        for c in text.chars() {
+            if matches!(c, ' ' | '\t' | '\r' | '\n') {
+                self.pending = true;
+            } else {
+                self.flush();
+                self.text.push(c);
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.pending && !self.text.is_empty() {
+            self.text.push(' ');
+        }
+
+        self.pending = false;
+    }
+
+    pub(crate) fn finish(self) -> String {
+        self.text
+    }
+}
@@ -0,0 +1,68 @@
+use std::{fmt, io};
+
+use super::XmlWriter;
+
+/// A [`std::io::Write`] adapter over a [`fmt::Write`] sink, e.g. a `String`.
+///
+/// The writer only emits whole strings, so each write is passed on as `&str` without copying.
+#[derive(Debug, Default)]
+pub struct FmtSink<F>(pub F);
+
+impl<F> FmtSink<F> {
+    /// Returns the wrapped sink.
+    pub fn into_inner(self) -> F {
+        self.0
+    }
+}
+
+impl<F> io::Write for FmtSink<F>
+where
+    F: fmt::Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let content = std::str::from_utf8(buf)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        self.0.write_str(content).map_err(io::Error::other)?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<F> XmlWriter<FmtSink<F>>
+where
+    F: fmt::Write,
+{
+    /// Create a xml document writer from [`fmt::Write`], e.g. a `&mut String`.
+    pub fn from_fmt(sink: F) -> Self {
+        Self::new(FmtSink(sink))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::writer::XmlWriter;
+
+    #[test]
+    fn test_from_fmt() {
+        let mut buf = String::new();
+
+        let mut writer = XmlWriter::from_fmt(&mut buf);
+
+        writer
+            .write_elment_start("a")
+            .unwrap()
+            .write_escaped_attr("title", "é & ü")
+            .unwrap();
+        writer.write_text("1 < 2").unwrap();
+        writer.write_element_end("a").unwrap();
+
+        drop(writer);
+
+        assert_eq!(buf, r#"<a title="é &amp; ü">1 &lt; 2</a>"#);
+    }
+}
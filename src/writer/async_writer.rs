@@ -0,0 +1,296 @@
+use std::io::Result;
+
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::{
+    chars::illegal_comment_offset,
+    escape::{escape_attr, escape_cdata, escape_text},
+    types::XmlVersion,
+};
+
+use super::{canonical_xmlns, xmlns_name};
+
+/// The asynchronous twin of [`XmlWriter`](super::XmlWriter) over [`tokio::io::AsyncWrite`].
+///
+/// Futures can't run in `Drop`, so the start tag returned by [`write_elment_start`](Self::write_elment_start)
+/// is closed by [`AsyncElemStart::finish`], or lazily by the next write on this writer. Call
+/// [`flush`](Self::flush) before dropping the writer.
+pub struct AsyncXmlWriter<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    /// underlying write.
+    sink: W,
+    /// the closing bytes of an unfinished start tag.
+    pending: Option<&'static [u8]>,
+    /// names of the open elements, outermost first.
+    stack: Vec<String>,
+}
+
+impl<W> AsyncXmlWriter<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    /// Create a xml document writer from [`tokio::io::AsyncWrite`].
+    pub fn new(sink: W) -> Self {
+        Self {
+            sink,
+            pending: None,
+            stack: vec![],
+        }
+    }
+
+    /// Returns the number of open elements.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Returns the name of the innermost open element.
+    pub fn current_element(&self) -> Option<&str> {
+        self.stack.last().map(String::as_str)
+    }
+
+    /// Create an iterator over the names of the open elements, outermost first.
+    pub fn open_elements(&self) -> impl ExactSizeIterator<Item = &str> + DoubleEndedIterator {
+        self.stack.iter().map(String::as_str)
+    }
+
+    /// Close the unfinished start tag, if any.
+    async fn close_pending(&mut self) -> Result<()> {
+        if let Some(pending) = self.pending.take() {
+            self.sink.write_all(pending).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn write_str(&mut self, content: &str) -> Result<()> {
+        self.close_pending().await?;
+        self.sink.write_all(content.as_bytes()).await
+    }
+
+    pub async fn write_xml_decl(
+        &mut self,
+        version: XmlVersion,
+        encoding: Option<&str>,
+        standalone: Option<bool>,
+    ) -> Result<()> {
+        let mut decl = format!("<?xml version=\"{}\"", version);
+
+        if let Some(encoding) = encoding {
+            decl.push_str(&format!(" encoding=\"{}\"", encoding));
+        }
+
+        if let Some(standalone) = standalone {
+            decl.push_str(&format!(
+                " standalone=\"{}\"",
+                if standalone { "yes" } else { "no" }
+            ));
+        }
+
+        decl.push_str("?>");
+
+        self.write_str(&decl).await
+    }
+
+    /// Write pi node, fails with [`std::io::ErrorKind::InvalidInput`] if `unparsed` contains
+    /// `?>`.
+    pub async fn write_pi<N, U>(&mut self, name: N, unparsed: U) -> Result<()>
+    where
+        N: AsRef<str>,
+        U: AsRef<str>,
+    {
+        if unparsed.as_ref().contains("?>") {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "illegal `?>` in pi",
+            ));
+        }
+
+        self.write_str(&format!("<?{} {} ?>", name.as_ref(), unparsed.as_ref()))
+            .await
+    }
+
+    /// Write comment node, fails with [`std::io::ErrorKind::InvalidInput`] if `content` contains
+    /// `--` or ends with `-`.
+    pub async fn write_comment<C>(&mut self, content: C) -> Result<()>
+    where
+        C: AsRef<str>,
+    {
+        if illegal_comment_offset(content.as_ref().as_bytes()).is_some() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "illegal `--` in comment",
+            ));
+        }
+
+        self.write_str(&format!("<!--{}-->", content.as_ref()))
+            .await
+    }
+
+    /// Write cdata, a `]]>` in `content` is split across two sections, see [`escape_cdata`].
+    pub async fn write_cdata<C>(&mut self, content: C) -> Result<()>
+    where
+        C: AsRef<str>,
+    {
+        self.write_str(&format!("<![CDATA[{}]]>", escape_cdata(content.as_ref())))
+            .await
+    }
+
+    /// Write chardata.
+    pub async fn write_chardata<C>(&mut self, content: C) -> Result<()>
+    where
+        C: AsRef<str>,
+    {
+        self.write_str(content.as_ref()).await
+    }
+
+    /// Write text, escaping markup characters, see [`escape_text`].
+    pub async fn write_text<C>(&mut self, content: C) -> Result<()>
+    where
+        C: AsRef<str>,
+    {
+        self.write_chardata(escape_text(content.as_ref())).await
+    }
+
+    /// Start write element start tag.
+    pub async fn write_elment_start<N>(&mut self, name: N) -> Result<AsyncElemStart<'_, W>>
+    where
+        N: AsRef<str>,
+    {
+        self.write_str(&format!("<{}", name.as_ref())).await?;
+        self.pending = Some(b">");
+        self.stack.push(name.as_ref().to_string());
+
+        Ok(AsyncElemStart { sink: self })
+    }
+
+    /// Start write empty element start tag.
+    pub async fn write_empty_elment<N>(&mut self, name: N) -> Result<AsyncElemStart<'_, W>>
+    where
+        N: AsRef<str>,
+    {
+        self.write_str(&format!("<{}", name.as_ref())).await?;
+        self.pending = Some(b"/>");
+
+        Ok(AsyncElemStart { sink: self })
+    }
+
+    /// Write a element end tag.
+    pub async fn write_element_end(&mut self, name: &str) -> Result<()> {
+        self.write_str(&format!("</{}>", name)).await?;
+        self.stack.pop();
+
+        Ok(())
+    }
+
+    /// Close any unfinished start tag and flush the underlying sink.
+    pub async fn flush(&mut self) -> Result<()> {
+        self.close_pending().await?;
+        self.sink.flush().await
+    }
+}
+
+/// A write for element start tag.
+pub struct AsyncElemStart<'a, W>
+where
+    W: AsyncWrite + Unpin,
+{
+    sink: &'a mut AsyncXmlWriter<W>,
+}
+
+impl<'a, W> AsyncElemStart<'a, W>
+where
+    W: AsyncWrite + Unpin,
+{
+    /// Write new attribute value pair, the value is written as is.
+    ///
+    /// Use [`write_escaped_attr`](Self::write_escaped_attr) for values that may contain markup
+    /// characters or both quotes.
+    pub async fn write_attr<N, V>(&mut self, name: N, value: V) -> Result<()>
+    where
+        N: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let attr = if value.as_ref().contains('"') {
+            format!(" {}='{}'", name.as_ref(), value.as_ref())
+        } else {
+            format!(" {}=\"{}\"", name.as_ref(), value.as_ref())
+        };
+
+        self.sink.sink.write_all(attr.as_bytes()).await
+    }
+
+    /// Write new attribute, escaping the value, see [`escape_attr`].
+    pub async fn write_escaped_attr<N, V>(&mut self, name: N, value: V) -> Result<()>
+    where
+        N: AsRef<str>,
+        V: AsRef<str>,
+    {
+        // the escaped value contains no `"`, so it is always double quoted.
+        self.write_attr(name, escape_attr(value.as_ref())).await
+    }
+
+    /// Write namespace declarations in canonical order.
+    ///
+    /// See [`ElemStart::write_xmlns`](super::ElemStart::write_xmlns).
+    pub async fn write_xmlns<'b, B>(&mut self, bindings: B) -> Result<()>
+    where
+        B: IntoIterator<Item = (Option<&'b str>, &'b str)>,
+    {
+        for (prefix, uri) in canonical_xmlns(bindings, |_, _| false) {
+            self.write_attr(xmlns_name(prefix), uri).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the closing `>` or `/>` of this start tag.
+    pub async fn finish(self) -> Result<()> {
+        self.sink.close_pending().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AsyncXmlWriter;
+
+    #[tokio::test]
+    async fn test_async_write() {
+        let mut buf = Vec::new();
+
+        let mut writer = AsyncXmlWriter::new(&mut buf);
+
+        writer.write_comment("helloworld").await.unwrap();
+
+        assert_eq!(
+            writer.write_pi("pi", "a ?> b").await.unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+
+        let mut el = writer.write_elment_start("svg").await.unwrap();
+
+        el.write_xmlns([(Some("xlink"), "http://www.w3.org/1999/xlink")])
+            .await
+            .unwrap();
+        el.write_attr("hello", "world").await.unwrap();
+        el.finish().await.unwrap();
+
+        // the unfinished empty start tag is closed by the next write.
+        let mut el = writer.write_empty_elment("rect").await.unwrap();
+        el.write_attr("title", r#"say "hi""#).await.unwrap();
+        el.write_escaped_attr("alt", r#"<"it's">"#).await.unwrap();
+
+        writer.write_chardata("hello world").await.unwrap();
+        writer.write_text(" & <more>").await.unwrap();
+        assert_eq!(writer.current_element(), Some("svg"));
+        writer.write_element_end("svg").await.unwrap();
+        assert_eq!(writer.depth(), 0);
+        writer.flush().await.unwrap();
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r#"<!--helloworld--><svg xmlns:xlink="http://www.w3.org/1999/xlink" hello="world"><rect title='say "hi"' alt="&lt;&quot;it's&quot;&gt;"/>hello world &amp; &lt;more&gt;</svg>"#
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use std::io::{self, Write};
+
+/// Output buffer of [`XmlWriter`](super::XmlWriter), like [`std::io::BufWriter`] but able to
+/// give back its sink through `&mut self`, as the writer implements `Drop`.
+pub(super) struct BufSink<W> {
+    buf: Vec<u8>,
+    /// `None` after [`take`](Self::take).
+    inner: Option<W>,
+}
+
+impl<W> BufSink<W>
+where
+    W: Write,
+{
+    /// The buffer size, output is passed on in chunks of at least this size unless flushed.
+    pub(super) const CAPACITY: usize = 8 * 1024;
+
+    pub(super) fn new(inner: W) -> Self {
+        Self {
+            buf: Vec::with_capacity(Self::CAPACITY),
+            inner: Some(inner),
+        }
+    }
+
+    pub(super) fn get_ref(&self) -> &W {
+        self.inner.as_ref().expect("sink taken")
+    }
+
+    pub(super) fn get_mut(&mut self) -> &mut W {
+        self.inner.as_mut().expect("sink taken")
+    }
+
+    /// Write the buffered output to the sink, without flushing it.
+    pub(super) fn flush_buf(&mut self) -> io::Result<()> {
+        let Some(inner) = self.inner.as_mut() else {
+            return Ok(());
+        };
+
+        if !self.buf.is_empty() {
+            inner.write_all(&self.buf)?;
+            self.buf.clear();
+        }
+
+        Ok(())
+    }
+
+    /// Write the buffered output and return the sink, later writes fail.
+    pub(super) fn take(&mut self) -> io::Result<W> {
+        self.flush_buf()?;
+
+        Ok(self.inner.take().expect("sink taken"))
+    }
+}
+
+impl<W> Write for BufSink<W>
+where
+    W: Write,
+{
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.buf.len() + data.len() > Self::CAPACITY {
+            self.flush_buf()?;
+        }
+
+        let Some(inner) = self.inner.as_mut() else {
+            return Err(io::Error::other("sink taken"));
+        };
+
+        if data.len() >= Self::CAPACITY {
+            inner.write(data)
+        } else {
+            self.buf.extend_from_slice(data);
+            Ok(data.len())
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_buf()?;
+
+        match self.inner.as_mut() {
+            Some(inner) => inner.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::BufSink;
+
+    #[test]
+    fn test_buffering() {
+        let mut sink = BufSink::new(Vec::new());
+
+        sink.write_all(b"abc").unwrap();
+
+        assert!(sink.get_ref().is_empty());
+
+        sink.flush().unwrap();
+
+        assert_eq!(sink.get_ref(), b"abc");
+
+        let large = vec![b'x'; BufSink::<Vec<u8>>::CAPACITY];
+
+        sink.write_all(b"d").unwrap();
+        sink.write_all(&large).unwrap();
+
+        assert_eq!(sink.get_ref().len(), 4 + large.len());
+        assert_eq!(sink.take().unwrap().len(), 4 + large.len());
+        assert!(sink.write_all(b"e").is_err());
+        assert!(sink.flush().is_ok());
+    }
+}
@@ -0,0 +1,468 @@
+use std::{fmt::Debug, io::Write};
+
+use parserc::{AsBytes, Input};
+
+use crate::{
+    chars::normalize_newlines,
+    reader::{ElemStart, XmlNode, XmlReader},
+};
+
+use super::{Result, WriteError};
+
+/// The namespace bound to the `xml` prefix.
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Namespace rendering rules of a [`Canonicalizer`].
+#[derive(Debug, Default, PartialEq, Clone)]
+pub enum C14nMode {
+    /// [Canonical XML 1.0](https://www.w3.org/TR/xml-c14n/): namespace declarations are written
+    /// where they change the bindings in scope.
+    #[default]
+    Inclusive,
+    /// [Exclusive XML Canonicalization](https://www.w3.org/TR/xml-exc-c14n/): namespace
+    /// declarations are written where a name visibly uses them.
+    Exclusive {
+        /// the `InclusiveNamespaces PrefixList`, rendered with the inclusive rules;
+        /// `#default` is the default namespace.
+        inclusive_prefixes: Vec<String>,
+    },
+}
+
+/// Options of a [`Canonicalizer`].
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct C14nOptions {
+    /// namespace rendering rules.
+    pub mode: C14nMode,
+    /// keep comments, the `#WithComments` variants of the algorithms.
+    pub with_comments: bool,
+}
+
+/// Writes the canonical form of documents read by [`XmlReader`], e.g. for xml digital signatures.
+///
+/// The output is utf-8 without xml declaration and doctype. References and cdata sections are
+/// expanded, attribute values are normalized, empty elements are written as start/end tag pairs
+/// and attributes are sorted by namespace uri and local name, after the namespace declarations
+/// sorted by prefix. Comments and PIs outside the root element are separated by line feeds.
+pub struct Canonicalizer<W>
+where
+    W: Write,
+{
+    sink: W,
+    options: C14nOptions,
+    /// declared `(prefix, namespace)` bindings, `""` is the default prefix, innermost last.
+    bindings: Vec<(String, String)>,
+    /// the subset of `bindings` written to the output, innermost last.
+    rendered: Vec<(String, String)>,
+    /// element name and `bindings`/`rendered` lengths at the start of each open element.
+    stack: Vec<(String, usize, usize)>,
+    /// true if the root element has been closed.
+    root_closed: bool,
+}
+
+impl<W> Canonicalizer<W>
+where
+    W: Write,
+{
+    /// Create a canonicalizer writing to `sink`.
+    pub fn new(sink: W, options: C14nOptions) -> Self {
+        Self {
+            sink,
+            options,
+            bindings: vec![],
+            rendered: vec![],
+            stack: vec![],
+            root_closed: false,
+        }
+    }
+
+    /// Returns the underlying write.
+    pub fn into_inner(self) -> W {
+        self.sink
+    }
+
+    /// Read all nodes of `reader` and write their canonical form.
+    pub fn write_document<I>(&mut self, reader: &mut XmlReader<I>) -> Result<()>
+    where
+        I: Input<Item = u8> + AsBytes + Clone + Debug,
+    {
+        while let Some(node) = reader.read_next().map_err(read_err)? {
+            self.write_node(reader, node)?;
+        }
+
+        if let Some((name, _, _)) = self.stack.last() {
+            return Err(WriteError::InvalidEvent(format!(
+                "unclosed element `{}`",
+                name
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn write_node<I>(&mut self, reader: &XmlReader<I>, node: XmlNode<I>) -> Result<()>
+    where
+        I: Input<Item = u8> + AsBytes + Clone + Debug,
+    {
+        match node {
+            XmlNode::XmlDecl(_) | XmlNode::DocType(_) => Ok(()),
+            XmlNode::PI(pi) => {
+                let target = as_str(&pi.name)?;
+                let data = as_str(&pi.unparsed)?.trim_start();
+
+                self.write_outside(|sink| {
+                    if data.is_empty() {
+                        write!(sink, "<?{}?>", target)
+                    } else {
+                        write!(sink, "<?{} {}?>", target, normalize_newlines(data))
+                    }
+                })
+            }
+            XmlNode::Comment(comment) if self.options.with_comments => {
+                let comment = normalize_newlines(as_str(&comment.0)?);
+
+                self.write_outside(|sink| write!(sink, "<!--{}-->", comment))
+            }
+            XmlNode::Comment(_) => Ok(()),
+            // whitespace outside the root element is not part of the canonical form.
+            XmlNode::S(_) | XmlNode::CharData(_) | XmlNode::CData(_) | XmlNode::Text(_)
+                if self.stack.is_empty() =>
+            {
+                Ok(())
+            }
+            XmlNode::S(s) => self.write_text_escaped(&normalize_newlines(as_str(&s)?)),
+            XmlNode::CharData(chardata) => {
+                let raw = normalize_newlines(as_str(&chardata.0)?);
+                let raw = raw.as_bytes();
+
+                let text = reader.entities().expand(&raw).map_err(read_err)?;
+
+                self.write_text_escaped(&text)
+            }
+            XmlNode::CData(cdata) => {
+                self.write_text_escaped(&normalize_newlines(as_str(&cdata.0)?))
+            }
+            XmlNode::Text(text) => self.write_text_escaped(&text),
+            XmlNode::Start(start) => self.write_start(reader, start),
+            XmlNode::End(end) => self.write_end(as_str(&end.name)?),
+            XmlNode::Unknown(_) => Err(WriteError::InvalidEvent(
+                "unclassified markup has no canonical form".to_string(),
+            )),
+        }
+    }
+
+    /// Write a comment or PI, separated from the root element by a line feed.
+    fn write_outside<F>(&mut self, write: F) -> Result<()>
+    where
+        F: FnOnce(&mut W) -> std::io::Result<()>,
+    {
+        let outside = self.stack.is_empty();
+
+        if outside && self.root_closed {
+            self.sink.write_all(b"\n")?;
+        }
+
+        write(&mut self.sink)?;
+
+        if outside && !self.root_closed {
+            self.sink.write_all(b"\n")?;
+        }
+
+        Ok(())
+    }
+
+    fn write_text_escaped(&mut self, text: &str) -> Result<()> {
+        write_escaped(&mut self.sink, text, |c| match c {
+            b'&' => Some("&amp;"),
+            b'<' => Some("&lt;"),
+            b'>' => Some("&gt;"),
+            b'\r' => Some("&#xD;"),
+            _ => None,
+        })
+    }
+
+    fn write_start<I>(&mut self, reader: &XmlReader<I>, start: ElemStart<I>) -> Result<()>
+    where
+        I: Input<Item = u8> + AsBytes + Clone + Debug,
+    {
+        let name = as_str(&start.name)?.to_string();
+
+        self.stack
+            .push((name.clone(), self.bindings.len(), self.rendered.len()));
+
+        let mut attrs = vec![];
+
+        for attr in start.attrs() {
+            let attr = attr.map_err(read_err)?;
+            let attr_name = as_str(&attr.name)?;
+
+            // literal whitespace is normalized before references are expanded.
+            let raw = normalize_newlines(as_str(&attr.value)?).replace(['\t', '\n'], " ");
+
+            let value = reader
+                .entities()
+                .expand_attr(&raw.as_bytes())
+                .map_err(read_err)?
+                .into_owned();
+
+            if attr_name == "xmlns" {
+                self.bindings.push((String::new(), value));
+            } else if let Some(prefix) = attr_name.strip_prefix("xmlns:") {
+                self.bindings.push((prefix.to_string(), value));
+            } else {
+                attrs.push((attr_name.to_string(), value));
+            }
+        }
+
+        // prefixes whose declaration may be rendered on this element.
+        let mut prefixes = match &self.options.mode {
+            C14nMode::Inclusive => self.bindings[self.stack.last().unwrap().1..]
+                .iter()
+                .map(|(prefix, _)| prefix.clone())
+                .collect::<Vec<_>>(),
+            C14nMode::Exclusive { inclusive_prefixes } => {
+                let mut prefixes = inclusive_prefixes
+                    .iter()
+                    .map(|prefix| match prefix.as_str() {
+                        "#default" => String::new(),
+                        prefix => prefix.to_string(),
+                    })
+                    .filter(|prefix| lookup(&self.bindings, prefix).is_some())
+                    .collect::<Vec<_>>();
+
+                prefixes.push(prefix_of(&name).unwrap_or_default().to_string());
+
+                prefixes.extend(
+                    attrs
+                        .iter()
+                        .filter_map(|(name, _)| prefix_of(name))
+                        .map(str::to_string),
+                );
+
+                prefixes
+            }
+        };
+
+        prefixes.sort();
+        prefixes.dedup();
+
+        let mut namespaces = vec![];
+
+        for prefix in prefixes {
+            if prefix == "xml" {
+                continue;
+            }
+
+            let namespace = lookup(&self.bindings, &prefix).unwrap_or_default();
+
+            if namespace.is_empty() && !prefix.is_empty() {
+                return Err(WriteError::InvalidEvent(format!(
+                    "unbound namespace prefix `{}`",
+                    prefix
+                )));
+            }
+
+            if lookup(&self.rendered, &prefix).unwrap_or_default() != namespace {
+                namespaces.push((prefix, namespace.to_string()));
+            }
+        }
+
+        let mut sorted = Vec::with_capacity(attrs.len());
+
+        for (name, value) in &attrs {
+            let namespace = match prefix_of(name) {
+                Some("xml") => XML_NAMESPACE.to_string(),
+                Some(prefix) => lookup(&self.bindings, prefix)
+                    .ok_or_else(|| {
+                        WriteError::InvalidEvent(format!("unbound namespace prefix `{}`", prefix))
+                    })?
+                    .to_string(),
+                None => String::new(),
+            };
+
+            let local = name
+                .split_once(':')
+                .map_or(name.as_str(), |(_, local)| local);
+
+            sorted.push(((namespace, local), name, value));
+        }
+
+        sorted.sort_by(|a, b| a.0.cmp(&b.0));
+
+        write!(self.sink, "<{}", name)?;
+
+        for (prefix, namespace) in &namespaces {
+            if prefix.is_empty() {
+                write!(self.sink, " xmlns=\"")?;
+            } else {
+                write!(self.sink, " xmlns:{}=\"", prefix)?;
+            }
+
+            self.write_attr_escaped(namespace)?;
+            self.sink.write_all(b"\"")?;
+        }
+
+        for (_, name, value) in sorted {
+            write!(self.sink, " {}=\"", name)?;
+            self.write_attr_escaped(value)?;
+            self.sink.write_all(b"\"")?;
+        }
+
+        self.sink.write_all(b">")?;
+
+        self.rendered.extend(namespaces);
+
+        if start.is_empty {
+            self.write_end(&name)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_attr_escaped(&mut self, value: &str) -> Result<()> {
+        write_escaped(&mut self.sink, value, |c| match c {
+            b'&' => Some("&amp;"),
+            b'<' => Some("&lt;"),
+            b'"' => Some("&quot;"),
+            b'\t' => Some("&#x9;"),
+            b'\n' => Some("&#xA;"),
+            b'\r' => Some("&#xD;"),
+            _ => None,
+        })
+    }
+
+    fn write_end(&mut self, name: &str) -> Result<()> {
+        let Some((expected, bindings, rendered)) = self.stack.pop() else {
+            return Err(WriteError::UnexpectedEnd(name.to_string()));
+        };
+
+        if expected != name {
+            return Err(WriteError::Mismatch {
+                expected,
+                found: name.to_string(),
+            });
+        }
+
+        self.bindings.truncate(bindings);
+        self.rendered.truncate(rendered);
+        self.root_closed = self.stack.is_empty();
+
+        write!(self.sink, "</{}>", name)?;
+
+        Ok(())
+    }
+}
+
+fn read_err<E: Debug>(err: E) -> WriteError {
+    WriteError::InvalidEvent(format!("{:?}", err))
+}
+
+fn as_str<I>(raw: &I) -> Result<&str>
+where
+    I: AsBytes,
+{
+    std::str::from_utf8(raw.as_bytes())
+        .map_err(|err| WriteError::InvalidEvent(format!("invalid utf-8 sequence: {}", err)))
+}
+
+/// Returns the prefix of a qualified name.
+fn prefix_of(name: &str) -> Option<&str> {
+    name.split_once(':').map(|(prefix, _)| prefix)
+}
+
+/// Returns the namespace bound to `prefix`, innermost last.
+fn lookup<'a>(bindings: &'a [(String, String)], prefix: &str) -> Option<&'a str> {
+    bindings
+        .iter()
+        .rev()
+        .find(|(bound, _)| bound == prefix)
+        .map(|(_, namespace)| namespace.as_str())
+}
+
+fn write_escaped<W, F>(sink: &mut W, value: &str, escape: F) -> Result<()>
+where
+    W: Write,
+    F: Fn(u8) -> Option<&'static str>,
+{
+    let bytes = value.as_bytes();
+    let mut offset = 0;
+
+    for (i, c) in bytes.iter().enumerate() {
+        if let Some(replacement) = escape(*c) {
+            sink.write_all(&bytes[offset..i])?;
+            sink.write_all(replacement.as_bytes())?;
+            offset = i + 1;
+        }
+    }
+
+    sink.write_all(&bytes[offset..])?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reader::XmlReader;
+
+    use super::{C14nMode, C14nOptions, Canonicalizer};
+
+    fn canonicalize(source: &str, options: C14nOptions) -> String {
+        let mut canonicalizer = Canonicalizer::new(vec![], options);
+
+        canonicalizer
+            .write_document(&mut XmlReader::from(source.as_bytes()))
+            .unwrap();
+
+        String::from_utf8(canonicalizer.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn test_canonical() {
+        let source = "<?xml version=\"1.0\"?>\r\n<?pi  data?>\n<!--c-->\n<doc xmlns=\"urn:a\" xmlns:b=\"urn:b\" z='1' b:y=\"&quot;\" a=\"x\ty\"><e   /><b:f xmlns=\"urn:a\" xmlns:c=\"urn:c\"><![CDATA[<&>]]>&#xD;</b:f></doc>\n<!--d-->";
+
+        assert_eq!(
+            canonicalize(source, C14nOptions::default()),
+            "<?pi data?>\n<doc xmlns=\"urn:a\" xmlns:b=\"urn:b\" a=\"x y\" z=\"1\" b:y=\"&quot;\"><e></e><b:f xmlns:c=\"urn:c\">&lt;&amp;&gt;&#xD;</b:f></doc>"
+        );
+
+        assert_eq!(
+            canonicalize(
+                source,
+                C14nOptions {
+                    with_comments: true,
+                    ..Default::default()
+                }
+            ),
+            "<?pi data?>\n<!--c-->\n<doc xmlns=\"urn:a\" xmlns:b=\"urn:b\" a=\"x y\" z=\"1\" b:y=\"&quot;\"><e></e><b:f xmlns:c=\"urn:c\">&lt;&amp;&gt;&#xD;</b:f></doc>\n<!--d-->"
+        );
+    }
+
+    #[test]
+    fn test_exclusive() {
+        let source = r#"<?xml version="1.0"?><a:doc xmlns:a="urn:a" xmlns:b="urn:b" xmlns:c="urn:c" xmlns="urn:d"><e b:x="1"/><a:f/></a:doc>"#;
+
+        let exclusive = |inclusive_prefixes: &[&str]| {
+            canonicalize(
+                source,
+                C14nOptions {
+                    mode: C14nMode::Exclusive {
+                        inclusive_prefixes: inclusive_prefixes
+                            .iter()
+                            .map(|prefix| prefix.to_string())
+                            .collect(),
+                    },
+                    with_comments: false,
+                },
+            )
+        };
+
+        assert_eq!(
+            exclusive(&[]),
+            r#"<a:doc xmlns:a="urn:a"><e xmlns="urn:d" xmlns:b="urn:b" b:x="1"></e><a:f></a:f></a:doc>"#
+        );
+
+        assert_eq!(
+            exclusive(&["c", "#default"]),
+            r#"<a:doc xmlns="urn:d" xmlns:a="urn:a" xmlns:c="urn:c"><e xmlns:b="urn:b" b:x="1"></e><a:f></a:f></a:doc>"#
+        );
+    }
+}
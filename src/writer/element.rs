@@ -0,0 +1,186 @@
+use std::{fmt, io::Write};
+
+use super::{FmtSink, Result, XmlWriter};
+
+/// A child of an [`Element`].
+#[derive(Debug, PartialEq, Clone)]
+pub enum Content {
+    Element(Element),
+    /// Text, escaped when written.
+    Text(String),
+    CData(String),
+    Comment(String),
+}
+
+/// An element tree built in memory and written in one call, see [`write_to`](Self::write_to).
+///
+/// ```
+/// use rexml::writer::Element;
+///
+/// let svg = Element::new("svg")
+///     .attr("width", 100)
+///     .child(Element::new("rect").attr("x", 1.5))
+///     .child(Element::new("text").text("a < b"));
+///
+/// assert_eq!(
+///     svg.to_string(),
+///     r#"<svg width="100"><rect x="1.5"/><text>a &lt; b</text></svg>"#
+/// );
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct Element {
+    pub name: String,
+    /// `(name, value)` pairs in written order, values are escaped when written.
+    pub attrs: Vec<(String, String)>,
+    pub children: Vec<Content>,
+}
+
+impl Element {
+    /// Create an element without attributes and children.
+    pub fn new<N>(name: N) -> Self
+    where
+        N: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            attrs: vec![],
+            children: vec![],
+        }
+    }
+
+    /// Add the attribute `name`, with `value` formatted by [`Display`](fmt::Display).
+    pub fn attr<N, V>(mut self, name: N, value: V) -> Self
+    where
+        N: Into<String>,
+        V: fmt::Display,
+    {
+        self.attrs.push((name.into(), value.to_string()));
+        self
+    }
+
+    /// Append the child element `child`.
+    pub fn child(mut self, child: Element) -> Self {
+        self.children.push(Content::Element(child));
+        self
+    }
+
+    /// Append all elements of `children`.
+    pub fn children<C>(mut self, children: C) -> Self
+    where
+        C: IntoIterator<Item = Element>,
+    {
+        self.children
+            .extend(children.into_iter().map(Content::Element));
+        self
+    }
+
+    /// Append a text node.
+    pub fn text<T>(mut self, text: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.children.push(Content::Text(text.into()));
+        self
+    }
+
+    /// Append a cdata section.
+    pub fn cdata<T>(mut self, content: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.children.push(Content::CData(content.into()));
+        self
+    }
+
+    /// Append a comment.
+    pub fn comment<T>(mut self, content: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.children.push(Content::Comment(content.into()));
+        self
+    }
+
+    /// Write this element and its descendants to `writer`.
+    ///
+    /// Elements without children are written as empty elements.
+    pub fn write_to<W>(&self, writer: &mut XmlWriter<W>) -> Result<()>
+    where
+        W: Write,
+    {
+        let mut start = match self.children.is_empty() {
+            true => writer.write_empty_elment(&self.name)?,
+            false => writer.write_elment_start(&self.name)?,
+        };
+
+        for (name, value) in &self.attrs {
+            start.write_escaped_attr(name, value)?;
+        }
+
+        start.finish()?;
+
+        if self.children.is_empty() {
+            return Ok(());
+        }
+
+        for child in &self.children {
+            match child {
+                Content::Element(el) => el.write_to(writer)?,
+                Content::Text(text) => writer.write_text(text)?,
+                Content::CData(content) => writer.write_cdata(content)?,
+                Content::Comment(content) => writer.write_comment(content)?,
+            }
+        }
+
+        writer.write_element_end(&self.name)
+    }
+}
+
+impl fmt::Display for Element {
+    /// Write this element as a fragment.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut writer = XmlWriter::fragment(FmtSink(f));
+
+        self.write_to(&mut writer)
+            .and_then(|_| writer.flush())
+            .map_err(|_| fmt::Error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::writer::{WriteError, XmlWriter};
+
+    use super::Element;
+
+    #[test]
+    fn test_write_to() {
+        let mut buf = Vec::new();
+
+        let mut writer = XmlWriter::new(&mut buf).with_indent("  ");
+
+        Element::new("svg")
+            .attr("title", "\"a\" & b")
+            .comment("shapes")
+            .children((0..2).map(|i| Element::new("rect").attr("x", i * 10)))
+            .write_to(&mut writer)
+            .unwrap();
+
+        drop(writer);
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "<svg title=\"&quot;a&quot; &amp; b\">\n  <!--shapes-->\n  <rect x=\"0\"/>\n  <rect x=\"10\"/>\n</svg>"
+        );
+    }
+
+    #[test]
+    fn test_invalid_comment() {
+        let mut writer = XmlWriter::new(Vec::new());
+
+        assert!(matches!(
+            Element::new("a").comment("a--b").write_to(&mut writer),
+            Err(WriteError::InvalidComment(_))
+        ));
+    }
+}
@@ -0,0 +1,74 @@
+use std::io;
+
+/// Error returned by [`XmlWriter`](super::XmlWriter).
+#[derive(Debug, thiserror::Error)]
+pub enum WriteError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    /// `write_xml_decl` was called twice, or after the declaration was written by [`DeclPolicy::Always`](super::DeclPolicy::Always).
+    #[error("xml declaration already written")]
+    DuplicateDecl,
+
+    /// A node passed to `write_event` can't be written, e.g. its text is not utf-8.
+    #[error("invalid event: {0}")]
+    InvalidEvent(String),
+
+    /// Strict mode only: the end tag does not close the innermost open element.
+    #[error("end tag `{found}` does not match start tag `{expected}`")]
+    Mismatch { expected: String, found: String },
+
+    /// Strict mode only: an end tag was written without open element.
+    #[error("end tag `{0}` without open element")]
+    UnexpectedEnd(String),
+
+    /// Strict mode only: the attribute was already written on this start tag.
+    #[error("duplicate attribute `{0}`")]
+    DuplicateAttr(String),
+
+    /// Strict mode only: a start tag was written after the root element was closed.
+    #[error("second root element `{0}`")]
+    MultipleRoots(String),
+
+    /// A qualified name written by [`NsXmlWriter`](super::NsXmlWriter) uses a prefix without
+    /// namespace declaration in scope.
+    #[error("unbound namespace prefix `{0}`")]
+    UnboundPrefix(String),
+
+    /// A doctype declaration can't be written as given, e.g. its system literal contains both quotes.
+    #[error("invalid doctype: {0}")]
+    InvalidDoctype(String),
+
+    /// A doctype written after `standalone="yes"` requires declarations outside of the document,
+    /// e.g. it has an external subset or declares a parameter or external entity.
+    #[error("standalone document with {0}")]
+    NotStandalone(String),
+
+    /// The comment content contains `--` or ends with `-`, which can't be written.
+    #[error("illegal comment `{0}`")]
+    InvalidComment(String),
+
+    /// The document was closed with the carried element still open.
+    #[error("unclosed element `{0}`")]
+    Unclosed(String),
+
+    /// The document was closed without root element.
+    #[error("document without root element")]
+    NoRoot,
+
+    /// A xml declaration, doctype or prolog was written by a [`fragment`](super::XmlWriter::fragment) writer.
+    #[error("fragments have no prolog")]
+    PrologInFragment,
+
+    /// Strict mode only: a doctype declaration was written twice, or after the root element was started.
+    #[error("doctype declaration must precede the root element")]
+    MisplacedDoctype,
+
+    /// The content of a processing instruction contains `?>`, which can't be written.
+    #[error("illegal processing instruction `{0}`")]
+    InvalidPI(String),
+
+    /// An infinite or NaN float was written as attribute value.
+    #[error("non-finite number `{0}`")]
+    NonFinite(f64),
+}
@@ -0,0 +1,201 @@
+use std::{borrow::Borrow, fmt::Debug, io::Write};
+
+use parserc::{AsBytes, Input};
+
+use crate::reader::{OwnedEvent, XmlDecl, XmlNode};
+
+use super::{DeclPolicy, Result, WriteError, XmlWriter};
+
+fn as_str<I>(raw: &I) -> Result<&str>
+where
+    I: AsBytes,
+{
+    std::str::from_utf8(raw.as_bytes())
+        .map_err(|err| WriteError::InvalidEvent(format!("invalid utf-8 sequence: {}", err)))
+}
+
+impl<W> XmlWriter<W>
+where
+    W: Write,
+{
+    /// Write a node returned by [`XmlReader`](crate::reader::XmlReader).
+    ///
+    /// Text and attribute values are written as read, references are not expanded again.
+    /// Coalesced [`XmlNode::Text`] is escaped.
+    pub fn write_event<I>(&mut self, node: &XmlNode<I>) -> Result<()>
+    where
+        I: Input<Item = u8> + AsBytes + Clone + Debug,
+    {
+        match node {
+            XmlNode::XmlDecl(decl) => self.write_xml_decl(
+                decl.version,
+                decl.encoding.as_ref().map(as_str).transpose()?,
+                decl.standalone,
+            ),
+            XmlNode::DocType(doctype) => self.write_doctype_raw(as_str(&doctype.0)?),
+            XmlNode::PI(pi) => self.write_raw_pi(as_str(&pi.name)?, as_str(&pi.unparsed)?),
+            XmlNode::S(s) => self.write_chardata(as_str(s)?),
+            XmlNode::Comment(comment) => self.write_comment(as_str(&comment.0)?),
+            XmlNode::Start(start) => {
+                let name = as_str(&start.name)?;
+
+                let mut el = if start.is_empty {
+                    self.write_empty_elment(name)?
+                } else {
+                    self.write_elment_start(name)?
+                };
+
+                for attr in start.attrs() {
+                    let attr = attr.map_err(|err| {
+                        WriteError::InvalidEvent(format!("invalid attribute: {:?}", err))
+                    })?;
+
+                    el.write_attr(as_str(&attr.name)?, as_str(&attr.value)?)?;
+                }
+
+                el.finish()
+            }
+            XmlNode::End(end) => self.write_element_end(as_str(&end.name)?),
+            XmlNode::CharData(chardata) => self.write_chardata(as_str(&chardata.0)?),
+            XmlNode::CData(cdata) => self.write_cdata(as_str(&cdata.0)?),
+            XmlNode::Text(text) => self.write_text(text),
+            XmlNode::Unknown(unknown) => {
+                self.begin()?;
+                self.sink.write_all(unknown.0.as_bytes())?;
+
+                Ok(())
+            }
+        }
+    }
+
+    /// Write the xml declaration `decl` exactly as read, keeping its quote style and whitespace,
+    /// see [`XmlDecl::raw`].
+    ///
+    /// The output options of the declaration, e.g. [`version`](super::OutputOptions::version),
+    /// are not applied, except [`DeclPolicy::Never`].
+    pub fn write_raw_xml_decl<I>(&mut self, decl: &XmlDecl<I>) -> Result<()>
+    where
+        I: AsBytes,
+    {
+        if self.fragment {
+            return Err(WriteError::PrologInFragment);
+        }
+
+        if self.decl_written {
+            return Err(WriteError::DuplicateDecl);
+        }
+
+        if self.options.decl == DeclPolicy::Never {
+            return Ok(());
+        }
+
+        self.started = true;
+        self.decl_written = true;
+        self.standalone = decl.standalone;
+
+        self.break_line(0)?;
+        self.sink.write_all(decl.raw.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Write a node owning its content, see [`XmlNode::into_owned`].
+    pub fn write_owned_event(&mut self, node: &OwnedEvent) -> Result<()> {
+        self.write_event(&node.as_slice())
+    }
+
+    /// Write all `events`, e.g. the filtered nodes of a [`XmlReader`](crate::reader::XmlReader).
+    pub fn write_events<I, E>(&mut self, events: E) -> Result<()>
+    where
+        I: Input<Item = u8> + AsBytes + Clone + Debug,
+        E: IntoIterator,
+        E::Item: Borrow<XmlNode<I>>,
+    {
+        for event in events {
+            self.write_event(event.borrow())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        reader::{XmlNode, XmlReader},
+        writer::XmlWriter,
+    };
+
+    #[test]
+    fn test_round_trip() {
+        let source = r#"<?xml version="1.0" encoding="UTF-8"?><!DOCTYPE svg><!--c--><?t d?><svg width='1"'><g>a &amp; b<![CDATA[<c>]]><?u?></g><rect/></svg>"#;
+
+        let mut buf = Vec::new();
+
+        let mut writer = XmlWriter::new(&mut buf);
+
+        writer
+            .write_events(XmlReader::from(source.as_bytes()).map(Result::unwrap))
+            .unwrap();
+
+        drop(writer);
+
+        assert_eq!(String::from_utf8(buf).unwrap(), source);
+    }
+
+    #[test]
+    fn test_owned_round_trip() {
+        let source = r#"<?xml version="1.0"?><svg><rect x="1"/>a &lt; b</svg>"#;
+
+        let events = XmlReader::from(source.as_bytes())
+            .map(|node| node.unwrap().into_owned())
+            .collect::<Vec<_>>();
+
+        let mut writer = XmlWriter::new(Vec::new());
+
+        for event in &events {
+            writer.write_owned_event(event).unwrap();
+        }
+
+        assert_eq!(writer.into_inner().unwrap(), source.as_bytes());
+    }
+
+    #[test]
+    fn test_raw_xml_decl() {
+        let source = "<?xml version = '1.0'  encoding=\"UTF-8\" ?><a/>";
+
+        let mut writer = XmlWriter::new(Vec::new());
+
+        for node in XmlReader::from(source.as_bytes()) {
+            match node.unwrap() {
+                XmlNode::XmlDecl(decl) => writer.write_raw_xml_decl(&decl).unwrap(),
+                node => writer.write_event(&node).unwrap(),
+            }
+        }
+
+        assert_eq!(writer.into_inner().unwrap(), source.as_bytes());
+    }
+
+    #[test]
+    fn test_filter() {
+        let source = r#"<?xml version="1.0"?><svg><!--c--><rect/></svg>"#;
+
+        let events = XmlReader::from(source.as_bytes())
+            .map(Result::unwrap)
+            .filter(|node| !matches!(node, XmlNode::Comment(_)))
+            .collect::<Vec<_>>();
+
+        let mut buf = Vec::new();
+
+        let mut writer = XmlWriter::new(&mut buf);
+
+        writer.write_events(&events).unwrap();
+
+        drop(writer);
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r#"<?xml version="1.0"?><svg><rect/></svg>"#
+        );
+    }
+}
@@ -0,0 +1,1980 @@
+use std::{fmt, io::Write};
+
+use crate::{
+    chars::{illegal_comment_offset, is_name, is_pubid_char, is_ws},
+    escape::{escape_attr, escape_cdata, escape_text},
+    types::{Prolog, XmlStylesheet, XmlVersion},
+};
+
+mod errors;
+pub use errors::*;
+mod options;
+pub use options::*;
+mod ns;
+pub use ns::*;
+mod adapter;
+pub use adapter::*;
+mod element;
+pub use element::*;
+mod buffer;
+use buffer::BufSink;
+mod number;
+pub use number::Integer;
+use number::{EscapeAttr, round};
+
+#[cfg(feature = "reader-core")]
+mod canonical;
+#[cfg(feature = "reader-core")]
+mod event;
+#[cfg(feature = "reader-core")]
+pub use canonical::*;
+
+type Result<T> = std::result::Result<T, WriteError>;
+
+#[cfg(feature = "async")]
+mod async_writer;
+#[cfg(feature = "async")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+pub use async_writer::*;
+
+/// A low-level xml document writer without semnatic check.
+///
+/// Call [`strict`](Self::strict) to reject output that is not well-formed: mismatched end tags,
+/// duplicate attributes, multiple root elements and misplaced doctype declarations. Attributes can't be written after child
+/// content in either mode, as [`ElemStart`] borrows the writer until the start tag is closed.
+pub struct XmlWriter<W>
+where
+    W: Write,
+{
+    /// underlying write, behind an output buffer.
+    sink: BufSink<W>,
+    /// output options.
+    options: OutputOptions,
+    /// true if anything has been written.
+    started: bool,
+    /// true if the xml declaration has been written.
+    decl_written: bool,
+    /// the effective standalone flag of the written declaration.
+    standalone: Option<bool>,
+    /// names of the open elements, outermost first.
+    stack: Vec<String>,
+    /// namespace bindings in scope `(prefix, uri)`, innermost last.
+    bindings: Vec<(Option<String>, String)>,
+    /// `bindings` length at the start of each open element.
+    scopes: Vec<usize>,
+    /// true if the next node starts on a new line when indenting.
+    needs_break: bool,
+    /// true if the last node written is a non-empty start tag.
+    empty_open: bool,
+    /// depth of the outermost open element containing text, indentation is off inside it.
+    text_depth: Option<usize>,
+    /// true if well-formedness checks are on.
+    strict: bool,
+    /// true if a root element has been started.
+    root_written: bool,
+    /// true if a doctype declaration has been written.
+    doctype_written: bool,
+    /// namespace declarations of the prolog, written on the root element.
+    root_namespaces: Vec<(Option<String>, String)>,
+    /// true if writing a fragment, see [`fragment`](Self::fragment).
+    fragment: bool,
+}
+
+impl<W> XmlWriter<W>
+where
+    W: Write,
+{
+    /// Create a xml document writer from [`std::io::Write`].
+    pub fn new(sink: W) -> Self {
+        Self::with_options(sink, Default::default())
+    }
+
+    /// Create a xml document writer from [`std::io::Write`] with output `options`.
+    pub fn with_options(sink: W, options: OutputOptions) -> Self {
+        Self {
+            sink: BufSink::new(sink),
+            options,
+            started: false,
+            decl_written: false,
+            standalone: None,
+            stack: vec![],
+            bindings: vec![],
+            scopes: vec![],
+            needs_break: false,
+            empty_open: false,
+            text_depth: None,
+            strict: false,
+            root_written: false,
+            doctype_written: false,
+            root_namespaces: vec![],
+            fragment: false,
+        }
+    }
+
+    /// Create a writer of a fragment, e.g. a SOAP body or XMPP stanza embedded into a parent document.
+    ///
+    /// Fragments have no prolog: no declaration is written, even with [`DeclPolicy::Always`],
+    /// and the prolog methods fail with [`WriteError::PrologInFragment`]. Any number of top-level
+    /// elements and text may be written in strict mode, which still checks tags and attributes.
+    pub fn fragment(sink: W) -> Self {
+        let mut writer = Self::new(sink);
+        writer.fragment = true;
+        writer
+    }
+
+    /// Returns true if this writer writes a fragment, see [`fragment`](Self::fragment).
+    pub fn is_fragment(&self) -> bool {
+        self.fragment
+    }
+
+    /// Turn on well-formedness checks, violations return a [`WriteError`] instead of being written.
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Returns true if well-formedness checks are on.
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Indent nested nodes with `indent`, see [`OutputOptions::indent`].
+    pub fn with_indent<S>(mut self, indent: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.options.indent = Some(indent.into());
+        self
+    }
+
+    /// Treat `bindings` as declared by the enclosing document.
+    ///
+    /// Use this when writing a fragment into a larger document (e.g. a SOAP body payload):
+    /// [`ElemStart::write_xmlns`] skips declarations already in scope with the same namespace.
+    pub fn with_inherited_namespaces<'b, B>(mut self, bindings: B) -> Self
+    where
+        B: IntoIterator<Item = (Option<&'b str>, &'b str)>,
+    {
+        self.bindings.extend(
+            bindings
+                .into_iter()
+                .map(|(prefix, uri)| (prefix.map(str::to_string), uri.to_string())),
+        );
+
+        self
+    }
+
+    /// Returns the namespace bound to `prefix` in the current scope, `None` prefix is the default namespace.
+    pub fn lookup_namespace(&self, prefix: Option<&str>) -> Option<&str> {
+        self.bindings
+            .iter()
+            .rev()
+            .find(|(bound, _)| bound.as_deref() == prefix)
+            .map(|(_, uri)| uri.as_str())
+            .filter(|uri| !uri.is_empty())
+    }
+
+    /// Returns the output options of this writer.
+    pub fn options(&self) -> &OutputOptions {
+        &self.options
+    }
+
+    /// Returns the effective `standalone` flag of the written xml declaration.
+    pub fn standalone(&self) -> Option<bool> {
+        self.standalone
+    }
+
+    /// Returns the number of open elements.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Returns the name of the innermost open element.
+    pub fn current_element(&self) -> Option<&str> {
+        self.stack.last().map(String::as_str)
+    }
+
+    /// Create an iterator over the names of the open elements, outermost first.
+    pub fn open_elements(&self) -> impl ExactSizeIterator<Item = &str> + DoubleEndedIterator {
+        self.stack.iter().map(String::as_str)
+    }
+
+    /// Called before writing any node, emits the xml declaration if [`DeclPolicy::Always`] is set.
+    fn begin(&mut self) -> Result<()> {
+        if !self.started {
+            self.started = true;
+
+            if self.options.decl == DeclPolicy::Always && !self.fragment {
+                self.write_decl(XmlVersion::Ver10, None, None)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Called before writing a node at `depth`, starts a new indented line if indenting.
+    fn break_line(&mut self, depth: usize) -> Result<()> {
+        if let Some(indent) = &self.options.indent
+            && self.needs_break
+            && self.text_depth.is_none()
+        {
+            self.sink
+                .write_all(self.options.newline.as_str().as_bytes())?;
+
+            for _ in 0..depth {
+                self.sink.write_all(indent.as_bytes())?;
+            }
+        }
+
+        self.needs_break = true;
+        self.empty_open = false;
+
+        Ok(())
+    }
+
+    /// Called before writing text, turns indentation off until the current element ends.
+    fn enter_text(&mut self) {
+        if self.text_depth.is_none() && !self.stack.is_empty() {
+            self.text_depth = Some(self.stack.len());
+        }
+
+        self.empty_open = false;
+    }
+
+    /// Write xml declaration, the [`OutputOptions`] may override or suppress the arguments.
+    pub fn write_xml_decl(
+        &mut self,
+        version: XmlVersion,
+        encoding: Option<&str>,
+        standalone: Option<bool>,
+    ) -> Result<()> {
+        if self.fragment {
+            return Err(WriteError::PrologInFragment);
+        }
+
+        if self.decl_written {
+            return Err(WriteError::DuplicateDecl);
+        }
+
+        if self.options.decl == DeclPolicy::Never {
+            return Ok(());
+        }
+
+        self.started = true;
+
+        self.write_decl(version, encoding, standalone)
+    }
+
+    /// Write the xml declaration and doctype of `prolog`.
+    pub fn write_prolog(&mut self, prolog: &Prolog) -> Result<()> {
+        self.write_xml_decl(
+            prolog.version,
+            prolog.encoding.as_deref(),
+            prolog.standalone,
+        )?;
+
+        if let Some(doctype) = &prolog.doctype {
+            self.write_doctype_raw(&format!(" {}", doctype))?;
+        }
+
+        self.root_namespaces = prolog.namespaces.clone();
+
+        Ok(())
+    }
+
+    /// Write a [`doctype declaration`](https://www.w3.org/TR/xml11/#NT-doctypedecl).
+    ///
+    /// `public_id` requires a `system_id`. Literals are quoted with `"`, or with `'` if they contain
+    /// a `"`. The `internal_subset` is written as is between `[` and `]`. After `standalone="yes"`,
+    /// an external subset and parameter or external entity declarations fail with
+    /// [`WriteError::NotStandalone`].
+    pub fn write_doctype(
+        &mut self,
+        name: &str,
+        public_id: Option<&str>,
+        system_id: Option<&str>,
+        internal_subset: Option<&str>,
+    ) -> Result<()> {
+        let mut content = doctype_head(name, public_id, system_id)?;
+
+        if let Some(internal_subset) = internal_subset {
+            content.push_str(" [");
+            content.push_str(internal_subset);
+            content.push(']');
+        }
+
+        self.write_doctype_raw(&content)
+    }
+
+    /// Start a doctype declaration with an internal subset, written by the returned [`DocTypeStart`].
+    ///
+    /// The arguments are checked as by [`write_doctype`](Self::write_doctype), close the subset
+    /// with [`DocTypeStart::finish`].
+    pub fn write_doctype_start(
+        &mut self,
+        name: &str,
+        public_id: Option<&str>,
+        system_id: Option<&str>,
+    ) -> Result<DocTypeStart<'_, W>> {
+        let head = doctype_head(name, public_id, system_id)?;
+
+        self.begin_doctype(&head)?;
+
+        self.sink.write_all(b" [")?;
+
+        Ok(DocTypeStart {
+            sink: self,
+            finished: false,
+        })
+    }
+
+    /// Write `<!DOCTYPE{content}>`.
+    fn write_doctype_raw(&mut self, content: &str) -> Result<()> {
+        self.begin_doctype(content)?;
+
+        self.sink.write_all(b">")?;
+
+        Ok(())
+    }
+
+    /// Write `<!DOCTYPE{content}`, leaving the declaration open.
+    fn begin_doctype(&mut self, content: &str) -> Result<()> {
+        if self.fragment {
+            return Err(WriteError::PrologInFragment);
+        }
+
+        if self.strict && (self.doctype_written || self.root_written) {
+            return Err(WriteError::MisplacedDoctype);
+        }
+
+        if self.standalone == Some(true)
+            && let Some(markup) = external_markup(content)
+        {
+            return Err(WriteError::NotStandalone(markup.to_string()));
+        }
+
+        self.doctype_written = true;
+
+        self.begin()?;
+        self.break_line(0)?;
+
+        self.sink.write_fmt(format_args!("<!DOCTYPE{}", content))?;
+
+        Ok(())
+    }
+
+    fn write_decl(
+        &mut self,
+        version: XmlVersion,
+        encoding: Option<&str>,
+        standalone: Option<bool>,
+    ) -> Result<()> {
+        let version = self.options.version.unwrap_or(version);
+        let standalone = self.options.standalone.apply(standalone);
+
+        self.decl_written = true;
+        self.standalone = standalone;
+
+        self.break_line(0)?;
+
+        self.sink
+            .write_fmt(format_args!("<?xml version=\"{}\"", version))?;
+
+        if let Some(encoding) = encoding {
+            self.sink
+                .write_fmt(format_args!(" encoding=\"{}\"", encoding))?;
+        }
+
+        if let Some(standalone) = standalone {
+            self.sink.write_fmt(format_args!(
+                " standalone=\"{}\"",
+                if standalone { "yes" } else { "no" }
+            ))?;
+        }
+
+        self.sink.write_all(b"?>")?;
+
+        Ok(())
+    }
+
+    /// Write pi node, fails with [`WriteError::InvalidPI`] if `unparsed` contains `?>`.
+    pub fn write_pi<N, U>(&mut self, name: N, unparsed: U) -> Result<()>
+    where
+        N: AsRef<str>,
+        U: AsRef<str>,
+    {
+        self.begin_pi(unparsed.as_ref())?;
+
+        self.sink
+            .write_fmt(format_args!("<?{} {} ?>", name.as_ref(), unparsed.as_ref()))?;
+
+        Ok(())
+    }
+
+    /// Write `<?{name}{unparsed}?>`, keeping the whitespace read before and after the content.
+    fn write_raw_pi(&mut self, name: &str, unparsed: &str) -> Result<()> {
+        self.begin_pi(unparsed)?;
+
+        self.sink
+            .write_fmt(format_args!("<?{}{}?>", name, unparsed))?;
+
+        Ok(())
+    }
+
+    fn begin_pi(&mut self, unparsed: &str) -> Result<()> {
+        if unparsed.contains("?>") {
+            return Err(WriteError::InvalidPI(unparsed.to_string()));
+        }
+
+        self.begin()?;
+        self.break_line(self.stack.len())
+    }
+
+    /// Write an `xml-stylesheet` processing instruction, see [`XmlStylesheet`].
+    pub fn write_stylesheet_pi(&mut self, stylesheet: &XmlStylesheet) -> Result<()> {
+        self.write_pi(XmlStylesheet::TARGET, stylesheet.content())
+    }
+
+    /// Write comment node, fails with [`WriteError::InvalidComment`] if `content` contains `--`
+    /// or ends with `-`.
+    pub fn write_comment<C>(&mut self, content: C) -> Result<()>
+    where
+        C: AsRef<str>,
+    {
+        if illegal_comment_offset(content.as_ref().as_bytes()).is_some() {
+            return Err(WriteError::InvalidComment(content.as_ref().to_string()));
+        }
+
+        self.begin()?;
+        self.break_line(self.stack.len())?;
+
+        self.sink
+            .write_fmt(format_args!("<!--{}-->", content.as_ref()))?;
+
+        Ok(())
+    }
+
+    /// Write cdata, a `]]>` in `content` is split across two sections, see [`escape_cdata`].
+    pub fn write_cdata<C>(&mut self, content: C) -> Result<()>
+    where
+        C: AsRef<str>,
+    {
+        self.begin()?;
+        self.enter_text();
+
+        self.sink.write_fmt(format_args!(
+            "<![CDATA[{}]]>",
+            escape_cdata(content.as_ref())
+        ))?;
+
+        Ok(())
+    }
+
+    /// Write cdata.
+    pub fn write_chardata<C>(&mut self, content: C) -> Result<()>
+    where
+        C: AsRef<str>,
+    {
+        self.begin()?;
+        self.enter_text();
+
+        self.sink.write_all(content.as_ref().as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Write text, escaping markup characters, see [`escape_text`].
+    ///
+    /// Unlike [`write_chardata`](Self::write_chardata), any string produces well-formed content.
+    /// With [`HtmlPolicy::Xhtml`], text of `script` and `style` elements is written as is.
+    pub fn write_text<C>(&mut self, content: C) -> Result<()>
+    where
+        C: AsRef<str>,
+    {
+        if let Some(name) = self.stack.last()
+            && self.options.html.is_raw_text(name)
+        {
+            return self.write_chardata(content);
+        }
+
+        self.write_chardata(escape_text(content.as_ref()))
+    }
+
+    /// Start write element start tag.
+    pub fn write_elment_start<N>(&mut self, name: N) -> Result<ElemStart<'_, W>>
+    where
+        N: AsRef<str>,
+    {
+        self.check_root(name.as_ref())?;
+        self.begin()?;
+        self.break_line(self.stack.len())?;
+
+        self.sink.write_fmt(format_args!("<{}", name.as_ref()))?;
+
+        self.stack.push(name.as_ref().to_string());
+        self.scopes.push(self.bindings.len());
+        self.empty_open = true;
+
+        ElemStart {
+            sink: self,
+            is_empty: false,
+            expanded: None,
+            attrs: vec![],
+            names: vec![],
+            finished: false,
+        }
+        .with_root_namespaces()
+    }
+
+    /// Start write empty element start tag.
+    pub fn write_empty_elment<N>(&mut self, name: N) -> Result<ElemStart<'_, W>>
+    where
+        N: AsRef<str>,
+    {
+        self.check_root(name.as_ref())?;
+        self.begin()?;
+        self.break_line(self.stack.len())?;
+
+        self.sink.write_fmt(format_args!("<{}", name.as_ref()))?;
+
+        self.scopes.push(self.bindings.len());
+
+        let expanded = match self.options.html {
+            HtmlPolicy::Xml => self.options.self_close == SelfClose::Expanded,
+            HtmlPolicy::Xhtml => !self.options.html.is_void(name.as_ref()),
+        }
+        .then(|| name.as_ref().to_string());
+
+        ElemStart {
+            sink: self,
+            is_empty: true,
+            expanded,
+            attrs: vec![],
+            names: vec![],
+            finished: false,
+        }
+        .with_root_namespaces()
+    }
+
+    /// Write a element end tag.
+    pub fn write_element_end(&mut self, name: &str) -> Result<()> {
+        if self.strict {
+            match self.stack.last() {
+                None => return Err(WriteError::UnexpectedEnd(name.to_string())),
+                Some(expected) if expected != name => {
+                    return Err(WriteError::Mismatch {
+                        expected: expected.clone(),
+                        found: name.to_string(),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        self.begin()?;
+
+        // keep `<a></a>` on one line.
+        if self.empty_open {
+            self.needs_break = false;
+        }
+
+        self.break_line(self.stack.len().saturating_sub(1))?;
+
+        self.sink.write_fmt(format_args!("</{}>", name))?;
+
+        if self.text_depth == Some(self.stack.len()) {
+            self.text_depth = None;
+        }
+
+        self.stack.pop();
+        self.pop_scope();
+
+        Ok(())
+    }
+
+    /// Finish the document and flush the sink, checking that it is complete.
+    ///
+    /// Elements left open fail with [`WriteError::Unclosed`] or are closed, per `policy`, and a
+    /// document without root element fails with [`WriteError::NoRoot`]; fragments may have none.
+    /// Prefer it to dropping the writer, which flushes too but only logs errors.
+    pub fn close(&mut self, policy: UnclosedPolicy) -> Result<()> {
+        if let Some(name) = self.stack.last() {
+            if policy == UnclosedPolicy::Error {
+                return Err(WriteError::Unclosed(name.clone()));
+            }
+
+            while let Some(name) = self.stack.last() {
+                let name = name.clone();
+                self.write_element_end(&name)?;
+            }
+        }
+
+        if !self.fragment && !self.root_written {
+            return Err(WriteError::NoRoot);
+        }
+
+        self.sink.flush()?;
+
+        Ok(())
+    }
+
+    /// Write the buffered output to the sink and flush it.
+    ///
+    /// Output is buffered in chunks of 8 KiB, the buffer is also flushed by
+    /// [`close`](Self::close), [`into_inner`](Self::into_inner) and when the writer is dropped.
+    pub fn flush(&mut self) -> Result<()> {
+        self.sink.flush()?;
+
+        Ok(())
+    }
+
+    /// Returns the sink, output still in the buffer is not written to it yet.
+    pub fn get_ref(&self) -> &W {
+        self.sink.get_ref()
+    }
+
+    /// Returns the sink, writing to it directly may corrupt the output.
+    pub fn get_mut(&mut self) -> &mut W {
+        self.sink.get_mut()
+    }
+
+    /// Write the buffered output and return the sink, e.g. the `Vec<u8>` written to.
+    ///
+    /// The document is not checked for completeness, see [`close`](Self::close), and the sink
+    /// is not flushed.
+    pub fn into_inner(mut self) -> Result<W> {
+        Ok(self.sink.take()?)
+    }
+}
+
+impl<W> XmlWriter<W>
+where
+    W: Write,
+{
+    /// Called before writing a start tag, rejects a second root element in strict mode.
+    fn check_root(&mut self, name: &str) -> Result<()> {
+        if self.stack.is_empty() && !self.fragment {
+            if self.strict && self.root_written {
+                return Err(WriteError::MultipleRoots(name.to_string()));
+            }
+
+            self.root_written = true;
+        }
+
+        Ok(())
+    }
+
+    fn pop_scope(&mut self) {
+        if let Some(len) = self.scopes.pop() {
+            self.bindings.truncate(len);
+        }
+    }
+}
+
+/// Returns the name and external id of a doctype declaration, with a leading space.
+fn doctype_head(name: &str, public_id: Option<&str>, system_id: Option<&str>) -> Result<String> {
+    if !is_name(name) {
+        return Err(WriteError::InvalidDoctype(format!(
+            "invalid name `{}`",
+            name
+        )));
+    }
+
+    let mut content = format!(" {}", name);
+
+    match (public_id, system_id) {
+        (Some(public_id), Some(system_id)) => {
+            content.push_str(&public_literal(public_id)?);
+            content.push(' ');
+            content.push_str(&quote(system_id)?);
+        }
+        (None, Some(system_id)) => {
+            content.push_str(" SYSTEM ");
+            content.push_str(&quote(system_id)?);
+        }
+        (Some(_), None) => {
+            return Err(WriteError::InvalidDoctype(
+                "public id without system id".to_string(),
+            ));
+        }
+        (None, None) => {}
+    }
+
+    Ok(content)
+}
+
+/// Returns the construct of the doctype `content` requiring declarations outside of the
+/// document, which `standalone="yes"` rules out.
+fn external_markup(content: &str) -> Option<&'static str> {
+    let rest = skip_ws(content);
+    let rest = skip_ws(&rest[rest.find(|c: char| c == '[' || is_ws_char(c))?..]);
+
+    if rest.starts_with("SYSTEM") || rest.starts_with("PUBLIC") {
+        return Some("external subset");
+    }
+
+    let mut subset = rest.strip_prefix('[')?;
+
+    loop {
+        subset = skip_ws(subset);
+
+        if let Some(comment) = subset.strip_prefix("<!--") {
+            subset = &comment[comment.find("-->")? + 3..];
+        } else if let Some(pi) = subset.strip_prefix("<?") {
+            subset = &pi[pi.find("?>")? + 2..];
+        } else if let Some(decl) = subset.strip_prefix("<!ENTITY") {
+            let decl = skip_ws(decl);
+
+            if decl.starts_with('%') {
+                return Some("parameter entity declaration");
+            }
+
+            let def = skip_ws(&decl[decl.find(is_ws_char)?..]);
+
+            if def.starts_with("SYSTEM") || def.starts_with("PUBLIC") {
+                return Some("external entity declaration");
+            }
+
+            subset = skip_decl(def)?;
+        } else if subset.starts_with('<') {
+            subset = skip_decl(subset)?;
+        } else {
+            return None;
+        }
+    }
+}
+
+/// Returns the input after the `>` closing the markup declaration `decl`, skipping literals.
+fn skip_decl(decl: &str) -> Option<&str> {
+    let mut quote = None;
+
+    for (offset, c) in decl.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return Some(&decl[offset + 1..]),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn is_ws_char(c: char) -> bool {
+    c.is_ascii() && is_ws(c as u8)
+}
+
+fn skip_ws(s: &str) -> &str {
+    s.trim_start_matches(is_ws_char)
+}
+
+/// Returns ` PUBLIC "public_id"`, checking the public id characters.
+fn public_literal(public_id: &str) -> Result<String> {
+    if let Some(c) = public_id.chars().find(|c| !is_pubid_char(*c)) {
+        return Err(WriteError::InvalidDoctype(format!("`{}` in public id", c)));
+    }
+
+    Ok(format!(" PUBLIC {}", quote(public_id)?))
+}
+
+/// Returns the offset of the first `&` in `value` not starting a well-formed entity or character
+/// reference.
+fn illegal_ref_offset(value: &str) -> Option<usize> {
+    value
+        .match_indices('&')
+        .map(|(offset, _)| offset)
+        .find(|offset| {
+            let rest = &value[offset + 1..];
+
+            let Some(end) = rest.find(';') else {
+                return true;
+            };
+
+            let reference = &rest[..end];
+
+            let well_formed = if let Some(hex) = reference.strip_prefix("#x") {
+                !hex.is_empty() && hex.bytes().all(|c| c.is_ascii_hexdigit())
+            } else if let Some(digits) = reference.strip_prefix('#') {
+                !digits.is_empty() && digits.bytes().all(|c| c.is_ascii_digit())
+            } else {
+                is_name(reference)
+            };
+
+            !well_formed
+        })
+}
+
+/// Quote a system or public literal.
+fn quote(literal: &str) -> Result<String> {
+    match (literal.contains('"'), literal.contains('\'')) {
+        (false, _) => Ok(format!("\"{}\"", literal)),
+        (true, false) => Ok(format!("'{}'", literal)),
+        (true, true) => Err(WriteError::InvalidDoctype(format!(
+            "literal `{}` contains both quotes",
+            literal
+        ))),
+    }
+}
+
+impl<W> Drop for XmlWriter<W>
+where
+    W: Write,
+{
+    fn drop(&mut self) {
+        if let Err(err) = self.sink.flush() {
+            log::error!("{}", err);
+        }
+    }
+}
+
+/// A write for the internal subset of a doctype declaration, see [`XmlWriter::write_doctype_start`].
+///
+/// The declaration is closed with `]>` by [`finish`](Self::finish), or when dropped, which only
+/// logs errors.
+pub struct DocTypeStart<'a, W>
+where
+    W: Write,
+{
+    sink: &'a mut XmlWriter<W>,
+    /// true if `finish` was called.
+    finished: bool,
+}
+
+impl<'a, W> Drop for DocTypeStart<'a, W>
+where
+    W: Write,
+{
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+
+        if let Err(err) = self.sink.sink.write_all(b"]>") {
+            log::error!("{}", err);
+        }
+    }
+}
+
+impl<'a, W> DocTypeStart<'a, W>
+where
+    W: Write,
+{
+    /// Close the internal subset and the declaration.
+    pub fn finish(mut self) -> Result<()> {
+        self.finished = true;
+        self.sink.sink.write_all(b"]>")?;
+
+        Ok(())
+    }
+
+    /// Write the internal general entity declaration `<!ENTITY name "value">`.
+    ///
+    /// `value` is the replacement text, references in it are written as is, so readers
+    /// expand them; `%` is escaped as `&#37;`, as parameter entity references are not allowed
+    /// in the internal subset. A `&` not starting a reference fails with
+    /// [`WriteError::InvalidDoctype`], write `&amp;` for a literal one.
+    pub fn write_entity_decl(&mut self, name: &str, value: &str) -> Result<()> {
+        if !is_name(name) {
+            return Err(WriteError::InvalidDoctype(format!(
+                "invalid entity name `{}`",
+                name
+            )));
+        }
+
+        if let Some(offset) = illegal_ref_offset(value) {
+            return Err(WriteError::InvalidDoctype(format!(
+                "`&` without reference at offset {} of entity `{}`",
+                offset, name
+            )));
+        }
+
+        let value = quote(&value.replace('%', "&#37;"))?;
+
+        self.sink
+            .sink
+            .write_fmt(format_args!("<!ENTITY {} {}>", name, value))?;
+
+        Ok(())
+    }
+
+    /// Write the notation declaration `<!NOTATION name PUBLIC "public_id" "system_id">`.
+    ///
+    /// Unlike doctypes, notations may have a public id without system id, but not neither.
+    pub fn write_notation_decl(
+        &mut self,
+        name: &str,
+        public_id: Option<&str>,
+        system_id: Option<&str>,
+    ) -> Result<()> {
+        if !is_name(name) {
+            return Err(WriteError::InvalidDoctype(format!(
+                "invalid notation name `{}`",
+                name
+            )));
+        }
+
+        let id = match (public_id, system_id) {
+            (Some(public_id), Some(system_id)) => {
+                format!("{} {}", public_literal(public_id)?, quote(system_id)?)
+            }
+            (Some(public_id), None) => public_literal(public_id)?,
+            (None, Some(system_id)) => format!(" SYSTEM {}", quote(system_id)?),
+            (None, None) => {
+                return Err(WriteError::InvalidDoctype(format!(
+                    "notation `{}` without public or system id",
+                    name
+                )));
+            }
+        };
+
+        self.sink
+            .sink
+            .write_fmt(format_args!("<!NOTATION {}{}>", name, id))?;
+
+        Ok(())
+    }
+}
+
+/// A write for element start tag.
+///
+/// The tag is closed by [`finish`](Self::finish), or when dropped, which only logs errors.
+pub struct ElemStart<'a, W>
+where
+    W: Write,
+{
+    sink: &'a mut XmlWriter<W>,
+    is_empty: bool,
+    /// the element name, if closed by an end tag instead of `/>`.
+    expanded: Option<String>,
+    /// attributes held back until the tag ends, if [`OutputOptions::attr_wrap`] is set.
+    attrs: Vec<String>,
+    /// names of the written attributes, only tracked in strict mode.
+    names: Vec<String>,
+    /// true if `finish` was called.
+    finished: bool,
+}
+
+impl<'a, W> Drop for ElemStart<'a, W>
+where
+    W: Write,
+{
+    fn drop(&mut self) {
+        if self.is_empty {
+            self.sink.pop_scope();
+        }
+
+        if self.finished {
+            return;
+        }
+
+        if let Err(err) = self.write_end() {
+            log::error!("{}", err);
+        }
+    }
+}
+
+impl<'a, W> ElemStart<'a, W>
+where
+    W: Write,
+{
+    /// Write new attribute value pair.
+    pub fn write_attr<N, V>(&mut self, name: N, value: V) -> Result<()>
+    where
+        N: AsRef<str>,
+        V: AsRef<str>,
+    {
+        self.check_attr(name.as_ref())?;
+
+        let attr = if value.as_ref().contains('"') {
+            format!("{}='{}'", name.as_ref(), value.as_ref())
+        } else {
+            format!("{}=\"{}\"", name.as_ref(), value.as_ref())
+        };
+
+        if self.sink.options.attr_wrap.is_some() {
+            self.attrs.push(attr);
+        } else {
+            self.sink.sink.write_fmt(format_args!(" {}", attr))?;
+        }
+
+        Ok(())
+    }
+
+    /// Write new attribute value pair, escaping the value, see [`escape_attr`].
+    pub fn write_escaped_attr<N, V>(&mut self, name: N, value: V) -> Result<()>
+    where
+        N: AsRef<str>,
+        V: AsRef<str>,
+    {
+        // the escaped value contains no `"`, so it is always double quoted.
+        self.write_attr(name, escape_attr(value.as_ref()))
+    }
+
+    /// Write new attribute with the value formatted from `args`, e.g. `format_args!("{x},{y}")`,
+    /// escaping it, see [`escape_attr`].
+    ///
+    /// The value is formatted straight into the output without intermediate `String`, unless
+    /// held back by [`OutputOptions::attr_wrap`].
+    pub fn write_attr_fmt<N>(&mut self, name: N, args: fmt::Arguments<'_>) -> Result<()>
+    where
+        N: AsRef<str>,
+    {
+        self.check_attr(name.as_ref())?;
+
+        if self.sink.options.attr_wrap.is_some() {
+            let value = args.to_string();
+
+            self.attrs
+                .push(format!("{}=\"{}\"", name.as_ref(), escape_attr(&value)));
+        } else {
+            let sink = &mut self.sink.sink;
+
+            sink.write_fmt(format_args!(" {}=\"", name.as_ref()))?;
+            EscapeAttr(&mut *sink).write_fmt(args)?;
+            sink.write_all(b"\"")?;
+        }
+
+        Ok(())
+    }
+
+    /// Write new attribute with a float value, rounded to [`OutputOptions::float_precision`]
+    /// decimals and written without exponent or trailing zeros, e.g. `0.5` or `12`.
+    ///
+    /// Fails with [`WriteError::NonFinite`] for infinite and NaN values.
+    pub fn write_attr_f64<N>(&mut self, name: N, value: f64) -> Result<()>
+    where
+        N: AsRef<str>,
+    {
+        if !value.is_finite() {
+            return Err(WriteError::NonFinite(value));
+        }
+
+        let value = round(value, self.sink.options.float_precision);
+
+        self.write_attr_fmt(name, format_args!("{}", value))
+    }
+
+    /// Write new attribute with an integer value.
+    pub fn write_attr_int<N, V>(&mut self, name: N, value: V) -> Result<()>
+    where
+        N: AsRef<str>,
+        V: Integer,
+    {
+        self.write_attr_fmt(name, format_args!("{}", value))
+    }
+
+    /// Close the start tag, or the whole element if empty.
+    ///
+    /// Unlike dropping this value, which closes the tag too, write errors are returned.
+    pub fn finish(mut self) -> Result<()> {
+        self.finished = true;
+        self.write_end()
+    }
+
+    /// Rejects an attribute written twice in strict mode.
+    fn check_attr(&mut self, name: &str) -> Result<()> {
+        if self.sink.strict {
+            if self.names.iter().any(|written| written == name) {
+                return Err(WriteError::DuplicateAttr(name.to_string()));
+            }
+
+            self.names.push(name.to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Write the held back attributes and the end of the tag.
+    fn write_end(&mut self) -> Result<()> {
+        let writer = &mut *self.sink;
+
+        let wrap = writer
+            .options
+            .attr_wrap
+            .is_some_and(|max| self.attrs.len() > max);
+
+        // the element itself is already on the stack unless it is empty.
+        let depth = writer.stack.len() + usize::from(self.is_empty);
+
+        for attr in &self.attrs {
+            if wrap {
+                writer
+                    .sink
+                    .write_all(writer.options.newline.as_str().as_bytes())?;
+
+                for _ in 0..depth {
+                    writer
+                        .sink
+                        .write_all(writer.options.indent.as_deref().unwrap_or("").as_bytes())?;
+                }
+
+                writer.sink.write_all(attr.as_bytes())?;
+            } else {
+                writer.sink.write_fmt(format_args!(" {}", attr))?;
+            }
+        }
+
+        match (&self.expanded, writer.options.self_close) {
+            _ if !self.is_empty => writer.sink.write_all(b">")?,
+            (Some(name), _) => writer.sink.write_fmt(format_args!("></{}>", name))?,
+            (None, _) if writer.options.html == HtmlPolicy::Xhtml => {
+                writer.sink.write_all(b" />")?
+            }
+            (None, SelfClose::Spaced) => writer.sink.write_all(b" />")?,
+            (None, _) => writer.sink.write_all(b"/>")?,
+        }
+
+        Ok(())
+    }
+
+    /// Write the namespace declarations of the prolog, if this is the root element.
+    fn with_root_namespaces(mut self) -> Result<Self> {
+        let namespaces = std::mem::take(&mut self.sink.root_namespaces);
+
+        self.write_xmlns(
+            namespaces
+                .iter()
+                .map(|(prefix, uri)| (prefix.as_deref(), uri.as_str())),
+        )?;
+
+        Ok(self)
+    }
+
+    /// Write namespace declarations in canonical order.
+    ///
+    /// The default namespace (`None` prefix) is written first, followed by prefixed
+    /// declarations sorted by prefix, regardless of the order `bindings` yields them.
+    /// This keeps output byte-stable for consumers that hash or sign documents.
+    ///
+    /// Bindings already in scope with the same namespace are skipped.
+    pub fn write_xmlns<'b, B>(&mut self, bindings: B) -> Result<()>
+    where
+        B: IntoIterator<Item = (Option<&'b str>, &'b str)>,
+    {
+        let bindings = canonical_xmlns(bindings, |prefix, uri| {
+            self.sink.lookup_namespace(prefix).unwrap_or_default() == uri
+        });
+
+        for (prefix, uri) in bindings {
+            self.sink
+                .bindings
+                .push((prefix.map(str::to_string), uri.to_string()));
+
+            self.write_attr(xmlns_name(prefix), uri)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the namespace declarations of `bindings` not `bound` yet in canonical order, the
+/// default namespace first, then sorted by prefix.
+fn canonical_xmlns<'b, B, F>(bindings: B, bound: F) -> Vec<(Option<&'b str>, &'b str)>
+where
+    B: IntoIterator<Item = (Option<&'b str>, &'b str)>,
+    F: Fn(Option<&str>, &str) -> bool,
+{
+    let mut bindings = bindings
+        .into_iter()
+        .filter(|(prefix, uri)| !bound(*prefix, uri))
+        .collect::<Vec<_>>();
+
+    bindings.sort_by_key(|(prefix, _)| *prefix);
+
+    bindings
+}
+
+/// Returns the name of the attribute declaring the namespace `prefix`.
+fn xmlns_name(prefix: Option<&str>) -> String {
+    match prefix {
+        Some(prefix) => format!("xmlns:{}", prefix),
+        None => "xmlns".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::types::{Prolog, XmlStylesheet, XmlVersion};
+
+    use super::{
+        HtmlPolicy, Newline, OutputOptions, SelfClose, UnclosedPolicy, WriteError, XmlWriter,
+    };
+
+    #[test]
+    fn test_write_xml_decl() {
+        let mut buf = Vec::new();
+
+        let mut writer = XmlWriter::new(&mut buf);
+
+        writer
+            .write_xml_decl(XmlVersion::Ver11, Some("UTF-8"), Some(true))
+            .unwrap();
+
+        drop(writer);
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r#"<?xml version="1.1" encoding="UTF-8" standalone="yes"?>"#
+        );
+    }
+
+    #[test]
+    fn test_write() {
+        let mut writer = XmlWriter::new(Vec::new());
+
+        writer
+            .write_xml_decl(XmlVersion::Ver11, None, None)
+            .unwrap();
+
+        writer.write_comment("helloworld").unwrap();
+
+        let mut el = writer.write_elment_start("svg").unwrap();
+
+        el.write_attr("hello", "world").unwrap();
+
+        drop(el);
+
+        writer.write_chardata("\nhello world").unwrap();
+
+        writer.write_element_end("svg").unwrap();
+    }
+
+    #[test]
+    fn test_write_xmlns() {
+        let mut buf = Vec::new();
+
+        let mut writer = XmlWriter::new(&mut buf);
+
+        let mut el = writer.write_empty_elment("svg").unwrap();
+
+        el.write_xmlns([
+            (Some("xlink"), "http://www.w3.org/1999/xlink"),
+            (None, "http://www.w3.org/2000/svg"),
+            (Some("ev"), "http://www.w3.org/2001/xml-events"),
+        ])
+        .unwrap();
+
+        drop(el);
+        drop(writer);
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:ev="http://www.w3.org/2001/xml-events" xmlns:xlink="http://www.w3.org/1999/xlink"/>"#
+        );
+    }
+
+    #[test]
+    fn test_open_elements() {
+        let mut writer = XmlWriter::new(Vec::new());
+
+        drop(writer.write_elment_start("svg").unwrap());
+        drop(writer.write_elment_start("g").unwrap());
+        drop(writer.write_empty_elment("rect").unwrap());
+
+        assert_eq!(writer.depth(), 2);
+        assert_eq!(writer.current_element(), Some("g"));
+        assert_eq!(writer.open_elements().collect::<Vec<_>>(), ["svg", "g"]);
+
+        writer.write_element_end("g").unwrap();
+
+        assert_eq!(writer.current_element(), Some("svg"));
+    }
+
+    #[test]
+    fn test_inherited_namespaces() {
+        let mut buf = Vec::new();
+
+        let mut writer = XmlWriter::new(&mut buf).with_inherited_namespaces([
+            (None, "http://www.w3.org/2000/svg"),
+            (Some("xlink"), "http://www.w3.org/1999/xlink"),
+        ]);
+
+        let mut el = writer.write_elment_start("g").unwrap();
+        el.write_xmlns([
+            (None, "http://www.w3.org/2000/svg"),
+            (Some("xlink"), "http://www.w3.org/1999/xlink"),
+            (Some("ev"), "http://www.w3.org/2001/xml-events"),
+        ])
+        .unwrap();
+        drop(el);
+
+        assert_eq!(
+            writer.lookup_namespace(Some("ev")),
+            Some("http://www.w3.org/2001/xml-events")
+        );
+
+        let mut el = writer.write_empty_elment("use").unwrap();
+        el.write_xmlns([(Some("ev"), "http://www.w3.org/2001/xml-events")])
+            .unwrap();
+        drop(el);
+
+        writer.write_element_end("g").unwrap();
+
+        assert_eq!(writer.lookup_namespace(Some("ev")), None);
+        assert_eq!(
+            writer.lookup_namespace(None),
+            Some("http://www.w3.org/2000/svg")
+        );
+
+        drop(writer);
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r#"<g xmlns:ev="http://www.w3.org/2001/xml-events"><use/></g>"#
+        );
+    }
+
+    #[test]
+    fn test_indent() {
+        let mut buf = Vec::new();
+
+        let mut writer = XmlWriter::new(&mut buf).with_indent("  ");
+
+        writer
+            .write_xml_decl(XmlVersion::Ver10, None, None)
+            .unwrap();
+
+        drop(writer.write_elment_start("svg").unwrap());
+        writer.write_comment("shapes").unwrap();
+        drop(writer.write_elment_start("g").unwrap());
+        drop(writer.write_empty_elment("rect").unwrap());
+        writer.write_element_end("g").unwrap();
+        drop(writer.write_elment_start("text").unwrap());
+        writer.write_chardata("a ").unwrap();
+        drop(writer.write_elment_start("tspan").unwrap());
+        writer.write_chardata("b").unwrap();
+        writer.write_element_end("tspan").unwrap();
+        writer.write_element_end("text").unwrap();
+        drop(writer.write_elment_start("desc").unwrap());
+        writer.write_element_end("desc").unwrap();
+        writer.write_element_end("svg").unwrap();
+
+        drop(writer);
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "<?xml version=\"1.0\"?>\n<svg>\n  <!--shapes-->\n  <g>\n    <rect/>\n  </g>\n  <text>a <tspan>b</tspan></text>\n  <desc></desc>\n</svg>"
+        );
+    }
+
+    #[test]
+    fn test_format_options() {
+        let mut buf = Vec::new();
+
+        let mut writer = XmlWriter::with_options(
+            &mut buf,
+            OutputOptions {
+                indent: Some("\t".to_string()),
+                newline: Newline::CrLf,
+                self_close: SelfClose::Expanded,
+                attr_wrap: Some(2),
+                ..Default::default()
+            },
+        );
+
+        let mut el = writer.write_elment_start("svg").unwrap();
+        el.write_attr("width", "10").unwrap();
+        el.write_attr("height", "10").unwrap();
+        drop(el);
+
+        let mut el = writer.write_empty_elment("rect").unwrap();
+        el.write_attr("x", "1").unwrap();
+        el.write_attr("y", "2").unwrap();
+        el.write_attr("fill", "red").unwrap();
+        drop(el);
+
+        writer.write_element_end("svg").unwrap();
+
+        drop(writer);
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "<svg width=\"10\" height=\"10\">\r\n\t<rect\r\n\t\tx=\"1\"\r\n\t\ty=\"2\"\r\n\t\tfill=\"red\"></rect>\r\n</svg>"
+        );
+
+        let mut buf = Vec::new();
+
+        let mut writer = XmlWriter::with_options(
+            &mut buf,
+            OutputOptions {
+                self_close: SelfClose::Spaced,
+                ..Default::default()
+            },
+        );
+
+        drop(writer.write_empty_elment("br").unwrap());
+        drop(writer);
+
+        assert_eq!(String::from_utf8(buf).unwrap(), "<br />");
+    }
+
+    #[test]
+    fn test_strict() {
+        let mut writer = XmlWriter::new(Vec::new()).strict();
+
+        let mut el = writer.write_elment_start("svg").unwrap();
+        el.write_attr("width", "10").unwrap();
+        assert!(matches!(
+            el.write_attr("width", "20"),
+            Err(WriteError::DuplicateAttr(name)) if name == "width"
+        ));
+        drop(el);
+
+        drop(writer.write_elment_start("g").unwrap());
+
+        assert!(matches!(
+            writer.write_element_end("svg"),
+            Err(WriteError::Mismatch { expected, found }) if expected == "g" && found == "svg"
+        ));
+
+        writer.write_element_end("g").unwrap();
+        writer.write_element_end("svg").unwrap();
+
+        assert!(matches!(
+            writer.write_element_end("svg"),
+            Err(WriteError::UnexpectedEnd(_))
+        ));
+        assert!(matches!(
+            writer.write_empty_elment("svg"),
+            Err(WriteError::MultipleRoots(_))
+        ));
+
+        // without strict mode, the writer writes whatever it is told.
+        let mut writer = XmlWriter::new(Vec::new());
+
+        drop(writer.write_empty_elment("a").unwrap());
+        drop(writer.write_empty_elment("b").unwrap());
+        writer.write_element_end("c").unwrap();
+    }
+
+    #[test]
+    fn test_standalone_external_markup() {
+        let standalone = |standalone| {
+            let mut writer = XmlWriter::new(Vec::new());
+
+            writer
+                .write_xml_decl(XmlVersion::Ver10, None, Some(standalone))
+                .unwrap();
+
+            writer
+        };
+
+        let rejects = |result: super::Result<()>, markup: &str| {
+            assert!(matches!(
+                result,
+                Err(WriteError::NotStandalone(found)) if found == markup
+            ));
+        };
+
+        rejects(
+            standalone(true).write_doctype("a", None, Some("a.dtd"), None),
+            "external subset",
+        );
+        rejects(
+            standalone(true)
+                .write_doctype_start("a", Some("-//A//EN"), Some("a.dtd"))
+                .map(drop),
+            "external subset",
+        );
+        rejects(
+            standalone(true).write_doctype("a", None, None, Some("<!ENTITY % p 'x'>")),
+            "parameter entity declaration",
+        );
+        rejects(
+            standalone(true).write_doctype(
+                "a",
+                None,
+                None,
+                Some("<!ENTITY i 'x'>\n<!ENTITY e PUBLIC '-//E//EN' 'e.xml'>"),
+            ),
+            "external entity declaration",
+        );
+
+        let mut writer = standalone(true);
+
+        writer
+            .write_doctype(
+                "a",
+                None,
+                None,
+                Some("<!-- <!ENTITY % p 'x'> --><!ENTITY e '<!ENTITY % p \"x\">'><!ELEMENT a ANY>"),
+            )
+            .unwrap();
+
+        standalone(false)
+            .write_doctype("a", None, Some("a.dtd"), Some("<!ENTITY % p 'x'>"))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_write_doctype() {
+        let mut buf = Vec::new();
+
+        let mut writer = XmlWriter::new(&mut buf).strict();
+
+        writer
+            .write_doctype(
+                "html",
+                Some("-//W3C//DTD XHTML 1.0 Strict//EN"),
+                Some("http://www.w3.org/TR/xhtml1/DTD/xhtml1-strict.dtd"),
+                None,
+            )
+            .unwrap();
+
+        assert!(matches!(
+            writer.write_doctype("html", None, None, None),
+            Err(WriteError::MisplacedDoctype)
+        ));
+
+        drop(writer.write_empty_elment("html").unwrap());
+        drop(writer);
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r#"<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Strict//EN" "http://www.w3.org/TR/xhtml1/DTD/xhtml1-strict.dtd"><html/>"#
+        );
+
+        let mut buf = Vec::new();
+
+        let mut writer = XmlWriter::new(&mut buf);
+
+        writer
+            .write_doctype("a", None, Some(r#"say "hi".dtd"#), Some("<!ENTITY e 'x'>"))
+            .unwrap();
+
+        assert!(matches!(
+            writer.write_doctype("a", Some("a"), None, None),
+            Err(WriteError::InvalidDoctype(_))
+        ));
+        assert!(matches!(
+            writer.write_doctype("a", Some("{"), Some("b"), None),
+            Err(WriteError::InvalidDoctype(_))
+        ));
+        assert!(matches!(
+            writer.write_doctype("a", None, Some(r#"'""#), None),
+            Err(WriteError::InvalidDoctype(_))
+        ));
+        assert!(matches!(
+            writer.write_doctype("1a", None, None, None),
+            Err(WriteError::InvalidDoctype(_))
+        ));
+
+        drop(writer);
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r#"<!DOCTYPE a SYSTEM 'say "hi".dtd' [<!ENTITY e 'x'>]>"#
+        );
+    }
+
+    #[test]
+    fn test_write_doctype_start() {
+        let mut buf = Vec::new();
+
+        let mut writer = XmlWriter::new(&mut buf).strict();
+
+        let mut doctype = writer.write_doctype_start("book", None, None).unwrap();
+
+        doctype
+            .write_entity_decl("h", "hard &amp; 100% &#x22;cover\"")
+            .unwrap();
+        doctype
+            .write_notation_decl("gif", Some("image/gif"), None)
+            .unwrap();
+
+        assert!(matches!(
+            doctype.write_entity_decl("1h", ""),
+            Err(WriteError::InvalidDoctype(_))
+        ));
+
+        for value in ["hard & cover", "&", "&h", "&#;", "&#x1g;", "&1h;"] {
+            assert!(matches!(
+                doctype.write_entity_decl("h", value),
+                Err(WriteError::InvalidDoctype(_))
+            ));
+        }
+        assert!(matches!(
+            doctype.write_notation_decl("png", None, None),
+            Err(WriteError::InvalidDoctype(_))
+        ));
+
+        drop(doctype);
+
+        assert!(matches!(
+            writer.write_doctype_start("book", None, None),
+            Err(WriteError::MisplacedDoctype)
+        ));
+
+        drop(writer.write_empty_elment("book").unwrap());
+        drop(writer);
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r#"<!DOCTYPE book [<!ENTITY h 'hard &amp; 100&#37; &#x22;cover"'><!NOTATION gif PUBLIC "image/gif">]><book/>"#
+        );
+    }
+
+    #[cfg(feature = "dtd")]
+    #[test]
+    fn test_entity_decl_round_trip() {
+        use crate::reader::{XmlNode, XmlReader};
+
+        let mut buf = Vec::new();
+
+        let mut writer = XmlWriter::new(&mut buf);
+
+        writer
+            .write_doctype_start("book", None, None)
+            .unwrap()
+            .write_entity_decl("h", "hardcover")
+            .unwrap();
+
+        drop(writer.write_elment_start("book").unwrap());
+        writer.write_chardata("&h;").unwrap();
+        writer.write_element_end("book").unwrap();
+        drop(writer);
+
+        let mut reader = XmlReader::from(buf.as_slice());
+        let mut texts = vec![];
+
+        while let Some(node) = reader.read_next().unwrap() {
+            match node {
+                XmlNode::DocType(doctype) => {
+                    let decl = doctype.parse_decl().unwrap();
+                    reader.entities_mut().insert_decls(&decl.subset);
+                }
+                XmlNode::CharData(chardata) => {
+                    texts.push(reader.text(&chardata).unwrap().into_owned());
+                }
+                _ => {}
+            }
+        }
+
+        assert_eq!(texts, ["hardcover"]);
+    }
+
+    #[test]
+    fn test_prolog_presets() {
+        let mut buf = Vec::new();
+
+        let mut writer = XmlWriter::new(&mut buf);
+
+        writer.write_prolog(&Prolog::svg11()).unwrap();
+
+        let mut svg = writer.write_elment_start("svg").unwrap();
+        svg.write_attr("width", "10").unwrap();
+        drop(svg);
+
+        drop(writer.write_empty_elment("use").unwrap());
+        writer.write_element_end("svg").unwrap();
+        drop(writer);
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?><!DOCTYPE svg PUBLIC "-//W3C//DTD SVG 1.1//EN" "http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd"><svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" width="10"><use/></svg>"#
+        );
+
+        let mut buf = Vec::new();
+
+        let mut writer = XmlWriter::new(&mut buf);
+
+        writer
+            .write_prolog(
+                &Prolog::plain(XmlVersion::Ver11, Some("UTF-8")).with_namespace(Some("a"), "urn:a"),
+            )
+            .unwrap();
+
+        drop(writer.write_empty_elment("a:root").unwrap());
+        drop(writer);
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r#"<?xml version="1.1" encoding="UTF-8"?><a:root xmlns:a="urn:a"/>"#
+        );
+
+        assert!(
+            Prolog::xhtml1_strict()
+                .doctype
+                .unwrap()
+                .starts_with("html PUBLIC")
+        );
+    }
+
+    #[test]
+    fn test_write_prolog() {
+        let mut buf = Vec::new();
+
+        let mut writer = XmlWriter::new(&mut buf);
+
+        writer
+            .write_prolog(&Prolog {
+                encoding: Some("UTF-8".to_string()),
+                doctype: Some("svg".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+
+        drop(writer.write_empty_elment("svg").unwrap());
+        drop(writer);
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r#"<?xml version="1.0" encoding="UTF-8"?><!DOCTYPE svg><svg/>"#
+        );
+    }
+
+    #[test]
+    fn test_write_stylesheet_pi() {
+        let mut buf = Vec::new();
+
+        let mut writer = XmlWriter::new(&mut buf);
+
+        writer
+            .write_stylesheet_pi(&XmlStylesheet {
+                media: Some("print".to_string()),
+                alternate: Some(true),
+                ..XmlStylesheet::new("a.css?x=1&y=2", "text/css")
+            })
+            .unwrap();
+
+        drop(writer.write_empty_elment("svg").unwrap());
+        drop(writer);
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r#"<?xml-stylesheet href="a.css?x=1&amp;y=2" type="text/css" media="print" alternate="yes" ?><svg/>"#
+        );
+    }
+
+    #[test]
+    fn test_xhtml() {
+        let mut buf = Vec::new();
+
+        let mut writer = XmlWriter::with_options(
+            &mut buf,
+            OutputOptions {
+                html: HtmlPolicy::Xhtml,
+                ..Default::default()
+            },
+        );
+
+        drop(writer.write_elment_start("html").unwrap());
+        drop(writer.write_empty_elment("br").unwrap());
+        drop(writer.write_empty_elment("p").unwrap());
+        drop(writer.write_elment_start("script").unwrap());
+        writer.write_text("if (a < b && c) {}").unwrap();
+        writer.write_element_end("script").unwrap();
+        writer.write_text("a < b").unwrap();
+        writer.write_element_end("html").unwrap();
+        drop(writer);
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "<html><br /><p></p><script>if (a < b && c) {}</script>a &lt; b</html>"
+        );
+    }
+
+    #[test]
+    fn test_escaped() {
+        let mut buf = Vec::new();
+
+        let mut writer = XmlWriter::new(&mut buf);
+
+        let mut el = writer.write_elment_start("a").unwrap();
+        el.write_escaped_attr("title", "say \"hi\" & <go>").unwrap();
+        drop(el);
+
+        writer.write_text("1 < 2 && ]]>").unwrap();
+        writer.write_cdata("]]>").unwrap();
+        writer.write_element_end("a").unwrap();
+
+        assert!(matches!(
+            writer.write_pi("pi", "a?>b"),
+            Err(WriteError::InvalidPI(_))
+        ));
+
+        drop(writer);
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r#"<a title="say &quot;hi&quot; &amp; &lt;go&gt;">1 &lt; 2 &amp;&amp; ]]&gt;<![CDATA[]]]]><![CDATA[>]]></a>"#
+        );
+    }
+
+    #[test]
+    fn test_fragment() {
+        let mut buf = Vec::new();
+
+        let mut writer = XmlWriter::fragment(&mut buf).strict();
+
+        assert!(writer.is_fragment());
+
+        assert!(matches!(
+            writer.write_xml_decl(XmlVersion::Ver10, None, None),
+            Err(WriteError::PrologInFragment)
+        ));
+        assert!(matches!(
+            writer.write_doctype("a", None, None, None),
+            Err(WriteError::PrologInFragment)
+        ));
+
+        writer.write_empty_elment("iq").unwrap();
+        writer.write_text("and").unwrap();
+        writer.write_elment_start("message").unwrap();
+        writer.write_element_end("message").unwrap();
+
+        assert!(matches!(
+            writer.write_element_end("message"),
+            Err(WriteError::UnexpectedEnd(_))
+        ));
+
+        drop(writer);
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "<iq/>and<message></message>"
+        );
+    }
+
+    #[test]
+    fn test_invalid_comment() {
+        let mut writer = XmlWriter::new(Vec::new());
+
+        assert!(matches!(
+            writer.write_comment("a -- b"),
+            Err(WriteError::InvalidComment(_))
+        ));
+        assert!(matches!(
+            writer.write_comment("a -"),
+            Err(WriteError::InvalidComment(_))
+        ));
+
+        writer.write_comment(" a - b ").unwrap();
+
+        assert_eq!(writer.into_inner().unwrap(), b"<!-- a - b -->");
+    }
+
+    #[test]
+    fn test_finish() {
+        let mut writer = XmlWriter::new(Vec::new()).with_indent("  ");
+
+        let mut el = writer.write_elment_start("a").unwrap();
+        el.write_attr("x", "1").unwrap();
+        el.finish().unwrap();
+
+        writer.write_empty_elment("b").unwrap().finish().unwrap();
+
+        let mut doctype = XmlWriter::new(Vec::new());
+
+        doctype
+            .write_doctype_start("a", None, None)
+            .unwrap()
+            .finish()
+            .unwrap();
+
+        writer.write_element_end("a").unwrap();
+
+        assert_eq!(writer.into_inner().unwrap(), b"<a x=\"1\">\n  <b/>\n</a>");
+        assert_eq!(doctype.into_inner().unwrap(), b"<!DOCTYPE a []>");
+    }
+
+    #[test]
+    fn test_flush() {
+        let mut writer = XmlWriter::new(Vec::new());
+
+        writer.write_elment_start("a").unwrap();
+
+        assert!(writer.get_ref().is_empty());
+
+        writer.flush().unwrap();
+
+        assert_eq!(writer.get_ref(), b"<a>");
+
+        writer.write_element_end("a").unwrap();
+
+        assert_eq!(writer.into_inner().unwrap(), b"<a></a>");
+    }
+
+    #[test]
+    fn test_write_attr_numbers() {
+        let mut writer = XmlWriter::with_options(
+            Vec::new(),
+            OutputOptions {
+                float_precision: Some(2),
+                ..Default::default()
+            },
+        )
+        .strict();
+
+        let mut el = writer.write_empty_elment("circle").unwrap();
+        el.write_attr_f64("cx", 2.0 / 3.0).unwrap();
+        el.write_attr_f64("cy", -0.001).unwrap();
+        el.write_attr_f64("r", 12.5).unwrap();
+        el.write_attr_int("n", u64::MAX).unwrap();
+        el.write_attr_fmt("d", format_args!("M{},{} \"{}\"", 1, -2.5, 'z'))
+            .unwrap();
+
+        assert!(matches!(
+            el.write_attr_f64("x", f64::NAN),
+            Err(WriteError::NonFinite(_))
+        ));
+        assert!(matches!(
+            el.write_attr_int("n", 1),
+            Err(WriteError::DuplicateAttr(name)) if name == "n"
+        ));
+
+        el.finish().unwrap();
+
+        assert_eq!(
+            String::from_utf8(writer.into_inner().unwrap()).unwrap(),
+            r#"<circle cx="0.67" cy="0" r="12.5" n="18446744073709551615" d="M1,-2.5 &quot;z&quot;"/>"#
+        );
+
+        let mut writer = XmlWriter::with_options(
+            Vec::new(),
+            OutputOptions {
+                attr_wrap: Some(1),
+                indent: Some(" ".to_string()),
+                ..Default::default()
+            },
+        );
+
+        let mut el = writer.write_empty_elment("a").unwrap();
+        el.write_attr_f64("x", 0.1 + 0.2).unwrap();
+        el.write_attr_fmt("y", format_args!("{}<{}", 1, 2)).unwrap();
+        el.finish().unwrap();
+
+        assert_eq!(
+            writer.into_inner().unwrap(),
+            b"<a\n x=\"0.30000000000000004\"\n y=\"1&lt;2\"/>"
+        );
+    }
+
+    #[test]
+    fn test_close() {
+        let mut writer = XmlWriter::new(Vec::new());
+
+        assert!(matches!(
+            writer.close(UnclosedPolicy::Error),
+            Err(WriteError::NoRoot)
+        ));
+
+        writer.write_elment_start("a").unwrap();
+        writer.write_elment_start("b").unwrap();
+
+        assert!(matches!(
+            writer.close(UnclosedPolicy::Error),
+            Err(WriteError::Unclosed(name)) if name == "b"
+        ));
+
+        writer.close(UnclosedPolicy::AutoClose).unwrap();
+
+        assert_eq!(writer.depth(), 0);
+        assert_eq!(writer.get_ref(), b"<a><b></b></a>");
+
+        XmlWriter::fragment(Vec::new())
+            .close(UnclosedPolicy::Error)
+            .unwrap();
+    }
+}
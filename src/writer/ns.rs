@@ -0,0 +1,376 @@
+use std::io::Write;
+
+use super::{ElemStart, Result, WriteError, XmlWriter};
+
+/// Prefix choice state of a [`NsXmlWriter`].
+#[derive(Debug, Default)]
+struct Prefixes {
+    /// preferred `(prefix, namespace)` pairs, `None` for the default namespace.
+    preferred: Vec<(Option<String>, String)>,
+    /// counter of generated `nsN` prefixes.
+    generated: usize,
+}
+
+/// A namespace-aware layer over [`XmlWriter`].
+///
+/// Names are written by namespace uri and local name, and `xmlns` declarations are written
+/// automatically where a namespace is not in scope yet, with the [`preferred`](Self::prefer)
+/// prefix or a generated `nsN` one. The `qname` variants write literal qualified names
+/// instead, and fail with [`WriteError::UnboundPrefix`] if their prefix is not declared.
+pub struct NsXmlWriter<W>
+where
+    W: Write,
+{
+    writer: XmlWriter<W>,
+    prefixes: Prefixes,
+    /// qualified names of the open elements.
+    names: Vec<String>,
+}
+
+impl<W> From<XmlWriter<W>> for NsXmlWriter<W>
+where
+    W: Write,
+{
+    fn from(writer: XmlWriter<W>) -> Self {
+        Self {
+            writer,
+            prefixes: Default::default(),
+            names: vec![],
+        }
+    }
+}
+
+impl<W> NsXmlWriter<W>
+where
+    W: Write,
+{
+    /// Create a namespace-aware writer from [`std::io::Write`].
+    pub fn new(sink: W) -> Self {
+        XmlWriter::new(sink).into()
+    }
+
+    /// Prefer `prefix` for `namespace` when it must be declared, `None` for the default namespace.
+    pub fn prefer<U>(mut self, prefix: Option<&str>, namespace: U) -> Self
+    where
+        U: Into<String>,
+    {
+        self.prefixes
+            .preferred
+            .push((prefix.map(str::to_string), namespace.into()));
+
+        self
+    }
+
+    /// Returns the underlying writer.
+    pub fn inner(&self) -> &XmlWriter<W> {
+        &self.writer
+    }
+
+    /// Returns the underlying writer, e.g. to write text.
+    pub fn inner_mut(&mut self) -> &mut XmlWriter<W> {
+        &mut self.writer
+    }
+
+    /// Start writing an element start tag of `local` in `namespace`, `None` for no namespace.
+    pub fn write_elment_start(
+        &mut self,
+        namespace: Option<&str>,
+        local: &str,
+    ) -> Result<NsElemStart<'_, W>> {
+        self.write_start(namespace, local, false)
+    }
+
+    /// Start writing an empty element of `local` in `namespace`, `None` for no namespace.
+    pub fn write_empty_elment(
+        &mut self,
+        namespace: Option<&str>,
+        local: &str,
+    ) -> Result<NsElemStart<'_, W>> {
+        self.write_start(namespace, local, true)
+    }
+
+    /// Start writing an element start tag with the qualified name `qname`.
+    pub fn write_qname_start(&mut self, qname: &str) -> Result<NsElemStart<'_, W>> {
+        self.write_qname(qname, false)
+    }
+
+    /// Start writing an empty element with the qualified name `qname`.
+    pub fn write_qname_empty(&mut self, qname: &str) -> Result<NsElemStart<'_, W>> {
+        self.write_qname(qname, true)
+    }
+
+    /// Write the end tag of the innermost open element.
+    pub fn write_element_end(&mut self) -> Result<()> {
+        let name = self
+            .names
+            .pop()
+            .ok_or_else(|| WriteError::UnexpectedEnd(String::new()))?;
+
+        self.writer.write_element_end(&name)
+    }
+
+    fn write_start(
+        &mut self,
+        namespace: Option<&str>,
+        local: &str,
+        is_empty: bool,
+    ) -> Result<NsElemStart<'_, W>> {
+        let (qname, declare) = match namespace {
+            // unprefixed names are in the default namespace, undeclare it.
+            None => (
+                local.to_string(),
+                self.writer
+                    .lookup_namespace(None)
+                    .map(|_| (None, String::new())),
+            ),
+            Some(namespace) if self.writer.lookup_namespace(None) == Some(namespace) => {
+                (local.to_string(), None)
+            }
+            Some(namespace) => match find_prefix(&self.writer, namespace) {
+                Some(prefix) => (format!("{}:{}", prefix, local), None),
+                None => {
+                    let prefix = self.prefixes.choose(&self.writer, namespace, true);
+
+                    let qname = match &prefix {
+                        Some(prefix) => format!("{}:{}", prefix, local),
+                        None => local.to_string(),
+                    };
+
+                    (qname, Some((prefix, namespace.to_string())))
+                }
+            },
+        };
+
+        let mut start = self.open(qname, is_empty)?;
+
+        if let Some((prefix, namespace)) = declare {
+            start
+                .start
+                .write_xmlns([(prefix.as_deref(), namespace.as_str())])?;
+        }
+
+        Ok(start)
+    }
+
+    fn write_qname(&mut self, qname: &str, is_empty: bool) -> Result<NsElemStart<'_, W>> {
+        if let Some((prefix, _)) = qname.split_once(':') {
+            check_bound(&self.writer, prefix)?;
+        }
+
+        self.open(qname.to_string(), is_empty)
+    }
+
+    fn open(&mut self, qname: String, is_empty: bool) -> Result<NsElemStart<'_, W>> {
+        let start = if is_empty {
+            self.writer.write_empty_elment(&qname)?
+        } else {
+            self.names.push(qname.clone());
+            self.writer.write_elment_start(&qname)?
+        };
+
+        Ok(NsElemStart {
+            start,
+            prefixes: &mut self.prefixes,
+        })
+    }
+}
+
+impl Prefixes {
+    /// Choose the prefix declared for `namespace`, `None` only if the default namespace is allowed.
+    fn choose<W>(
+        &mut self,
+        writer: &XmlWriter<W>,
+        namespace: &str,
+        allow_default: bool,
+    ) -> Option<String>
+    where
+        W: Write,
+    {
+        let preferred = self
+            .preferred
+            .iter()
+            .find(|(prefix, uri)| uri == namespace && (prefix.is_some() || allow_default))
+            .map(|(prefix, _)| prefix.clone());
+
+        // a preferred prefix bound to another namespace in scope would shadow it.
+        match preferred {
+            Some(None) => return None,
+            Some(Some(prefix)) if writer.lookup_namespace(Some(&prefix)).is_none() => {
+                return Some(prefix);
+            }
+            _ => {}
+        }
+
+        loop {
+            let prefix = format!("ns{}", self.generated);
+            self.generated += 1;
+
+            if writer.lookup_namespace(Some(&prefix)).is_none() {
+                return Some(prefix);
+            }
+        }
+    }
+}
+
+/// Returns a prefix bound to `namespace` in the current scope of `writer`.
+fn find_prefix<'a, W>(writer: &'a XmlWriter<W>, namespace: &str) -> Option<&'a str>
+where
+    W: Write,
+{
+    writer
+        .bindings
+        .iter()
+        .rev()
+        .filter_map(|(prefix, uri)| Some((prefix.as_deref()?, uri)))
+        .find(|(prefix, uri)| {
+            *uri == namespace && writer.lookup_namespace(Some(prefix)) == Some(namespace)
+        })
+        .map(|(prefix, _)| prefix)
+}
+
+fn check_bound<W>(writer: &XmlWriter<W>, prefix: &str) -> Result<()>
+where
+    W: Write,
+{
+    if prefix == "xml" || writer.lookup_namespace(Some(prefix)).is_some() {
+        Ok(())
+    } else {
+        Err(WriteError::UnboundPrefix(prefix.to_string()))
+    }
+}
+
+/// A write for namespace-aware element start tag, see [`NsXmlWriter`].
+pub struct NsElemStart<'a, W>
+where
+    W: Write,
+{
+    start: ElemStart<'a, W>,
+    prefixes: &'a mut Prefixes,
+}
+
+impl<'a, W> NsElemStart<'a, W>
+where
+    W: Write,
+{
+    /// Write the attribute `local` in `namespace`, `None` for no namespace, escaping the value.
+    ///
+    /// Namespaced attributes are always prefixed, as the default namespace doesn't apply to them.
+    pub fn write_attr(&mut self, namespace: Option<&str>, local: &str, value: &str) -> Result<()> {
+        let Some(namespace) = namespace else {
+            return self.start.write_escaped_attr(local, value);
+        };
+
+        let prefix = match find_prefix(self.start.sink, namespace) {
+            Some(prefix) => prefix.to_string(),
+            None => {
+                let prefix = self
+                    .prefixes
+                    .choose(self.start.sink, namespace, false)
+                    .unwrap_or_default();
+
+                self.start
+                    .write_xmlns([(Some(prefix.as_str()), namespace)])?;
+
+                prefix
+            }
+        };
+
+        self.start
+            .write_escaped_attr(format!("{}:{}", prefix, local), value)
+    }
+
+    /// Write the attribute with the qualified name `qname`, escaping the value.
+    ///
+    /// `xmlns` attributes are written as declarations, see [`write_xmlns`](Self::write_xmlns).
+    pub fn write_qname_attr(&mut self, qname: &str, value: &str) -> Result<()> {
+        match qname.split_once(':') {
+            None if qname == "xmlns" => return self.start.write_xmlns([(None, value)]),
+            Some(("xmlns", prefix)) => return self.start.write_xmlns([(Some(prefix), value)]),
+            Some((prefix, _)) => check_bound(self.start.sink, prefix)?,
+            None => {}
+        }
+
+        self.start.write_escaped_attr(qname, value)
+    }
+
+    /// Declare `bindings` on this element, see [`ElemStart::write_xmlns`].
+    pub fn write_xmlns<'b, B>(&mut self, bindings: B) -> Result<()>
+    where
+        B: IntoIterator<Item = (Option<&'b str>, &'b str)>,
+    {
+        self.start.write_xmlns(bindings)
+    }
+
+    /// Close the start tag, see [`ElemStart::finish`].
+    pub fn finish(self) -> Result<()> {
+        self.start.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::writer::WriteError;
+
+    use super::NsXmlWriter;
+
+    const SVG: &str = "http://www.w3.org/2000/svg";
+    const XLINK: &str = "http://www.w3.org/1999/xlink";
+
+    #[test]
+    fn test_ns_writer() {
+        let mut writer = NsXmlWriter::new(Vec::new())
+            .prefer(None, SVG)
+            .prefer(Some("xlink"), XLINK);
+
+        writer.write_elment_start(Some(SVG), "svg").unwrap();
+
+        writer
+            .write_empty_elment(Some(SVG), "use")
+            .unwrap()
+            .write_attr(Some(XLINK), "href", "#a")
+            .unwrap();
+
+        {
+            let mut el = writer.write_empty_elment(Some("urn:x"), "x").unwrap();
+
+            el.write_attr(None, "id", "1 < 2").unwrap();
+            el.write_attr(Some("urn:y"), "y", "2").unwrap();
+        }
+
+        writer.write_empty_elment(None, "plain").unwrap();
+
+        writer.write_qname_empty("xlink:a").map(|_| ()).unwrap_err();
+
+        writer.write_element_end().unwrap();
+
+        writer.inner_mut().flush().unwrap();
+
+        let buf = writer.inner().get_ref().clone();
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r##"<svg xmlns="http://www.w3.org/2000/svg"><use xmlns:xlink="http://www.w3.org/1999/xlink" xlink:href="#a"/><ns0:x xmlns:ns0="urn:x" id="1 &lt; 2" xmlns:ns1="urn:y" ns1:y="2"/><plain xmlns=""/></svg>"##
+        );
+    }
+
+    #[test]
+    fn test_unbound_qname() {
+        let mut writer = NsXmlWriter::new(Vec::new());
+
+        assert!(matches!(
+            writer.write_qname_start("a:b").map(|_| ()),
+            Err(WriteError::UnboundPrefix(prefix)) if prefix == "a"
+        ));
+
+        let mut el = writer.write_qname_start("b").unwrap();
+
+        el.write_qname_attr("xmlns:a", "urn:a").unwrap();
+        el.write_qname_attr("xml:lang", "en").unwrap();
+        el.write_qname_attr("a:c", "2").unwrap();
+
+        assert!(matches!(
+            el.write_qname_attr("c:d", "1"),
+            Err(WriteError::UnboundPrefix(prefix)) if prefix == "c"
+        ));
+    }
+}
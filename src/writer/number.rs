@@ -0,0 +1,95 @@
+use std::{
+    fmt,
+    io::{self, Write},
+};
+
+use crate::escape::{ATTR_ESCAPE, escape_byte};
+
+/// Primitive integers written by [`ElemStart::write_attr_int`](super::ElemStart::write_attr_int).
+pub trait Integer: fmt::Display + Copy {}
+
+macro_rules! integer {
+    ($($ty:ty),*) => {
+        $(impl Integer for $ty {})*
+    };
+}
+
+integer!(
+    i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize
+);
+
+/// Round `value` to `precision` decimals, so that its shortest representation has at most as
+/// many, and turn `-0` into `0`.
+pub(super) fn round(value: f64, precision: Option<usize>) -> f64 {
+    let rounded = match precision {
+        Some(precision) => {
+            let scale = 10f64.powi(precision.min(i32::MAX as usize) as i32);
+            let rounded = (value * scale).round() / scale;
+
+            // large values have no decimals to round.
+            if rounded.is_finite() { rounded } else { value }
+        }
+        None => value,
+    };
+
+    if rounded == 0.0 { 0.0 } else { rounded }
+}
+
+/// A [`Write`] adapter escaping the attribute value written through it, see
+/// [`escape_attr`](crate::escape::escape_attr).
+pub(super) struct EscapeAttr<W>(pub(super) W);
+
+impl<W> Write for EscapeAttr<W>
+where
+    W: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut offset = 0;
+
+        for (i, c) in buf.iter().enumerate() {
+            if ATTR_ESCAPE.contains(*c)
+                && let Some(replacement) = escape_byte(*c)
+            {
+                self.0.write_all(&buf[offset..i])?;
+                self.0.write_all(replacement.as_bytes())?;
+                offset = i + 1;
+            }
+        }
+
+        self.0.write_all(&buf[offset..])?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::{EscapeAttr, round};
+
+    #[test]
+    fn test_round() {
+        assert_eq!(round(0.1 + 0.2, None), 0.30000000000000004);
+        assert_eq!(round(0.1 + 0.2, Some(3)), 0.3);
+        assert_eq!(round(2.0 / 3.0, Some(2)).to_string(), "0.67");
+        assert_eq!(round(1.5, Some(0)), 2.0);
+        assert_eq!(round(-0.0001, Some(2)).to_string(), "0");
+        assert_eq!(round(1e300, Some(10)), 1e300);
+    }
+
+    #[test]
+    fn test_escape_attr() {
+        let mut buf = Vec::new();
+
+        EscapeAttr(&mut buf)
+            .write_fmt(format_args!("{}<{}\n\"{}\"", 1, 2, "é"))
+            .unwrap();
+
+        assert_eq!(buf, "1&lt;2&#10;&quot;é&quot;".as_bytes());
+    }
+}
@@ -0,0 +1,249 @@
+use crate::types::XmlVersion;
+
+/// Controls whether [`XmlWriter`](super::XmlWriter) writes the xml declaration.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum DeclPolicy {
+    /// Write the declaration only when `write_xml_decl` is called.
+    #[default]
+    AsWritten,
+    /// Write the declaration before the first node, if `write_xml_decl` was not called.
+    Always,
+    /// Never write the declaration, `write_xml_decl` becomes a no-op.
+    Never,
+}
+
+/// Controls how [`XmlWriter::close`](super::XmlWriter::close) treats elements left open.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum UnclosedPolicy {
+    /// Fail with [`WriteError::Unclosed`](super::WriteError::Unclosed).
+    #[default]
+    Error,
+    /// Write the missing end tags.
+    AutoClose,
+}
+
+/// Controls the `standalone` pseudo-attribute of the xml declaration.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum StandalonePolicy {
+    /// Use the flag passed to `write_xml_decl`.
+    #[default]
+    AsWritten,
+    /// Never write the `standalone` pseudo-attribute.
+    Omit,
+    /// Always write `standalone="yes"`.
+    Yes,
+    /// Always write `standalone="no"`.
+    No,
+}
+
+impl StandalonePolicy {
+    /// Returns the effective standalone flag for a declaration requesting `standalone`.
+    pub fn apply(&self, standalone: Option<bool>) -> Option<bool> {
+        match self {
+            StandalonePolicy::AsWritten => standalone,
+            StandalonePolicy::Omit => None,
+            StandalonePolicy::Yes => Some(true),
+            StandalonePolicy::No => Some(false),
+        }
+    }
+}
+
+/// Line break written by an indenting [`XmlWriter`](super::XmlWriter).
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum Newline {
+    /// `\n`
+    #[default]
+    Lf,
+    /// `\r\n`
+    CrLf,
+}
+
+impl Newline {
+    /// Returns the line break text.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Newline::Lf => "\n",
+            Newline::CrLf => "\r\n",
+        }
+    }
+}
+
+/// Controls how elements written by `write_empty_elment` are closed.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum SelfClose {
+    /// `<a/>`
+    #[default]
+    Compact,
+    /// `<a />`
+    Spaced,
+    /// `<a></a>`
+    Expanded,
+}
+
+/// Controls whether [`XmlWriter`](super::XmlWriter) follows the
+/// [`HTML compatibility guidelines`](https://www.w3.org/TR/xhtml1/#guidelines) of XHTML.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum HtmlPolicy {
+    /// Plain xml output.
+    #[default]
+    Xml,
+    /// Output also read correctly by html parsers: empty [`void elements`](Self::VOID_ELEMENTS)
+    /// are written as `<br />`, other empty elements as `<p></p>`, and text of `script` and
+    /// `style` elements is written unescaped by `write_text`.
+    Xhtml,
+}
+
+impl HtmlPolicy {
+    /// Elements without content in html.
+    pub const VOID_ELEMENTS: &[&str] = &[
+        "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param",
+        "source", "track", "wbr",
+    ];
+
+    /// Elements whose content html parsers read as raw text.
+    pub const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style"];
+
+    /// Returns true if `name` is a void element under this policy.
+    pub fn is_void(&self, name: &str) -> bool {
+        *self == HtmlPolicy::Xhtml && contains(Self::VOID_ELEMENTS, name)
+    }
+
+    /// Returns true if text of the element `name` is written unescaped under this policy.
+    pub fn is_raw_text(&self, name: &str) -> bool {
+        *self == HtmlPolicy::Xhtml && contains(Self::RAW_TEXT_ELEMENTS, name)
+    }
+}
+
+/// html element names are case-insensitive.
+fn contains(names: &[&str], name: &str) -> bool {
+    names.iter().any(|known| known.eq_ignore_ascii_case(name))
+}
+
+/// Output options of [`XmlWriter`](super::XmlWriter).
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct OutputOptions {
+    /// whether the xml declaration is written.
+    pub decl: DeclPolicy,
+    /// the version stamped into the declaration, overriding the one passed to `write_xml_decl`.
+    pub version: Option<XmlVersion>,
+    /// whether the `standalone` pseudo-attribute is written.
+    pub standalone: StandalonePolicy,
+    /// indent nested nodes with this string, `None` writes everything on one line.
+    ///
+    /// Content of elements containing text is never indented, so mixed content is preserved.
+    pub indent: Option<String>,
+    /// the line break written before indented nodes.
+    pub newline: Newline,
+    /// how empty elements are closed.
+    pub self_close: SelfClose,
+    /// put each attribute on its own line if a start tag has more than this many attributes.
+    pub attr_wrap: Option<usize>,
+    /// whether html compatible output is written, overrides `self_close`.
+    pub html: HtmlPolicy,
+    /// the maximal number of decimals written by
+    /// [`write_attr_f64`](super::ElemStart::write_attr_f64), `None` writes the shortest text
+    /// reading back as the same value.
+    pub float_precision: Option<usize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{types::XmlVersion, writer::XmlWriter};
+
+    use super::{DeclPolicy, OutputOptions, StandalonePolicy};
+
+    fn write(options: OutputOptions, decl: bool) -> String {
+        let mut buf = Vec::new();
+
+        let mut writer = XmlWriter::with_options(&mut buf, options);
+
+        if decl {
+            writer
+                .write_xml_decl(XmlVersion::Ver10, Some("UTF-8"), Some(true))
+                .unwrap();
+        }
+
+        drop(writer.write_empty_elment("a").unwrap());
+        drop(writer);
+
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_decl_policy() {
+        assert_eq!(
+            write(Default::default(), true),
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><a/>"#
+        );
+
+        assert_eq!(write(Default::default(), false), r#"<a/>"#);
+
+        assert_eq!(
+            write(
+                OutputOptions {
+                    decl: DeclPolicy::Always,
+                    ..Default::default()
+                },
+                false
+            ),
+            r#"<?xml version="1.0"?><a/>"#
+        );
+
+        assert_eq!(
+            write(
+                OutputOptions {
+                    decl: DeclPolicy::Never,
+                    ..Default::default()
+                },
+                true
+            ),
+            r#"<a/>"#
+        );
+    }
+
+    #[test]
+    fn test_version_and_standalone() {
+        assert_eq!(
+            write(
+                OutputOptions {
+                    version: Some(XmlVersion::Ver11),
+                    standalone: StandalonePolicy::Omit,
+                    ..Default::default()
+                },
+                true
+            ),
+            r#"<?xml version="1.1" encoding="UTF-8"?><a/>"#
+        );
+
+        assert_eq!(
+            write(
+                OutputOptions {
+                    decl: DeclPolicy::Always,
+                    standalone: StandalonePolicy::No,
+                    ..Default::default()
+                },
+                false
+            ),
+            r#"<?xml version="1.0" standalone="no"?><a/>"#
+        );
+    }
+
+    #[test]
+    fn test_duplicate_decl() {
+        let mut writer = XmlWriter::with_options(
+            Vec::new(),
+            OutputOptions {
+                decl: DeclPolicy::Always,
+                ..Default::default()
+            },
+        );
+
+        writer.write_comment("hello").unwrap();
+
+        assert!(
+            writer
+                .write_xml_decl(XmlVersion::Ver10, None, None)
+                .is_err()
+        );
+    }
+}
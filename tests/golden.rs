@@ -0,0 +1,120 @@
+//! Golden event sequences, the ordering contract documented on [`XmlReader`].
+
+use rexml::reader::{XmlNode, XmlReader};
+
+fn string(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Render the events of `input`, one per line.
+fn events(input: &str) -> Vec<String> {
+    XmlReader::from(input.as_bytes())
+        .map(|node| match node {
+            Ok(XmlNode::XmlDecl(decl)) => format!("decl {}", decl.version),
+            Ok(XmlNode::DocType(doctype)) => format!("doctype {:?}", string(doctype.0)),
+            Ok(XmlNode::PI(pi)) => format!("pi {} {:?}", string(pi.name), string(pi.unparsed)),
+            Ok(XmlNode::S(ws)) => format!("ws {:?}", string(ws)),
+            Ok(XmlNode::Comment(comment)) => format!("comment {:?}", string(comment.0)),
+            Ok(XmlNode::Start(start)) if start.is_empty => format!("empty {}", string(start.name)),
+            Ok(XmlNode::Start(start)) => format!("start {}", string(start.name)),
+            Ok(XmlNode::End(end)) => format!("end {}", string(end.name)),
+            Ok(XmlNode::CharData(text)) => format!("text {:?}", string(text.0)),
+            Ok(XmlNode::CData(cdata)) => format!("cdata {:?}", string(cdata.0)),
+            Ok(XmlNode::Unknown(unknown)) => format!("unknown {:?}", string(unknown.0)),
+            Ok(XmlNode::Text(text)) => format!("coalesced {:?}", text),
+            Err(_) => "error".to_string(),
+        })
+        .collect()
+}
+
+#[test]
+fn test_empty_elements() {
+    assert_eq!(
+        events(r#"<?xml version="1.0"?><a><b/><c></c>  </a>"#),
+        [
+            "decl 1.0",
+            "start a",
+            "empty b",
+            "start c",
+            "end c",
+            r#"text "  ""#,
+            "end a"
+        ]
+    );
+
+    assert_eq!(
+        events(r#"<?xml version="1.0"?><a/>"#),
+        ["decl 1.0", "empty a"]
+    );
+}
+
+#[test]
+fn test_adjacent_text_and_cdata() {
+    assert_eq!(
+        events(r#"<?xml version="1.0"?><a>x<![CDATA[y]]>z<![CDATA[]]><![CDATA[w]]></a>"#),
+        [
+            "decl 1.0",
+            "start a",
+            r#"text "x""#,
+            r#"cdata "y""#,
+            r#"text "z""#,
+            r#"cdata """#,
+            r#"cdata "w""#,
+            "end a"
+        ]
+    );
+}
+
+#[test]
+fn test_markup_inside_text() {
+    assert_eq!(
+        events(r#"<?xml version="1.0"?><a>x<?p d?>y<!--c-->z&amp;&#65;</a>"#),
+        [
+            "decl 1.0",
+            "start a",
+            r#"text "x""#,
+            r#"pi p " d""#,
+            r#"text "y""#,
+            r#"comment "c""#,
+            r#"text "z&amp;&#65;""#,
+            "end a"
+        ]
+    );
+}
+
+#[test]
+fn test_prolog_and_epilog() {
+    assert_eq!(
+        events("<?xml version=\"1.0\"?>\n<!DOCTYPE a>\n<!--c-->\n<a>\n  <b/>\n</a>\n<?p?>\n"),
+        [
+            "decl 1.0",
+            r#"ws "\n""#,
+            r#"doctype " a""#,
+            r#"ws "\n""#,
+            r#"comment "c""#,
+            r#"ws "\n""#,
+            "start a",
+            r#"text "\n  ""#,
+            "empty b",
+            r#"text "\n""#,
+            "end a",
+            r#"ws "\n""#,
+            r#"pi p """#,
+            r#"ws "\n""#
+        ]
+    );
+}
+
+#[test]
+fn test_markup_in_start_tag() {
+    // the tag ends at the first `>` outside quotes, here the end of the comment.
+    assert_eq!(
+        events(r#"<?xml version="1.0"?><a b="1" <!--c--> c="2"/>"#),
+        ["decl 1.0", "start a", r#"text " c=\"2\"/>""#, "error"]
+    );
+
+    assert_eq!(
+        events(r#"<?xml version="1.0"?><a>x</b>y"#),
+        ["decl 1.0", "start a", r#"text "x""#, "error"]
+    );
+}
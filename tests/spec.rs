@@ -3,12 +3,12 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use rexml::reader::XmlReader;
-
-#[test]
-fn test_specs() {
-    // _ = pretty_env_logger::try_init();
+use rexml::{
+    intern::Interner,
+    reader::{XmlNode, XmlReader},
+};
 
+fn spec_files() -> Vec<(PathBuf, std::ffi::OsString)> {
     let root_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("spec");
 
     let mut xml_files = vec![];
@@ -20,16 +20,25 @@ fn test_specs() {
             continue;
         }
 
-        if let Some(extension) = entry.path().extension() {
-            if extension == "xml" || extension == "svg" {
-                xml_files.push((
-                    root_dir.join(entry.path()).join(entry.path()),
-                    entry.file_name(),
-                ));
-            }
+        if let Some(extension) = entry.path().extension()
+            && (extension == "xml" || extension == "svg")
+        {
+            xml_files.push((
+                root_dir.join(entry.path()).join(entry.path()),
+                entry.file_name(),
+            ));
         }
     }
 
+    xml_files
+}
+
+#[test]
+fn test_specs() {
+    // _ = pretty_env_logger::try_init();
+
+    let xml_files = spec_files();
+
     let mut succ = 0;
     let mut faileds = 0;
 
@@ -70,3 +79,28 @@ fn test_xml(xml: impl AsRef<Path>) {
 
     print!(" {} ", counter)
 }
+
+#[test]
+fn test_intern_attr_values() {
+    let mut interner = Interner::new();
+
+    for (xml, _) in spec_files() {
+        let content = std::fs::read_to_string(xml).unwrap();
+
+        for node in XmlReader::from(content.as_bytes()) {
+            if let XmlNode::Start(start) = node.unwrap() {
+                for attr in start.attrs() {
+                    interner.intern(std::str::from_utf8(attr.unwrap().value).unwrap());
+                }
+            }
+        }
+    }
+
+    println!(
+        "attribute values: {} bytes, {} bytes interned",
+        interner.requested_bytes(),
+        interner.interned_bytes()
+    );
+
+    assert!(interner.interned_bytes() < interner.requested_bytes());
+}